@@ -0,0 +1,76 @@
+use fabric_ui::events::UserEvent;
+use fabric_ui::prelude::*;
+use fabric_ui::r#box::AreaBox;
+use fabric_ui::traits::Widget;
+use fabric_ui::{DrawCtx, EventCtx, UpdateCtx};
+
+/// Space between the rows of the form
+const GAP: f32 = 12.;
+
+/// Widgets stacked from the top, each at the size it asks for.
+struct Column<D> {
+    children: Vec<WidgetPod<D>>,
+}
+
+impl<D> Column<D> {
+    fn new(children: Vec<Box<dyn Widget<D>>>) -> Self {
+        Self {
+            children: children.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl<D> Widget<D> for Column<D> {
+    fn event(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        for child in &mut self.children {
+            child.event(ctx, data);
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        for child in &mut self.children {
+            child.update(ctx, data);
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, data: &D) {
+        for child in &mut self.children {
+            child.draw(ctx, data);
+        }
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let mut y = 0.;
+        let mut width: f32 = 0.;
+        for child in &mut self.children {
+            let remaining = (max_box.height() as f32 - y).max(0.);
+            let (w, h) = child
+                .layout(AreaBox::with_size(max_box.width(), remaining as isize))
+                .to_size_f32();
+            child.set_origin(0., y);
+            y += h + GAP;
+            width = width.max(w);
+        }
+        AreaBox::with_size(width as isize, (y - GAP).max(0.) as isize)
+    }
+}
+
+fn main() {
+    let name = widgets::InputControl::new()
+        .with_size(200, 0)
+        .with_placeholder("Your name")
+        .on_change(|name: &mut String, text| *name = text.into());
+    let button = widgets::ButtonControl::new("Say hello").with_size(120, 32);
+    let hello = button.uid();
+    let form = Column::new(vec![Box::new(name), Box::new(button)]);
+    Application::new(
+        Box::new(widgets::WindowControl::new(Box::new(form))),
+        String::new(),
+    )
+    .on_user_event(move |name: &mut String, event| {
+        if event == UserEvent::ControlClicked(hello) {
+            println!("Hello, {}!", name);
+        }
+    })
+    .run();
+}
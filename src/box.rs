@@ -23,6 +23,26 @@ impl AreaBox {
         down: isize::MAX,
     };
 
+    /// A box at the origin with the given size.
+    pub fn with_size(width: isize, height: isize) -> Self {
+        Self {
+            left: 0,
+            right: width,
+            top: 0,
+            down: height,
+        }
+    }
+
+    #[inline]
+    pub fn width(&self) -> isize {
+        self.right.saturating_sub(self.left)
+    }
+
+    #[inline]
+    pub fn height(&self) -> isize {
+        self.down.saturating_sub(self.top)
+    }
+
     pub fn expand(self, offset: isize) -> Self {
         Self {
             left: self.left + offset,
@@ -43,8 +63,8 @@ impl AreaBox {
 
     pub fn to_size_f32(&self) -> (f32, f32) {
         (
-            (self.right - self.left) as _,
-            (self.down - self.top) as _
+            self.width() as _,
+            self.height() as _
         )
     }
 }
@@ -1,20 +1,82 @@
 //! Some context that can get useful data
 
-use tiny_skia::PixmapMut;
+use std::any::Any;
+use std::time::{Duration, Instant};
+
+use tiny_skia::{ClipMask, FillRule, Paint, Path, PixmapMut, PixmapPaint, PixmapRef, Stroke};
 
 use crate::{
-    events::UserEvent,
-    utils::{DrawTargetExt, WidgetUid},
+    events::{Modifiers, PopupId, TimerToken, ToastId, UserEvent, WindowEvent, WindowId},
+    core::OwnedWindow,
+    popup::Popup,
+    toast::Toast,
+    system::enums::Cursor,
+    utils::{
+        drawing::gen_rect_path,
+        text::{cached_text, FontType},
+        uid::gen_uid,
+        WidgetUid,
+    },
 };
 
 pub struct EventCtx {
-    event: UserEvent,
+    event: WindowEvent,
+    /// Mouse position relative to the widget which is receiving the event.
+    pub(crate) mouse_pos: (f32, f32),
+    pub(crate) widget_id: WidgetUid,
+    pub(crate) widget_size: (f32, f32),
+    pub(crate) modifiers: Modifiers,
+    /// The widget receiving keyboard events
+    pub(crate) focus: Option<WidgetUid>,
+    pub(crate) user_events: Vec<UserEvent>,
+    pub(crate) handled: bool,
+    pub(crate) request_paint: bool,
+    pub(crate) request_layout: bool,
+    pub(crate) request_anim: bool,
+    /// Capture or release the pointer after the event
+    pub(crate) active: Option<bool>,
+    pub(crate) cursor: Option<Cursor>,
+    /// Timers requested while handling the event
+    pub(crate) timers: Vec<(Instant, TimerToken)>,
+    /// Mouse position in the window
+    pub(crate) window_mouse_pos: (f32, f32),
+    /// Popups to open after the event, the content is a `Popup<D>` of the application data
+    pub(crate) open_popups: Vec<(PopupId, Box<dyn Any>)>,
+    pub(crate) close_popups: Vec<PopupId>,
+    /// New anchors in the window of open popups
+    pub(crate) move_popups: Vec<(PopupId, (f32, f32, f32, f32))>,
+    /// The popup holding the widget receiving the event
+    pub(crate) popup: Option<PopupId>,
+    /// The window receiving the event, `None` for the main window
+    pub(crate) window: Option<WindowId>,
+    /// Windows to open after the event, the content is an `OwnedWindow<D>` of the application data
+    pub(crate) open_windows: Vec<(WindowId, Box<dyn Any>)>,
+    pub(crate) close_windows: Vec<WindowId>,
+    /// Focusable widgets in `Tab` order, collected on `WindowEvent::CollectFocus`
+    pub(crate) focus_chain: Option<Vec<WidgetUid>>,
+    /// Toasts to show after the event, the content is a `Toast<D>` of the application data
+    pub(crate) show_toasts: Vec<(ToastId, Box<dyn Any>)>,
+    pub(crate) dismiss_toasts: Vec<ToastId>,
+}
+
+/// Context of `Widget::update`, called after the application data may have changed.
+pub struct UpdateCtx {
+    pub(crate) request_paint: bool,
+    pub(crate) request_layout: bool,
 }
 
 pub struct DrawCtx<'a> {
     pub pixmapmut: &'a mut PixmapMut<'a>,
     pub transform: tiny_skia::Transform,
+    pub clip_mask: Option<ClipMask>,
     pub widget_size: (f32, f32),
+    pub(crate) widget_id: WidgetUid,
+    pub(crate) focus: Option<WidgetUid>,
+    pub(crate) request_anim: bool,
+    pub(crate) request_layout: bool,
+    pub(crate) timers: Vec<(Instant, TimerToken)>,
+    /// Bounds of the clip in pixels, as `(left, top, right, bottom)`
+    pub(crate) visible_rect: (f32, f32, f32, f32),
 }
 
 trait CommonCtx {
@@ -23,12 +85,283 @@ trait CommonCtx {
     fn widget_size() -> (f32, f32);
 }
 
+impl EventCtx {
+    pub(crate) fn new(
+        event: WindowEvent,
+        mouse_pos: (f32, f32),
+        modifiers: Modifiers,
+        focus: Option<WidgetUid>,
+    ) -> Self {
+        Self {
+            event,
+            mouse_pos,
+            widget_id: 0,
+            widget_size: (f32::MAX, f32::MAX),
+            modifiers,
+            focus,
+            user_events: Vec::new(),
+            handled: false,
+            request_paint: false,
+            request_layout: false,
+            request_anim: false,
+            active: None,
+            cursor: None,
+            timers: Vec::new(),
+            window_mouse_pos: mouse_pos,
+            open_popups: Vec::new(),
+            close_popups: Vec::new(),
+            move_popups: Vec::new(),
+            popup: None,
+            window: None,
+            open_windows: Vec::new(),
+            close_windows: Vec::new(),
+            focus_chain: None,
+            show_toasts: Vec::new(),
+            dismiss_toasts: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn event(&self) -> WindowEvent {
+        self.event
+    }
+
+    /// Mouse position relative to the top left corner of the current widget.
+    #[inline]
+    pub fn mouse_pos(&self) -> (f32, f32) {
+        self.mouse_pos
+    }
+
+    #[inline]
+    pub fn widget_id(&self) -> WidgetUid {
+        self.widget_id
+    }
+
+    #[inline]
+    pub fn widget_size(&self) -> (f32, f32) {
+        self.widget_size
+    }
+
+    #[inline]
+    pub fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    /// Let the current widget receive keyboard events.
+    #[inline]
+    pub fn request_focus(&mut self) {
+        self.focus = Some(self.widget_id);
+        self.request_paint = true;
+    }
+
+    /// Give up the keyboard focus if the current widget has it.
+    pub fn resign_focus(&mut self) {
+        if self.is_focused() {
+            self.focus = None;
+            self.request_paint = true;
+        }
+    }
+
+    #[inline]
+    pub fn is_focused(&self) -> bool {
+        self.focus == Some(self.widget_id)
+    }
+
+    /// Let `Tab` move the focus to the current widget, called by focusable widgets on `WindowEvent::CollectFocus`.
+    pub fn register_for_focus(&mut self) {
+        let id = self.widget_id;
+        if let Some(chain) = &mut self.focus_chain {
+            if !chain.contains(&id) {
+                chain.push(id);
+            }
+        }
+    }
+
+    /// Whether the mouse is inside the current widget.
+    pub fn is_hovering(&self) -> bool {
+        let (x, y) = self.mouse_pos;
+        x >= 0. && y >= 0. && x < self.widget_size.0 && y < self.widget_size.1
+    }
+
+    /// Mark the event as handled, so parent widgets can skip it.
+    #[inline]
+    pub fn set_handled(&mut self) {
+        self.handled = true;
+    }
+
+    #[inline]
+    pub fn is_handled(&self) -> bool {
+        self.handled
+    }
+
+    /// Send an event to the user of the application.
+    pub fn submit_user_event(&mut self, event: UserEvent) {
+        self.user_events.push(event);
+    }
+
+    #[inline]
+    pub fn request_paint(&mut self) {
+        self.request_paint = true;
+    }
+
+    #[inline]
+    pub fn request_layout(&mut self) {
+        self.request_layout = true;
+        self.request_paint = true;
+    }
+
+    /// Ask for the window to be redrawn continuously until no widget requests it anymore.
+    #[inline]
+    pub fn request_anim_frame(&mut self) {
+        self.request_anim = true;
+        self.request_paint = true;
+    }
+
+    /// Capture the pointer, so mouse events keep coming even outside of the window, e.g. while dragging.
+    ///
+    /// Call it again with `false` when the mouse is released.
+    #[inline]
+    pub fn set_active(&mut self, active: bool) {
+        self.active = Some(active);
+    }
+
+    /// Set the pointer style, it's reset to an arrow on every mouse event unless a widget sets it again.
+    #[inline]
+    pub fn set_cursor(&mut self, cursor: Cursor) {
+        self.cursor = Some(cursor);
+    }
+
+    /// Receive a `WindowEvent::Timer` with the returned token once `delay` has passed.
+    ///
+    /// The event goes through the whole widget tree, so compare the token before reacting.
+    pub fn request_timer(&mut self, delay: Duration) -> TimerToken {
+        let token = gen_uid();
+        self.timers.push((Instant::now() + delay, token));
+        token
+    }
+
+    /// Position of the top left corner of the current widget in the window.
+    pub fn window_origin(&self) -> (f32, f32) {
+        (
+            self.window_mouse_pos.0 - self.mouse_pos.0,
+            self.window_mouse_pos.1 - self.mouse_pos.1,
+        )
+    }
+
+    /// Show content above every widget of the window, anchored to the current widget.
+    ///
+    /// A `WindowEvent::PopupClosed` with the returned id is sent when it's closed.
+    pub fn open_popup<D: 'static>(&mut self, mut popup: Popup<D>) -> PopupId {
+        let id = gen_uid();
+        popup.resolve_anchor(self.window_origin(), self.widget_size);
+        self.open_popups.push((id, Box::new(popup)));
+        self.request_paint = true;
+        id
+    }
+
+    pub fn close_popup(&mut self, id: PopupId) {
+        self.close_popups.push(id);
+        self.request_paint = true;
+    }
+
+    /// Anchor an open popup to another rectangle of the current widget, e.g. to follow the pointer.
+    pub fn move_popup(&mut self, id: PopupId, x: f32, y: f32, width: f32, height: f32) {
+        let (left, top) = self.window_origin();
+        self.move_popups
+            .push((id, (left + x, top + y, width, height)));
+        self.request_paint = true;
+    }
+
+    /// Close the popup holding the current widget, e.g. once a menu item is chosen.
+    pub fn dismiss_popup(&mut self) {
+        if let Some(id) = self.popup {
+            self.close_popup(id);
+        }
+    }
+
+    /// The popup holding the current widget, `None` for widgets of the window.
+    #[inline]
+    pub fn popup(&self) -> Option<PopupId> {
+        self.popup
+    }
+
+    /// Show a notification in a corner of the window, it waits in a queue while too many are shown.
+    pub fn show_toast<D: 'static>(&mut self, toast: Toast<D>) -> ToastId {
+        let id = gen_uid();
+        self.show_toasts.push((id, Box::new(toast)));
+        self.request_paint = true;
+        id
+    }
+
+    /// Close a toast before its timeout, or remove it from the queue.
+    pub fn dismiss_toast(&mut self, id: ToastId) {
+        self.dismiss_toasts.push(id);
+        self.request_paint = true;
+    }
+
+    /// Open a system window owned by the current one, sharing the application data.
+    ///
+    /// A `WindowEvent::WindowClosed` with the returned id is sent to every window when it's closed.
+    pub fn open_window<D: 'static>(&mut self, window: OwnedWindow<D>) -> WindowId {
+        let id = gen_uid();
+        self.open_windows.push((id, Box::new(window)));
+        id
+    }
+
+    /// Close a window opened by `open_window`, and the windows it opened.
+    pub fn close_window(&mut self, id: WindowId) {
+        self.close_windows.push(id);
+    }
+
+    /// Close the window holding the current widget, unless it's the main window.
+    pub fn dismiss_window(&mut self) {
+        if let Some(id) = self.window {
+            self.close_window(id);
+        }
+    }
+
+    /// The window holding the current widget, `None` for the main window.
+    #[inline]
+    pub fn window(&self) -> Option<WindowId> {
+        self.window
+    }
+}
+
+impl UpdateCtx {
+    pub(crate) fn new() -> Self {
+        Self {
+            request_paint: false,
+            request_layout: false,
+        }
+    }
+
+    #[inline]
+    pub fn request_paint(&mut self) {
+        self.request_paint = true;
+    }
+
+    /// Ask for a new layout, e.g. when the size of the widget depends on the new data.
+    #[inline]
+    pub fn request_layout(&mut self) {
+        self.request_layout = true;
+        self.request_paint = true;
+    }
+}
+
 impl<'a> DrawCtx<'a> {
     pub(crate) fn new(pixmapmut: &'a mut PixmapMut<'a>) -> Self {
+        let visible_rect = (0., 0., pixmapmut.width() as f32, pixmapmut.height() as f32);
         Self {
             pixmapmut,
             transform: tiny_skia::Transform::default(),
+            clip_mask: None,
             widget_size: (f32::MAX, f32::MAX),
+            widget_id: 0,
+            focus: None,
+            request_anim: false,
+            request_layout: false,
+            timers: Vec::new(),
+            visible_rect,
         }
     }
 
@@ -36,8 +369,183 @@ impl<'a> DrawCtx<'a> {
         let mut new_ctx = DrawCtx {
             pixmapmut: &mut self.pixmapmut,
             transform: self.transform.to_owned(),
+            clip_mask: self.clip_mask.to_owned(),
             widget_size: (self.widget_size.0, self.widget_size.1),
+            widget_id: self.widget_id,
+            focus: self.focus,
+            request_anim: false,
+            request_layout: false,
+            timers: Vec::new(),
+            visible_rect: self.visible_rect,
         };
         f(&mut new_ctx);
+        self.request_anim |= new_ctx.request_anim;
+        self.request_layout |= new_ctx.request_layout;
+        self.timers.append(&mut new_ctx.timers);
+    }
+
+    /// Take over what was requested while drawing on another context, e.g. an offscreen layer.
+    pub(crate) fn adopt_requests(&mut self, other: &mut DrawCtx) {
+        self.request_anim |= other.request_anim;
+        self.request_layout |= other.request_layout;
+        self.timers.append(&mut other.timers);
+    }
+
+    /// Run `f` and restore the transform, the clip, the widget size and id afterwards.
+    pub fn with_save(&mut self, f: impl FnOnce(&mut Self)) {
+        let transform = self.transform;
+        let clip_mask = self.clip_mask.clone();
+        let widget_size = self.widget_size;
+        let widget_id = self.widget_id;
+        let visible_rect = self.visible_rect;
+        f(self);
+        self.transform = transform;
+        self.clip_mask = clip_mask;
+        self.visible_rect = visible_rect;
+        self.widget_size = widget_size;
+        self.widget_id = widget_id;
+    }
+
+    /// Whether the widget being drawn has the keyboard focus.
+    #[inline]
+    pub fn is_focused(&self) -> bool {
+        self.focus == Some(self.widget_id)
+    }
+
+    /// Whether some part of the widget being drawn is inside the clip, e.g. not scrolled out of view.
+    ///
+    /// Animating widgets check it before requesting another frame.
+    pub fn is_visible(&self) -> bool {
+        let (w, h) = self.widget_size;
+        if w <= 0. || h <= 0. {
+            return false;
+        }
+        let bounds = match gen_rect_path(0., 0., w, h).transform(self.transform) {
+            Some(path) => path.bounds(),
+            None => return false,
+        };
+        let (left, top, right, bottom) = self.visible_rect;
+        bounds.left() < right
+            && bounds.right() > left
+            && bounds.top() < bottom
+            && bounds.bottom() > top
+    }
+
+    /// Move the origin of the following drawing operations.
+    pub fn translate(&mut self, x: f32, y: f32) {
+        self.transform = self.transform.pre_translate(x, y);
+    }
+
+    /// Scale the following drawing operations.
+    pub fn scale(&mut self, sx: f32, sy: f32) {
+        self.transform = self.transform.pre_scale(sx, sy);
+    }
+
+    /// Intersect the current clip with a rectangle in the current coordinate.
+    pub fn clip_rect(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        let path = if width <= 0. || height <= 0. {
+            // Nothing can be drawn, clip with a rectangle outside the pixmap.
+            gen_rect_path(-2., -2., 1., 1.)
+        } else {
+            match gen_rect_path(x, y, width, height).transform(self.transform) {
+                Some(path) => path,
+                None => return,
+            }
+        };
+        let bounds = path.bounds();
+        let (left, top, right, bottom) = self.visible_rect;
+        self.visible_rect = (
+            left.max(bounds.left()),
+            top.max(bounds.top()),
+            right.min(bounds.right()),
+            bottom.min(bounds.bottom()),
+        );
+        match &mut self.clip_mask {
+            Some(mask) => {
+                mask.intersect_path(&path, FillRule::Winding, false);
+            }
+            None => {
+                let mut mask = ClipMask::new();
+                mask.set_path(
+                    self.pixmapmut.width(),
+                    self.pixmapmut.height(),
+                    &path,
+                    FillRule::Winding,
+                    false,
+                );
+                self.clip_mask = Some(mask);
+            }
+        }
+    }
+
+    pub fn fill_path(&mut self, path: &Path, paint: &Paint) {
+        self.pixmapmut.fill_path(
+            path,
+            paint,
+            FillRule::Winding,
+            self.transform,
+            self.clip_mask.as_ref(),
+        );
+    }
+
+    pub fn stroke_path(&mut self, path: &Path, paint: &Paint, stroke: &Stroke) {
+        self.pixmapmut
+            .stroke_path(path, paint, stroke, self.transform, self.clip_mask.as_ref());
+    }
+
+    pub fn draw_pixmap(&mut self, x: i32, y: i32, pixmap: PixmapRef, paint: &PixmapPaint) {
+        self.pixmapmut.draw_pixmap(
+            x,
+            y,
+            pixmap,
+            paint,
+            self.transform,
+            self.clip_mask.as_ref(),
+        );
+    }
+
+    /// Draw a line of text, `x` and `y` are the top left corner of the line.
+    pub fn draw_text(&mut self, x: f32, y: f32, text: &str, size: f32, color: u32) {
+        self.draw_text_custom(x, y, text, size, color, FontType::Normal);
+    }
+
+    pub fn draw_text_custom(
+        &mut self,
+        x: f32,
+        y: f32,
+        text: &str,
+        size: f32,
+        color: u32,
+        font: FontType,
+    ) {
+        if let Some(pixmap) = cached_text(text, size, color, font) {
+            self.draw_pixmap(
+                x.round() as i32,
+                y.round() as i32,
+                pixmap.as_ref().as_ref(),
+                &PixmapPaint::default(),
+            );
+        }
+    }
+
+    /// Ask for another frame after this one, used by animating widgets.
+    #[inline]
+    pub fn request_anim_frame(&mut self) {
+        self.request_anim = true;
+    }
+
+    /// Ask for a `WindowEvent::Timer` with the returned token after `delay`, e.g. for the next frame
+    /// of a slow animation instead of drawing every frame.
+    pub fn request_timer(&mut self, delay: Duration) -> TimerToken {
+        let token = gen_uid();
+        self.timers.push((Instant::now() + delay, token));
+        token
+    }
+
+    /// Ask for a layout before the next frame, used by widgets whose size animates.
+    #[inline]
+    pub fn request_layout(&mut self) {
+        self.request_layout = true;
+        self.request_anim = true;
     }
 }
@@ -1,40 +1,656 @@
 //! An application struct
 
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
+use crate::{DrawCtx, EventCtx, UpdateCtx, events::{Modifiers, TimerToken, UserEvent, WindowEvent, WindowId, keys}, popup::{OpenPopup, Popup, PopupArea, PopupLayer}, r#box::AreaBox, toast::{Toast, ToastLayer}, system::{enums::Cursor, traits::SystemDrawableWindow}, traits::{Widget}, utils::{WidgetUid, uid::gen_uid}};
 
-use crate::{DrawCtx, EventCtx, r#box::AreaBox, system::traits::SystemDrawableWindow, traits::{Widget}, utils::{WidgetUid, uid::gen_uid}};
+/// A system window opened by `EventCtx::open_window`, owned by the window opening it.
+pub struct OwnedWindow<D> {
+    content: Box<dyn Widget<D>>,
+    /// Zero to fit the content
+    size: (u32, u32),
+    modal: bool,
+}
 
-pub struct Application<D> {
+impl<D> OwnedWindow<D> {
+    pub fn new(content: Box<dyn Widget<D>>) -> Self {
+        Self {
+            content,
+            size: (0, 0),
+            modal: true,
+        }
+    }
+
+    /// Size of the window, the size the content asks for by default.
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.size = (width, height);
+        self
+    }
+
+    /// Disable the owner until the window is closed, on by default.
+    pub fn with_modal(mut self, v: bool) -> Self {
+        self.modal = v;
+        self
+    }
+}
+
+/// A system window and the widgets in it.
+struct Window<D> {
+    /// `None` for the main window
+    id: Option<WindowId>,
+    /// The window which opened this one
+    owner: Option<WindowId>,
+    /// The owner is disabled while this window is open
+    modal: bool,
     system_window: Box<dyn SystemDrawableWindow>,
     root: WidgetPod<D>,
-    data: D
+    mouse_pos: (f32, f32),
+    modifiers: Modifiers,
+    focus: Option<WidgetUid>,
+    need_layout: bool,
+    need_paint: bool,
+    anim_frame: bool,
+    captured: bool,
+    popups: PopupLayer<D>,
+    toasts: ToastLayer<D>,
 }
 
-impl<D> Application<D> {
-    pub fn new(window: Box<dyn Widget<D>>, data: D) -> Self {
+impl<D: 'static> Window<D> {
+    fn new(
+        id: Option<WindowId>,
+        owner: Option<WindowId>,
+        modal: bool,
+        system_window: Box<dyn SystemDrawableWindow>,
+        root: WidgetPod<D>,
+    ) -> Self {
         Self {
-            system_window: Box::new(crate::system::SystemWindow::new()),
-            root: window.into(),
-            data,
+            id,
+            owner,
+            modal,
+            system_window,
+            root,
+            mouse_pos: (f32::MIN, f32::MIN),
+            modifiers: Modifiers::default(),
+            focus: None,
+            need_layout: true,
+            need_paint: true,
+            anim_frame: false,
+            captured: false,
+            popups: PopupLayer::new(),
+            toasts: ToastLayer::new(),
         }
     }
 
-    pub fn run(&mut self) {
-        let mut f = self.system_window.fabric().pixmap_mut();
-        let width = f.width() as _;
-        let height = f.height() as _;
-        let mut draw_ctx = DrawCtx::new(&mut f);
-        draw_ctx.widget_size = self.root.layout(AreaBox {
+    fn window_size(&self) -> (f32, f32) {
+        (
+            self.system_window.size_x() as f32,
+            self.system_window.size_y() as f32,
+        )
+    }
+
+    /// Where popups may go: the window without the parts out of the screen work area, or the work area for the popups
+    /// in their own window.
+    fn popup_area(&self) -> PopupArea {
+        let (width, height) = self.window_size();
+        let (x, y, w, h) = self.system_window.work_area();
+        let screen = (x as f32, y as f32, (x + w) as f32, (y + h) as f32);
+        let window = (
+            screen.0.max(0.),
+            screen.1.max(0.),
+            screen.2.min(width),
+            screen.3.min(height),
+        );
+        if window.2 > window.0 && window.3 > window.1 {
+            PopupArea { window, screen }
+        } else {
+            let window = (0., 0., width, height);
+            PopupArea { window, screen: window }
+        }
+    }
+
+    fn layout(&mut self) {
+        let width = self.system_window.size_x() as isize;
+        let height = self.system_window.size_y() as isize;
+        self.root.layout(AreaBox {
             left: 0,
             right: width,
             top: 0,
             down: height,
-        }).to_size_f32();
-        self.root.draw(&mut draw_ctx, &self.data);
+        });
+        let area = self.popup_area();
+        self.popups.layout(area);
+        self.toasts.layout(area.window);
+        self.need_layout = false;
+    }
+
+    /// Returns the timers requested while drawing.
+    fn paint(&mut self, data: &D) -> Vec<(Instant, TimerToken)> {
+        let handle = self.system_window.raw_handle();
+        let mut f = self.system_window.fabric().pixmap_mut();
+        f.fill(tiny_skia::Color::TRANSPARENT);
+        let mut draw_ctx = DrawCtx::new(&mut f);
+        draw_ctx.focus = self.focus;
+        self.root.draw(&mut draw_ctx, data);
+        self.popups.draw(&mut draw_ctx, data, handle);
+        self.toasts.draw(&mut draw_ctx);
+        self.anim_frame = draw_ctx.request_anim;
+        self.need_layout |= draw_ctx.request_layout;
+        let timers = std::mem::take(&mut draw_ctx.timers);
         // println!("Pixmap {:?}", self.system_window.fabric().pixmap_mut().data_mut());
         self.system_window.sync();
+        self.need_paint = false;
+        timers
+    }
+
+    /// Let the widgets see the latest data.
+    fn update(&mut self, data: &D) {
+        let mut ctx = UpdateCtx::new();
+        self.root.update(&mut ctx, data);
+        self.popups.update(&mut ctx, data);
+        self.need_layout |= ctx.request_layout;
+        self.need_paint |= ctx.request_paint || self.need_layout;
+    }
+
+    /// Dispatch an event to the widget tree and apply what the widgets asked for in the window.
+    ///
+    /// Returns the context for the requests concerning the application, and the popups closed.
+    fn dispatch(&mut self, event: WindowEvent, data: &mut D) -> (EventCtx, Vec<OpenPopup<D>>) {
+        let mut is_mouse = false;
+        match event {
+            WindowEvent::MouseMove(x, y)
+            | WindowEvent::MouseDown(x, y)
+            | WindowEvent::MouseUp(x, y)
+            | WindowEvent::MousePress(x, y)
+            | WindowEvent::ContextMenu(x, y) => {
+                self.mouse_pos = (x as f32, y as f32);
+                is_mouse = true;
+            }
+            WindowEvent::WindowResize(_, _) => self.need_layout = true,
+            WindowEvent::FocusLost => self.modifiers = Modifiers::default(),
+            WindowEvent::KeyDown(key) | WindowEvent::KeyUp(key) => {
+                let down = matches!(event, WindowEvent::KeyDown(_));
+                match key {
+                    keys::SHIFT => self.modifiers.shift = down,
+                    keys::CONTROL => self.modifiers.ctrl = down,
+                    keys::MENU => self.modifiers.alt = down,
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+        let mut ctx = EventCtx::new(event, self.mouse_pos, self.modifiers, self.focus);
+        ctx.window = self.id;
+        // Toasts are above everything, the pointer on them does not reach the widgets
+        let on_toast = self.toasts.event(&mut ctx, data);
+        match event {
+            WindowEvent::MouseMove(_, _) | WindowEvent::MouseUp(_, _) if on_toast && !self.captured => {
+                ctx.mouse_pos = (f32::MIN, f32::MIN);
+                ctx.window_mouse_pos = ctx.mouse_pos;
+                self.route(&mut ctx, data);
+            }
+            WindowEvent::MouseDown(_, _)
+            | WindowEvent::MousePress(_, _)
+            | WindowEvent::ContextMenu(_, _)
+            | WindowEvent::MouseWheel(_)
+            | WindowEvent::MouseHWheel(_)
+                if on_toast => {}
+            _ => self.route(&mut ctx, data),
+        }
+        if event == WindowEvent::KeyDown(keys::TAB)
+            && !ctx.is_handled()
+            && !self.modifiers.ctrl
+            && !self.modifiers.alt
+        {
+            self.focus = ctx.focus;
+            if let Some(next) = self.next_focus(self.modifiers.shift, data) {
+                ctx.focus = Some(next);
+                ctx.request_paint = true;
+                ctx.set_handled();
+            }
+        }
+        self.focus = ctx.focus;
+        if let Some(active) = ctx.active {
+            if active != self.captured {
+                self.captured = active;
+                self.system_window.set_capture(active);
+            }
+        }
+        match ctx.cursor {
+            Some(cursor) => {
+                self.system_window.set_cursor(cursor);
+                self.popups.set_cursor(cursor);
+            }
+            None if is_mouse => {
+                self.system_window.set_cursor(Cursor::Arrow);
+                self.popups.set_cursor(Cursor::Arrow);
+            }
+            None => {}
+        }
+        let mut closed = Vec::new();
+        for id in ctx.close_popups.drain(..) {
+            closed.append(&mut self.popups.close(id));
+        }
+        let area = self.popup_area();
+        for (id, popup) in ctx.open_popups.drain(..) {
+            if let Ok(popup) = popup.downcast::<Popup<D>>() {
+                let popup = OpenPopup::new(id, *popup, self.focus);
+                let modal = popup.modal();
+                self.popups.open(popup, area);
+                if modal {
+                    // Move the focus into the dialog, nothing outside of it can have it
+                    self.focus = self.focus_chain(data).first().copied();
+                }
+            }
+        }
+        for (id, anchor) in ctx.move_popups.drain(..) {
+            self.popups.set_anchor(id, anchor, area);
+        }
+        let dismissed: Vec<_> = ctx.dismiss_toasts.drain(..).collect();
+        for id in dismissed {
+            self.toasts.dismiss(id, &mut ctx);
+        }
+        let shown: Vec<_> = ctx.show_toasts.drain(..).collect();
+        for (id, toast) in shown {
+            if let Ok(toast) = toast.downcast::<Toast<D>>() {
+                self.toasts.show(id, *toast, &mut ctx);
+            }
+        }
+        self.need_layout |= ctx.request_layout;
+        self.need_paint |= ctx.request_paint;
+        (ctx, closed)
+    }
+
+    /// The focusable widgets in `Tab` order, only the ones of the top modal popup and above while there is one.
+    fn focus_chain(&mut self, data: &mut D) -> Vec<WidgetUid> {
+        let mut ctx = EventCtx::new(
+            WindowEvent::CollectFocus,
+            (f32::MIN, f32::MIN),
+            self.modifiers,
+            self.focus,
+        );
+        ctx.window = self.id;
+        ctx.focus_chain = Some(Vec::new());
+        self.popups.event(&mut ctx, data);
+        if self.popups.modal().is_none() {
+            self.root.event(&mut ctx, data);
+        }
+        ctx.focus_chain.unwrap_or_default()
+    }
+
+    /// The widget after the focused one in `Tab` order, or before it when going `backwards`.
+    fn next_focus(&mut self, backwards: bool, data: &mut D) -> Option<WidgetUid> {
+        let chain = self.focus_chain(data);
+        let len = chain.len();
+        if len == 0 {
+            return None;
+        }
+        let current = self
+            .focus
+            .and_then(|focus| chain.iter().position(|&id| id == focus));
+        let next = match current {
+            Some(i) if backwards => (i + len - 1) % len,
+            Some(i) => (i + 1) % len,
+            None if backwards => len - 1,
+            None => 0,
+        };
+        Some(chain[next])
+    }
+
+    /// Send an event to the popups and the widgets of the window.
+    fn route(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        if self.popups.is_empty() {
+            self.root.event(ctx, data);
+            return;
+        }
+        let (x, y) = self.mouse_pos;
+        // Nothing under a modal popup takes input
+        let modal = self.popups.modal();
+        let hit = self
+            .popups
+            .hit(x, y)
+            .filter(|&index| modal.is_none_or(|m| index >= m));
+        let keyboard = matches!(
+            ctx.event(),
+            WindowEvent::KeyDown(_)
+                | WindowEvent::KeyUp(_)
+                | WindowEvent::KeyPress(_)
+                | WindowEvent::CharInput(_, _)
+        );
+        match ctx.event() {
+            WindowEvent::MouseDown(_, _) | WindowEvent::ContextMenu(_, _) => {
+                // Clicking outside closes the light dismissed popups, the click is not passed through
+                let dismissed = self.popups.dismissed_by_click(x, y);
+                if !dismissed.is_empty() {
+                    ctx.close_popups.extend(dismissed);
+                } else if let Some(index) = hit {
+                    self.popups.event_at(index, ctx, data);
+                } else if modal.is_none() {
+                    self.root.event(ctx, data);
+                }
+            }
+            WindowEvent::MousePress(_, _)
+            | WindowEvent::MouseWheel(_)
+            | WindowEvent::MouseHWheel(_) => match hit {
+                Some(index) => self.popups.event_at(index, ctx, data),
+                None if modal.is_none() => self.root.event(ctx, data),
+                None => {}
+            },
+            WindowEvent::MouseMove(_, _) | WindowEvent::MouseUp(_, _) => {
+                self.popups.event(ctx, data);
+                // Widgets under a popup should not look hovered, unless one is dragging
+                if (hit.is_some() || modal.is_some()) && !self.captured {
+                    ctx.mouse_pos = (f32::MIN, f32::MIN);
+                    ctx.window_mouse_pos = ctx.mouse_pos;
+                }
+                self.root.event(ctx, data);
+            }
+            WindowEvent::FocusLost => {
+                // Switching to another window dismisses the popups like a click outside
+                let dismissed = self.popups.dismissed_by_click(f32::MIN, f32::MIN);
+                ctx.close_popups.extend(dismissed);
+                self.popups.event(ctx, data);
+                self.root.event(ctx, data);
+            }
+            WindowEvent::KeyDown(keys::ESCAPE) => {
+                // Escape closes the top popup, unless its content uses the key
+                if let Some(index) = self.popups.top() {
+                    self.popups.event_at(index, ctx, data);
+                    if !ctx.is_handled() {
+                        ctx.close_popups.push(self.popups.id_at(index));
+                        ctx.set_handled();
+                    }
+                }
+            }
+            _ => {
+                self.popups.event(ctx, data);
+                if !keyboard || (!ctx.is_handled() && modal.is_none()) {
+                    self.root.event(ctx, data);
+                }
+            }
+        }
+    }
+}
+
+/// Time between two animation frames
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+type UserEventCallback<D> = Rc<dyn Fn(&mut D, UserEvent)>;
+
+pub struct Application<D> {
+    /// The main window first, then the windows opened by `EventCtx::open_window`
+    windows: Vec<Window<D>>,
+    data: D,
+    timers: Vec<(Instant, TimerToken)>,
+    user_event_callback: Option<UserEventCallback<D>>,
+}
+
+impl<D: 'static> Application<D> {
+    pub fn new(window: Box<dyn Widget<D>>, data: D) -> Self {
+        let system_window = Box::new(crate::system::SystemWindow::new());
+        Self {
+            windows: vec![Window::new(None, None, false, system_window, window.into())],
+            data,
+            timers: Vec::new(),
+            user_event_callback: None,
+        }
+    }
+
+    /// Called with the events the widgets send with `EventCtx::submit_user_event`, e.g.
+    /// `UserEvent::ControlClicked` when a button is clicked. The windows see the changes to the data afterwards.
+    ///
+    /// `UserEvent::Quit` is passed on too before the application quits.
+    pub fn on_user_event<F: Fn(&mut D, UserEvent) + 'static>(mut self, v: F) -> Self {
+        self.user_event_callback = Some(Rc::new(v));
+        self
+    }
+
+    /// Let the widgets of every window see the latest data.
+    fn update(&mut self) {
+        for window in &mut self.windows {
+            window.update(&self.data);
+        }
+    }
+
+    fn index_of(&self, id: Option<WindowId>) -> Option<usize> {
+        self.windows.iter().position(|w| w.id == id)
+    }
+
+    /// Dispatch an event to the window at `index`, returns false if the application should quit.
+    fn dispatch(&mut self, index: usize, event: WindowEvent) -> bool {
+        let (ctx, closed) = self.windows[index].dispatch(event, &mut self.data);
+        self.apply(index, ctx, closed)
+    }
+
+    /// Carry out what the widgets of the window at `index` asked for, returns false if the application should quit.
+    fn apply(&mut self, index: usize, mut ctx: EventCtx, closed: Vec<OpenPopup<D>>) -> bool {
+        self.timers.append(&mut ctx.timers);
+        for (id, window) in ctx.open_windows.drain(..) {
+            if let Ok(window) = window.downcast::<OwnedWindow<D>>() {
+                self.open_window(index, id, *window);
+            }
+        }
+        if let Some(callback) = self.user_event_callback.clone() {
+            for &event in &ctx.user_events {
+                if !matches!(event, UserEvent::None | UserEvent::WindowResize(_, _)) {
+                    callback(&mut self.data, event);
+                }
+            }
+        }
+        // The data may have changed, every window shows it
+        self.update();
+        self.windows[index].anim_frame |= ctx.request_anim;
+        let mut running = !ctx.user_events.contains(&UserEvent::Quit);
+        let id = self.windows[index].id;
+        for popup in closed {
+            // Closing a popup may close its window
+            let index = match self.index_of(id) {
+                Some(index) => index,
+                None => break,
+            };
+            // Give the focus back to where it was when the popup opened
+            let window = &mut self.windows[index];
+            if popup.interactive() {
+                window.focus = popup.return_focus;
+            }
+            window.need_paint = true;
+            running &= self.dispatch(index, WindowEvent::PopupClosed(popup.id));
+        }
+        for id in ctx.close_windows.drain(..) {
+            running &= self.close_window(id);
+        }
+        running
+    }
+
+    /// Dispatch an event to every window, returns false if the application should quit.
+    fn broadcast(&mut self, event: WindowEvent) -> bool {
+        let ids: Vec<Option<WindowId>> = self.windows.iter().map(|w| w.id).collect();
+        let mut running = true;
+        for id in ids {
+            if let Some(index) = self.index_of(id) {
+                running &= self.dispatch(index, event);
+            }
+        }
+        running
+    }
+
+    /// Create the system window for a window opened by a widget of the window at `owner`.
+    fn open_window(&mut self, owner: usize, id: WindowId, window: OwnedWindow<D>) {
+        let mut root: WidgetPod<D> = window.content.into();
+        let owner = &mut self.windows[owner];
+        let (width, height) = if window.size.0 > 0 && window.size.1 > 0 {
+            window.size
+        } else {
+            // Fit the content, as large as the screen at most
+            let (_, _, w, h) = owner.system_window.work_area();
+            let max = if w > 0 && h > 0 {
+                AreaBox::with_size(w as isize, h as isize)
+            } else {
+                let (w, h) = owner.window_size();
+                AreaBox::with_size(w as isize, h as isize)
+            };
+            let size = root.layout(max);
+            (size.width().max(1) as u32, size.height().max(1) as u32)
+        };
+        if window.modal {
+            owner.system_window.set_enabled(false);
+        }
+        let system_window = crate::system::SystemWindow::new_owned(
+            owner.system_window.raw_handle(),
+            width,
+            height,
+        );
+        let mut new = Window::new(Some(id), owner.id, window.modal, system_window, root);
+        new.focus = new.focus_chain(&mut self.data).first().copied();
+        self.windows.push(new);
+    }
+
+    /// Close an owned window and the windows it opened, then tell every window.
+    fn close_window(&mut self, id: WindowId) -> bool {
+        if self.index_of(Some(id)).is_none() {
+            return true;
+        }
+        let mut running = true;
+        let children: Vec<WindowId> = self
+            .windows
+            .iter()
+            .filter(|w| w.owner == Some(id))
+            .filter_map(|w| w.id)
+            .collect();
+        for child in children {
+            running &= self.close_window(child);
+        }
+        let index = match self.index_of(Some(id)) {
+            Some(index) => index,
+            None => return running,
+        };
+        let window = self.windows.remove(index);
+        let owner_free = !self
+            .windows
+            .iter()
+            .any(|w| w.modal && w.owner == window.owner);
+        if window.modal && owner_free {
+            // Enable the owner before the window goes, so the system activates it instead of another application
+            if let Some(owner) = self.index_of(window.owner) {
+                self.windows[owner].system_window.set_enabled(true);
+            }
+        }
+        drop(window);
+        running &= self.broadcast(WindowEvent::WindowClosed(id));
+        running
+    }
+
+    /// Dispatch the events queued by the owned windows, returns false if the application should quit.
+    ///
+    /// Their messages are dispatched by the polling of any window of the thread.
+    fn poll_owned_windows(&mut self) -> bool {
+        let mut index = 1;
+        while index < self.windows.len() {
+            let running = match self.windows[index].system_window.query_event(true) {
+                WindowEvent::None => {
+                    index += 1;
+                    true
+                }
+                WindowEvent::Quit => match self.windows[index].id {
+                    Some(id) => self.close_window(id),
+                    None => true,
+                },
+                // The widgets may answer the request, a dialog closes itself as cancelled
+                WindowEvent::CloseWindow => {
+                    let id = self.windows[index].id;
+                    let (ctx, closed) =
+                        self.windows[index].dispatch(WindowEvent::CloseWindow, &mut self.data);
+                    let handled = ctx.is_handled();
+                    let mut running = self.apply(index, ctx, closed);
+                    if let (false, Some(id)) = (handled, id) {
+                        running &= self.close_window(id);
+                    }
+                    running
+                }
+                event => self.dispatch(index, event),
+            };
+            if !running {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Dispatch the mouse events of the popups in their own window to the windows owning them, returns false if the
+    /// application should quit.
+    fn poll_popup_windows(&mut self) -> bool {
+        let mut index = 0;
+        while index < self.windows.len() {
+            match self.windows[index].popups.query_event() {
+                WindowEvent::None => index += 1,
+                event => {
+                    if !self.dispatch(index, event) {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    pub fn run(&mut self) {
+        self.update();
+        let mut last_frame = Instant::now();
         loop {
-            self.system_window.query_event(false);
+            // Animation frames are paced, painting for an event happens at once
+            let frame_due = last_frame.elapsed() >= FRAME_INTERVAL;
+            let mut animating = false;
+            let mut painted = false;
+            for window in &mut self.windows {
+                if window.need_layout {
+                    window.layout();
+                }
+                // Nothing is painted or animated while minimized, restoring the window resizes it and repaints
+                let minimized = window.system_window.is_minimized();
+                if (window.need_paint || (window.anim_frame && frame_due)) && !minimized {
+                    let mut timers = window.paint(&self.data);
+                    self.timers.append(&mut timers);
+                    painted = true;
+                }
+                animating |= window.anim_frame && !minimized;
+            }
+            let now = Instant::now();
+            if painted {
+                last_frame = now;
+            }
+            if let Some(i) = self.timers.iter().position(|(due, _)| *due <= now) {
+                let (_, token) = self.timers.swap_remove(i);
+                if !self.broadcast(WindowEvent::Timer(token)) {
+                    break;
+                }
+                continue;
+            }
+            if !self.poll_owned_windows() || !self.poll_popup_windows() {
+                break;
+            }
+            // Wake up the blocking poll when the next timer or the next frame is due
+            let next_frame = if animating {
+                Some(last_frame + FRAME_INTERVAL)
+            } else {
+                None
+            };
+            let next = self.timers.iter().map(|(due, _)| *due).chain(next_frame).min();
+            let main = &mut self.windows[0].system_window;
+            if let Some(next) = next {
+                if next <= now {
+                    continue;
+                }
+            }
+            main.set_wakeup(next.map(|due| due - now));
+            match main.query_event(false) {
+                WindowEvent::None => {}
+                WindowEvent::Quit | WindowEvent::CloseWindow => break,
+                event => {
+                    if !self.dispatch(0, event) {
+                        break;
+                    }
+                }
+            }
         }
     }
 }
@@ -43,21 +659,63 @@ pub struct WidgetPod<D = ()> {
     pub widget_id: WidgetUid,
     pub widget: Box<dyn Widget<D>>,
     widget_size: (f32, f32),
+    widget_origin: (f32, f32),
 }
 
 impl<D> WidgetPod<D> {
     pub fn uid(&self) -> WidgetUid {
         self.widget_id
     }
+    pub fn size(&self) -> (f32, f32) {
+        self.widget_size
+    }
+    pub fn origin(&self) -> (f32, f32) {
+        self.widget_origin
+    }
+    /// Set the position of the widget relative to its parent, should be called after `layout`.
+    pub fn set_origin(&mut self, x: f32, y: f32) {
+        self.widget_origin = (x, y);
+    }
     pub fn event(&mut self, ctx: &mut EventCtx, data: &mut D) {
-        self.widget.event(ctx, data);
+        let parent_pos = ctx.mouse_pos;
+        let parent_id = ctx.widget_id;
+        let parent_size = ctx.widget_size;
+        ctx.mouse_pos = (
+            parent_pos.0 - self.widget_origin.0,
+            parent_pos.1 - self.widget_origin.1,
+        );
+        ctx.widget_id = self.widget_id;
+        ctx.widget_size = self.widget_size;
+        // Moving and releasing are sent to every widget so they can reset their states
+        let deliver = match ctx.event() {
+            WindowEvent::MouseDown(_, _)
+            | WindowEvent::MousePress(_, _)
+            | WindowEvent::ContextMenu(_, _)
+            | WindowEvent::MouseWheel(_)
+            | WindowEvent::MouseHWheel(_) => ctx.is_hovering(),
+            _ => true,
+        };
+        if deliver {
+            self.widget.event(ctx, data);
+        }
+        ctx.mouse_pos = parent_pos;
+        ctx.widget_id = parent_id;
+        ctx.widget_size = parent_size;
     }
-    pub fn update(&mut self, data: &D) {
-        self.widget.update(data);
+    pub fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        self.widget.update(ctx, data);
     }
     pub fn draw(&mut self, ctx: &mut DrawCtx, data: &D) {
-        ctx.widget_size = self.widget_size.to_owned();
-        self.widget.draw(ctx, data);
+        let origin = self.widget_origin;
+        let size = self.widget_size;
+        let id = self.widget_id;
+        let widget = &mut self.widget;
+        ctx.with_save(|ctx| {
+            ctx.translate(origin.0, origin.1);
+            ctx.widget_size = size;
+            ctx.widget_id = id;
+            widget.draw(ctx, data);
+        });
     }
     pub fn layout(&mut self, max_box: AreaBox) -> AreaBox {
         let b = self.widget.layout(max_box);
@@ -71,7 +729,121 @@ impl<D> From<Box<dyn Widget<D>>> for WidgetPod<D> {
         Self {
             widget_id: gen_uid(),
             widget: c,
-            widget_size: (f32::MAX, f32::MAX)
+            widget_size: (f32::MAX, f32::MAX),
+            widget_origin: (0., 0.),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::system::enums::HitResult;
+    use crate::system::traits::Fabric;
+    use crate::widgets::{ButtonControl, InputControl, SplitControl};
+
+    /// A window of a fixed size which is never drawn
+    struct TestWindow;
+
+    impl SystemDrawableWindow for TestWindow {
+        fn raw_handle(&self) -> usize {
+            0
+        }
+        fn resize(&mut self, _: u32, _: u32) {}
+        fn set_bounds(&mut self, _: i32, _: i32, _: u32, _: u32) {}
+        fn set_popup_bounds(&mut self, _: i32, _: i32, _: u32, _: u32) {}
+        fn pos_x(&self) -> i32 {
+            0
+        }
+        fn set_pos_x(&mut self, _: i32) {}
+        fn pos_y(&self) -> i32 {
+            0
+        }
+        fn set_pos_y(&mut self, _: i32) {}
+        fn size_x(&self) -> u32 {
+            200
+        }
+        fn set_size_x(&mut self, _: u32) {}
+        fn size_y(&self) -> u32 {
+            100
         }
+        fn set_size_y(&mut self, _: u32) {}
+        fn query_system_event(&mut self, _: bool) {}
+        fn query_event(&mut self, _: bool) -> WindowEvent {
+            WindowEvent::None
+        }
+        fn hit_test(&mut self, _: i32, _: i32) -> HitResult {
+            HitResult::Client
+        }
+        fn sync(&mut self) {}
+        fn fabric(&mut self) -> &mut dyn Fabric<'_> {
+            unreachable!()
+        }
+        fn show(&mut self) {}
+        fn hide(&mut self) {}
+        fn set_capture(&mut self, _: bool) {}
+        fn set_cursor(&mut self, _: Cursor) {}
+        fn set_wakeup(&mut self, _: Option<Duration>) {}
+        fn set_enabled(&mut self, _: bool) {}
+        fn is_minimized(&self) -> bool {
+            false
+        }
+        fn work_area(&self) -> (i32, i32, i32, i32) {
+            (0, 0, 200, 100)
+        }
+    }
+
+    fn application<D: 'static>(root: Box<dyn Widget<D>>, data: D) -> Application<D> {
+        let mut window = Window::new(None, None, false, Box::new(TestWindow), root.into());
+        window.layout();
+        Application {
+            windows: vec![window],
+            data,
+            timers: Vec::new(),
+            user_event_callback: None,
+        }
+    }
+
+    #[test]
+    fn click_delivers_user_event() {
+        let button = ButtonControl::new("OK").with_size(80, 30);
+        let uid = button.uid();
+        let mut app = application(Box::new(button), Vec::new())
+            .on_user_event(|events: &mut Vec<UserEvent>, event| events.push(event));
+        assert!(app.dispatch(0, WindowEvent::MouseMove(10, 10)));
+        assert!(app.dispatch(0, WindowEvent::MouseDown(10, 10)));
+        assert!(app.data.is_empty());
+        assert!(app.dispatch(0, WindowEvent::MouseUp(10, 10)));
+        assert_eq!(app.data, vec![UserEvent::ControlClicked(uid)]);
+    }
+
+    #[test]
+    fn release_outside_does_not_click() {
+        let button = ButtonControl::new("OK").with_size(80, 30);
+        let mut app = application(Box::new(button), Vec::new())
+            .on_user_event(|events: &mut Vec<UserEvent>, event| events.push(event));
+        app.dispatch(0, WindowEvent::MouseDown(10, 10));
+        app.dispatch(0, WindowEvent::MouseUp(150, 80));
+        assert!(app.data.is_empty());
+    }
+
+    #[test]
+    fn tab_moves_the_focus_in_order() {
+        let inputs =
+            SplitControl::new(Box::new(InputControl::new()), Box::new(InputControl::new()));
+        let mut app = application(Box::new(inputs), ());
+        let tab = |app: &mut Application<()>| {
+            app.dispatch(0, WindowEvent::KeyDown(keys::TAB));
+            app.windows[0].focus.unwrap()
+        };
+        let first = tab(&mut app);
+        let second = tab(&mut app);
+        assert_ne!(first, second);
+        // Wraps around
+        assert_eq!(tab(&mut app), first);
+        app.dispatch(0, WindowEvent::KeyDown(keys::SHIFT));
+        assert_eq!(tab(&mut app), second);
     }
 }
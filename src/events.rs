@@ -16,16 +16,88 @@ pub enum WindowEvent {
     KeyDown(usize),
     KeyUp(usize),
     KeyPress(usize),
-    /// Pos X Y
-    MouseMove(u32, u32),
-    MouseDown(u32, u32),
-    MouseUp(u32, u32),
-    MousePress(u32, u32),
+    /// Pos X Y, can be negative or out of the window while the pointer is captured
+    MouseMove(i32, i32),
+    MouseDown(i32, i32),
+    MouseUp(i32, i32),
+    MousePress(i32, i32),
+    /// The right button was released at Pos X Y, widgets under the pointer may open a context menu
+    ContextMenu(i32, i32),
+    /// Wheel delta, a notch is 120, positive when scrolling up
+    MouseWheel(i32),
+    /// Horizontal wheel delta, positive when scrolling right
+    MouseHWheel(i32),
+    /// The pointer left the window
+    MouseLeave,
     // Resize & Move
     WindowMove(i32, i32),
     WindowResize(u32, u32),
     // Character input
     CharInput(WidgetUid, char),
+    /// A timer requested by `EventCtx::request_timer` is due
+    Timer(TimerToken),
+    /// A popup was closed, by its owner or dismissed by the user
+    PopupClosed(PopupId),
+    /// The window lost the keyboard focus, the keys held are considered released
+    FocusLost,
+    /// A window opened by `EventCtx::open_window` was closed
+    WindowClosed(WindowId),
+    /// Sent before moving the focus with `Tab`, focusable widgets call `EventCtx::register_for_focus`
+    CollectFocus,
+    /// The accent colour of the system changed, widgets painted with it draw again
+    ThemeChanged,
+}
+
+/// Identify a timer, widgets compare it with the one they requested
+pub type TimerToken = usize;
+
+/// Identify a popup opened by `EventCtx::open_popup`
+pub type PopupId = usize;
+
+/// Identify a window opened by `EventCtx::open_window`
+pub type WindowId = usize;
+
+/// Identify a toast shown by `EventCtx::show_toast`
+pub type ToastId = usize;
+
+/// Modifier keys held while the event happened
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+}
+
+/// Virtual key codes carried by `WindowEvent::KeyDown` and `WindowEvent::KeyUp`, same as Windows
+pub mod keys {
+    pub const BACK: usize = 0x08;
+    pub const TAB: usize = 0x09;
+    pub const RETURN: usize = 0x0D;
+    pub const SHIFT: usize = 0x10;
+    pub const CONTROL: usize = 0x11;
+    pub const MENU: usize = 0x12;
+    pub const ESCAPE: usize = 0x1B;
+    pub const SPACE: usize = 0x20;
+    pub const PRIOR: usize = 0x21;
+    pub const NEXT: usize = 0x22;
+    pub const END: usize = 0x23;
+    pub const HOME: usize = 0x24;
+    pub const LEFT: usize = 0x25;
+    pub const UP: usize = 0x26;
+    pub const RIGHT: usize = 0x27;
+    pub const DOWN: usize = 0x28;
+    pub const DELETE: usize = 0x2E;
+    pub const A: usize = 0x41;
+    pub const C: usize = 0x43;
+    pub const V: usize = 0x56;
+    pub const X: usize = 0x58;
+    pub const Y: usize = 0x59;
+    pub const Z: usize = 0x5A;
+    pub const ADD: usize = 0x6B;
+    pub const SUBTRACT: usize = 0x6D;
+    pub const F10: usize = 0x79;
+    pub const OEM_PLUS: usize = 0xBB;
+    pub const OEM_MINUS: usize = 0xBD;
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -6,5 +6,7 @@ mod context;
 mod core;
 pub mod prelude;
 pub mod r#box;
+pub mod popup;
+pub mod toast;
 pub use context::*;
 pub mod events;
\ No newline at end of file
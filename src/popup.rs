@@ -0,0 +1,704 @@
+//! 弹出层：下拉列表、菜单和提示等内容画在窗口内所有控件之上，不受打开它的控件的边界限制
+//!
+//! 控件通过 `EventCtx::open_popup` 打开弹出内容，弹出层会把它放在锚点旁边，空间不够时翻转到另一侧并移回窗口内，
+//! 窗口超出屏幕工作区时也不会放到工作区外面
+//!
+//! 模态的弹出内容（如对话框）居中显示并调暗下面的内容，关闭之前窗口的其他部分收不到鼠标和键盘事件
+//!
+//! 弹出内容也可以显示在单独的无边框系统窗口中，这样可以超出窗口的边界，只受屏幕工作区限制，
+//! 此时它仍属于打开它的窗口：键盘焦点留在该窗口，弹出窗口收到的鼠标事件会换算到该窗口的坐标后分发
+
+use crate::core::WidgetPod;
+use crate::events::{PopupId, WindowEvent};
+use crate::r#box::AreaBox;
+use crate::system::enums::Cursor;
+use crate::system::traits::SystemDrawableWindow;
+use crate::traits::Widget;
+use crate::utils::drawing::{gen_paint, gen_rect_path, gen_round_rect_path};
+use crate::utils::WidgetUid;
+use crate::{DrawCtx, EventCtx, UpdateCtx};
+
+const FRAME_RADIUS: f32 = 6.;
+const FRAME_COLOR: u32 = 0xFFFFFFFF;
+const FRAME_BORDER_COLOR: u32 = 0x00000030;
+/// Layers of the shadow under a framed popup, from the outside
+const SHADOW_LAYERS: [(f32, u32); 3] = [(6., 0x00000008), (4., 0x00000010), (2., 0x00000018)];
+/// Drawn over the window under a modal popup
+const MODAL_DIM_COLOR: u32 = 0x00000060;
+/// Room around a framed popup in its own window for the shadow
+const WINDOW_MARGIN: f32 = 8.;
+
+/// Where popups may go in the window, as left, top, right and bottom.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct PopupArea {
+    /// The window without the parts out of the screen work area
+    pub(crate) window: (f32, f32, f32, f32),
+    /// The screen work area, for the popups in their own window
+    pub(crate) screen: (f32, f32, f32, f32),
+}
+
+/// Which side of the anchor the popup prefers, it goes to the opposite side when there is not enough room.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Placement {
+    Bottom,
+    Top,
+    Right,
+    Left,
+}
+
+impl Placement {
+    fn flipped(self) -> Self {
+        match self {
+            Placement::Bottom => Placement::Top,
+            Placement::Top => Placement::Bottom,
+            Placement::Right => Placement::Left,
+            Placement::Left => Placement::Right,
+        }
+    }
+}
+
+/// Content to show in the popup layer, opened with `EventCtx::open_popup`.
+pub struct Popup<D> {
+    content: WidgetPod<D>,
+    placement: Placement,
+    /// Relative to the widget opening the popup, the whole widget by default
+    anchor: Option<(f32, f32, f32, f32)>,
+    gap: f32,
+    light_dismiss: bool,
+    frame: bool,
+    match_width: bool,
+    interactive: bool,
+    modal: bool,
+    window: bool,
+}
+
+impl<D> Popup<D> {
+    pub fn new(content: Box<dyn Widget<D>>) -> Self {
+        Self {
+            content: content.into(),
+            placement: Placement::Bottom,
+            anchor: None,
+            gap: 4.,
+            light_dismiss: true,
+            frame: true,
+            match_width: false,
+            interactive: true,
+            modal: false,
+            window: false,
+        }
+    }
+
+    pub fn with_placement(mut self, v: Placement) -> Self {
+        self.placement = v;
+        self
+    }
+
+    /// Anchor the popup to a rectangle of the widget opening it, e.g. the mouse position for a context menu.
+    pub fn with_anchor(mut self, x: f32, y: f32, width: f32, height: f32) -> Self {
+        self.anchor = Some((x, y, width, height));
+        self
+    }
+
+    /// Space between the anchor and the popup.
+    pub fn with_gap(mut self, v: f32) -> Self {
+        self.gap = v;
+        self
+    }
+
+    /// Close the popup when clicking outside of it, on by default. `Escape` closes it anyway.
+    pub fn with_light_dismiss(mut self, v: bool) -> Self {
+        self.light_dismiss = v;
+        self
+    }
+
+    /// Draw a card with a shadow under the content, on by default.
+    pub fn with_frame(mut self, v: bool) -> Self {
+        self.frame = v;
+        self
+    }
+
+    /// Make the popup as wide as the anchor, used by dropdowns.
+    pub fn with_match_width(mut self, v: bool) -> Self {
+        self.match_width = v;
+        self
+    }
+
+    /// Let the pointer go through the popup to the widgets under it, used by tooltips.
+    ///
+    /// Such a popup is not light dismissed, and closing it leaves the focus alone.
+    pub fn with_interactive(mut self, v: bool) -> Self {
+        self.interactive = v;
+        self
+    }
+
+    /// Center the popup in the window and dim everything under it, which receives no input until it's closed.
+    ///
+    /// A modal popup is not light dismissed and ignores the anchor, used by dialogs.
+    pub fn with_modal(mut self, v: bool) -> Self {
+        self.modal = v;
+        self
+    }
+
+    /// Show the popup in a borderless window of its own, so it can go past the edges of the window, e.g. a menu
+    /// opened near the bottom of a small window. The screen work area still bounds it.
+    ///
+    /// A modal popup stays in the window.
+    pub fn with_window(mut self, v: bool) -> Self {
+        self.window = v;
+        self
+    }
+
+    /// Place the anchor in the window, given the origin of the widget opening the popup.
+    pub(crate) fn resolve_anchor(&mut self, origin: (f32, f32), size: (f32, f32)) {
+        let (x, y, w, h) = self.anchor.unwrap_or((0., 0., size.0, size.1));
+        self.anchor = Some((origin.0 + x, origin.1 + y, w, h));
+    }
+    /// Draw the frame and the content at their place in the window.
+    fn draw_card(&mut self, ctx: &mut DrawCtx, data: &D) {
+        let (x, y) = self.content.origin();
+        let (w, h) = self.content.size();
+        if self.frame {
+            for &(spread, color) in SHADOW_LAYERS.iter() {
+                let path = gen_round_rect_path(
+                    x - spread,
+                    y - spread + 2.,
+                    w + spread * 2.,
+                    h + spread * 2.,
+                    FRAME_RADIUS + spread,
+                );
+                ctx.fill_path(&path, &gen_paint(color));
+            }
+            let path = gen_round_rect_path(x + 0.5, y + 0.5, w - 1., h - 1., FRAME_RADIUS);
+            ctx.fill_path(&path, &gen_paint(FRAME_COLOR));
+            ctx.stroke_path(
+                &path,
+                &gen_paint(FRAME_BORDER_COLOR),
+                &tiny_skia::Stroke::default(),
+            );
+        }
+        let content = &mut self.content;
+        ctx.with_save(|ctx| {
+            ctx.clip_rect(x, y, w, h);
+            content.draw(ctx, data);
+        });
+    }
+}
+
+/// Find where a popup of `size` goes next to `anchor`, staying inside `area`.
+fn place(
+    anchor: (f32, f32, f32, f32),
+    size: (f32, f32),
+    placement: Placement,
+    gap: f32,
+    area: (f32, f32, f32, f32),
+) -> (f32, f32) {
+    let (left, top, right, bottom) = area;
+    let (ax, ay, aw, ah) = anchor;
+    let (w, h) = size;
+    let position = |placement| match placement {
+        Placement::Bottom => (ax, ay + ah + gap),
+        Placement::Top => (ax, ay - gap - h),
+        Placement::Right => (ax + aw + gap, ay),
+        Placement::Left => (ax - gap - w, ay),
+    };
+    let fits = |(x, y): (f32, f32)| x >= left && y >= top && x + w <= right && y + h <= bottom;
+    let (mut x, mut y) = position(placement);
+    let vertical = matches!(placement, Placement::Bottom | Placement::Top);
+    // Flip when the preferred side is too small and the other side is not
+    let other = position(placement.flipped());
+    let fits_main = |(x, y): (f32, f32)| {
+        if vertical {
+            fits((left, y))
+        } else {
+            fits((x, top))
+        }
+    };
+    if !fits_main((x, y)) && fits_main(other) {
+        x = other.0;
+        y = other.1;
+    }
+    // Shift along the anchor to stay in the area
+    x = x.min(right - w).max(left);
+    y = y.min(bottom - h).max(top);
+    (x.round(), y.round())
+}
+
+pub(crate) struct OpenPopup<D> {
+    pub(crate) id: PopupId,
+    popup: Popup<D>,
+    /// Focus to give back when the popup is closed
+    pub(crate) return_focus: Option<WidgetUid>,
+    /// The system window of a popup in its own window, created when it's first drawn
+    surface: Option<Box<dyn SystemDrawableWindow>>,
+}
+
+impl<D> OpenPopup<D> {
+    pub(crate) fn new(id: PopupId, popup: Popup<D>, return_focus: Option<WidgetUid>) -> Self {
+        Self {
+            id,
+            popup,
+            return_focus,
+            surface: None,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn light_dismiss(&self) -> bool {
+        self.popup.light_dismiss && self.popup.interactive && !self.popup.modal
+    }
+
+    #[inline]
+    pub(crate) fn interactive(&self) -> bool {
+        self.popup.interactive || self.popup.modal
+    }
+
+    #[inline]
+    pub(crate) fn modal(&self) -> bool {
+        self.popup.modal
+    }
+
+    #[inline]
+    fn windowed(&self) -> bool {
+        self.popup.window && !self.popup.modal
+    }
+
+    /// Bounds of the system window of a popup in its own window, in the window owning it.
+    fn window_bounds(&self) -> (i32, i32, u32, u32) {
+        let margin = if self.popup.frame { WINDOW_MARGIN } else { 0. };
+        let (x, y) = self.popup.content.origin();
+        let (w, h) = self.popup.content.size();
+        (
+            (x - margin).floor() as i32,
+            (y - margin).floor() as i32,
+            (w + margin * 2.).ceil().max(1.) as u32,
+            (h + margin * 2.).ceil().max(1.) as u32,
+        )
+    }
+
+    pub(crate) fn contains(&self, x: f32, y: f32) -> bool {
+        if !self.interactive() {
+            return false;
+        }
+        let (left, top) = self.popup.content.origin();
+        let (w, h) = self.popup.content.size();
+        x >= left && y >= top && x < left + w && y < top + h
+    }
+
+    /// Lay out the content and place it in the area it may go.
+    pub(crate) fn layout(&mut self, area: PopupArea) {
+        let area = if self.windowed() {
+            area.screen
+        } else {
+            area.window
+        };
+        let popup = &mut self.popup;
+        let anchor = popup.anchor.unwrap_or((0., 0., 0., 0.));
+        let (area_width, area_height) = (area.2 - area.0, area.3 - area.1);
+        let width = if popup.match_width && !popup.modal {
+            anchor.2.min(area_width)
+        } else {
+            area_width
+        };
+        let size = popup
+            .content
+            .layout(AreaBox::with_size(width as isize, area_height as isize))
+            .to_size_f32();
+        let (x, y) = if popup.modal {
+            (
+                (area.0 + (area_width - size.0) / 2.).max(area.0).round(),
+                (area.1 + (area_height - size.1) / 2.).max(area.1).round(),
+            )
+        } else {
+            place(anchor, size, popup.placement, popup.gap, area)
+        };
+        popup.content.set_origin(x, y);
+    }
+
+    /// Dispatch an event, `ctx` holds the mouse position in the window.
+    pub(crate) fn event(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        let outer = ctx.popup;
+        ctx.popup = Some(self.id);
+        self.popup.content.event(ctx, data);
+        ctx.popup = outer;
+    }
+
+    pub(crate) fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        self.popup.content.update(ctx, data);
+    }
+
+    pub(crate) fn draw(&mut self, ctx: &mut DrawCtx, data: &D) {
+        if self.popup.modal {
+            let (width, height) = (ctx.pixmapmut.width(), ctx.pixmapmut.height());
+            let path = gen_rect_path(0., 0., width as f32, height as f32);
+            ctx.fill_path(&path, &gen_paint(MODAL_DIM_COLOR));
+        }
+        self.popup.draw_card(ctx, data);
+    }
+
+    /// Draw a popup in its own window, creating the window or moving it to where the popup is.
+    ///
+    /// What the content asks for while drawing goes to `ctx`.
+    fn draw_window(&mut self, owner: usize, ctx: &mut DrawCtx, data: &D) {
+        let (x, y, width, height) = self.window_bounds();
+        let interactive = self.interactive();
+        let surface = match &mut self.surface {
+            Some(surface) => {
+                surface.set_popup_bounds(x, y, width, height);
+                surface
+            }
+            None => self.surface.insert(crate::system::SystemWindow::new_popup(
+                owner,
+                x,
+                y,
+                width,
+                height,
+                interactive,
+            )),
+        };
+        {
+            let mut pixmap = surface.fabric().pixmap_mut();
+            pixmap.fill(tiny_skia::Color::TRANSPARENT);
+            let mut window_ctx = DrawCtx::new(&mut pixmap);
+            window_ctx.focus = ctx.focus;
+            window_ctx.translate(-x as f32, -y as f32);
+            self.popup.draw_card(&mut window_ctx, data);
+            ctx.adopt_requests(&mut window_ctx);
+        }
+        surface.sync();
+    }
+
+    /// Take the next event of the window of a popup in its own window, mouse positions are moved into the
+    /// window owning it. Other events concern the owner and are dropped.
+    fn query_event(&mut self) -> WindowEvent {
+        let (left, top, _, _) = self.window_bounds();
+        let surface = match &mut self.surface {
+            Some(surface) => surface,
+            None => return WindowEvent::None,
+        };
+        loop {
+            return match surface.query_event(true) {
+                WindowEvent::None => WindowEvent::None,
+                WindowEvent::MouseMove(x, y) => WindowEvent::MouseMove(x + left, y + top),
+                WindowEvent::MouseDown(x, y) => WindowEvent::MouseDown(x + left, y + top),
+                WindowEvent::MouseUp(x, y) => WindowEvent::MouseUp(x + left, y + top),
+                WindowEvent::MousePress(x, y) => WindowEvent::MousePress(x + left, y + top),
+                WindowEvent::ContextMenu(x, y) => WindowEvent::ContextMenu(x + left, y + top),
+                event @ WindowEvent::MouseWheel(_) | event @ WindowEvent::MouseHWheel(_) => event,
+                _ => continue,
+            };
+        }
+    }
+}
+
+/// The popups of a window, the last one is on the top.
+pub(crate) struct PopupLayer<D> {
+    popups: Vec<OpenPopup<D>>,
+}
+
+impl<D> PopupLayer<D> {
+    pub(crate) fn new() -> Self {
+        Self { popups: Vec::new() }
+    }
+
+    #[inline]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.popups.is_empty()
+    }
+
+    pub(crate) fn open(&mut self, popup: OpenPopup<D>, area: PopupArea) {
+        let mut popup = popup;
+        popup.layout(area);
+        self.popups.push(popup);
+    }
+
+    /// Move a popup to a new anchor in the window.
+    pub(crate) fn set_anchor(
+        &mut self,
+        id: PopupId,
+        anchor: (f32, f32, f32, f32),
+        area: PopupArea,
+    ) {
+        if let Some(popup) = self.popups.iter_mut().find(|p| p.id == id) {
+            popup.popup.anchor = Some(anchor);
+            popup.layout(area);
+        }
+    }
+
+    /// Remove a popup and the ones opened after it, returns them from the top.
+    pub(crate) fn close(&mut self, id: PopupId) -> Vec<OpenPopup<D>> {
+        match self.popups.iter().position(|p| p.id == id) {
+            Some(index) => self.popups.drain(index..).rev().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Index of the top popup under the point.
+    pub(crate) fn hit(&self, x: f32, y: f32) -> Option<usize> {
+        self.popups.iter().rposition(|p| p.contains(x, y))
+    }
+
+    /// Index of the top modal popup, the ones under it and the window receive no input.
+    pub(crate) fn modal(&self) -> Option<usize> {
+        self.popups.iter().rposition(|p| p.modal())
+    }
+
+    /// The light dismissed popups to close for a click at the point, from the top to the popup clicked.
+    ///
+    /// Popups under a modal one are never dismissed.
+    pub(crate) fn dismissed_by_click(&self, x: f32, y: f32) -> Vec<PopupId> {
+        let modal = self.modal().map_or(0, |i| i + 1);
+        let keep = self.hit(x, y).map_or(0, |i| i + 1).max(modal);
+        self.popups[keep..]
+            .iter()
+            .rev()
+            .filter(|p| p.interactive())
+            .take_while(|p| p.light_dismiss())
+            .map(|p| p.id)
+            .collect()
+    }
+
+    /// Index of the top popup taking the keyboard, tooltips are skipped.
+    pub(crate) fn top(&self) -> Option<usize> {
+        self.popups.iter().rposition(|p| p.interactive())
+    }
+
+    #[inline]
+    pub(crate) fn id_at(&self, index: usize) -> PopupId {
+        self.popups[index].id
+    }
+
+    pub(crate) fn layout(&mut self, area: PopupArea) {
+        for popup in &mut self.popups {
+            popup.layout(area);
+        }
+    }
+
+    /// Dispatch an event to the popup at `index`.
+    pub(crate) fn event_at(&mut self, index: usize, ctx: &mut EventCtx, data: &mut D) {
+        self.popups[index].event(ctx, data);
+    }
+
+    /// Dispatch an event to every popup from the top, keyboard events stop once handled.
+    ///
+    /// While a modal popup is open only it and the popups above it receive input, other events go everywhere.
+    pub(crate) fn event(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        let input = matches!(
+            ctx.event(),
+            WindowEvent::MouseMove(_, _)
+                | WindowEvent::MouseDown(_, _)
+                | WindowEvent::MouseUp(_, _)
+                | WindowEvent::MousePress(_, _)
+                | WindowEvent::ContextMenu(_, _)
+                | WindowEvent::MouseWheel(_)
+                | WindowEvent::MouseHWheel(_)
+                | WindowEvent::KeyDown(_)
+                | WindowEvent::KeyUp(_)
+                | WindowEvent::KeyPress(_)
+                | WindowEvent::CharInput(_, _)
+                | WindowEvent::CollectFocus
+        );
+        let from = if input { self.modal().unwrap_or(0) } else { 0 };
+        for popup in self.popups[from..].iter_mut().rev() {
+            popup.event(ctx, data);
+            let keyboard = matches!(
+                ctx.event(),
+                WindowEvent::KeyDown(_)
+                    | WindowEvent::KeyUp(_)
+                    | WindowEvent::KeyPress(_)
+                    | WindowEvent::CharInput(_, _)
+            );
+            if keyboard && ctx.is_handled() {
+                break;
+            }
+        }
+    }
+
+    pub(crate) fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        for popup in &mut self.popups {
+            popup.update(ctx, data);
+        }
+    }
+
+    /// Draw the popups, the ones in their own window go to it. `owner` is the handle of the window.
+    pub(crate) fn draw(&mut self, ctx: &mut DrawCtx, data: &D, owner: usize) {
+        for popup in &mut self.popups {
+            if popup.windowed() {
+                popup.draw_window(owner, ctx, data);
+            } else {
+                popup.draw(ctx, data);
+            }
+        }
+    }
+
+    /// Take the next event of the windows of the popups in their own window, `WindowEvent::None` if there is none.
+    pub(crate) fn query_event(&mut self) -> WindowEvent {
+        for popup in self.popups.iter_mut().rev() {
+            let event = popup.query_event();
+            if event != WindowEvent::None {
+                return event;
+            }
+        }
+        WindowEvent::None
+    }
+
+    /// Show the pointer with this style over the windows of the popups too.
+    pub(crate) fn set_cursor(&mut self, cursor: Cursor) {
+        for surface in self.popups.iter_mut().filter_map(|p| p.surface.as_mut()) {
+            surface.set_cursor(cursor);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Content of a fixed size
+    struct Block(isize, isize);
+
+    impl Widget<()> for Block {
+        fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+            AreaBox::with_size(self.0.min(max_box.width()), self.1.min(max_box.height()))
+        }
+    }
+
+    const WINDOW: (f32, f32, f32, f32) = (0., 0., 400., 300.);
+    /// The window on a larger screen
+    const AREA: PopupArea = PopupArea {
+        window: WINDOW,
+        screen: (-100., -100., 800., 600.),
+    };
+
+    fn open(id: PopupId, anchor: (f32, f32), light_dismiss: bool) -> OpenPopup<()> {
+        let popup = Popup::new(Box::new(Block(100, 50)))
+            .with_anchor(anchor.0, anchor.1, 80., 20.)
+            .with_light_dismiss(light_dismiss);
+        OpenPopup::new(id, popup, None)
+    }
+
+    #[test]
+    fn placed_next_to_the_anchor() {
+        let anchor = (50., 100., 80., 20.);
+        let size = (100., 50.);
+        assert_eq!(
+            place(anchor, size, Placement::Bottom, 4., WINDOW),
+            (50., 124.)
+        );
+        assert_eq!(place(anchor, size, Placement::Top, 4., WINDOW), (50., 46.));
+        assert_eq!(
+            place(anchor, size, Placement::Right, 4., WINDOW),
+            (134., 100.)
+        );
+        // No room on the left
+        assert_eq!(
+            place(anchor, size, Placement::Left, 4., WINDOW),
+            (134., 100.)
+        );
+        let anchor = (150., 100., 80., 20.);
+        assert_eq!(
+            place(anchor, size, Placement::Left, 4., WINDOW),
+            (46., 100.)
+        );
+    }
+
+    #[test]
+    fn flips_and_shifts_into_the_window() {
+        let size = (100., 50.);
+        // No room below
+        let anchor = (350., 260., 40., 20.);
+        assert_eq!(
+            place(anchor, size, Placement::Bottom, 4., WINDOW),
+            (300., 206.)
+        );
+        // No room on either side, it's moved inside
+        let anchor = (10., 10., 20., 280.);
+        assert_eq!(
+            place(anchor, size, Placement::Bottom, 4., WINDOW),
+            (10., 250.)
+        );
+    }
+
+    #[test]
+    fn anchors_are_relative_to_the_opener() {
+        let mut popup = Popup::<()>::new(Box::new(Block(10, 10)));
+        popup.resolve_anchor((20., 30.), (40., 10.));
+        assert_eq!(popup.anchor, Some((20., 30., 40., 10.)));
+        let mut popup = Popup::<()>::new(Box::new(Block(10, 10))).with_anchor(5., 5., 0., 0.);
+        popup.resolve_anchor((20., 30.), (40., 10.));
+        assert_eq!(popup.anchor, Some((25., 35., 0., 0.)));
+    }
+
+    #[test]
+    fn clicks_outside_close_the_light_dismissed_popups() {
+        let mut layer = PopupLayer::new();
+        layer.open(open(1, (0., 0.), false), AREA);
+        layer.open(open(2, (0., 100.), true), AREA);
+        layer.open(open(3, (200., 100.), true), AREA);
+        assert_eq!(layer.hit(10., 130.), Some(1));
+        assert_eq!(layer.top(), Some(2));
+        // Inside the second popup, only the one above closes
+        assert_eq!(layer.dismissed_by_click(10., 130.), [3]);
+        // Outside all, stops at the first one which is not light dismissed
+        assert_eq!(layer.dismissed_by_click(390., 290.), [3, 2]);
+        let closed: Vec<_> = layer.close(2).iter().map(|p| p.id).collect();
+        assert_eq!(closed, [3, 2]);
+        assert_eq!(layer.top(), Some(0));
+    }
+
+    #[test]
+    fn stays_in_the_work_area_outside_the_window() {
+        // The window is partly off the screen on the left
+        let area = (-50., 0., 400., 300.);
+        let anchor = (-40., 100., 20., 20.);
+        assert_eq!(
+            place(anchor, (100., 50.), Placement::Bottom, 4., area),
+            (-40., 124.)
+        );
+        let anchor = (-80., 100., 20., 20.);
+        assert_eq!(
+            place(anchor, (100., 50.), Placement::Bottom, 4., area),
+            (-50., 124.)
+        );
+    }
+
+    #[test]
+    fn tooltips_are_skipped_by_the_pointer_and_the_keyboard() {
+        let mut layer = PopupLayer::new();
+        layer.open(open(1, (0., 0.), true), AREA);
+        let tip = Popup::new(Box::new(Block(100, 50)))
+            .with_anchor(0., 100., 80., 20.)
+            .with_interactive(false);
+        layer.open(OpenPopup::new(2, tip, None), AREA);
+        assert_eq!(layer.hit(10., 130.), None);
+        assert_eq!(layer.top(), Some(0));
+        assert_eq!(layer.dismissed_by_click(390., 290.), [1]);
+    }
+
+    #[test]
+    fn popups_under_a_modal_one_stay_open() {
+        let mut layer = PopupLayer::new();
+        layer.open(open(1, (0., 0.), true), AREA);
+        let dialog = Popup::new(Box::new(Block(100, 50))).with_modal(true);
+        layer.open(OpenPopup::new(2, dialog, None), AREA);
+        layer.open(open(3, (200., 100.), true), AREA);
+        assert_eq!(layer.modal(), Some(1));
+        assert_eq!(layer.dismissed_by_click(390., 290.), [3]);
+    }
+
+    #[test]
+    fn popups_in_their_own_window_go_past_the_window() {
+        let popup = Popup::new(Box::new(Block(100, 50)))
+            .with_anchor(0., 280., 80., 20.)
+            .with_window(true);
+        let mut popup = OpenPopup::new(1, popup, None);
+        popup.layout(AREA);
+        assert_eq!(popup.popup.content.origin(), (0., 304.));
+        // Modal popups stay in the window
+        let dialog = Popup::new(Box::new(Block(100, 50)))
+            .with_modal(true)
+            .with_window(true);
+        let mut dialog = OpenPopup::new(2, dialog, None);
+        dialog.layout(AREA);
+        assert_eq!(dialog.popup.content.origin(), (150., 125.));
+    }
+}
@@ -1,2 +1,3 @@
 pub use crate::core::*;
 pub use crate::widgets;
+pub use crate::widgets::tooltip::WidgetExt;
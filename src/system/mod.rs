@@ -17,14 +17,39 @@ pub mod enums {
         MinButton,
         Close,
     }
+
+    /// 鼠标指针的样式
+    #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+    pub enum Cursor {
+        #[default]
+        Arrow,
+        Hand,
+        IBeam,
+        Wait,
+        Crosshair,
+        NotAllowed,
+        /// 左右调整大小
+        ResizeWestEast,
+        /// 上下调整大小
+        ResizeNorthSouth,
+        /// 左上到右下调整大小
+        ResizeNwSe,
+        /// 右上到左下调整大小
+        ResizeNeSw,
+        Move,
+    }
 }
 
 /// 不同系统模块都需要实现的 trait
 pub mod traits {
 
+    use std::time::Duration;
+
     use tiny_skia::PixmapMut;
 
-    use super::enums::HitResult;
+    use crate::events::WindowEvent;
+
+    use super::enums::{Cursor, HitResult};
 
     /// 系统窗口，提供了可绘制的画布以进行绘图
     pub trait SystemDrawableWindow {
@@ -32,6 +57,10 @@ pub mod traits {
         fn raw_handle(&self) -> usize;
         /// 立即改变系统窗口 & 画布的大小
         fn resize(&mut self, width: u32, height: u32);
+        /// 移动窗口并改变其大小，位置相对屏幕
+        fn set_bounds(&mut self, x: i32, y: i32, width: u32, height: u32);
+        /// 移动弹出窗口并改变其大小，位置相对所有者窗口的客户区，没有所有者时同 `set_bounds`
+        fn set_popup_bounds(&mut self, x: i32, y: i32, width: u32, height: u32);
         /// 获取窗口左上角的横坐标
         fn pos_x(&self) -> i32;
         /// 设置窗口左上角的横坐标
@@ -52,10 +81,10 @@ pub mod traits {
         ///
         /// 第一个参数为是否不阻塞请求消息，否则除非事件队列内仍有事件，该函数将会阻塞直到有事件发生
         fn query_system_event(&mut self, peek: bool);
-        /// 进行一次用户控件事件轮询，返回简化的用户控件事件
+        /// 进行一次用户控件事件轮询，返回简化的用户控件事件，没有事件时返回 `WindowEvent::None`
         ///
         /// 第一个参数为是否不阻塞请求消息，否则除非事件队列内仍有事件，该函数将会阻塞直到有事件发生
-        fn query_event(&mut self, peek: bool);
+        fn query_event(&mut self, peek: bool) -> WindowEvent;
         /// 执行点击测试，确认该位置属于什么控件，如关闭按钮，客户区域等
         fn hit_test(&mut self, x: i32, y: i32) -> HitResult;
         /// 将画板的内容同步到系统窗口上
@@ -66,6 +95,18 @@ pub mod traits {
         fn show(&mut self);
         /// 隐藏窗口
         fn hide(&mut self);
+        /// 捕获或释放鼠标，捕获时即使指针移出窗口也会继续收到鼠标事件
+        fn set_capture(&mut self, capture: bool);
+        /// 设置指针在窗口客户区内的样式
+        fn set_cursor(&mut self, cursor: Cursor);
+        /// 在指定时间后唤醒阻塞中的事件轮询，`None` 则取消唤醒
+        fn set_wakeup(&mut self, delay: Option<Duration>);
+        /// 窗口是否被最小化，此时无需绘制
+        fn is_minimized(&self) -> bool;
+        /// 窗口所在显示器的工作区（不含任务栏），返回相对客户区左上角的位置和大小
+        fn work_area(&self) -> (i32, i32, i32, i32);
+        /// 启用或禁用窗口，禁用的窗口不接收鼠标和键盘输入，用于在模态窗口打开时禁用其所有者
+        fn set_enabled(&mut self, enabled: bool);
     }
 
     pub trait Fabric<'a> {
@@ -0,0 +1,67 @@
+//! 系统剪贴板，只支持纯文本
+
+use std::ptr::{copy_nonoverlapping, null_mut};
+use std::slice::from_raw_parts;
+
+use winapi::um::winbase::{GlobalAlloc, GlobalFree, GlobalLock, GlobalUnlock, GMEM_MOVEABLE};
+use winapi::um::winuser::*;
+
+use crate::traits::w_str;
+
+/// 读取剪贴板中的文本，剪贴板为空或者不是文本时返回 `None`
+pub fn get_clipboard_text() -> Option<String> {
+    unsafe {
+        if OpenClipboard(null_mut()) == 0 {
+            return None;
+        }
+        let handle = GetClipboardData(CF_UNICODETEXT);
+        let ptr = if handle.is_null() {
+            null_mut()
+        } else {
+            GlobalLock(handle) as *const u16
+        };
+        let text = if ptr.is_null() {
+            None
+        } else {
+            let mut len = 0;
+            while *ptr.add(len) != 0 {
+                len += 1;
+            }
+            let text = String::from_utf16_lossy(from_raw_parts(ptr, len));
+            GlobalUnlock(handle);
+            Some(text)
+        };
+        CloseClipboard();
+        text
+    }
+}
+
+/// 将文本写入剪贴板，返回是否成功，换行符会被转换成 CRLF
+pub fn set_clipboard_text(text: &str) -> bool {
+    let wide = w_str(&text.replace("\r\n", "\n").replace('\n', "\r\n"));
+    unsafe {
+        if OpenClipboard(null_mut()) == 0 {
+            return false;
+        }
+        EmptyClipboard();
+        let mem = GlobalAlloc(GMEM_MOVEABLE, wide.len() * 2);
+        let ptr = if mem.is_null() {
+            null_mut()
+        } else {
+            GlobalLock(mem) as *mut u16
+        };
+        let ok = if ptr.is_null() {
+            false
+        } else {
+            copy_nonoverlapping(wide.as_ptr(), ptr, wide.len());
+            GlobalUnlock(mem);
+            !SetClipboardData(CF_UNICODETEXT, mem).is_null()
+        };
+        // The system owns the memory only when it has been set
+        if !ok && !mem.is_null() {
+            GlobalFree(mem);
+        }
+        CloseClipboard();
+        ok
+    }
+}
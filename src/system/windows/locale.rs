@@ -0,0 +1,30 @@
+//! 用户的区域设置
+
+use std::ptr::null;
+
+use winapi::um::winnls::{GetLocaleInfoEx, LCTYPE};
+
+// Not exported by winapi
+const LOCALE_SDECIMAL: LCTYPE = 0x0E;
+const LOCALE_STHOUSAND: LCTYPE = 0x0F;
+
+fn locale_char(kind: LCTYPE, default: char) -> char {
+    let mut buf = [0u16; 8];
+    // A null locale name means the locale of the current user
+    let len = unsafe { GetLocaleInfoEx(null(), kind, buf.as_mut_ptr(), buf.len() as i32) };
+    if len <= 1 {
+        return default;
+    }
+    String::from_utf16_lossy(&buf[..len as usize - 1])
+        .chars()
+        .next()
+        .unwrap_or(default)
+}
+
+/// 用户区域设置中数字的小数点与千位分隔符
+pub fn number_separators() -> (char, char) {
+    (
+        locale_char(LOCALE_SDECIMAL, '.'),
+        locale_char(LOCALE_STHOUSAND, ','),
+    )
+}
@@ -1,4 +1,8 @@
+mod clipboard;
 mod fabric;
+mod locale;
 mod system_window;
 mod window_proc;
+pub use clipboard::*;
+pub use locale::*;
 pub use system_window::*;
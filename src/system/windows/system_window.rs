@@ -1,5 +1,7 @@
+use std::collections::VecDeque;
 use std::mem;
 use std::ptr::null_mut;
+use std::time::Duration;
 
 use std::{ffi::c_void};
 
@@ -9,9 +11,10 @@ use winapi::um::libloaderapi::GetModuleHandleW;
 use winapi::um::wingdi::*;
 use winapi::um::winuser::*;
 
+use crate::events::WindowEvent;
 use crate::system::traits::{Fabric, SystemDrawableWindow};
 use crate::traits::{w_str};
-use crate::{system::enums::HitResult};
+use crate::system::enums::{Cursor, HitResult};
 
 use super::fabric::WindowsFabric;
 use super::window_proc::window_proc;
@@ -26,6 +29,9 @@ unsafe fn set_window_long(window: HWND, data: usize) -> usize {
     SetWindowLongW(window, GWLP_USERDATA, data as _) as _
 }
 
+/// 用于唤醒事件轮询的计时器
+const WAKEUP_TIMER_ID: usize = 1;
+
 pub fn destroy_window(handle: usize) {
     unsafe {
         PostMessageW(handle as _, WM_DESTROY, 0, 0);
@@ -40,14 +46,98 @@ pub struct SystemWindow {
     ppt_src: POINT,
     fabric: WindowsFabric,
     blend_func: BLENDFUNCTION,
+    events: VecDeque<WindowEvent>,
+    cursor: Cursor,
 }
 
 impl SystemWindow {
     /// 创建自带 Fabric 的窗口
     /// 渲染时需要注意：在 Windows 中位图的字节顺序是 BGRA，而 Tiny Skia 使用的是 RGBA
     pub fn new() -> Self {
-        let real_width = 800;
-        let real_height = 600;
+        let mut area: RECT = unsafe { mem::zeroed() };
+        unsafe {
+            SystemParametersInfoW(
+                SPI_GETWORKAREA,
+                0,
+                (&mut area as *mut RECT) as *mut c_void,
+                0,
+            );
+        }
+        let mut r = Self::create(0 as HWND, WS_EX_LAYERED, 800, 600, area);
+        unsafe {
+            set_window_long(r.hwnd, &mut r as *mut Self as usize);
+        }
+        r
+    }
+
+    /// 创建属于 `owner` 窗口的窗口，显示在所有者之上并居中，所有者最小化或关闭时随之隐藏
+    ///
+    /// 放在堆上以便窗口过程在其他窗口轮询消息时也能找到它
+    pub fn new_owned(owner: usize, width: u32, height: u32) -> Box<Self> {
+        let mut area: RECT = unsafe { mem::zeroed() };
+        unsafe {
+            GetWindowRect(owner as HWND, &mut area);
+        }
+        let mut r = Box::new(Self::create(
+            owner as HWND,
+            WS_EX_LAYERED,
+            width as i32,
+            height as i32,
+            area,
+        ));
+        unsafe {
+            set_window_long(r.hwnd, r.as_mut() as *mut Self as usize);
+            SetForegroundWindow(r.hwnd);
+        }
+        r
+    }
+
+    /// 创建用于显示弹出内容的无边框窗口，位置相对 `owner` 的客户区，显示时不会激活，所有者保持键盘焦点
+    ///
+    /// `input` 为假时鼠标会穿过窗口，用于提示等不可交互的内容
+    pub fn new_popup(owner: usize, x: i32, y: i32, width: u32, height: u32, input: bool) -> Box<Self> {
+        let mut origin = POINT { x, y };
+        unsafe {
+            ClientToScreen(owner as HWND, &mut origin);
+        }
+        let area = RECT {
+            left: origin.x,
+            top: origin.y,
+            right: origin.x + width as i32,
+            bottom: origin.y + height as i32,
+        };
+        let mut ex_style = WS_EX_LAYERED | WS_EX_NOACTIVATE | WS_EX_TOOLWINDOW;
+        if !input {
+            ex_style |= WS_EX_TRANSPARENT;
+        }
+        let mut r = Box::new(Self::create(
+            owner as HWND,
+            ex_style,
+            width as i32,
+            height as i32,
+            area,
+        ));
+        unsafe {
+            set_window_long(r.hwnd, r.as_mut() as *mut Self as usize);
+        }
+        r
+    }
+
+    /// 创建窗口并居中放在 `center_on` 内
+    fn create(
+        owner: HWND,
+        ex_style: DWORD,
+        real_width: i32,
+        real_height: i32,
+        center_on: RECT,
+    ) -> Self {
+        // Showing a window at creation activates it, the ones which must not be activated are shown when placed
+        let activate = ex_style & WS_EX_NOACTIVATE == 0;
+        let (style, pos_flags) = if activate {
+            (WS_POPUP | WS_VISIBLE, 0)
+        } else {
+            (WS_POPUP, SWP_NOACTIVATE | SWP_SHOWWINDOW)
+        };
         let class_name = w_str("FabricWindowClass");
         let wc = unsafe {
             let wc = WNDCLASSW {
@@ -67,28 +157,22 @@ impl SystemWindow {
         };
         let hwnd_win = unsafe {
             CreateWindowExW(
-                WS_EX_LAYERED,
+                ex_style,
                 class_name.as_ptr(),
                 class_name.as_ptr(),
-                WS_POPUP | WS_VISIBLE,
+                style,
                 0,
                 0,
                 real_width,
                 real_height,
-                0 as HWND,
+                owner,
                 0 as HMENU,
                 0 as HINSTANCE,
                 null_mut(),
             )
         };
         unsafe {
-            let mut area: RECT = mem::zeroed();
-            SystemParametersInfoW(
-                SPI_GETWORKAREA,
-                0,
-                (&mut area as *mut RECT) as *mut c_void,
-                0,
-            );
+            let area = center_on;
             let pos_x = area.left + (area.right - area.left) / 2;
             let pos_y = area.top + (area.bottom - area.top) / 2;
             SetWindowPos(
@@ -98,7 +182,7 @@ impl SystemWindow {
                 pos_y - real_height / 2,
                 real_width,
                 real_height,
-                0,
+                pos_flags,
             );
         }
         let fabric = WindowsFabric::new(hwnd_win, real_width as u32, real_height as u32);
@@ -111,7 +195,7 @@ impl SystemWindow {
         unsafe {
             GetWindowRect(hwnd_win, &mut pos_rect);
         }
-        let mut r = Self {
+        Self {
             size: SIZE {
                 cx: real_width,
                 cy: real_height,
@@ -127,11 +211,34 @@ impl SystemWindow {
             fabric,
             hwnd: hwnd_win,
             pos_rect,
+            events: VecDeque::new(),
+            cursor: Cursor::Arrow,
+        }
+    }
+
+    /// 将窗口过程中收到的消息转换为统一的窗口事件后放入队列
+    pub(super) fn push_event(&mut self, event: WindowEvent) {
+        self.events.push_back(event);
+    }
+
+    /// 使用当前记录的样式设置系统指针，收到 `WM_SETCURSOR` 时也会调用
+    pub(super) fn apply_cursor(&self) {
+        let id = match self.cursor {
+            Cursor::Arrow => IDC_ARROW,
+            Cursor::Hand => IDC_HAND,
+            Cursor::IBeam => IDC_IBEAM,
+            Cursor::Wait => IDC_WAIT,
+            Cursor::Crosshair => IDC_CROSS,
+            Cursor::NotAllowed => IDC_NO,
+            Cursor::ResizeWestEast => IDC_SIZEWE,
+            Cursor::ResizeNorthSouth => IDC_SIZENS,
+            Cursor::ResizeNwSe => IDC_SIZENWSE,
+            Cursor::ResizeNeSw => IDC_SIZENESW,
+            Cursor::Move => IDC_SIZEALL,
         };
         unsafe {
-            set_window_long(hwnd_win, &mut r as *mut Self as usize);
+            SetCursor(LoadCursorW(null_mut(), id));
         }
-        r
     }
 }
 
@@ -144,6 +251,38 @@ impl SystemDrawableWindow for SystemWindow {
         self.fabric.resize(width, height);
     }
 
+    fn set_bounds(&mut self, x: i32, y: i32, width: u32, height: u32) {
+        unsafe {
+            SetWindowPos(
+                self.hwnd,
+                null_mut(),
+                x,
+                y,
+                width as i32,
+                height as i32,
+                SWP_NOZORDER | SWP_NOACTIVATE,
+            );
+            GetWindowRect(self.hwnd, &mut self.pos_rect);
+        }
+        // `WM_SIZE` resizes the pixmap when it arrives while moving, this makes sure it's done before drawing
+        if self.size_x() != width || self.size_y() != height {
+            self.set_size_x(width);
+            self.set_size_y(height);
+            self.resize(width, height);
+        }
+    }
+
+    fn set_popup_bounds(&mut self, x: i32, y: i32, width: u32, height: u32) {
+        let mut origin = POINT { x, y };
+        unsafe {
+            let owner = GetWindow(self.hwnd, GW_OWNER);
+            if !owner.is_null() {
+                ClientToScreen(owner, &mut origin);
+            }
+        }
+        self.set_bounds(origin.x, origin.y, width, height);
+    }
+
     fn pos_x(&self) -> i32 {
         self.pos_rect.left
     }
@@ -176,8 +315,12 @@ impl SystemDrawableWindow for SystemWindow {
         self.size.cy = h as i32;
     }
 
-    fn query_event(&mut self, peek: bool) {
-        let _evt = self.query_system_event(peek);
+    fn query_event(&mut self, peek: bool) -> WindowEvent {
+        if let Some(evt) = self.events.pop_front() {
+            return evt;
+        }
+        self.query_system_event(peek);
+        self.events.pop_front().unwrap_or(WindowEvent::None)
     }
 
     fn query_system_event(&mut self, peek: bool) {
@@ -224,6 +367,66 @@ impl SystemDrawableWindow for SystemWindow {
         }
     }
 
+    fn set_capture(&mut self, capture: bool) {
+        unsafe {
+            if capture {
+                SetCapture(self.hwnd);
+            } else {
+                ReleaseCapture();
+            }
+        }
+    }
+
+    fn set_cursor(&mut self, cursor: Cursor) {
+        if self.cursor != cursor {
+            self.cursor = cursor;
+            self.apply_cursor();
+        }
+    }
+
+    fn set_wakeup(&mut self, delay: Option<Duration>) {
+        unsafe {
+            match delay {
+                Some(delay) => {
+                    let ms = delay.as_millis().clamp(1, u32::MAX as u128) as u32;
+                    SetTimer(self.hwnd, WAKEUP_TIMER_ID, ms, None);
+                }
+                None => {
+                    KillTimer(self.hwnd, WAKEUP_TIMER_ID);
+                }
+            }
+        }
+    }
+
+    fn is_minimized(&self) -> bool {
+        unsafe { IsIconic(self.hwnd) != 0 }
+    }
+
+    fn work_area(&self) -> (i32, i32, i32, i32) {
+        unsafe {
+            let monitor = MonitorFromWindow(self.hwnd, MONITOR_DEFAULTTONEAREST);
+            let mut info: MONITORINFO = mem::zeroed();
+            info.cbSize = mem::size_of::<MONITORINFO>() as u32;
+            let mut origin = POINT { x: 0, y: 0 };
+            if GetMonitorInfoW(monitor, &mut info) == 0 || ClientToScreen(self.hwnd, &mut origin) == 0 {
+                return (0, 0, self.size_x() as i32, self.size_y() as i32);
+            }
+            let work = info.rcWork;
+            (
+                work.left - origin.x,
+                work.top - origin.y,
+                work.right - work.left,
+                work.bottom - work.top,
+            )
+        }
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        unsafe {
+            EnableWindow(self.hwnd, enabled as BOOL);
+        }
+    }
+
     fn raw_handle(&self) -> usize {
         self.hwnd as usize
     }
@@ -236,6 +439,9 @@ impl SystemDrawableWindow for SystemWindow {
 impl Drop for SystemWindow {
     fn drop(&mut self) {
         unsafe {
+            // The window may outlive the struct, its messages must not reach the freed memory
+            set_window_long(self.hwnd, 0);
+            DestroyWindow(self.hwnd);
             UnregisterClassW(self.wc.lpszClassName, self.wc.hInstance);
         }
     }
@@ -1,10 +1,10 @@
 use std::mem;
 
-use winapi::shared::minwindef::{LPARAM, LRESULT, UINT, WPARAM};
+use winapi::shared::minwindef::{LPARAM, LRESULT, TRUE, UINT, WPARAM};
 use winapi::shared::windef::HWND;
 use winapi::um::winuser::*;
 
-
+use crate::events::WindowEvent;
 
 use super::super::traits::SystemDrawableWindow;
 use super::SystemWindow;
@@ -33,36 +33,92 @@ pub unsafe extern "system" fn window_proc(
         let win: &mut SystemWindow = mem::transmute(win);
         match msg {
             WM_CHAR => match std::char::from_u32(w_param as u32) {
-                Some(_c) => {
+                Some(c) => {
+                    win.push_event(WindowEvent::CharInput(0, c));
                     return 0;
                 }
                 None => {}
             },
+            // The application decides whether to destroy the window, e.g. a dialog answers it as cancelled
+            WM_CLOSE => {
+                win.push_event(WindowEvent::CloseWindow);
+                return 0;
+            }
             WM_DESTROY => {
+                win.push_event(WindowEvent::Quit);
                 DestroyWindow(h_wnd);
                 return 0;
             }
             WM_MOUSEMOVE => {
-                let _x = (l_param as u32) & 0xFFFF;
-                let _y = (l_param as u32) >> 16;
+                let x = (l_param & 0xFFFF) as i16 as i32;
+                let y = ((l_param >> 16) & 0xFFFF) as i16 as i32;
+                // Ask for `WM_MOUSELEAVE`, the request ends with each leave
+                let mut track = TRACKMOUSEEVENT {
+                    cbSize: mem::size_of::<TRACKMOUSEEVENT>() as u32,
+                    dwFlags: TME_LEAVE,
+                    hwndTrack: h_wnd,
+                    dwHoverTime: 0,
+                };
+                TrackMouseEvent(&mut track);
+                win.push_event(WindowEvent::MouseMove(x, y));
+                return 0;
+            }
+            WM_MOUSELEAVE => {
+                win.push_event(WindowEvent::MouseLeave);
                 return 0;
             }
             WM_LBUTTONDOWN => {
-                let _x = (l_param as u32) & 0xFFFF;
-                let _y = (l_param as u32) >> 16;
+                let x = (l_param & 0xFFFF) as i16 as i32;
+                let y = ((l_param >> 16) & 0xFFFF) as i16 as i32;
+                win.push_event(WindowEvent::MouseDown(x, y));
                 return 0;
             }
             WM_LBUTTONUP => {
-                let _x = (l_param as u32) & 0xFFFF;
-                let _y = (l_param as u32) >> 16;
+                let x = (l_param & 0xFFFF) as i16 as i32;
+                let y = ((l_param >> 16) & 0xFFFF) as i16 as i32;
+                win.push_event(WindowEvent::MouseUp(x, y));
+                return 0;
+            }
+            WM_RBUTTONUP => {
+                let x = (l_param & 0xFFFF) as i16 as i32;
+                let y = ((l_param >> 16) & 0xFFFF) as i16 as i32;
+                win.push_event(WindowEvent::ContextMenu(x, y));
+                return 0;
+            }
+            WM_MOUSEWHEEL => {
+                let delta = (w_param >> 16) as i16;
+                win.push_event(WindowEvent::MouseWheel(delta as i32));
+                return 0;
+            }
+            WM_MOUSEHWHEEL => {
+                let delta = (w_param >> 16) as i16;
+                win.push_event(WindowEvent::MouseHWheel(delta as i32));
                 return 0;
             }
             WM_KEYDOWN => {
+                win.push_event(WindowEvent::KeyDown(w_param as usize));
                 return 0;
             }
             WM_KEYUP => {
+                win.push_event(WindowEvent::KeyUp(w_param as usize));
+                return 0;
+            }
+            // Alt, F10 and the keys pressed with Alt, used by menu mnemonics
+            WM_SYSKEYDOWN => {
+                win.push_event(WindowEvent::KeyDown(w_param as usize));
+                // Alt+F4 still closes the window
+                if w_param as i32 != VK_F4 {
+                    return 0;
+                }
+            }
+            WM_SYSKEYUP => {
+                win.push_event(WindowEvent::KeyUp(w_param as usize));
                 return 0;
             }
+            // Handled from the key events, the default procedure beeps for unknown mnemonics
+            WM_SYSCHAR => return 0,
+            WM_KILLFOCUS => win.push_event(WindowEvent::FocusLost),
+            WM_DWMCOLORIZATIONCOLORCHANGED => win.push_event(WindowEvent::ThemeChanged),
             WM_MOVE => {
                 let x = ((l_param as u32) & 0xFFFF) as i32;
                 let y = ((l_param as u32) >> 16) as i32;
@@ -70,8 +126,29 @@ pub unsafe extern "system" fn window_proc(
                 win.set_pos_y(y);
             }
             WM_SIZE => {
-                let _w = (l_param as u32) & 0xFFFF;
-                let _h = (l_param as u32) >> 16;
+                let w = (l_param as u32) & 0xFFFF;
+                let h = (l_param as u32) >> 16;
+                // Minimizing reports an empty size, the pixmap keeps the last one
+                if w_param != SIZE_MINIMIZED && w > 0 && h > 0 {
+                    win.set_size_x(w);
+                    win.set_size_y(h);
+                    win.resize(w, h);
+                }
+                win.push_event(WindowEvent::WindowResize(w, h));
+            }
+            // Clicking a popup window leaves the focus in its owner
+            WM_MOUSEACTIVATE => {
+                if GetWindowLongW(h_wnd, GWL_EXSTYLE) as u32 & WS_EX_NOACTIVATE != 0 {
+                    return MA_NOACTIVATE as LRESULT;
+                }
+            }
+            // Only wakes the event loop up, the application checks its timers
+            WM_TIMER => return 0,
+            WM_SETCURSOR => {
+                if (l_param & 0xFFFF) as isize == HTCLIENT {
+                    win.apply_cursor();
+                    return TRUE as LRESULT;
+                }
             }
             WM_NCLBUTTONDOWN => match w_param as isize {
                 HTCLOSE => {
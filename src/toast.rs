@@ -0,0 +1,640 @@
+//! 通知：控件通过 `EventCtx::show_toast` 在窗口角落显示短暂的消息，可以带一个操作按钮
+//!
+//! 通知从窗口边缘滑入并叠放在同一个角落，超时或关闭后滑出；同时显示的数量有限，多出的排队等待。
+//! 鼠标停在通知上时暂停计时
+
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use tiny_skia::Stroke;
+
+use crate::events::{TimerToken, ToastId, WindowEvent};
+use crate::system::enums::Cursor;
+use crate::utils::drawing::{gen_paint, gen_round_rect_path};
+use crate::utils::spring::Spring2D;
+use crate::utils::text::{line_metrics, measure_text, wrap_text, FontType, TextLine};
+use crate::utils::theme::get_theme_color;
+use crate::{DrawCtx, EventCtx};
+
+const WIDTH: f32 = 320.;
+const MARGIN: f32 = 16.;
+/// Space between stacked toasts
+const GAP: f32 = 8.;
+const PADDING: f32 = 12.;
+const RADIUS: f32 = 6.;
+/// Width of the bar in the severity colour
+const ACCENT_WIDTH: f32 = 4.;
+const TEXT_SIZE: f32 = 14.;
+const TEXT_COLOR: u32 = 0x202020FF;
+const BACKGROUND_COLOR: u32 = 0xFFFFFFFF;
+const BORDER_COLOR: u32 = 0x00000030;
+const SHADOW_LAYERS: [(f32, u32); 3] = [(6., 0x00000008), (4., 0x00000010), (2., 0x00000018)];
+const CLOSE_SIZE: f32 = 16.;
+const CLOSE_COLOR: u32 = 0x00000080;
+const HOVER_COLOR: u32 = 0x0000000F;
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(4);
+/// More toasts wait in the queue
+const MAX_VISIBLE: usize = 3;
+
+/// How important a toast is, shown by its colour.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn color(self) -> u32 {
+        match self {
+            Severity::Info => get_theme_color() | 0xFF,
+            Severity::Success => 0x107C10FF,
+            Severity::Warning => 0xF7A600FF,
+            Severity::Error => 0xD13438FF,
+        }
+    }
+}
+
+/// Corner of the window where toasts stack.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToastCorner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl ToastCorner {
+    #[inline]
+    fn is_left(self) -> bool {
+        matches!(self, ToastCorner::TopLeft | ToastCorner::BottomLeft)
+    }
+
+    #[inline]
+    fn is_top(self) -> bool {
+        matches!(self, ToastCorner::TopLeft | ToastCorner::TopRight)
+    }
+}
+
+type ToastAction<D> = Rc<dyn Fn(&mut D)>;
+
+/// A short message shown with `EventCtx::show_toast`.
+pub struct Toast<D> {
+    text: String,
+    severity: Severity,
+    action: Option<(String, ToastAction<D>)>,
+    timeout: Option<Duration>,
+    corner: ToastCorner,
+}
+
+impl<D> Toast<D> {
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.into(),
+            severity: Severity::Info,
+            action: None,
+            timeout: Some(DEFAULT_TIMEOUT),
+            corner: ToastCorner::BottomRight,
+        }
+    }
+
+    pub fn with_severity(mut self, v: Severity) -> Self {
+        self.severity = v;
+        self
+    }
+
+    /// Show a button running `action`, choosing it closes the toast.
+    pub fn with_action<F: Fn(&mut D) + 'static>(mut self, label: &str, action: F) -> Self {
+        self.action = Some((label.into(), Rc::new(action)));
+        self
+    }
+
+    /// Close the toast after `v`, 4 seconds by default. `None` keeps it until it's closed.
+    pub fn with_timeout(mut self, v: Option<Duration>) -> Self {
+        self.timeout = v;
+        self
+    }
+
+    /// Corner of the window where the toast goes, the bottom right one by default.
+    pub fn with_corner(mut self, v: ToastCorner) -> Self {
+        self.corner = v;
+        self
+    }
+}
+
+/// Part of a toast under the pointer
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Part {
+    Body,
+    Action,
+    Close,
+}
+
+struct ShownToast<D> {
+    id: ToastId,
+    toast: Toast<D>,
+    lines: Vec<TextLine>,
+    size: (f32, f32),
+    /// Top left corner in the window
+    pos: Spring2D,
+    /// Time left before closing, counted down while not hovered
+    remaining: Option<Duration>,
+    /// When the countdown resumed, `None` while paused
+    since: Option<Instant>,
+    timer: Option<TimerToken>,
+    hovering: Option<Part>,
+    pressed: Option<Part>,
+}
+
+impl<D> ShownToast<D> {
+    fn new(id: ToastId, toast: Toast<D>) -> Self {
+        let mut pos = Spring2D::new((0., 0.));
+        pos.set_speed(16.);
+        pos.set_damper(0.85);
+        Self {
+            id,
+            remaining: toast.timeout,
+            toast,
+            lines: Vec::new(),
+            size: (0., 0.),
+            pos,
+            since: None,
+            timer: None,
+            hovering: None,
+            pressed: None,
+        }
+    }
+
+    fn layout(&mut self, width: f32) {
+        let text_width = width - PADDING * 2. - ACCENT_WIDTH - CLOSE_SIZE - PADDING;
+        self.lines = wrap_text(&self.toast.text, TEXT_SIZE, FontType::Normal, text_width);
+        let line_height = line_metrics(TEXT_SIZE, FontType::Normal).height;
+        let mut height = self.lines.len().max(1) as f32 * line_height;
+        if self.toast.action.is_some() {
+            height += GAP + line_height + PADDING / 2.;
+        }
+        self.size = (width, (height + PADDING * 2.).ceil());
+    }
+
+    /// Rectangle of the action button relative to the toast.
+    fn action_rect(&self) -> Option<(f32, f32, f32, f32)> {
+        let (label, _) = self.toast.action.as_ref()?;
+        let line_height = line_metrics(TEXT_SIZE, FontType::Normal).height;
+        let w = measure_text(label, TEXT_SIZE, FontType::Normal).ceil() + PADDING;
+        let h = line_height + PADDING / 2.;
+        Some((
+            self.size.0 - PADDING / 2. - w,
+            self.size.1 - PADDING / 2. - h,
+            w,
+            h,
+        ))
+    }
+
+    fn close_rect(&self) -> (f32, f32, f32, f32) {
+        (
+            self.size.0 - PADDING / 2. - CLOSE_SIZE,
+            PADDING / 2.,
+            CLOSE_SIZE,
+            CLOSE_SIZE,
+        )
+    }
+
+    fn part_at(&mut self, x: f32, y: f32) -> Option<Part> {
+        let (left, top) = self.pos.position();
+        let (x, y) = (x - left, y - top);
+        let inside = |(rx, ry, rw, rh): (f32, f32, f32, f32)| {
+            x >= rx && y >= ry && x < rx + rw && y < ry + rh
+        };
+        if !inside((0., 0., self.size.0, self.size.1)) {
+            None
+        } else if inside(self.close_rect()) {
+            Some(Part::Close)
+        } else if self.action_rect().is_some_and(inside) {
+            Some(Part::Action)
+        } else {
+            Some(Part::Body)
+        }
+    }
+
+    /// Start or resume counting down.
+    fn resume(&mut self, ctx: &mut EventCtx) {
+        if let (Some(remaining), None) = (self.remaining, self.since) {
+            self.since = Some(Instant::now());
+            self.timer = Some(ctx.request_timer(remaining));
+        }
+    }
+
+    fn pause(&mut self) {
+        if let (Some(remaining), Some(since)) = (self.remaining, self.since.take()) {
+            self.remaining = Some(remaining.saturating_sub(since.elapsed()));
+            self.timer = None;
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx) {
+        if !self.pos.arrived() {
+            ctx.request_anim_frame();
+        }
+        let (x, y) = self.pos.position();
+        let (w, h) = self.size;
+        ctx.with_save(|ctx| {
+            ctx.translate(x.round(), y.round());
+            for &(spread, color) in SHADOW_LAYERS.iter() {
+                let path = gen_round_rect_path(
+                    -spread,
+                    -spread + 2.,
+                    w + spread * 2.,
+                    h + spread * 2.,
+                    RADIUS + spread,
+                );
+                ctx.fill_path(&path, &gen_paint(color));
+            }
+            let path = gen_round_rect_path(0.5, 0.5, w - 1., h - 1., RADIUS);
+            ctx.fill_path(&path, &gen_paint(BACKGROUND_COLOR));
+            ctx.with_save(|ctx| {
+                ctx.clip_rect(0., 0., ACCENT_WIDTH, h);
+                ctx.fill_path(&path, &gen_paint(self.toast.severity.color()));
+            });
+            ctx.stroke_path(&path, &gen_paint(BORDER_COLOR), &Stroke::default());
+
+            let line_height = line_metrics(TEXT_SIZE, FontType::Normal).height;
+            let text_x = ACCENT_WIDTH + PADDING;
+            for (i, line) in self.lines.iter().enumerate() {
+                let line_y = PADDING + i as f32 * line_height;
+                ctx.draw_text(text_x, line_y, &line.text, TEXT_SIZE, TEXT_COLOR);
+            }
+
+            let (cx, cy, cw, ch) = self.close_rect();
+            if self.hovering == Some(Part::Close) {
+                let path = gen_round_rect_path(cx, cy, cw, ch, 3.);
+                ctx.fill_path(&path, &gen_paint(HOVER_COLOR));
+            }
+            let mut cross = tiny_skia::PathBuilder::new();
+            cross.move_to(cx + 4.5, cy + 4.5);
+            cross.line_to(cx + cw - 4.5, cy + ch - 4.5);
+            cross.move_to(cx + cw - 4.5, cy + 4.5);
+            cross.line_to(cx + 4.5, cy + ch - 4.5);
+            if let Some(cross) = cross.finish() {
+                let stroke = Stroke {
+                    width: 1.5,
+                    ..Default::default()
+                };
+                ctx.stroke_path(&cross, &gen_paint(CLOSE_COLOR), &stroke);
+            }
+
+            if let (Some((ax, ay, aw, ah)), Some((label, _))) =
+                (self.action_rect(), &self.toast.action)
+            {
+                if self.hovering == Some(Part::Action) {
+                    let path = gen_round_rect_path(ax, ay, aw, ah, 3.);
+                    ctx.fill_path(&path, &gen_paint(HOVER_COLOR));
+                }
+                let color = self.toast.severity.color();
+                ctx.draw_text_custom(
+                    ax + PADDING / 2.,
+                    ay + PADDING / 4.,
+                    label,
+                    TEXT_SIZE,
+                    color,
+                    FontType::Bold,
+                );
+            }
+        });
+    }
+}
+
+/// The toasts of a window, shown ones stack in their corner and the others wait in the queue.
+pub(crate) struct ToastLayer<D> {
+    shown: Vec<ShownToast<D>>,
+    /// Sliding out, dropped once out of the window
+    leaving: Vec<ShownToast<D>>,
+    queue: VecDeque<(ToastId, Toast<D>)>,
+    /// Left, top, right and bottom of the window part toasts go in
+    area: (f32, f32, f32, f32),
+}
+
+impl<D> ToastLayer<D> {
+    pub(crate) fn new() -> Self {
+        Self {
+            shown: Vec::new(),
+            leaving: Vec::new(),
+            queue: VecDeque::new(),
+            area: (0., 0., 0., 0.),
+        }
+    }
+
+    /// Position outside of the window on the side of the corner, where toasts slide from.
+    fn outside_x(&self, corner: ToastCorner, width: f32) -> f32 {
+        if corner.is_left() {
+            self.area.0 - width - MARGIN
+        } else {
+            self.area.2 + MARGIN
+        }
+    }
+
+    /// Move the shown toasts to their places in the stacks.
+    fn arrange(&mut self) {
+        let (left, top, right, bottom) = self.area;
+        let width = WIDTH.min(right - left - MARGIN * 2.).max(1.);
+        for corner in [
+            ToastCorner::TopLeft,
+            ToastCorner::TopRight,
+            ToastCorner::BottomLeft,
+            ToastCorner::BottomRight,
+        ] {
+            let mut offset = MARGIN;
+            for toast in self.shown.iter_mut().filter(|t| t.toast.corner == corner) {
+                if toast.size.0 != width {
+                    toast.layout(width);
+                }
+                let x = if corner.is_left() {
+                    left + MARGIN
+                } else {
+                    right - MARGIN - width
+                };
+                let y = if corner.is_top() {
+                    top + offset
+                } else {
+                    bottom - offset - toast.size.1
+                };
+                offset += toast.size.1 + GAP;
+                toast.pos.set_target((x, y));
+            }
+        }
+    }
+
+    pub(crate) fn layout(&mut self, area: (f32, f32, f32, f32)) {
+        self.area = area;
+        self.arrange();
+    }
+
+    pub(crate) fn show(&mut self, id: ToastId, toast: Toast<D>, ctx: &mut EventCtx) {
+        self.queue.push_back((id, toast));
+        self.promote(ctx);
+    }
+
+    /// Close a toast, shown or waiting.
+    pub(crate) fn dismiss(&mut self, id: ToastId, ctx: &mut EventCtx) {
+        self.queue.retain(|(queued, _)| *queued != id);
+        if let Some(index) = self.shown.iter().position(|t| t.id == id) {
+            let mut toast = self.shown.remove(index);
+            let (_, y) = toast.pos.target();
+            let x = self.outside_x(toast.toast.corner, toast.size.0);
+            toast.pos.set_target((x, y));
+            self.leaving.push(toast);
+            self.promote(ctx);
+        }
+        ctx.request_anim_frame();
+    }
+
+    /// Show waiting toasts while there is room.
+    fn promote(&mut self, ctx: &mut EventCtx) {
+        let first_new = self.shown.len();
+        while self.shown.len() < MAX_VISIBLE {
+            let (id, toast) = match self.queue.pop_front() {
+                Some(next) => next,
+                None => break,
+            };
+            let mut shown = ShownToast::new(id, toast);
+            shown.resume(ctx);
+            self.shown.push(shown);
+        }
+        self.arrange();
+        // New toasts slide in from outside, at their places in the stack
+        for index in first_new..self.shown.len() {
+            let toast = &self.shown[index];
+            let x = self.outside_x(toast.toast.corner, toast.size.0);
+            let toast = &mut self.shown[index];
+            let target = toast.pos.target();
+            toast.pos.set_position((x, target.1));
+            toast.pos.set_target(target);
+        }
+        ctx.request_anim_frame();
+    }
+
+    /// Handle the pointer and the timers, returns whether the pointer is on a toast.
+    pub(crate) fn event(&mut self, ctx: &mut EventCtx, data: &mut D) -> bool {
+        if self.shown.is_empty() {
+            return false;
+        }
+        let (x, y) = ctx.window_mouse_pos;
+        let mut over = false;
+        let mut dismissed = Vec::new();
+        match ctx.event() {
+            WindowEvent::Timer(token) => {
+                if let Some(toast) = self.shown.iter().find(|t| t.timer == Some(token)) {
+                    dismissed.push(toast.id);
+                }
+            }
+            WindowEvent::MouseMove(_, _) => {
+                for toast in &mut self.shown {
+                    let part = toast.part_at(x, y);
+                    over |= part.is_some();
+                    if part != toast.hovering {
+                        if part.is_some() && toast.hovering.is_none() {
+                            toast.pause();
+                        } else if part.is_none() {
+                            toast.resume(ctx);
+                        }
+                        toast.hovering = part;
+                        ctx.request_paint();
+                    }
+                    if matches!(part, Some(Part::Action) | Some(Part::Close)) {
+                        ctx.set_cursor(Cursor::Hand);
+                    }
+                }
+            }
+            WindowEvent::MouseDown(_, _) => {
+                for toast in &mut self.shown {
+                    toast.pressed = toast.part_at(x, y);
+                    over |= toast.pressed.is_some();
+                }
+            }
+            WindowEvent::MouseUp(_, _) => {
+                for toast in &mut self.shown {
+                    let part = toast.part_at(x, y);
+                    over |= part.is_some();
+                    let pressed = toast.pressed.take();
+                    if pressed != part {
+                        continue;
+                    }
+                    match part {
+                        Some(Part::Close) => dismissed.push(toast.id),
+                        Some(Part::Action) => {
+                            if let Some((_, action)) = &toast.toast.action {
+                                action(data);
+                            }
+                            dismissed.push(toast.id);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            WindowEvent::MousePress(_, _)
+            | WindowEvent::ContextMenu(_, _)
+            | WindowEvent::MouseWheel(_)
+            | WindowEvent::MouseHWheel(_) => {
+                over = self.shown.iter_mut().any(|t| t.part_at(x, y).is_some());
+            }
+            WindowEvent::MouseLeave | WindowEvent::FocusLost => {
+                for toast in &mut self.shown {
+                    if toast.hovering.take().is_some() {
+                        ctx.request_paint();
+                    }
+                    toast.resume(ctx);
+                }
+            }
+            _ => {}
+        }
+        for id in dismissed {
+            self.dismiss(id, ctx);
+        }
+        over
+    }
+
+    pub(crate) fn draw(&mut self, ctx: &mut DrawCtx) {
+        self.leaving = std::mem::take(&mut self.leaving)
+            .into_iter()
+            .filter_map(|mut t| if t.pos.arrived() { None } else { Some(t) })
+            .collect();
+        let area = self.area;
+        ctx.with_save(|ctx| {
+            let (left, top, right, bottom) = area;
+            ctx.clip_rect(left, top, right - left, bottom - top);
+            for toast in self.leaving.iter_mut().chain(self.shown.iter_mut()) {
+                toast.draw(ctx);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Modifiers;
+
+    const WINDOW: (f32, f32, f32, f32) = (0., 0., 800., 600.);
+
+    fn ctx(event: WindowEvent, pos: (f32, f32)) -> EventCtx {
+        EventCtx::new(event, pos, Modifiers::default(), None)
+    }
+
+    fn layer(count: usize) -> ToastLayer<Vec<usize>> {
+        let mut layer = ToastLayer::new();
+        layer.layout(WINDOW);
+        for id in 0..count {
+            layer.show(
+                id,
+                Toast::new("Saved")
+                    .with_action("Undo", move |undone: &mut Vec<usize>| undone.push(id)),
+                &mut ctx(WindowEvent::None, (0., 0.)),
+            );
+        }
+        settle(&mut layer);
+        layer
+    }
+
+    /// Finish sliding the toasts to their places
+    fn settle(layer: &mut ToastLayer<Vec<usize>>) {
+        for toast in &mut layer.shown {
+            let target = toast.pos.target();
+            toast.pos.set_position(target);
+        }
+    }
+
+    fn shown(layer: &ToastLayer<Vec<usize>>) -> Vec<ToastId> {
+        layer.shown.iter().map(|t| t.id).collect()
+    }
+
+    /// A point in a part of the first toast
+    fn point(layer: &ToastLayer<Vec<usize>>, rect: (f32, f32, f32, f32)) -> (f32, f32) {
+        let (left, top) = layer.shown[0].pos.target();
+        (left + rect.0 + rect.2 / 2., top + rect.1 + rect.3 / 2.)
+    }
+
+    fn click(layer: &mut ToastLayer<Vec<usize>>, pos: (f32, f32), data: &mut Vec<usize>) {
+        for event in [WindowEvent::MouseDown(0, 0), WindowEvent::MouseUp(0, 0)] {
+            assert!(layer.event(&mut ctx(event, pos), data));
+        }
+    }
+
+    #[test]
+    fn extra_toasts_wait_in_the_queue() {
+        let mut layer = layer(5);
+        assert_eq!(shown(&layer), [0, 1, 2]);
+        assert_eq!(layer.queue.len(), 2);
+        layer.dismiss(1, &mut ctx(WindowEvent::None, (0., 0.)));
+        assert_eq!(shown(&layer), [0, 2, 3]);
+        // Waiting ones are dismissed from the queue
+        layer.dismiss(4, &mut ctx(WindowEvent::None, (0., 0.)));
+        assert!(layer.queue.is_empty());
+    }
+
+    #[test]
+    fn toasts_stack_in_their_corner() {
+        let layer = layer(2);
+        let (first, second) = (layer.shown[0].pos.target(), layer.shown[1].pos.target());
+        assert_eq!(first.0, WINDOW.2 - MARGIN - WIDTH);
+        assert_eq!(first.1, WINDOW.3 - MARGIN - layer.shown[0].size.1);
+        assert_eq!(second.1, first.1 - GAP - layer.shown[1].size.1);
+    }
+
+    #[test]
+    fn timeouts_close_the_toasts() {
+        let mut layer = layer(1);
+        let token = layer.shown[0].timer.unwrap();
+        layer.event(
+            &mut ctx(WindowEvent::Timer(token), (0., 0.)),
+            &mut Vec::new(),
+        );
+        assert!(layer.shown.is_empty());
+        assert_eq!(layer.leaving.len(), 1);
+    }
+
+    #[test]
+    fn hovering_pauses_the_timeout() {
+        let mut layer = layer(1);
+        let body = point(&layer, (0., 0., 20., 20.));
+        assert!(layer.event(
+            &mut ctx(WindowEvent::MouseMove(0, 0), body),
+            &mut Vec::new()
+        ));
+        assert_eq!(layer.shown[0].timer, None);
+        let mut moved = ctx(WindowEvent::MouseMove(0, 0), (0., 0.));
+        assert!(!layer.event(&mut moved, &mut Vec::new()));
+        assert_eq!(moved.timers.len(), 1);
+        assert_eq!(layer.shown[0].timer, Some(moved.timers[0].1));
+    }
+
+    #[test]
+    fn leaving_the_window_resumes_the_timeout() {
+        let mut layer = layer(1);
+        let body = point(&layer, (0., 0., 20., 20.));
+        layer.event(
+            &mut ctx(WindowEvent::MouseMove(0, 0), body),
+            &mut Vec::new(),
+        );
+        assert_eq!(layer.shown[0].timer, None);
+        let mut left = ctx(WindowEvent::MouseLeave, body);
+        layer.event(&mut left, &mut Vec::new());
+        assert_eq!(layer.shown[0].hovering, None);
+        assert_eq!(layer.shown[0].timer, Some(left.timers[0].1));
+    }
+
+    #[test]
+    fn action_and_close_buttons() {
+        let mut layer = layer(2);
+        let mut undone = Vec::new();
+        let action = point(&layer, layer.shown[0].action_rect().unwrap());
+        click(&mut layer, action, &mut undone);
+        assert_eq!(undone, [0]);
+        assert_eq!(shown(&layer), [1]);
+        settle(&mut layer);
+        let close = point(&layer, layer.shown[0].close_rect());
+        click(&mut layer, close, &mut undone);
+        assert_eq!(undone, [0]);
+        assert!(layer.shown.is_empty());
+    }
+}
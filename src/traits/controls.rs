@@ -1,11 +1,11 @@
-use crate::{DrawCtx, EventCtx};
+use crate::{DrawCtx, EventCtx, UpdateCtx};
 use crate::r#box::AreaBox;
 use crate::system::enums::HitResult;
 use crate::utils::{WidgetUid};
 
 pub trait Widget<D> {
     fn event(&mut self, _ctx: &mut EventCtx, _data: &mut D) {}
-    fn update(&mut self, _data: &D) {}
+    fn update(&mut self, _ctx: &mut UpdateCtx, _data: &D) {}
     fn draw(&mut self, _ctx: &mut DrawCtx, _data: &D) {}
     fn layout(&mut self, _max_box: AreaBox) -> AreaBox {
         AreaBox::ZERO
@@ -0,0 +1,36 @@
+//! 数据绑定：从应用数据中取出控件需要的那一部分
+
+/// A pair of accessors pointing to a part of the application data.
+pub struct Binding<D, T> {
+    get: Box<dyn Fn(&D) -> &T>,
+    get_mut: Box<dyn Fn(&mut D) -> &mut T>,
+}
+
+impl<D, T> Binding<D, T> {
+    pub fn new(
+        get: impl Fn(&D) -> &T + 'static,
+        get_mut: impl Fn(&mut D) -> &mut T + 'static,
+    ) -> Self {
+        Self {
+            get: Box::new(get),
+            get_mut: Box::new(get_mut),
+        }
+    }
+
+    #[inline]
+    pub fn get<'a>(&self, data: &'a D) -> &'a T {
+        (self.get)(data)
+    }
+
+    #[inline]
+    pub fn get_mut<'a>(&self, data: &'a mut D) -> &'a mut T {
+        (self.get_mut)(data)
+    }
+}
+
+impl<D> Binding<D, D> {
+    /// Bind to the whole data
+    pub fn identity() -> Self {
+        Self::new(|d| d, |d| d)
+    }
+}
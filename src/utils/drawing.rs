@@ -1,6 +1,37 @@
 //! 和绘图有关的常用代码都在这里
 
-use tiny_skia::{Path, PathBuilder};
+use tiny_skia::{Color, Paint, Path, PathBuilder, Pixmap, PremultipliedColorU8};
+
+/// 从 RGBA 颜色生成一个开启抗锯齿的纯色画笔
+pub fn gen_paint(color: u32) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color(Color::from_rgba8(
+        (color >> 24) as u8,
+        (color >> 16) as u8,
+        (color >> 8) as u8,
+        (color & 0xFF) as u8,
+    ));
+    paint.anti_alias = true;
+    paint
+}
+
+/// 将未预乘透明度的 RGBA 像素数据转换成位图，数据长度不符时返回 `None`
+pub fn rgba_to_pixmap(width: u32, height: u32, data: &[u8]) -> Option<Pixmap> {
+    if data.len() != (width * height * 4) as usize {
+        return None;
+    }
+    let mut pixmap = Pixmap::new(width, height)?;
+    for (pixel, rgba) in pixmap.pixels_mut().iter_mut().zip(data.chunks_exact(4)) {
+        let a = rgba[3] as u32;
+        *pixel = PremultipliedColorU8::from_rgba(
+            muldiv255(rgba[0] as u32, a) as u8,
+            muldiv255(rgba[1] as u32, a) as u8,
+            muldiv255(rgba[2] as u32, a) as u8,
+            a as u8,
+        )?;
+    }
+    Some(pixmap)
+}
 
 /// 生成一个给 DrawTarget 绘制的矩形路径
 pub fn gen_rect_path(x: f32, y: f32, width: f32, height: f32) -> Path {
@@ -26,6 +57,33 @@ pub fn gen_round_rect_path(x: f32, y: f32, width: f32, height: f32, radius: f32)
     pt.finish().unwrap()
 }
 
+/// 生成一段圆弧路径，角度为弧度，从 x 轴正方向开始顺时针计算，`sweep` 为负时逆时针
+///
+/// 圆弧被分为不超过四分之一圆的几段，每段用一条三次贝塞尔曲线近似
+pub fn gen_arc_path(cx: f32, cy: f32, radius: f32, start: f32, sweep: f32) -> Option<Path> {
+    let segments = (sweep.abs() / std::f32::consts::FRAC_PI_2).ceil().max(1.);
+    let step = sweep / segments;
+    let k = 4. / 3. * (step / 4.).tan() * radius;
+    let mut pb = PathBuilder::new();
+    let mut angle = start;
+    pb.move_to(cx + radius * angle.cos(), cy + radius * angle.sin());
+    for _ in 0..segments as usize {
+        let next = angle + step;
+        let (sin0, cos0) = angle.sin_cos();
+        let (sin1, cos1) = next.sin_cos();
+        pb.cubic_to(
+            cx + radius * cos0 - k * sin0,
+            cy + radius * sin0 + k * cos0,
+            cx + radius * cos1 + k * sin1,
+            cy + radius * sin1 - k * cos1,
+            cx + radius * cos1,
+            cy + radius * sin1,
+        );
+        angle = next;
+    }
+    pb.finish()
+}
+
 // Calculates floor(a*b/255 + 0.5)
 #[inline]
 pub fn muldiv255(a: u32, b: u32) -> u32 {
@@ -0,0 +1,560 @@
+//! 文本编辑的核心逻辑，光标与选区都以字节为单位，并且总是落在字素簇的边界上
+//!
+//! 输入框和多行文本框共用这一部分，它们只负责排版和绘制
+
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::text::{measure_chars, FontType};
+
+/// Steps kept in the undo history
+const HISTORY_SIZE: usize = 100;
+
+/// Byte offset of the grapheme boundary before `pos`.
+pub fn prev_grapheme(text: &str, pos: usize) -> usize {
+    text[..pos]
+        .grapheme_indices(true)
+        .next_back()
+        .map_or(0, |(i, _)| i)
+}
+
+/// Byte offset of the grapheme boundary after `pos`.
+pub fn next_grapheme(text: &str, pos: usize) -> usize {
+    text[pos..]
+        .graphemes(true)
+        .next()
+        .map_or(pos, |g| pos + g.len())
+}
+
+/// Byte offset of the start of the word before `pos`, spaces and punctuations are skipped.
+pub fn prev_word(text: &str, pos: usize) -> usize {
+    text[..pos]
+        .split_word_bound_indices()
+        .rev()
+        .find(|(_, word)| is_word(word))
+        .map_or(0, |(i, _)| i)
+}
+
+/// Byte offset of the end of the word after `pos`, spaces and punctuations are skipped.
+pub fn next_word(text: &str, pos: usize) -> usize {
+    text[pos..]
+        .split_word_bound_indices()
+        .find(|(_, word)| is_word(word))
+        .map_or(text.len(), |(i, word)| pos + i + word.len())
+}
+
+/// Byte range of the word around `pos`, used by double clicks.
+pub fn word_at(text: &str, pos: usize) -> Range<usize> {
+    for (i, word) in text.split_word_bound_indices() {
+        let end = i + word.len();
+        if pos < end || end == text.len() {
+            return i..end;
+        }
+    }
+    pos..pos
+}
+
+fn is_word(word: &str) -> bool {
+    word.chars().any(char::is_alphanumeric)
+}
+
+/// 测量一行文字中每个字素簇边界的横坐标，返回 `(字节位置, 横坐标)`，包含开头和末尾
+pub fn grapheme_offsets(text: &str, size: f32, font: FontType) -> Vec<(usize, f32)> {
+    let widths = measure_chars(text, size, font);
+    let mut widths = widths.iter();
+    let mut x = 0.;
+    let mut offsets = vec![(0, 0.)];
+    for (i, grapheme) in text.grapheme_indices(true) {
+        x += widths.by_ref().take(grapheme.chars().count()).sum::<f32>();
+        offsets.push((i + grapheme.len(), x));
+    }
+    offsets
+}
+
+/// The boundary nearest to `x` in the result of `grapheme_offsets`.
+pub fn hit_offset(offsets: &[(usize, f32)], x: f32) -> usize {
+    offsets
+        .iter()
+        .min_by(|a, b| (a.1 - x).abs().total_cmp(&(b.1 - x).abs()))
+        .map_or(0, |(i, _)| *i)
+}
+
+/// The horizontal position of the boundary at byte `pos` in the result of `grapheme_offsets`.
+pub fn offset_x(offsets: &[(usize, f32)], pos: usize) -> f32 {
+    offsets
+        .iter()
+        .find(|(i, _)| *i >= pos)
+        .or_else(|| offsets.last())
+        .map_or(0., |(_, x)| *x)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EditKind {
+    None,
+    Typing,
+    Deleting,
+}
+
+#[derive(Debug, Clone)]
+struct Snapshot {
+    text: String,
+    caret: usize,
+    anchor: usize,
+}
+
+/// 可撤销的文本编辑状态
+#[derive(Debug, Clone)]
+pub struct TextEditor {
+    text: String,
+    caret: usize,
+    /// The other end of the selection, equals to `caret` when nothing is selected
+    anchor: usize,
+    /// Limit of the text length in chars
+    max_length: Option<usize>,
+    multiline: bool,
+    undo_stack: Vec<Snapshot>,
+    redo_stack: Vec<Snapshot>,
+    /// Consecutive typing or deleting is undone at once
+    last_edit: EditKind,
+}
+
+impl Default for TextEditor {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            caret: 0,
+            anchor: 0,
+            max_length: None,
+            multiline: false,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            last_edit: EditKind::None,
+        }
+    }
+}
+
+impl TextEditor {
+    pub fn new(multiline: bool) -> Self {
+        Self {
+            multiline,
+            ..Default::default()
+        }
+    }
+
+    #[inline]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replace the whole text, the caret goes to the end and the history is cleared.
+    pub fn set_text(&mut self, text: &str) {
+        self.text = self.filter(text);
+        if let Some(max) = self.max_length {
+            self.text = truncate_chars(&self.text, max).to_string();
+        }
+        self.caret = self.text.len();
+        self.anchor = self.caret;
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.last_edit = EditKind::None;
+    }
+
+    #[inline]
+    pub fn max_length(&self) -> Option<usize> {
+        self.max_length
+    }
+
+    /// Limit the length of the text, texts already longer than it are kept.
+    #[inline]
+    pub fn set_max_length(&mut self, max_length: Option<usize>) {
+        self.max_length = max_length;
+    }
+
+    #[inline]
+    pub fn caret(&self) -> usize {
+        self.caret
+    }
+
+    #[inline]
+    pub fn anchor(&self) -> usize {
+        self.anchor
+    }
+
+    /// The selected byte range, empty when nothing is selected.
+    pub fn selection(&self) -> Range<usize> {
+        self.caret.min(self.anchor)..self.caret.max(self.anchor)
+    }
+
+    #[inline]
+    pub fn has_selection(&self) -> bool {
+        self.caret != self.anchor
+    }
+
+    pub fn selected_text(&self) -> &str {
+        &self.text[self.selection()]
+    }
+
+    /// Move the caret to byte `pos`, keep the anchor to extend the selection.
+    pub fn set_caret(&mut self, pos: usize, extend: bool) {
+        let mut pos = pos.min(self.text.len());
+        while !self.text.is_char_boundary(pos) {
+            pos -= 1;
+        }
+        self.caret = pos;
+        if !extend {
+            self.anchor = pos;
+        }
+        self.last_edit = EditKind::None;
+    }
+
+    /// Replace the whole text as a part of the last edit, used to format what the user typed.
+    pub fn rewrite(&mut self, text: &str, caret: usize) {
+        self.text = text.to_string();
+        let mut caret = caret.min(self.text.len());
+        while !self.text.is_char_boundary(caret) {
+            caret -= 1;
+        }
+        self.caret = caret;
+        self.anchor = caret;
+    }
+
+    /// Select the byte range, the caret is placed at its end.
+    pub fn select(&mut self, range: Range<usize>) {
+        self.set_caret(range.start, false);
+        self.set_caret(range.end, true);
+    }
+
+    pub fn select_all(&mut self) {
+        self.select(0..self.text.len());
+    }
+
+    /// Move one grapheme or one word to the left, or collapse the selection.
+    pub fn move_left(&mut self, word: bool, extend: bool) {
+        let pos = if self.has_selection() && !extend && !word {
+            self.selection().start
+        } else if word {
+            prev_word(&self.text, self.caret)
+        } else {
+            prev_grapheme(&self.text, self.caret)
+        };
+        self.set_caret(pos, extend);
+    }
+
+    /// Move one grapheme or one word to the right, or collapse the selection.
+    pub fn move_right(&mut self, word: bool, extend: bool) {
+        let pos = if self.has_selection() && !extend && !word {
+            self.selection().end
+        } else if word {
+            next_word(&self.text, self.caret)
+        } else {
+            next_grapheme(&self.text, self.caret)
+        };
+        self.set_caret(pos, extend);
+    }
+
+    /// Replace the selection with `text`, returns false if nothing changed.
+    ///
+    /// Line breaks are turned into spaces in a single line editor, and the text is cut to fit the max length.
+    pub fn insert(&mut self, text: &str) -> bool {
+        let text = self.filter(text);
+        let range = self.selection();
+        let text = match self.max_length {
+            Some(max) => {
+                let kept = self.text.chars().count() - self.text[range.clone()].chars().count();
+                truncate_graphemes(&text, max.saturating_sub(kept))
+            }
+            None => &text,
+        };
+        if text.is_empty() && range.is_empty() {
+            return false;
+        }
+        // A whole word typed is undone at once
+        let typing = text.chars().count() == 1 && range.is_empty();
+        let kind = if typing && !text.starts_with(char::is_whitespace) {
+            EditKind::Typing
+        } else {
+            EditKind::None
+        };
+        self.record(kind);
+        self.text.replace_range(range.clone(), text);
+        self.caret = range.start + text.len();
+        self.anchor = self.caret;
+        true
+    }
+
+    /// Delete the selection, or the grapheme or word before the caret.
+    pub fn backspace(&mut self, word: bool) -> bool {
+        if !self.has_selection() {
+            let start = if word {
+                prev_word(&self.text, self.caret)
+            } else {
+                prev_grapheme(&self.text, self.caret)
+            };
+            self.anchor = start;
+        }
+        self.delete_selection()
+    }
+
+    /// Delete the selection, or the grapheme or word after the caret.
+    pub fn delete(&mut self, word: bool) -> bool {
+        if !self.has_selection() {
+            let end = if word {
+                next_word(&self.text, self.caret)
+            } else {
+                next_grapheme(&self.text, self.caret)
+            };
+            self.anchor = end;
+        }
+        self.delete_selection()
+    }
+
+    /// Remove the selected text and return it.
+    pub fn cut(&mut self) -> Option<String> {
+        if !self.has_selection() {
+            return None;
+        }
+        let text = self.selected_text().to_string();
+        self.last_edit = EditKind::None;
+        self.delete_selection();
+        Some(text)
+    }
+
+    fn delete_selection(&mut self) -> bool {
+        let range = self.selection();
+        if range.is_empty() {
+            return false;
+        }
+        let single = self.text[range.clone()].graphemes(true).count() == 1;
+        self.record(if single {
+            EditKind::Deleting
+        } else {
+            EditKind::None
+        });
+        self.text.replace_range(range.clone(), "");
+        self.caret = range.start;
+        self.anchor = range.start;
+        true
+    }
+
+    #[inline]
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    #[inline]
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    pub fn undo(&mut self) -> bool {
+        match self.undo_stack.pop() {
+            Some(snapshot) => {
+                let current = self.restore(snapshot);
+                self.redo_stack.push(current);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        match self.redo_stack.pop() {
+            Some(snapshot) => {
+                let current = self.restore(snapshot);
+                self.undo_stack.push(current);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) -> Snapshot {
+        self.last_edit = EditKind::None;
+        Snapshot {
+            text: std::mem::replace(&mut self.text, snapshot.text),
+            caret: std::mem::replace(&mut self.caret, snapshot.caret),
+            anchor: std::mem::replace(&mut self.anchor, snapshot.anchor),
+        }
+    }
+
+    /// Save the state before an edit, unless it continues the last one.
+    fn record(&mut self, kind: EditKind) {
+        if kind == EditKind::None || kind != self.last_edit {
+            if self.undo_stack.len() >= HISTORY_SIZE {
+                self.undo_stack.remove(0);
+            }
+            self.undo_stack.push(Snapshot {
+                text: self.text.clone(),
+                caret: self.caret,
+                anchor: self.anchor,
+            });
+        }
+        self.redo_stack.clear();
+        self.last_edit = kind;
+    }
+
+    fn filter(&self, text: &str) -> String {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        if self.multiline {
+            text
+        } else {
+            text.replace('\n', " ")
+        }
+    }
+}
+
+fn truncate_chars(text: &str, max: usize) -> &str {
+    match text.char_indices().nth(max) {
+        Some((i, _)) => &text[..i],
+        None => text,
+    }
+}
+
+/// Keep at most `max` chars without splitting a grapheme.
+fn truncate_graphemes(text: &str, max: usize) -> &str {
+    let mut count = 0;
+    let mut end = 0;
+    for grapheme in text.graphemes(true) {
+        count += grapheme.chars().count();
+        if count > max {
+            break;
+        }
+        end += grapheme.len();
+    }
+    &text[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(text: &str) -> TextEditor {
+        let mut editor = TextEditor::new(false);
+        editor.set_text(text);
+        editor
+    }
+
+    fn type_text(editor: &mut TextEditor, text: &str) {
+        for c in text.chars() {
+            editor.insert(&c.to_string());
+        }
+    }
+
+    #[test]
+    fn grapheme_steps_over_clusters() {
+        // e + combining acute, then a family emoji joined by ZWJs
+        let text = "ae\u{301}👨\u{200d}👩\u{200d}👧b";
+        let emoji = 1 + "e\u{301}".len();
+        assert_eq!(next_grapheme(text, 1), emoji);
+        assert_eq!(prev_grapheme(text, emoji), 1);
+        let b = text.len() - 1;
+        assert_eq!(next_grapheme(text, emoji), b);
+        assert_eq!(prev_grapheme(text, b), emoji);
+        assert_eq!(prev_grapheme(text, 0), 0);
+        assert_eq!(next_grapheme(text, text.len()), text.len());
+    }
+
+    #[test]
+    fn word_steps_skip_spaces_and_punctuations() {
+        let text = "hello, world  foo";
+        assert_eq!(next_word(text, 0), 5);
+        assert_eq!(next_word(text, 5), 12);
+        assert_eq!(next_word(text, 12), text.len());
+        assert_eq!(prev_word(text, text.len()), 14);
+        assert_eq!(prev_word(text, 14), 7);
+        assert_eq!(prev_word(text, 7), 0);
+        assert_eq!(word_at(text, 8), 7..12);
+        assert_eq!(word_at(text, text.len()), 14..text.len());
+    }
+
+    #[test]
+    fn moving_collapses_or_extends_the_selection() {
+        let mut editor = editor("one two");
+        editor.select(1..5);
+        editor.move_left(false, false);
+        assert_eq!((editor.caret(), editor.anchor()), (1, 1));
+        editor.move_right(true, true);
+        assert_eq!(editor.selection(), 1..3);
+        editor.move_right(true, true);
+        assert_eq!(editor.selected_text(), "ne two");
+        editor.move_right(false, false);
+        assert!(!editor.has_selection());
+        assert_eq!(editor.caret(), 7);
+    }
+
+    #[test]
+    fn typed_words_are_undone_at_once() {
+        let mut editor = editor("");
+        type_text(&mut editor, "ab cd");
+        assert!(editor.undo());
+        assert_eq!(editor.text(), "ab ");
+        assert!(editor.undo());
+        assert_eq!(editor.text(), "ab");
+        assert!(editor.undo());
+        assert_eq!(editor.text(), "");
+        assert!(!editor.undo());
+        assert!(editor.redo());
+        assert_eq!(editor.text(), "ab");
+        assert_eq!(editor.caret(), 2);
+    }
+
+    #[test]
+    fn moving_the_caret_ends_the_group() {
+        let mut editor = editor("");
+        type_text(&mut editor, "ab");
+        editor.set_caret(0, false);
+        type_text(&mut editor, "c");
+        editor.undo();
+        assert_eq!(editor.text(), "ab");
+    }
+
+    #[test]
+    fn deletions_are_grouped_apart_from_typing() {
+        let mut editor = editor("");
+        type_text(&mut editor, "abc");
+        editor.backspace(false);
+        editor.backspace(false);
+        assert_eq!(editor.text(), "a");
+        editor.undo();
+        assert_eq!(editor.text(), "abc");
+        editor.undo();
+        assert_eq!(editor.text(), "");
+    }
+
+    #[test]
+    fn editing_clears_the_redo_history() {
+        let mut editor = editor("");
+        type_text(&mut editor, "ab");
+        editor.undo();
+        assert!(editor.can_redo());
+        editor.insert("x");
+        assert!(!editor.can_redo());
+    }
+
+    #[test]
+    fn insert_filters_and_truncates() {
+        let mut editor = editor("ab");
+        editor.set_max_length(Some(4));
+        assert!(editor.insert("c\r\nde\u{301}f"));
+        assert_eq!(editor.text(), "abc ");
+        assert!(!editor.insert("g"));
+
+        let mut editor = TextEditor::new(true);
+        editor.insert("a\r\nb");
+        assert_eq!(editor.text(), "a\nb");
+    }
+
+    #[test]
+    fn clicks_hit_the_nearest_boundary() {
+        let offsets = [(0, 0.), (1, 10.), (3, 18.), (4, 30.)];
+        assert_eq!(hit_offset(&offsets, -5.), 0);
+        assert_eq!(hit_offset(&offsets, 13.), 1);
+        assert_eq!(hit_offset(&offsets, 15.), 3);
+        assert_eq!(hit_offset(&offsets, 100.), 4);
+        // A NaN position must not panic
+        assert_eq!(hit_offset(&offsets, f32::NAN), 0);
+        assert_eq!(offset_x(&offsets, 2), 18.);
+        assert_eq!(offset_x(&offsets, 9), 30.);
+    }
+}
@@ -1,8 +1,12 @@
+pub mod binding;
 pub mod blur;
 pub mod color;
 pub mod drawing;
+pub mod editor;
 pub mod math;
 pub mod spring;
+pub mod svg;
+pub mod text;
 pub mod theme;
 pub mod uid;
 
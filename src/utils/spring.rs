@@ -180,23 +180,23 @@ impl Spring2D {
     }
 
     pub fn set_position(&mut self, value: (f32, f32)) {
-        self.sx.position = value.0;
-        self.sy.position = value.1;
+        self.sx.set_position(value.0);
+        self.sy.set_position(value.1);
     }
 
     pub fn set_velocity(&mut self, value: (f32, f32)) {
-        self.sx.velocity = value.0;
-        self.sy.velocity = value.1;
+        self.sx.set_velocity(value.0);
+        self.sy.set_velocity(value.1);
     }
 
     pub fn set_damper(&mut self, value: f32) {
-        self.sx.damper = value;
-        self.sy.damper = value;
+        self.sx.set_damper(value);
+        self.sy.set_damper(value);
     }
 
     pub fn set_speed(&mut self, value: f32) {
-        self.sx.speed = value;
-        self.sy.speed = value;
+        self.sx.set_speed(value);
+        self.sy.set_speed(value);
     }
 
     pub fn set_target(&mut self, value: (f32, f32)) {
@@ -0,0 +1,75 @@
+//! SVG 矢量图的解析与光栅化
+
+use tiny_skia::{Pixmap, PremultipliedColorU8};
+
+use super::color::split_color;
+use super::drawing::muldiv255;
+
+pub use usvg::Error as SvgError;
+pub use usvg::Tree as SvgTree;
+
+/// 解析 SVG 或者 gzip 压缩后的 SVGZ 数据
+pub fn parse_svg(data: &[u8]) -> Result<SvgTree, SvgError> {
+    SvgTree::from_data(data, &usvg::Options::default())
+}
+
+/// 矢量图自身声明的大小
+pub fn svg_size(tree: &SvgTree) -> (f32, f32) {
+    let size = tree.svg_node().size;
+    (size.width() as f32, size.height() as f32)
+}
+
+/// 将矢量图按倍数缩放后渲染成位图，结果为空时返回 `None`
+pub fn render_svg(tree: &SvgTree, zoom: f32) -> Option<Pixmap> {
+    let (width, height) = svg_size(tree);
+    let mut pixmap = Pixmap::new((width * zoom).ceil() as u32, (height * zoom).ceil() as u32)?;
+    resvg::render(tree, usvg::FitTo::Zoom(zoom), pixmap.as_mut())?;
+    Some(pixmap)
+}
+
+/// 将位图的所有像素替换为同一种颜色，只保留透明度，用于给单色图标重新着色
+pub fn tint_pixmap(pixmap: &mut Pixmap, color: u32) {
+    let (r, g, b, a) = split_color(color);
+    for pixel in pixmap.pixels_mut() {
+        let alpha = muldiv255(pixel.alpha() as u32, a as u32);
+        *pixel = PremultipliedColorU8::from_rgba(
+            muldiv255(r as u32, alpha) as u8,
+            muldiv255(g as u32, alpha) as u8,
+            muldiv255(b as u32, alpha) as u8,
+            alpha as u8,
+        )
+        .unwrap_or(*pixel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQUARE: &[u8] = br#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="8">
+        <rect x="0" y="0" width="5" height="8" fill="red"/>
+    </svg>"#;
+
+    #[test]
+    fn renders_at_the_zoom() {
+        let tree = parse_svg(SQUARE).unwrap();
+        assert_eq!(svg_size(&tree), (10., 8.));
+        let pixmap = render_svg(&tree, 2.).unwrap();
+        assert_eq!((pixmap.width(), pixmap.height()), (20, 16));
+        let inside = pixmap.pixel(2, 2).unwrap();
+        assert_eq!((inside.red(), inside.alpha()), (255, 255));
+        assert_eq!(pixmap.pixel(15, 2).unwrap().alpha(), 0);
+        assert!(render_svg(&tree, 0.).is_none());
+        assert!(parse_svg(b"<svg").is_err());
+    }
+
+    #[test]
+    fn tint_keeps_the_alpha() {
+        let mut pixmap = Pixmap::new(2, 1).unwrap();
+        pixmap.pixels_mut()[0] = PremultipliedColorU8::from_rgba(100, 0, 0, 100).unwrap();
+        tint_pixmap(&mut pixmap, 0x0000FF80);
+        let tinted = pixmap.pixel(0, 0).unwrap();
+        assert_eq!((tinted.red(), tinted.blue(), tinted.alpha()), (0, 50, 50));
+        assert_eq!(pixmap.pixel(1, 0).unwrap().alpha(), 0);
+    }
+}
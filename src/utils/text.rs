@@ -0,0 +1,487 @@
+//! 文字的测量与光栅化，字体和渲染结果都按线程缓存
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::rc::Rc;
+
+use font_kit::canvas::{Canvas, Format, RasterizationOptions};
+use font_kit::family_name::FamilyName;
+use font_kit::font::Font;
+use font_kit::hinting::HintingOptions;
+use font_kit::properties::{Properties, Weight};
+use font_kit::source::SystemSource;
+use pathfinder_geometry::transform2d::Transform2F;
+use pathfinder_geometry::vector::{Vector2F, Vector2I};
+use tiny_skia::{Pixmap, PremultipliedColorU8};
+
+use super::color::split_color;
+use super::drawing::muldiv255;
+
+/// Rendered texts kept around, the cache is cleared once it's full
+const TEXT_CACHE_SIZE: usize = 512;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum FontType {
+    Normal,
+    Bold,
+}
+
+/// 字体在某一字号下的行高信息，单位为像素
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LineMetrics {
+    /// Distance from the top of the line to the baseline
+    pub ascent: f32,
+    /// Distance from the baseline to the bottom of the line
+    pub descent: f32,
+    pub height: f32,
+}
+
+fn get_fonts() -> [FamilyName; 4] {
+    [
+        FamilyName::Title("Microsoft YaHei".to_string()),
+        FamilyName::Title("Helvetica".to_string()),
+        FamilyName::Title("Arial".to_string()),
+        FamilyName::SansSerif,
+    ]
+}
+
+fn load_font(properties: &Properties) -> Font {
+    SystemSource::new()
+        .select_best_match(&get_fonts(), properties)
+        .unwrap()
+        .load()
+        .unwrap()
+}
+
+type TextKey = (String, u32, u32, FontType);
+
+thread_local! {
+    static FONTS: RefCell<Option<Rc<(Font, Font)>>> = const { RefCell::new(None) };
+    static TEXT_CACHE: RefCell<HashMap<TextKey, Rc<Pixmap>>> = RefCell::new(HashMap::new());
+}
+
+fn with_font<R>(font: FontType, f: impl FnOnce(&Font) -> R) -> R {
+    let fonts = FONTS.with(|fonts| {
+        fonts
+            .borrow_mut()
+            .get_or_insert_with(|| {
+                Rc::new((
+                    load_font(&Properties::new()),
+                    load_font(Properties::new().weight(Weight::BOLD)),
+                ))
+            })
+            .clone()
+    });
+    match font {
+        FontType::Normal => f(&fonts.0),
+        FontType::Bold => f(&fonts.1),
+    }
+}
+
+fn glyph_for_char(font: &Font, c: char) -> Option<u32> {
+    font.glyph_for_char(c).or_else(|| font.glyph_for_char('?'))
+}
+
+fn advance(font: &Font, glyph: u32, size: f32) -> f32 {
+    let units_per_em = font.metrics().units_per_em as f32;
+    font.advance(glyph).map(|v| v.x()).unwrap_or(0.) * size / units_per_em
+}
+
+pub fn line_metrics(size: f32, font: FontType) -> LineMetrics {
+    with_font(font, |font| {
+        let metrics = font.metrics();
+        let scale = size / metrics.units_per_em as f32;
+        let ascent = metrics.ascent * scale;
+        let descent = -metrics.descent * scale;
+        LineMetrics {
+            ascent,
+            descent,
+            height: ascent + descent + metrics.line_gap * scale,
+        }
+    })
+}
+
+/// 测量每个字符的宽度，控制字符的宽度为 0
+pub fn measure_chars(text: &str, size: f32, font: FontType) -> Vec<f32> {
+    with_font(font, |font| {
+        text.chars()
+            .map(|c| {
+                if c.is_control() {
+                    0.
+                } else {
+                    glyph_for_char(font, c).map_or(0., |id| advance(font, id, size))
+                }
+            })
+            .collect()
+    })
+}
+
+/// 测量一行文字的宽度
+pub fn measure_text(text: &str, size: f32, font: FontType) -> f32 {
+    measure_chars(text, size, font).iter().sum()
+}
+
+/// 将一行文字光栅化成位图，位图的上边缘即为行的上边缘
+///
+/// 文字为空或者完全透明时返回 `None`
+pub fn rasterize_text(text: &str, size: f32, color: u32, font: FontType) -> Option<Pixmap> {
+    let metrics = line_metrics(size, font);
+    let width = measure_text(text, size, font).ceil() as i32 + 2;
+    let height = metrics.height.ceil() as i32 + 1;
+    if text.is_empty() || width <= 0 || height <= 0 || color & 0xFF == 0 {
+        return None;
+    }
+    // Glyphs may overlap each other, so they're rasterized one by one and merged
+    let mut coverage = vec![0u8; (width * height) as usize];
+    with_font(font, |font| {
+        let mut pen_x = 0.;
+        for c in text.chars() {
+            if c.is_control() {
+                continue;
+            }
+            let id = match glyph_for_char(font, c) {
+                Some(id) => id,
+                None => continue,
+            };
+            let origin = Vector2F::new(pen_x, metrics.ascent);
+            pen_x += advance(font, id, size);
+            let bounds = match font.raster_bounds(
+                id,
+                size,
+                Transform2F::from_translation(origin),
+                HintingOptions::None,
+                RasterizationOptions::GrayscaleAa,
+            ) {
+                Ok(bounds) if bounds.width() > 0 && bounds.height() > 0 => bounds,
+                _ => continue,
+            };
+            let mut canvas = Canvas::new(bounds.size(), Format::A8);
+            let drawn = font.rasterize_glyph(
+                &mut canvas,
+                id,
+                size,
+                Transform2F::from_translation(origin - bounds.origin().to_f32()),
+                HintingOptions::None,
+                RasterizationOptions::GrayscaleAa,
+            );
+            if drawn.is_err() {
+                continue;
+            }
+            blit_max(&mut coverage, width, height, &canvas, bounds.origin());
+        }
+    });
+    let (r, g, b, a) = split_color(color);
+    let mut pixmap = Pixmap::new(width as u32, height as u32)?;
+    for (pixel, &cover) in pixmap.pixels_mut().iter_mut().zip(coverage.iter()) {
+        if cover == 0 {
+            continue;
+        }
+        let alpha = muldiv255(cover as u32, a as u32);
+        *pixel = PremultipliedColorU8::from_rgba(
+            muldiv255(r as u32, alpha) as u8,
+            muldiv255(g as u32, alpha) as u8,
+            muldiv255(b as u32, alpha) as u8,
+            alpha as u8,
+        )
+        .unwrap_or(*pixel);
+    }
+    Some(pixmap)
+}
+
+/// Merge a glyph canvas into the coverage buffer, keeping the larger value.
+fn blit_max(coverage: &mut [u8], width: i32, height: i32, canvas: &Canvas, origin: Vector2I) {
+    for y in 0..canvas.size.y() {
+        let ty = y + origin.y();
+        if ty < 0 || ty >= height {
+            continue;
+        }
+        for x in 0..canvas.size.x() {
+            let tx = x + origin.x();
+            if tx < 0 || tx >= width {
+                continue;
+            }
+            let src = canvas.pixels[y as usize * canvas.stride + x as usize];
+            let dst = &mut coverage[(ty * width + tx) as usize];
+            *dst = (*dst).max(src);
+        }
+    }
+}
+
+/// 和 `rasterize_text` 相同，但会复用之前渲染过的结果
+pub fn cached_text(text: &str, size: f32, color: u32, font: FontType) -> Option<Rc<Pixmap>> {
+    let key = (text.to_string(), size.to_bits(), color, font);
+    if let Some(pixmap) = TEXT_CACHE.with(|cache| cache.borrow().get(&key).cloned()) {
+        return Some(pixmap);
+    }
+    let pixmap = Rc::new(rasterize_text(text, size, color, font)?);
+    TEXT_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.len() >= TEXT_CACHE_SIZE {
+            cache.clear();
+        }
+        cache.insert(key, pixmap.clone());
+    });
+    Some(pixmap)
+}
+
+/// 排版后的一行文字
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextLine {
+    pub text: String,
+    pub width: f32,
+}
+
+/// Punctuations which can't start a line
+const NO_BREAK_BEFORE: &str = "，。、；：！？）」』》〉】〕”’…,.;:!?)]}%";
+/// Punctuations which can't end a line
+const NO_BREAK_AFTER: &str = "（「『《〈【〔“‘([{$";
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x2E80..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF | 0xFF00..=0xFFEF | 0x20000..=0x2FFFF)
+}
+
+/// Whether a line can break between `prev` and `next`
+fn can_break(prev: char, next: char) -> bool {
+    if prev.is_whitespace() {
+        !next.is_whitespace()
+    } else if NO_BREAK_BEFORE.contains(next) || NO_BREAK_AFTER.contains(prev) {
+        false
+    } else {
+        is_cjk(prev) || is_cjk(next) || (prev == '-' && next.is_alphanumeric())
+    }
+}
+
+fn make_line(chars: &[char], widths: &[f32]) -> TextLine {
+    // Spaces at the end of a wrapped line are not visible
+    let end = chars
+        .iter()
+        .rposition(|c| !c.is_whitespace())
+        .map_or(0, |i| i + 1);
+    TextLine {
+        text: chars[..end].iter().collect(),
+        width: widths[..end].iter().sum(),
+    }
+}
+
+/// Char index where each line of a paragraph starts, the first one is always 0.
+fn break_paragraph(chars: &[char], widths: &[f32], max_width: f32) -> Vec<usize> {
+    let mut starts = vec![0];
+    let mut start = 0;
+    let mut width = 0.;
+    // Where the current line can be broken, the last break is preferred
+    let mut last_break = None;
+    let mut i = 0;
+    while i < chars.len() {
+        if i > start && can_break(chars[i - 1], chars[i]) {
+            last_break = Some(i);
+        }
+        let overflow = width + widths[i] > max_width && !chars[i].is_whitespace();
+        if overflow && i > start {
+            start = last_break.unwrap_or(i);
+            while start < chars.len() && chars[start].is_whitespace() {
+                start += 1;
+            }
+            starts.push(start);
+            width = widths[start..i].iter().sum();
+            last_break = None;
+        }
+        width += widths[i];
+        i += 1;
+    }
+    starts
+}
+
+/// 将文字按照最大宽度进行折行，会在空格处与中日韩文字之间断行，放不下的单词则会被强制截断
+///
+/// 换行符总会开始新的一行，`max_width` 为无穷大时只按换行符分行
+pub fn wrap_text(text: &str, size: f32, font: FontType, max_width: f32) -> Vec<TextLine> {
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let chars: Vec<char> = paragraph.trim_end_matches('\r').chars().collect();
+        let widths = measure_chars(&chars.iter().collect::<String>(), size, font);
+        let starts = break_paragraph(&chars, &widths, max_width);
+        for (i, &start) in starts.iter().enumerate() {
+            let end = starts.get(i + 1).copied().unwrap_or(chars.len());
+            lines.push(make_line(&chars[start..end], &widths[start..end]));
+        }
+    }
+    lines
+}
+
+/// 和 `wrap_text` 相同，但只处理不含换行符的一段文字，并返回每一行的字节范围
+///
+/// 行与行首尾相接，折行处的空格留在上一行的末尾，编辑器可以把光标放在它们后面
+pub fn wrap_ranges(text: &str, size: f32, font: FontType, max_width: f32) -> Vec<Range<usize>> {
+    let chars: Vec<char> = text.chars().collect();
+    let widths = measure_chars(text, size, font);
+    let bytes: Vec<usize> = text
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(text.len()))
+        .collect();
+    let starts = break_paragraph(&chars, &widths, max_width);
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, &start)| {
+            let end = starts.get(i + 1).copied().unwrap_or(chars.len());
+            bytes[start]..bytes[end]
+        })
+        .collect()
+}
+
+/// 截断一行文字并在末尾加上省略号，使其宽度不超过 `max_width`
+pub fn ellipsize(text: &str, size: f32, font: FontType, max_width: f32) -> TextLine {
+    let widths = measure_chars(text, size, font);
+    let full: f32 = widths.iter().sum();
+    if full <= max_width {
+        return TextLine {
+            text: text.to_string(),
+            width: full,
+        };
+    }
+    let ellipsis = measure_text("…", size, font);
+    let mut width = 0.;
+    let mut end = 0;
+    for (i, w) in widths.iter().enumerate() {
+        if width + w + ellipsis > max_width {
+            break;
+        }
+        width += w;
+        end = i + 1;
+    }
+    let mut text: String = text.chars().take(end).collect();
+    text.truncate(text.trim_end().len());
+    text.push('…');
+    let width = measure_text(&text, size, font);
+    TextLine { text, width }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: f32 = 14.;
+
+    fn texts(lines: &[TextLine]) -> Vec<&str> {
+        lines.iter().map(|l| l.text.as_str()).collect()
+    }
+
+    #[test]
+    fn breaks_after_spaces_hyphens_and_around_cjk() {
+        assert!(can_break(' ', 'a'));
+        assert!(!can_break(' ', ' '));
+        assert!(!can_break('a', 'b'));
+        assert!(can_break('-', 'b'));
+        assert!(can_break('你', '好'));
+        assert!(can_break('a', '好'));
+    }
+
+    #[test]
+    fn kinsoku_keeps_punctuations_with_their_chars() {
+        // A closing punctuation never starts a line
+        assert!(!can_break('好', '，'));
+        assert!(!can_break('a', ')'));
+        // An opening punctuation never ends a line
+        assert!(!can_break('（', '好'));
+        assert!(!can_break('$', '5'));
+        assert!(can_break('，', '好'));
+    }
+
+    #[test]
+    fn wraps_at_the_last_break_that_fits() {
+        let width = measure_text("hello wor", SIZE, FontType::Normal);
+        let lines = wrap_text("hello world", SIZE, FontType::Normal, width);
+        assert_eq!(texts(&lines), ["hello", "world"]);
+        // The trailing space is not counted
+        assert_eq!(
+            lines[0].width,
+            measure_text("hello", SIZE, FontType::Normal)
+        );
+        let width = measure_text("well-kn", SIZE, FontType::Normal);
+        let lines = wrap_text("well-known", SIZE, FontType::Normal, width);
+        assert_eq!(texts(&lines), ["well-", "known"]);
+    }
+
+    #[test]
+    fn long_words_are_cut() {
+        let width = measure_text("abcd", SIZE, FontType::Normal) + 0.5;
+        let lines = wrap_text("abcdefghij", SIZE, FontType::Normal, width);
+        assert_eq!(texts(&lines).concat(), "abcdefghij");
+        assert!(lines.len() >= 3);
+        assert!(lines.iter().all(|l| l.width <= width));
+    }
+
+    #[test]
+    fn line_breaks_start_new_lines() {
+        let lines = wrap_text("a\r\nb \n", SIZE, FontType::Normal, f32::INFINITY);
+        assert_eq!(texts(&lines), ["a", "b", ""]);
+    }
+
+    #[test]
+    fn ellipsis_fits_the_width() {
+        let full = measure_text("hello world", SIZE, FontType::Normal);
+        assert_eq!(
+            ellipsize("hello world", SIZE, FontType::Normal, full).text,
+            "hello world"
+        );
+        let line = ellipsize("hello world", SIZE, FontType::Normal, full - 1.);
+        assert!(line.text.ends_with('…'));
+        assert!(line.width <= full - 1.);
+        // Spaces before the ellipsis are dropped
+        let width = measure_text("hello …", SIZE, FontType::Normal);
+        assert_eq!(
+            ellipsize("hello world", SIZE, FontType::Normal, width).text,
+            "hello…"
+        );
+    }
+
+    /// Break with every char one unit wide.
+    fn lines(text: &str, max_width: f32) -> Vec<String> {
+        let chars: Vec<char> = text.chars().collect();
+        let starts = break_paragraph(&chars, &vec![1.; chars.len()], max_width);
+        starts
+            .iter()
+            .enumerate()
+            .map(|(i, &start)| {
+                let end = starts.get(i + 1).copied().unwrap_or(chars.len());
+                chars[start..end].iter().collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn paragraphs_break_at_spaces() {
+        assert_eq!(lines("hello world", 8.), ["hello ", "world"]);
+        // Spaces may overflow, the next line starts at the word
+        assert_eq!(lines("ab   cd", 2.), ["ab   ", "cd"]);
+        assert_eq!(lines("well-known", 6.), ["well-", "known"]);
+        assert_eq!(lines("abcdefgh", 3.), ["abc", "def", "gh"]);
+        assert_eq!(lines("hello world", f32::INFINITY), ["hello world"]);
+    }
+
+    #[test]
+    fn paragraphs_break_between_cjk_chars() {
+        assert_eq!(lines("你好世界", 3.), ["你好世", "界"]);
+        assert_eq!(lines("see 你好", 5.), ["see 你", "好"]);
+        // Punctuations stay with their chars
+        assert_eq!(lines("你好世界。", 4.), ["你好世", "界。"]);
+        assert_eq!(lines("ab「你好」", 3.), ["ab", "「你", "好」"]);
+    }
+
+    #[test]
+    fn ranges_cover_the_text() {
+        let ranges = wrap_ranges("你好 world", SIZE, FontType::Normal, f32::INFINITY);
+        assert_eq!(ranges, vec![0..12]);
+        let ranges = wrap_ranges("", SIZE, FontType::Normal, 100.);
+        assert_eq!(ranges, vec![0..0]);
+        let text = "the quick brown fox 跳过了懒狗";
+        let ranges = wrap_ranges(text, SIZE, FontType::Normal, 60.);
+        assert!(ranges.len() > 1);
+        assert_eq!(ranges.first().map(|r| r.start), Some(0));
+        assert_eq!(ranges.last().map(|r| r.end), Some(text.len()));
+        assert!(ranges.windows(2).all(|w| w[0].end == w[1].start));
+    }
+}
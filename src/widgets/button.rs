@@ -1,69 +1,261 @@
+use std::rc::Rc;
 
+use tiny_skia::Stroke;
 
-use crate::DrawCtx;
-
-
+use crate::events::{keys, UserEvent, WindowEvent};
+use crate::r#box::AreaBox;
+use crate::utils::color::mix_color;
+use crate::utils::drawing::{gen_paint, gen_round_rect_path};
+use crate::utils::spring::Spring;
+use crate::utils::text::{line_metrics, measure_text, FontType};
+use crate::utils::theme::get_theme_color;
 use crate::utils::uid::gen_uid;
-use crate::utils::{WidgetUid};
-use crate::{
-    traits::{Widget},
-};
+use crate::utils::WidgetUid;
+use crate::{traits::Widget, DrawCtx, EventCtx};
+
+const PADDING_X: f32 = 16.;
+const PADDING_Y: f32 = 6.;
+const RADIUS: f32 = 4.;
+const BORDER_COLOR: u32 = 0x00000024;
+const TEXT_COLOR: u32 = 0x202020FF;
+const BLOCKED_TEXT_COLOR: u32 = 0x20202060;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum ButtonStatus {
     Normal,
     Hovering,
     Active,
-    // Blocked,
+    Blocked,
+}
+
+impl ButtonStatus {
+    fn background(self) -> u32 {
+        match self {
+            ButtonStatus::Normal => 0xFDFDFDFF,
+            ButtonStatus::Hovering => 0xEFEFEFFF,
+            ButtonStatus::Active => 0xDCDCDCFF,
+            ButtonStatus::Blocked => 0xF5F5F5FF,
+        }
+    }
 }
 
 pub struct ButtonControl {
     _uid: WidgetUid,
-    pos: (i32, i32),
-    g_pos: (i32, i32),
+    /// Wanted size, 0 means fitting the text
     size: (u32, u32),
+    layout_size: (f32, f32),
     text: String,
     text_size: f32,
     status: ButtonStatus,
-    need_update: bool,
-    click_callback: Box<dyn Fn()>,
+    pressed: bool,
+    /// Background colour transition, goes from 0 to 1
+    transition: Spring,
+    from_color: u32,
+    click_callback: Rc<dyn Fn()>,
 }
 
 impl ButtonControl {
-    pub fn new(x: i32, y: i32, w: u32, h: u32, text: String) -> Self {
+    /// The parent places the button, it fits the text unless `with_size` asks for a size.
+    ///
+    /// This replaces `new(x, y, w, h, text)`: positions are no longer given to controls.
+    pub fn new(text: &str) -> Self {
+        let mut transition = Spring::new(1.);
+        transition.speed = 20.;
         ButtonControl {
             _uid: gen_uid(),
-            pos: (x, y),
-            size: (w, h),
-            g_pos: (x as i32, y as i32),
-            text,
+            size: (0, 0),
+            layout_size: (0., 0.),
+            text: text.into(),
             text_size: 14.,
             status: ButtonStatus::Normal,
-            need_update: true,
-            click_callback: Box::new(|| {}),
+            pressed: false,
+            transition,
+            from_color: ButtonStatus::Normal.background(),
+            click_callback: Rc::new(|| {}),
         }
     }
 
+    pub fn with_size(mut self, w: u32, h: u32) -> Self {
+        self.size = (w, h);
+        self
+    }
+
+    pub fn with_text_size(mut self, v: f32) -> Self {
+        self.text_size = v;
+        self
+    }
+
+    pub fn with_disabled(mut self, v: bool) -> Self {
+        self.set_disabled(v);
+        self
+    }
+
     pub fn on_click<F: Fn() + 'static>(mut self, v: F) -> Self {
-        self.click_callback = Box::new(v);
+        self.click_callback = Rc::new(v);
         self
     }
+
+    /// The id sent with `UserEvent::ControlClicked`
+    #[inline]
+    pub fn uid(&self) -> WidgetUid {
+        self._uid
+    }
+
+    #[inline]
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.into();
+    }
+
+    pub fn set_disabled(&mut self, v: bool) {
+        self.set_status(if v {
+            ButtonStatus::Blocked
+        } else {
+            ButtonStatus::Normal
+        });
+    }
+
+    #[inline]
+    pub fn is_disabled(&self) -> bool {
+        self.status == ButtonStatus::Blocked
+    }
+
+    /// Start the background transition to the colour of the new status.
+    fn set_status(&mut self, status: ButtonStatus) -> bool {
+        if self.status == status {
+            return false;
+        }
+        let progress = self.transition.position();
+        self.from_color = mix_color(self.from_color, self.status.background(), progress);
+        self.status = status;
+        self.transition.set_position(0.);
+        self.transition.set_target(1.);
+        true
+    }
+
+    fn click(&mut self, ctx: &mut EventCtx) {
+        (self.click_callback)();
+        ctx.submit_user_event(UserEvent::ControlClicked(self._uid));
+        ctx.set_handled();
+    }
 }
+
 impl Clone for ButtonControl {
     fn clone(&self) -> Self {
+        let mut transition = Spring::new(1.);
+        transition.speed = self.transition.speed;
         Self {
             _uid: gen_uid(),
             size: self.size,
-            pos: self.pos,
-            g_pos: self.g_pos,
-            status: self.status,
+            layout_size: self.layout_size,
+            status: if self.is_disabled() {
+                ButtonStatus::Blocked
+            } else {
+                ButtonStatus::Normal
+            },
+            pressed: false,
+            transition,
+            from_color: self.status.background(),
             text_size: self.text_size,
             text: self.text.clone(),
-            need_update: true,
-            click_callback: Box::new(|| {}),
+            click_callback: self.click_callback.clone(),
         }
     }
 }
 
 impl<D> Widget<D> for ButtonControl {
+    fn event(&mut self, ctx: &mut EventCtx, _data: &mut D) {
+        if self.is_disabled() {
+            // Disabled while being pressed, still release the pointer
+            if self.pressed && matches!(ctx.event(), WindowEvent::MouseUp(_, _)) {
+                self.pressed = false;
+                ctx.set_active(false);
+            }
+            return;
+        }
+        let hovering = ctx.is_hovering();
+        match ctx.event() {
+            WindowEvent::CollectFocus => ctx.register_for_focus(),
+            WindowEvent::MouseDown(_, _) => {
+                self.pressed = true;
+                ctx.set_active(true);
+                ctx.request_focus();
+                ctx.set_handled();
+            }
+            WindowEvent::MouseUp(_, _) if self.pressed => {
+                self.pressed = false;
+                ctx.set_active(false);
+                if hovering {
+                    self.click(ctx);
+                }
+            }
+            WindowEvent::KeyDown(keys::SPACE) | WindowEvent::KeyDown(keys::RETURN)
+                if ctx.is_focused() =>
+            {
+                self.click(ctx);
+            }
+            _ => {}
+        }
+        let status = if self.pressed && hovering {
+            ButtonStatus::Active
+        } else if hovering || self.pressed {
+            ButtonStatus::Hovering
+        } else {
+            ButtonStatus::Normal
+        };
+        if self.set_status(status) {
+            ctx.request_anim_frame();
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, _data: &D) {
+        let (width, height) = self.layout_size;
+        let progress = self.transition.position();
+        if !self.transition.arrived() {
+            ctx.request_anim_frame();
+        }
+        let background = mix_color(self.from_color, self.status.background(), progress);
+        let path = gen_round_rect_path(0.5, 0.5, width - 1., height - 1., RADIUS);
+        ctx.fill_path(&path, &gen_paint(background));
+        let border = if ctx.is_focused() {
+            get_theme_color() | 0xFF
+        } else {
+            BORDER_COLOR
+        };
+        ctx.stroke_path(&path, &gen_paint(border), &Stroke::default());
+        let text_width = measure_text(&self.text, self.text_size, FontType::Normal);
+        let line_height = line_metrics(self.text_size, FontType::Normal).height;
+        let text_color = if self.is_disabled() {
+            BLOCKED_TEXT_COLOR
+        } else {
+            TEXT_COLOR
+        };
+        ctx.with_save(|ctx| {
+            ctx.clip_rect(0., 0., width, height);
+            ctx.draw_text(
+                (width - text_width) / 2.,
+                (height - line_height) / 2.,
+                &self.text,
+                self.text_size,
+                text_color,
+            );
+        });
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let width = if self.size.0 > 0 {
+            self.size.0 as f32
+        } else {
+            measure_text(&self.text, self.text_size, FontType::Normal).ceil() + PADDING_X * 2.
+        };
+        let height = if self.size.1 > 0 {
+            self.size.1 as f32
+        } else {
+            line_metrics(self.text_size, FontType::Normal).height.ceil() + PADDING_Y * 2.
+        };
+        self.layout_size = (
+            width.min(max_box.width() as f32),
+            height.min(max_box.height() as f32),
+        );
+        AreaBox::with_size(self.layout_size.0 as isize, self.layout_size.1 as isize)
+    }
 }
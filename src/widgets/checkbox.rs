@@ -0,0 +1,423 @@
+//! 复选框，支持第三种“部分选中”状态
+
+use std::rc::Rc;
+
+use tiny_skia::{LineCap, LineJoin, PathBuilder, Stroke};
+
+use crate::events::{keys, WindowEvent};
+use crate::r#box::AreaBox;
+use crate::traits::Widget;
+use crate::utils::binding::Binding;
+use crate::utils::color::mix_color;
+use crate::utils::drawing::{gen_paint, gen_rect_path, gen_round_rect_path};
+use crate::utils::spring::Spring;
+use crate::utils::text::{line_metrics, measure_text, FontType};
+use crate::utils::theme::get_theme_color;
+use crate::utils::uid::gen_uid;
+use crate::utils::WidgetUid;
+use crate::{DrawCtx, EventCtx, UpdateCtx};
+
+const BOX_SIZE: f32 = 16.;
+const RADIUS: f32 = 3.;
+/// Space between the box and the label
+const GAP: f32 = 8.;
+const BACKGROUND_COLOR: u32 = 0xFFFFFFFF;
+const BORDER_COLOR: u32 = 0x00000070;
+const HOVER_BORDER_COLOR: u32 = 0x000000A0;
+const TEXT_COLOR: u32 = 0x202020FF;
+const DISABLED_COLOR: u32 = 0x20202060;
+const MARK_COLOR: u32 = 0xFFFFFFFF;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckState {
+    Unchecked,
+    Checked,
+    /// Partly checked, e.g. some of the children of a tree node are checked
+    Indeterminate,
+}
+
+impl CheckState {
+    #[inline]
+    pub fn is_checked(self) -> bool {
+        self == CheckState::Checked
+    }
+}
+
+impl From<bool> for CheckState {
+    fn from(v: bool) -> Self {
+        if v {
+            CheckState::Checked
+        } else {
+            CheckState::Unchecked
+        }
+    }
+}
+
+type ChangeCallback<D> = Rc<dyn Fn(&mut D, CheckState)>;
+
+pub struct Checkbox<D = ()> {
+    _uid: WidgetUid,
+    label: String,
+    text_size: f32,
+    state: CheckState,
+    /// Clicking goes through the indeterminate state too
+    tri_state: bool,
+    disabled: bool,
+    hovering: bool,
+    pressed: bool,
+    /// Goes from 0 to 1 when the state changes
+    transition: Spring,
+    from_state: CheckState,
+    /// Set when the state changes, the next update paints the start of the transition
+    need_paint: bool,
+    binding: Option<Binding<D, CheckState>>,
+    checked_binding: Option<Binding<D, bool>>,
+    change_callback: Option<ChangeCallback<D>>,
+    layout_size: (f32, f32),
+}
+
+impl<D> Checkbox<D> {
+    pub fn new(label: &str) -> Self {
+        let mut transition = Spring::new(1.);
+        transition.speed = 20.;
+        Self {
+            _uid: gen_uid(),
+            label: label.into(),
+            text_size: 14.,
+            state: CheckState::Unchecked,
+            tri_state: false,
+            disabled: false,
+            hovering: false,
+            pressed: false,
+            transition,
+            from_state: CheckState::Unchecked,
+            need_paint: false,
+            binding: None,
+            checked_binding: None,
+            change_callback: None,
+            layout_size: (0., 0.),
+        }
+    }
+
+    pub fn with_state(mut self, v: CheckState) -> Self {
+        self.state = v;
+        self.from_state = v;
+        self
+    }
+
+    pub fn with_checked(self, v: bool) -> Self {
+        self.with_state(v.into())
+    }
+
+    /// Let clicking cycle through unchecked, checked and indeterminate.
+    pub fn with_tri_state(mut self, v: bool) -> Self {
+        self.tri_state = v;
+        self
+    }
+
+    pub fn with_text_size(mut self, v: f32) -> Self {
+        self.text_size = v;
+        self
+    }
+
+    pub fn with_disabled(mut self, v: bool) -> Self {
+        self.disabled = v;
+        self
+    }
+
+    /// Keep the state in the application data.
+    pub fn with_binding(mut self, v: Binding<D, CheckState>) -> Self {
+        self.binding = Some(v);
+        self
+    }
+
+    /// Keep the state in a `bool` of the application data, the indeterminate state is stored as `false`.
+    pub fn with_checked_binding(mut self, v: Binding<D, bool>) -> Self {
+        self.checked_binding = Some(v);
+        self
+    }
+
+    /// Called with the new state when the user toggles the checkbox.
+    pub fn on_change<F: Fn(&mut D, CheckState) + 'static>(mut self, v: F) -> Self {
+        self.change_callback = Some(Rc::new(v));
+        self
+    }
+
+    #[inline]
+    pub fn state(&self) -> CheckState {
+        self.state
+    }
+
+    #[inline]
+    pub fn is_checked(&self) -> bool {
+        self.state.is_checked()
+    }
+
+    pub fn set_state(&mut self, state: CheckState) {
+        if self.state != state {
+            self.from_state = self.state;
+            self.state = state;
+            self.transition.set_position(0.);
+            self.transition.set_target(1.);
+            self.need_paint = true;
+        }
+    }
+
+    #[inline]
+    pub fn set_label(&mut self, label: &str) {
+        self.label = label.into();
+    }
+
+    #[inline]
+    pub fn set_disabled(&mut self, v: bool) {
+        self.disabled = v;
+    }
+
+    fn toggle(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        let next = match self.state {
+            CheckState::Unchecked => CheckState::Checked,
+            CheckState::Checked if self.tri_state => CheckState::Indeterminate,
+            _ => CheckState::Unchecked,
+        };
+        self.set_state(next);
+        if let Some(binding) = &self.binding {
+            *binding.get_mut(data) = next;
+        }
+        if let Some(binding) = &self.checked_binding {
+            *binding.get_mut(data) = next.is_checked();
+        }
+        if let Some(callback) = &self.change_callback {
+            callback(data, next);
+        }
+        ctx.set_handled();
+        ctx.request_anim_frame();
+    }
+
+    /// Draw the mark of a state, `progress` goes from 0 to 1 while it appears.
+    fn draw_mark(ctx: &mut DrawCtx, state: CheckState, x: f32, y: f32, progress: f32) {
+        let stroke = Stroke {
+            width: 2.,
+            line_cap: LineCap::Round,
+            line_join: LineJoin::Round,
+            ..Default::default()
+        };
+        let paint = gen_paint(MARK_COLOR);
+        match state {
+            CheckState::Checked => {
+                // The tick is drawn from its left end
+                let points = [(4., 8.5), (7., 11.5), (12., 5.)];
+                let mut pb = PathBuilder::new();
+                pb.move_to(x + points[0].0, y + points[0].1);
+                let first = (progress * 2.).min(1.);
+                let (mx, my) = (
+                    points[0].0 + (points[1].0 - points[0].0) * first,
+                    points[0].1 + (points[1].1 - points[0].1) * first,
+                );
+                pb.line_to(x + mx, y + my);
+                if progress > 0.5 {
+                    let second = (progress - 0.5) * 2.;
+                    pb.line_to(
+                        x + points[1].0 + (points[2].0 - points[1].0) * second,
+                        y + points[1].1 + (points[2].1 - points[1].1) * second,
+                    );
+                }
+                if let Some(path) = pb.finish() {
+                    ctx.stroke_path(&path, &paint, &stroke);
+                }
+            }
+            CheckState::Indeterminate => {
+                let half = 4. * progress;
+                let path = gen_rect_path(x + BOX_SIZE / 2. - half, y + 7., half * 2., 2.);
+                ctx.fill_path(&path, &paint);
+            }
+            CheckState::Unchecked => {}
+        }
+    }
+}
+
+impl<D> Widget<D> for Checkbox<D> {
+    fn event(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        let hovering = ctx.is_hovering();
+        if hovering != self.hovering {
+            self.hovering = hovering;
+            ctx.request_paint();
+        }
+        if self.disabled {
+            if self.pressed && matches!(ctx.event(), WindowEvent::MouseUp(_, _)) {
+                self.pressed = false;
+                ctx.set_active(false);
+            }
+            return;
+        }
+        match ctx.event() {
+            WindowEvent::CollectFocus => ctx.register_for_focus(),
+            WindowEvent::MouseDown(_, _) => {
+                self.pressed = true;
+                ctx.set_active(true);
+                ctx.request_focus();
+                ctx.set_handled();
+            }
+            WindowEvent::MouseUp(_, _) if self.pressed => {
+                self.pressed = false;
+                ctx.set_active(false);
+                if hovering {
+                    self.toggle(ctx, data);
+                }
+            }
+            WindowEvent::KeyDown(keys::SPACE) if ctx.is_focused() => self.toggle(ctx, data),
+            _ => {}
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        let state = match (&self.binding, &self.checked_binding) {
+            (Some(binding), _) => Some(*binding.get(data)),
+            (None, Some(binding)) => {
+                let checked = *binding.get(data);
+                // `false` is also stored for the indeterminate state
+                if !checked && self.state == CheckState::Indeterminate {
+                    None
+                } else {
+                    Some(checked.into())
+                }
+            }
+            _ => None,
+        };
+        if let Some(state) = state {
+            self.set_state(state);
+        }
+        // Drawing keeps the transition going
+        if self.need_paint {
+            self.need_paint = false;
+            ctx.request_paint();
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, _data: &D) {
+        let (_, height) = self.layout_size;
+        let progress = self.transition.position().clamp(0., 1.);
+        if !self.transition.arrived() {
+            ctx.request_anim_frame();
+        }
+        let theme = get_theme_color() | 0xFF;
+        let accent = if self.disabled { DISABLED_COLOR } else { theme };
+        let filled = |state: CheckState| state != CheckState::Unchecked;
+        let fill_of = |state: CheckState| {
+            if filled(state) {
+                accent
+            } else {
+                BACKGROUND_COLOR
+            }
+        };
+        let x = 0.5;
+        let y = ((height - BOX_SIZE) / 2.).round() + 0.5;
+        let path = gen_round_rect_path(x, y, BOX_SIZE - 1., BOX_SIZE - 1., RADIUS);
+        let background = mix_color(fill_of(self.from_state), fill_of(self.state), progress);
+        ctx.fill_path(&path, &gen_paint(background));
+        if !filled(self.state) {
+            let border = if self.disabled {
+                DISABLED_COLOR
+            } else if self.hovering || self.pressed {
+                HOVER_BORDER_COLOR
+            } else {
+                BORDER_COLOR
+            };
+            ctx.stroke_path(&path, &gen_paint(border), &Stroke::default());
+        }
+        Self::draw_mark(ctx, self.state, x - 0.5, y - 0.5, progress);
+        if ctx.is_focused() {
+            let ring =
+                gen_round_rect_path(x - 2., y - 2., BOX_SIZE + 3., BOX_SIZE + 3., RADIUS + 2.);
+            ctx.stroke_path(&ring, &gen_paint(theme), &Stroke::default());
+        }
+        let line_height = line_metrics(self.text_size, FontType::Normal).height;
+        let color = if self.disabled {
+            DISABLED_COLOR
+        } else {
+            TEXT_COLOR
+        };
+        ctx.draw_text(
+            BOX_SIZE + GAP,
+            (height - line_height) / 2.,
+            &self.label,
+            self.text_size,
+            color,
+        );
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let text_width = if self.label.is_empty() {
+            0.
+        } else {
+            GAP + measure_text(&self.label, self.text_size, FontType::Normal).ceil()
+        };
+        let line_height = line_metrics(self.text_size, FontType::Normal).height.ceil();
+        self.layout_size = (
+            (BOX_SIZE + text_width).min(max_box.width() as f32),
+            line_height.max(BOX_SIZE + 4.).min(max_box.height() as f32),
+        );
+        AreaBox::with_size(self.layout_size.0 as isize, self.layout_size.1 as isize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Modifiers;
+
+    fn ctx() -> EventCtx {
+        EventCtx::new(WindowEvent::None, (0., 0.), Modifiers::default(), None)
+    }
+
+    #[test]
+    fn clicks_cycle_through_the_states() {
+        let mut data = CheckState::Unchecked;
+        let mut checkbox = Checkbox::new("a")
+            .with_tri_state(true)
+            .with_binding(Binding::identity());
+        let mut ctx = ctx();
+        checkbox.toggle(&mut ctx, &mut data);
+        assert_eq!(
+            (checkbox.state(), data),
+            (CheckState::Checked, CheckState::Checked)
+        );
+        assert!(ctx.request_anim);
+        checkbox.toggle(&mut ctx, &mut data);
+        assert_eq!(data, CheckState::Indeterminate);
+        checkbox.toggle(&mut ctx, &mut data);
+        assert_eq!(data, CheckState::Unchecked);
+
+        let mut checkbox = Checkbox::<()>::new("b").with_checked(true);
+        checkbox.toggle(&mut ctx, &mut ());
+        assert_eq!(checkbox.state(), CheckState::Unchecked);
+    }
+
+    #[test]
+    fn bool_binding_keeps_the_indeterminate_state() {
+        let mut checkbox = Checkbox::new("a")
+            .with_state(CheckState::Indeterminate)
+            .with_checked_binding(Binding::identity());
+        let mut ctx = UpdateCtx::new();
+        checkbox.update(&mut ctx, &false);
+        assert_eq!(checkbox.state(), CheckState::Indeterminate);
+        assert!(!ctx.request_paint);
+        checkbox.update(&mut ctx, &true);
+        assert_eq!(checkbox.state(), CheckState::Checked);
+        assert!(ctx.request_paint);
+        checkbox.update(&mut ctx, &false);
+        assert_eq!(checkbox.state(), CheckState::Unchecked);
+    }
+
+    #[test]
+    fn set_state_paints_on_the_next_update() {
+        let mut checkbox = Checkbox::<()>::new("a");
+        checkbox.set_state(CheckState::Checked);
+        let mut ctx = UpdateCtx::new();
+        checkbox.update(&mut ctx, &());
+        assert!(ctx.request_paint);
+        // Only once
+        let mut ctx = UpdateCtx::new();
+        checkbox.update(&mut ctx, &());
+        assert!(!ctx.request_paint);
+    }
+}
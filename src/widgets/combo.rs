@@ -0,0 +1,884 @@
+//! 下拉选择框，选项列表在弹出层中打开，可以只用键盘操作；可编辑时输入的文字会筛选选项
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use tiny_skia::{LineCap, LineJoin, PathBuilder, Stroke};
+
+use super::input::InputControl;
+use super::scroll::{ScrollDirection, ScrollState};
+use crate::core::WidgetPod;
+use crate::events::{keys, PopupId, WindowEvent};
+use crate::popup::Popup;
+use crate::r#box::AreaBox;
+use crate::system::enums::Cursor;
+use crate::traits::Widget;
+use crate::utils::binding::Binding;
+use crate::utils::drawing::{gen_paint, gen_round_rect_path};
+use crate::utils::text::{line_metrics, FontType};
+use crate::utils::theme::get_theme_color;
+use crate::utils::uid::gen_uid;
+use crate::utils::WidgetUid;
+use crate::{DrawCtx, EventCtx, UpdateCtx};
+
+const PADDING_X: f32 = 8.;
+const RADIUS: f32 = 4.;
+/// Width of the arrow button on the right
+const ARROW_WIDTH: f32 = 28.;
+/// Space around the list inside the popup
+const LIST_PADDING: f32 = 4.;
+const ITEM_PADDING_Y: f32 = 6.;
+/// Items shown before the list scrolls
+const MAX_VISIBLE_ITEMS: usize = 8;
+/// Chars typed within this time are searched together
+const TYPE_AHEAD_TIMEOUT: Duration = Duration::from_millis(1000);
+const BACKGROUND_COLOR: u32 = 0xFFFFFFFF;
+const HOVER_BACKGROUND_COLOR: u32 = 0xF6F6F6FF;
+const BORDER_COLOR: u32 = 0x00000024;
+const TEXT_COLOR: u32 = 0x202020FF;
+const PLACEHOLDER_COLOR: u32 = 0x20202070;
+const ICON_COLOR: u32 = 0x20202090;
+const DISABLED_COLOR: u32 = 0x20202060;
+const HIGHLIGHT_COLOR: u32 = 0x00000010;
+const NO_MATCH_TEXT: &str = "No matches";
+
+struct ComboItem<D, T> {
+    value: T,
+    label: String,
+    /// Shown in the list instead of the label
+    widget: Option<WidgetPod<D>>,
+}
+
+/// State shared by the combo box and its list in the popup
+struct ComboState<D, T> {
+    items: Vec<ComboItem<D, T>>,
+    selected: Option<usize>,
+    /// Items in the list, filtered by the text of an editable combo box
+    visible: Vec<usize>,
+    /// Position in `visible` of the item under the keyboard or the mouse
+    highlighted: Option<usize>,
+    /// Top and height of the rows of the list
+    rows: Vec<(f32, f32)>,
+    scroll: ScrollState,
+    /// The item chosen in the list, the combo box takes it once the popup is closed
+    picked: Option<usize>,
+    text_size: f32,
+}
+
+impl<D, T> ComboState<D, T> {
+    fn item_height(&self) -> f32 {
+        line_metrics(self.text_size, FontType::Normal).height.ceil() + ITEM_PADDING_Y * 2.
+    }
+
+    /// Show the items containing `text`, or all of them with the selected one highlighted.
+    fn filter(&mut self, text: Option<&str>) {
+        match text.map(str::to_lowercase) {
+            Some(needle) if !needle.is_empty() => {
+                self.visible = (0..self.items.len())
+                    .filter(|&i| self.items[i].label.to_lowercase().contains(&needle))
+                    .collect();
+                self.highlighted = if self.visible.is_empty() {
+                    None
+                } else {
+                    Some(0)
+                };
+            }
+            _ => {
+                self.visible = (0..self.items.len()).collect();
+                self.highlighted = self.selected;
+            }
+        }
+        self.rows.clear();
+        self.scroll.set_offset(0., 0.);
+    }
+
+    fn highlight(&mut self, index: usize) {
+        self.highlighted = Some(index);
+        if let Some(&(top, height)) = self.rows.get(index) {
+            self.scroll.scroll_into_view(0., top, 1., height);
+        }
+    }
+
+    /// Move the highlight by some items, from the first or the last one when nothing is highlighted.
+    fn move_highlight(&mut self, delta: isize) {
+        let count = self.visible.len() as isize;
+        if count == 0 {
+            return;
+        }
+        let index = match self.highlighted {
+            Some(i) => (i as isize + delta).max(0).min(count - 1),
+            None if delta > 0 => 0,
+            None => count - 1,
+        };
+        self.highlight(index as usize);
+    }
+
+    /// Position in `visible` of the first item starting with `prefix`, searching from `from`.
+    fn find_prefix(&self, prefix: &str, from: usize) -> Option<usize> {
+        let count = self.visible.len();
+        (0..count).map(|i| (from + i) % count).find(|&i| {
+            self.items[self.visible[i]]
+                .label
+                .to_lowercase()
+                .starts_with(prefix)
+        })
+    }
+
+    /// Lay out the rows of the list, returns the height of the content.
+    fn layout_rows(&mut self, width: f32) -> f32 {
+        let item_height = self.item_height();
+        let mut top = 0.;
+        self.rows.clear();
+        for &index in &self.visible {
+            let height = match &mut self.items[index].widget {
+                Some(widget) => {
+                    let size = widget.layout(AreaBox::with_size(
+                        (width - PADDING_X * 2.) as isize,
+                        isize::MAX,
+                    ));
+                    widget.set_origin(PADDING_X, top + ITEM_PADDING_Y);
+                    (size.to_size_f32().1 + ITEM_PADDING_Y * 2.).max(item_height)
+                }
+                None => item_height,
+            };
+            self.rows.push((top, height));
+            top += height;
+        }
+        top
+    }
+
+    fn row_at(&self, y: f32) -> Option<usize> {
+        self.rows
+            .iter()
+            .position(|&(top, height)| y >= top && y < top + height)
+    }
+}
+
+type SharedState<D, T> = Rc<RefCell<ComboState<D, T>>>;
+type ChangeCallback<D, T> = Rc<dyn Fn(&mut D, &T)>;
+
+/// The list opened in the popup
+struct ComboList<D, T> {
+    state: SharedState<D, T>,
+    /// The row pressed by the mouse
+    pressed: Option<usize>,
+    /// The highlighted item is scrolled into view on the first layout
+    placed: bool,
+    layout_size: (f32, f32),
+}
+
+impl<D, T> ComboList<D, T> {
+    /// Handle an event with the mouse position relative to the viewport of the list.
+    fn list_event(&mut self, ctx: &mut EventCtx) {
+        let mut state = self.state.borrow_mut();
+        if state.scroll.bar_event(ctx) {
+            return;
+        }
+        let (x, y) = ctx.mouse_pos();
+        let (view_width, view_height) = state.scroll.viewport();
+        let (_, offset_y) = state.scroll.offset();
+        let inside = x >= 0. && y >= 0. && x < view_width && y < view_height;
+        let row = if inside {
+            state.row_at(y + offset_y)
+        } else {
+            None
+        };
+        match ctx.event() {
+            WindowEvent::MouseMove(_, _) if row.is_some() && row != state.highlighted => {
+                state.highlighted = row;
+                ctx.request_paint();
+            }
+            WindowEvent::MouseDown(_, _) => {
+                self.pressed = row;
+                ctx.set_handled();
+            }
+            WindowEvent::MouseUp(_, _) => {
+                let pressed = self.pressed.take();
+                if let (Some(pressed), true) = (pressed, row == pressed) {
+                    state.picked = Some(state.visible[pressed]);
+                    ctx.dismiss_popup();
+                    ctx.set_handled();
+                }
+            }
+            _ => {}
+        }
+        state.scroll.event(ctx);
+    }
+}
+
+impl<D, T> Widget<D> for ComboList<D, T> {
+    fn event(&mut self, ctx: &mut EventCtx, _data: &mut D) {
+        let (x, y) = ctx.mouse_pos();
+        ctx.mouse_pos = (x - LIST_PADDING, y - LIST_PADDING);
+        self.list_event(ctx);
+        ctx.mouse_pos = (x, y);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        for item in &mut self.state.borrow_mut().items {
+            if let Some(widget) = &mut item.widget {
+                widget.update(ctx, data);
+            }
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, data: &D) {
+        let (width, height) = self.layout_size;
+        let mut state = self.state.borrow_mut();
+        let (_, offset_y) = state.scroll.offset();
+        let (view_width, view_height) = (width - LIST_PADDING * 2., height - LIST_PADDING * 2.);
+        let accent = get_theme_color() | 0xFF;
+        let line_height = line_metrics(state.text_size, FontType::Normal).height;
+        let state = &mut *state;
+        ctx.with_save(|ctx| {
+            ctx.translate(LIST_PADDING, LIST_PADDING);
+            ctx.clip_rect(0., 0., view_width, view_height);
+            if state.visible.is_empty() {
+                let top = ((view_height - line_height) / 2.).round();
+                ctx.draw_text(
+                    PADDING_X,
+                    top,
+                    NO_MATCH_TEXT,
+                    state.text_size,
+                    PLACEHOLDER_COLOR,
+                );
+            }
+            ctx.translate(0., -offset_y);
+            for (row, &index) in state.visible.iter().enumerate() {
+                let (top, row_height) = match state.rows.get(row) {
+                    Some(&r) => r,
+                    None => break,
+                };
+                if top + row_height < offset_y || top > offset_y + view_height {
+                    continue;
+                }
+                if state.highlighted == Some(row) {
+                    let path = gen_round_rect_path(0., top, view_width, row_height, RADIUS);
+                    ctx.fill_path(&path, &gen_paint(HIGHLIGHT_COLOR));
+                }
+                if state.selected == Some(index) {
+                    let path = gen_round_rect_path(0., top + 8., 3., row_height - 16., 1.5);
+                    ctx.fill_path(&path, &gen_paint(accent));
+                }
+                let item = &mut state.items[index];
+                match &mut item.widget {
+                    Some(widget) => widget.draw(ctx, data),
+                    None => ctx.draw_text(
+                        PADDING_X,
+                        top + ((row_height - line_height) / 2.).round(),
+                        &item.label,
+                        state.text_size,
+                        TEXT_COLOR,
+                    ),
+                }
+            }
+        });
+        ctx.with_save(|ctx| {
+            ctx.translate(LIST_PADDING, LIST_PADDING);
+            state.scroll.draw_bars(ctx);
+        });
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let mut state = self.state.borrow_mut();
+        let width = max_box.width() as f32;
+        let view_width = width - LIST_PADDING * 2.;
+        let content = state.layout_rows(view_width);
+        let view_height = match state.rows.get(MAX_VISIBLE_ITEMS) {
+            Some(&(top, _)) => top,
+            None if state.rows.is_empty() => state.item_height(),
+            None => content,
+        };
+        let view_height = view_height.min(max_box.height() as f32 - LIST_PADDING * 2.);
+        state
+            .scroll
+            .set_sizes((view_width, view_height), (view_width, content));
+        if !self.placed {
+            self.placed = true;
+            if let Some(&(top, height)) = state.highlighted.and_then(|i| state.rows.get(i)) {
+                // Open with the highlighted item in the middle
+                state
+                    .scroll
+                    .set_offset(0., top + height / 2. - view_height / 2.);
+            }
+        }
+        self.layout_size = (width, view_height + LIST_PADDING * 2.);
+        AreaBox::with_size(self.layout_size.0 as isize, self.layout_size.1 as isize)
+    }
+}
+
+/// A dropdown selecting the value pointed by a binding, options are added with `with_option`.
+pub struct ComboBox<D, T> {
+    _uid: WidgetUid,
+    state: SharedState<D, T>,
+    binding: Binding<D, T>,
+    placeholder: String,
+    /// Typing filters the options
+    editable: bool,
+    /// The list is shown in a window of its own
+    list_window: bool,
+    /// The text field of an editable combo box, it shares the focus of the combo box
+    input: InputControl<D>,
+    /// The text the list was filtered with
+    filter_text: String,
+    disabled: bool,
+    hovering: bool,
+    pressed: bool,
+    popup: Option<PopupId>,
+    /// Chars typed to search an option and when the last one was typed
+    type_ahead: (String, Instant),
+    change_callback: Option<ChangeCallback<D, T>>,
+    layout_size: (f32, f32),
+}
+
+impl<D: 'static, T: PartialEq + Clone + 'static> ComboBox<D, T> {
+    pub fn new(binding: Binding<D, T>) -> Self {
+        let mut scroll = ScrollState::new(ScrollDirection::Vertical);
+        scroll.set_drag_scroll(false);
+        let mut input = InputControl::new();
+        input.set_trailing(ARROW_WIDTH - PADDING_X);
+        Self {
+            _uid: gen_uid(),
+            state: Rc::new(RefCell::new(ComboState {
+                items: Vec::new(),
+                selected: None,
+                visible: Vec::new(),
+                highlighted: None,
+                rows: Vec::new(),
+                scroll,
+                picked: None,
+                text_size: 14.,
+            })),
+            binding,
+            placeholder: String::new(),
+            editable: false,
+            list_window: false,
+            input,
+            filter_text: String::new(),
+            disabled: false,
+            hovering: false,
+            pressed: false,
+            popup: None,
+            type_ahead: (String::new(), Instant::now()),
+            change_callback: None,
+            layout_size: (0., 0.),
+        }
+    }
+
+    pub fn with_option(self, value: T, label: &str) -> Self {
+        self.push_item(value, label, None)
+    }
+
+    /// An option drawn by a widget in the list, `label` is shown in the box and used for filtering.
+    pub fn with_option_widget(self, value: T, label: &str, widget: Box<dyn Widget<D>>) -> Self {
+        self.push_item(value, label, Some(widget.into()))
+    }
+
+    pub fn with_placeholder(mut self, v: &str) -> Self {
+        self.placeholder = v.into();
+        self.input.set_placeholder(v);
+        self
+    }
+
+    /// Let the user type in the box to filter the options.
+    pub fn with_editable(mut self, v: bool) -> Self {
+        self.editable = v;
+        self
+    }
+
+    /// Show the list in a window of its own, so a long list can go past the edges of the window.
+    pub fn with_list_window(mut self, v: bool) -> Self {
+        self.list_window = v;
+        self
+    }
+
+    /// Wanted size, 0 means filling the width or fitting the text height.
+    pub fn with_size(mut self, w: u32, h: u32) -> Self {
+        self.input = std::mem::take(&mut self.input).with_size(w, h);
+        self
+    }
+
+    pub fn with_text_size(mut self, v: f32) -> Self {
+        self.state.borrow_mut().text_size = v;
+        self.input = std::mem::take(&mut self.input).with_text_size(v);
+        self
+    }
+
+    pub fn with_disabled(mut self, v: bool) -> Self {
+        self.disabled = v;
+        self
+    }
+
+    /// Called with the new value when the user selects an option.
+    pub fn on_change<F: Fn(&mut D, &T) + 'static>(mut self, v: F) -> Self {
+        self.change_callback = Some(Rc::new(v));
+        self
+    }
+
+    #[inline]
+    pub fn set_disabled(&mut self, v: bool) {
+        self.disabled = v;
+    }
+
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        self.popup.is_some()
+    }
+
+    fn push_item(self, value: T, label: &str, widget: Option<WidgetPod<D>>) -> Self {
+        self.state.borrow_mut().items.push(ComboItem {
+            value,
+            label: label.into(),
+            widget,
+        });
+        self
+    }
+
+    fn selected_label(&self) -> Option<String> {
+        let state = self.state.borrow();
+        state.selected.map(|i| state.items[i].label.clone())
+    }
+
+    fn open(&mut self, ctx: &mut EventCtx) {
+        if self.popup.is_some() {
+            return;
+        }
+        {
+            let mut state = self.state.borrow_mut();
+            let label = state.selected.map(|i| state.items[i].label.clone());
+            // An editable combo box shows all the options until something else is typed
+            let filtered = self.editable && Some(&self.filter_text) != label.as_ref();
+            state.filter(if filtered {
+                Some(&self.filter_text)
+            } else {
+                None
+            });
+            state.picked = None;
+        }
+        let list = ComboList {
+            state: self.state.clone(),
+            pressed: None,
+            placed: false,
+            layout_size: (0., 0.),
+        };
+        let popup = Popup::new(Box::new(list))
+            .with_match_width(true)
+            .with_gap(2.)
+            .with_window(self.list_window);
+        self.popup = Some(ctx.open_popup(popup));
+    }
+
+    fn close(&mut self, ctx: &mut EventCtx) {
+        if let Some(id) = self.popup {
+            ctx.close_popup(id);
+        }
+    }
+
+    /// Pick the highlighted item and close the list.
+    fn pick_highlighted(&mut self, ctx: &mut EventCtx) {
+        {
+            let mut state = self.state.borrow_mut();
+            state.picked = state.highlighted.map(|i| state.visible[i]);
+        }
+        self.close(ctx);
+    }
+
+    fn select(&mut self, ctx: &mut EventCtx, data: &mut D, index: usize) {
+        let (value, label) = {
+            let mut state = self.state.borrow_mut();
+            state.selected = Some(index);
+            let item = &state.items[index];
+            (item.value.clone(), item.label.clone())
+        };
+        if self.editable {
+            self.input.set_value(&label);
+            self.filter_text = label;
+        }
+        *self.binding.get_mut(data) = value.clone();
+        if let Some(callback) = &self.change_callback {
+            callback(data, &value);
+        }
+        ctx.request_paint();
+    }
+
+    /// Select the option before or after the selected one without opening the list.
+    fn step_selection(&mut self, ctx: &mut EventCtx, data: &mut D, delta: isize) {
+        let (count, selected) = {
+            let state = self.state.borrow();
+            (state.items.len() as isize, state.selected)
+        };
+        if count == 0 {
+            return;
+        }
+        let index = match selected {
+            Some(i) => (i as isize + delta).max(0).min(count - 1),
+            None => 0,
+        };
+        if Some(index as usize) != selected {
+            self.select(ctx, data, index as usize);
+        }
+    }
+
+    /// Search an option by the chars typed so far.
+    fn type_ahead(&mut self, ctx: &mut EventCtx, data: &mut D, c: char) {
+        let (typed, time) = &mut self.type_ahead;
+        if time.elapsed() > TYPE_AHEAD_TIMEOUT {
+            typed.clear();
+        }
+        *time = Instant::now();
+        typed.extend(c.to_lowercase());
+        let typed = typed.clone();
+        let open = self.popup.is_some();
+        let found = {
+            let mut state = self.state.borrow_mut();
+            if !open {
+                state.filter(None);
+            }
+            let current = if open {
+                state.highlighted
+            } else {
+                state.selected
+            };
+            // Typing the same letter again goes through the options starting with it
+            let repeated = typed.chars().all(|t| typed.starts_with(t));
+            let from = match current {
+                Some(i) if repeated => i + 1,
+                Some(i) => i,
+                None => 0,
+            };
+            let first = typed.chars().next().map_or(0, char::len_utf8);
+            let prefix = if repeated {
+                &typed[..first]
+            } else {
+                &typed[..]
+            };
+            let found = state.find_prefix(prefix, from);
+            if let (Some(row), true) = (found, open) {
+                state.highlight(row);
+            }
+            found.map(|row| state.visible[row])
+        };
+        match found {
+            Some(_) if open => ctx.request_anim_frame(),
+            Some(index) => self.select(ctx, data, index),
+            None => {}
+        }
+    }
+
+    /// Handle a key of the focused combo box, returns whether it was used.
+    fn handle_key(&mut self, ctx: &mut EventCtx, data: &mut D, key: usize) -> bool {
+        let open = self.popup.is_some();
+        let alt = ctx.modifiers().alt;
+        let page = MAX_VISIBLE_ITEMS as isize - 1;
+        match key {
+            keys::DOWN | keys::UP if alt => {
+                if open {
+                    self.close(ctx);
+                } else {
+                    self.open(ctx);
+                }
+            }
+            keys::DOWN | keys::UP | keys::NEXT | keys::PRIOR if open => {
+                let delta = match key {
+                    keys::DOWN => 1,
+                    keys::UP => -1,
+                    keys::NEXT => page,
+                    _ => -page,
+                };
+                self.state.borrow_mut().move_highlight(delta);
+                ctx.request_anim_frame();
+            }
+            keys::HOME | keys::END if open && !self.editable => {
+                let delta = if key == keys::HOME {
+                    isize::MIN / 2
+                } else {
+                    isize::MAX / 2
+                };
+                self.state.borrow_mut().move_highlight(delta);
+                ctx.request_anim_frame();
+            }
+            keys::RETURN if open => self.pick_highlighted(ctx),
+            keys::DOWN if self.editable => self.open(ctx),
+            keys::DOWN | keys::UP => {
+                let delta = if key == keys::DOWN { 1 } else { -1 };
+                self.step_selection(ctx, data, delta);
+            }
+            keys::RETURN | keys::SPACE if !self.editable => self.open(ctx),
+            _ => return false,
+        }
+        true
+    }
+
+    fn draw_arrow(&self, ctx: &mut DrawCtx) {
+        let (width, height) = self.layout_size;
+        let (cx, cy) = (width - ARROW_WIDTH / 2., height / 2.);
+        // Points down, or up while the list is open
+        let dir = if self.popup.is_some() { -1. } else { 1. };
+        let mut pb = PathBuilder::new();
+        pb.move_to(cx - 4., cy - 2. * dir);
+        pb.line_to(cx, cy + 2. * dir);
+        pb.line_to(cx + 4., cy - 2. * dir);
+        if let Some(path) = pb.finish() {
+            let stroke = Stroke {
+                width: 1.5,
+                line_cap: LineCap::Round,
+                line_join: LineJoin::Round,
+                ..Default::default()
+            };
+            let color = if self.disabled {
+                DISABLED_COLOR
+            } else {
+                ICON_COLOR
+            };
+            ctx.stroke_path(&path, &gen_paint(color), &stroke);
+        }
+    }
+}
+
+impl<D: 'static, T: PartialEq + Clone + 'static> Widget<D> for ComboBox<D, T> {
+    fn event(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        let (x, _) = ctx.mouse_pos();
+        let hovering = ctx.is_hovering();
+        if hovering != self.hovering {
+            self.hovering = hovering;
+            ctx.request_paint();
+        }
+        if let WindowEvent::PopupClosed(id) = ctx.event() {
+            if self.popup == Some(id) {
+                self.popup = None;
+                let picked = self.state.borrow_mut().picked.take();
+                match picked {
+                    Some(index) => self.select(ctx, data, index),
+                    None if self.editable => {
+                        // Nothing was picked, show the selected option again
+                        let label = self.selected_label().unwrap_or_default();
+                        self.input.set_value(&label);
+                        self.filter_text = label;
+                    }
+                    None => {}
+                }
+                ctx.request_paint();
+            }
+            return;
+        }
+        if self.disabled {
+            if self.pressed && matches!(ctx.event(), WindowEvent::MouseUp(_, _)) {
+                self.pressed = false;
+                ctx.set_active(false);
+            }
+            return;
+        }
+        let on_arrow = x >= self.layout_size.0 - ARROW_WIDTH;
+        match ctx.event() {
+            WindowEvent::CollectFocus => {
+                ctx.register_for_focus();
+                return;
+            }
+            WindowEvent::MouseDown(_, _) if on_arrow || !self.editable => {
+                self.pressed = true;
+                ctx.set_active(true);
+                ctx.request_focus();
+                ctx.set_handled();
+                ctx.request_paint();
+                return;
+            }
+            WindowEvent::MouseUp(_, _) if self.pressed => {
+                self.pressed = false;
+                ctx.set_active(false);
+                if hovering {
+                    if self.popup.is_some() {
+                        self.close(ctx);
+                    } else {
+                        self.open(ctx);
+                    }
+                }
+                ctx.request_paint();
+                return;
+            }
+            WindowEvent::KeyDown(key) if ctx.is_focused() && self.handle_key(ctx, data, key) => {
+                ctx.set_handled();
+                return;
+            }
+            WindowEvent::CharInput(_, c) if ctx.is_focused() && !self.editable => {
+                if !c.is_control() && !ctx.modifiers().ctrl {
+                    self.type_ahead(ctx, data, c);
+                    ctx.set_handled();
+                }
+                return;
+            }
+            _ => {}
+        }
+        if self.editable {
+            self.input.event(ctx, data);
+            if self.input.value() != self.filter_text {
+                // The typed text filters the list, which opens if it's closed
+                self.filter_text = self.input.value().to_string();
+                if self.popup.is_some() {
+                    self.state.borrow_mut().filter(Some(&self.filter_text));
+                    ctx.request_layout();
+                } else {
+                    self.open(ctx);
+                }
+            }
+            if hovering && on_arrow {
+                ctx.set_cursor(Cursor::Arrow);
+            }
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        let value = self.binding.get(data);
+        let index = {
+            let state = self.state.borrow();
+            state.items.iter().position(|item| &item.value == value)
+        };
+        let changed = {
+            let mut state = self.state.borrow_mut();
+            let changed = state.selected != index;
+            state.selected = index;
+            changed
+        };
+        if changed {
+            if self.editable && self.popup.is_none() {
+                let label = self.selected_label().unwrap_or_default();
+                self.input.set_value(&label);
+                self.filter_text = label;
+            }
+            ctx.request_paint();
+        }
+        // The list updates the item widgets while it's open
+        if self.popup.is_none() {
+            for item in &mut self.state.borrow_mut().items {
+                if let Some(widget) = &mut item.widget {
+                    widget.update(ctx, data);
+                }
+            }
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, data: &D) {
+        if self.editable {
+            self.input.draw(ctx, data);
+            self.draw_arrow(ctx);
+            return;
+        }
+        let (width, height) = self.layout_size;
+        let path = gen_round_rect_path(0.5, 0.5, width - 1., height - 1., RADIUS);
+        let background = if (self.hovering || self.pressed) && !self.disabled {
+            HOVER_BACKGROUND_COLOR
+        } else {
+            BACKGROUND_COLOR
+        };
+        ctx.fill_path(&path, &gen_paint(background));
+        let border = if ctx.is_focused() {
+            get_theme_color() | 0xFF
+        } else {
+            BORDER_COLOR
+        };
+        ctx.stroke_path(&path, &gen_paint(border), &Stroke::default());
+        let text_size = self.state.borrow().text_size;
+        let line_height = line_metrics(text_size, FontType::Normal).height;
+        let top = ((height - line_height) / 2.).round();
+        let (text, color) = match self.selected_label() {
+            Some(label) if self.disabled => (label, DISABLED_COLOR),
+            Some(label) => (label, TEXT_COLOR),
+            None => (self.placeholder.clone(), PLACEHOLDER_COLOR),
+        };
+        ctx.with_save(|ctx| {
+            ctx.clip_rect(1., 1., width - ARROW_WIDTH, height - 2.);
+            ctx.draw_text(PADDING_X, top, &text, text_size, color);
+        });
+        self.draw_arrow(ctx);
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let size = self.input.layout(max_box);
+        self.layout_size = size.to_size_f32();
+        size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Modifiers;
+
+    fn combo() -> ComboBox<&'static str, &'static str> {
+        ["Apple", "Apricot", "Banana", "Blueberry", "Cherry"]
+            .iter()
+            .fold(ComboBox::new(Binding::identity()), |combo, &v| {
+                combo.with_option(v, v)
+            })
+    }
+
+    fn ctx() -> EventCtx {
+        EventCtx::new(WindowEvent::None, (0., 0.), Modifiers::default(), None)
+    }
+
+    #[test]
+    fn filter_keeps_the_matching_items() {
+        let combo = combo();
+        let mut state = combo.state.borrow_mut();
+        state.filter(Some("AN"));
+        assert_eq!(state.visible, [2]);
+        assert_eq!(state.highlighted, Some(0));
+        state.filter(Some("xyz"));
+        assert!(state.visible.is_empty());
+        assert_eq!(state.highlighted, None);
+        state.selected = Some(3);
+        state.filter(None);
+        assert_eq!(state.visible.len(), 5);
+        assert_eq!(state.highlighted, Some(3));
+    }
+
+    #[test]
+    fn highlight_stays_in_the_list() {
+        let combo = combo();
+        let mut state = combo.state.borrow_mut();
+        state.filter(None);
+        state.move_highlight(-1);
+        assert_eq!(state.highlighted, Some(4));
+        state.move_highlight(-10);
+        assert_eq!(state.highlighted, Some(0));
+        state.move_highlight(2);
+        assert_eq!(state.highlighted, Some(2));
+        state.layout_rows(100.);
+        let height = state.item_height();
+        assert_eq!(state.row_at(height * 1.5), Some(1));
+        assert_eq!(state.row_at(height * 5.), None);
+    }
+
+    #[test]
+    fn typing_selects_by_prefix() {
+        let mut combo = combo();
+        let mut data = "";
+        let mut ctx = ctx();
+        combo.type_ahead(&mut ctx, &mut data, 'b');
+        assert_eq!(data, "Banana");
+        // The same letter again goes to the next option starting with it
+        combo.type_ahead(&mut ctx, &mut data, 'b');
+        assert_eq!(data, "Blueberry");
+        combo.type_ahead(&mut ctx, &mut data, 'b');
+        assert_eq!(data, "Banana");
+        combo.type_ahead = (String::new(), Instant::now());
+        combo.type_ahead(&mut ctx, &mut data, 'a');
+        combo.type_ahead(&mut ctx, &mut data, 'p');
+        combo.type_ahead(&mut ctx, &mut data, 'r');
+        assert_eq!(data, "Apricot");
+    }
+
+    #[test]
+    fn arrows_step_through_the_options() {
+        let mut combo = combo();
+        let mut data = "";
+        let mut ctx = ctx();
+        combo.step_selection(&mut ctx, &mut data, 1);
+        assert_eq!(data, "Apple");
+        combo.step_selection(&mut ctx, &mut data, 10);
+        assert_eq!(data, "Cherry");
+        let mut ctx = UpdateCtx::new();
+        combo.update(&mut ctx, &"Banana");
+        assert_eq!(combo.selected_label().as_deref(), Some("Banana"));
+    }
+}
@@ -0,0 +1,479 @@
+//! 对话框：模态地显示在窗口内容之上（或单独的模态窗口中），选择按钮或按 Escape 后关闭并给出结果
+//!
+//! 结果通过 `on_close` 回调和 `DialogResult` 返回，后者也可以作为 `Future` 等待；
+//! `message`、`confirm` 和 `prompt` 是常用的现成对话框
+
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+use tiny_skia::Stroke;
+
+use crate::core::{OwnedWindow, WidgetPod};
+use crate::events::{keys, WindowEvent};
+use crate::popup::Popup;
+use crate::r#box::AreaBox;
+use crate::traits::Widget;
+use crate::utils::drawing::{gen_paint, gen_round_rect_path};
+use crate::utils::text::{line_metrics, measure_text, wrap_text, FontType, TextLine};
+use crate::utils::uid::gen_uid;
+use crate::utils::WidgetUid;
+use crate::{DrawCtx, EventCtx, UpdateCtx};
+
+use super::button::ButtonControl;
+use super::input::InputControl;
+
+const DEFAULT_WIDTH: f32 = 360.;
+const PADDING: f32 = 20.;
+const RADIUS: f32 = 6.;
+const TITLE_SIZE: f32 = 16.;
+const TEXT_SIZE: f32 = 14.;
+const TITLE_COLOR: u32 = 0x202020FF;
+const TEXT_COLOR: u32 = 0x404040FF;
+const BACKGROUND_COLOR: u32 = 0xFFFFFFFF;
+const BORDER_COLOR: u32 = 0x00000030;
+/// Space between the title, the text, the content and the buttons
+const SPACING: f32 = 12.;
+const BUTTON_GAP: f32 = 8.;
+const BUTTON_MIN_WIDTH: f32 = 80.;
+/// Same as the padding of `ButtonControl`
+const BUTTON_PADDING: f32 = 16.;
+
+struct ResultState<R> {
+    /// Filled in when the dialog closes, `None` inside when it was cancelled without a value
+    value: Option<Option<R>>,
+    waker: Option<Waker>,
+}
+
+/// The answer of a dialog, available once it's closed.
+///
+/// Check it with `is_closed` and `take`, or await it, it resolves to the value of the button chosen.
+pub struct DialogResult<R> {
+    state: Rc<RefCell<ResultState<R>>>,
+}
+
+impl<R> Clone for DialogResult<R> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<R> DialogResult<R> {
+    fn new() -> Self {
+        Self {
+            state: Rc::new(RefCell::new(ResultState {
+                value: None,
+                waker: None,
+            })),
+        }
+    }
+
+    fn resolve(&self, value: Option<R>) {
+        let mut state = self.state.borrow_mut();
+        if state.value.is_none() {
+            state.value = Some(value);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.state.borrow().value.is_some()
+    }
+
+    /// Take the answer once the dialog is closed.
+    pub fn take(&self) -> Option<Option<R>> {
+        self.state.borrow_mut().value.take()
+    }
+}
+
+impl<R> Future for DialogResult<R> {
+    type Output = Option<R>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.borrow_mut();
+        match state.value.take() {
+            Some(value) => Poll::Ready(value),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+type ValueFn<R> = Rc<dyn Fn(&str) -> R>;
+type CloseCallback<D, R> = Rc<dyn Fn(&mut D, Option<R>)>;
+
+/// A modal dialog with a title, a message, optional content and a row of buttons.
+///
+/// `Enter` chooses the first button unless a focused widget uses the key, `Escape` chooses the cancel button,
+/// or closes the dialog without a value when there is none.
+pub struct Dialog<D, R = ()> {
+    _uid: WidgetUid,
+    title: String,
+    text: String,
+    lines: Vec<TextLine>,
+    content: Option<WidgetPod<D>>,
+    /// Each button gives its value from the text of the prompt input, or closes without a value
+    buttons: Vec<(WidgetPod<D>, Option<ValueFn<R>>)>,
+    /// Set by the buttons when clicked
+    clicked: Rc<Cell<Option<usize>>>,
+    cancel: Option<usize>,
+    /// Text of the input of a prompt
+    input_text: Rc<RefCell<String>>,
+    width: f32,
+    close_callback: Option<CloseCallback<D, R>>,
+    result: DialogResult<R>,
+    closed: bool,
+    layout_size: (f32, f32),
+}
+
+impl<D: 'static, R: 'static> Dialog<D, R> {
+    pub fn new(title: &str) -> Self {
+        Self {
+            _uid: gen_uid(),
+            title: title.into(),
+            text: String::new(),
+            lines: Vec::new(),
+            content: None,
+            buttons: Vec::new(),
+            clicked: Rc::new(Cell::new(None)),
+            cancel: None,
+            input_text: Rc::new(RefCell::new(String::new())),
+            width: DEFAULT_WIDTH,
+            close_callback: None,
+            result: DialogResult::new(),
+            closed: false,
+            layout_size: (0., 0.),
+        }
+    }
+
+    /// The message under the title, wrapped to the width of the dialog.
+    pub fn with_text(mut self, v: &str) -> Self {
+        self.text = v.into();
+        self
+    }
+
+    /// Widgets shown between the message and the buttons, e.g. a form.
+    pub fn with_content(mut self, v: Box<dyn Widget<D>>) -> Self {
+        self.content = Some(v.into());
+        self
+    }
+
+    /// Add a button closing the dialog with `value`, buttons are shown from left to right.
+    pub fn with_button(self, label: &str, value: R) -> Self
+    where
+        R: Clone,
+    {
+        self.with_value_button(label, Some(Rc::new(move |_| value.clone())))
+    }
+
+    /// Add the button chosen by `Escape` and by closing the window.
+    pub fn with_cancel_button(mut self, label: &str, value: R) -> Self
+    where
+        R: Clone,
+    {
+        self.cancel = Some(self.buttons.len());
+        self.with_button(label, value)
+    }
+
+    /// Width of the dialog, 360 by default.
+    pub fn with_width(mut self, v: f32) -> Self {
+        self.width = v;
+        self
+    }
+
+    /// Called when the dialog closes, with the value of the button chosen.
+    pub fn on_close<F: Fn(&mut D, Option<R>) + 'static>(mut self, v: F) -> Self {
+        self.close_callback = Some(Rc::new(v));
+        self
+    }
+
+    /// The answer of the dialog, also returned by `open` and `open_window`.
+    pub fn result(&self) -> DialogResult<R> {
+        self.result.clone()
+    }
+
+    /// Show the dialog over the window of the current widget, which takes no input until it's closed.
+    pub fn open(self, ctx: &mut EventCtx) -> DialogResult<R> {
+        let result = self.result();
+        ctx.open_popup(Popup::new(Box::new(self)).with_modal(true));
+        result
+    }
+
+    /// Show the dialog in its own window, the window of the current widget is disabled until it's closed.
+    pub fn open_window(self, ctx: &mut EventCtx) -> DialogResult<R> {
+        let result = self.result();
+        ctx.open_window(OwnedWindow::new(Box::new(self)).with_modal(true));
+        result
+    }
+
+    fn with_value_button(mut self, label: &str, value: Option<ValueFn<R>>) -> Self {
+        let index = self.buttons.len();
+        let clicked = self.clicked.clone();
+        let width = measure_text(label, TEXT_SIZE, FontType::Normal).ceil() + BUTTON_PADDING * 2.;
+        let button = ButtonControl::new(label)
+            .with_size(width.max(BUTTON_MIN_WIDTH) as u32, 0)
+            .on_click(move || clicked.set(Some(index)));
+        self.buttons
+            .push(((Box::new(button) as Box<dyn Widget<D>>).into(), value));
+        self
+    }
+
+    /// Close with the value of the button at `index`, `None` for no value.
+    fn finish(&mut self, ctx: &mut EventCtx, data: &mut D, index: Option<usize>) {
+        self.closed = true;
+        let text = self.input_text.borrow().clone();
+        let value = |buttons: &[(WidgetPod<D>, Option<ValueFn<R>>)]| {
+            index
+                .and_then(|i| buttons.get(i))
+                .and_then(|(_, value)| value.as_ref())
+                .map(|value| value(&text))
+        };
+        if let Some(callback) = &self.close_callback {
+            callback(data, value(&self.buttons));
+        }
+        self.result.resolve(value(&self.buttons));
+        if ctx.popup().is_some() {
+            ctx.dismiss_popup();
+        } else {
+            ctx.dismiss_window();
+        }
+    }
+}
+
+impl<D: 'static> Dialog<D, ()> {
+    /// A message with an OK button.
+    pub fn message(title: &str, text: &str) -> Self {
+        Dialog::new(title)
+            .with_text(text)
+            .with_cancel_button("OK", ())
+    }
+}
+
+impl<D: 'static> Dialog<D, bool> {
+    /// A question answered with OK (`true`) or Cancel (`false`).
+    pub fn confirm(title: &str, text: &str) -> Self {
+        Dialog::new(title)
+            .with_text(text)
+            .with_button("OK", true)
+            .with_cancel_button("Cancel", false)
+    }
+}
+
+impl<D: 'static> Dialog<D, String> {
+    /// Ask for a line of text, answered with the text, or `None` when cancelled.
+    pub fn prompt(title: &str, text: &str, initial: &str) -> Self {
+        let dialog = Dialog::new(title).with_text(text);
+        *dialog.input_text.borrow_mut() = initial.into();
+        let input_text = dialog.input_text.clone();
+        let clicked = dialog.clicked.clone();
+        let input = InputControl::new()
+            .with_value(initial)
+            .on_change(move |_, text| *input_text.borrow_mut() = text.into())
+            .on_submit(move |_, _| clicked.set(Some(0)));
+        let mut dialog = dialog
+            .with_content(Box::new(input))
+            .with_value_button("OK", Some(Rc::new(|text: &str| text.to_string())));
+        dialog.cancel = Some(1);
+        dialog.with_value_button("Cancel", None)
+    }
+}
+
+impl<D: 'static, R: 'static> Widget<D> for Dialog<D, R> {
+    fn event(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        if self.closed {
+            return;
+        }
+        if let Some(content) = &mut self.content {
+            content.event(ctx, data);
+        }
+        for (button, _) in &mut self.buttons {
+            button.event(ctx, data);
+        }
+        match ctx.event() {
+            WindowEvent::KeyDown(keys::RETURN) if !ctx.is_handled() && !self.buttons.is_empty() => {
+                self.clicked.set(Some(0));
+                ctx.set_handled();
+            }
+            WindowEvent::KeyDown(keys::ESCAPE) if !ctx.is_handled() => {
+                ctx.set_handled();
+                self.finish(ctx, data, self.cancel);
+                return;
+            }
+            // Closing the window answers like `Escape`
+            WindowEvent::CloseWindow => {
+                ctx.set_handled();
+                self.finish(ctx, data, self.cancel);
+                return;
+            }
+            _ => {}
+        }
+        if let Some(index) = self.clicked.take() {
+            self.finish(ctx, data, Some(index));
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        if let Some(content) = &mut self.content {
+            content.update(ctx, data);
+        }
+        for (button, _) in &mut self.buttons {
+            button.update(ctx, data);
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, data: &D) {
+        let (width, height) = self.layout_size;
+        let path = gen_round_rect_path(0.5, 0.5, width - 1., height - 1., RADIUS);
+        ctx.fill_path(&path, &gen_paint(BACKGROUND_COLOR));
+        ctx.stroke_path(&path, &gen_paint(BORDER_COLOR), &Stroke::default());
+        ctx.draw_text_custom(
+            PADDING,
+            PADDING,
+            &self.title,
+            TITLE_SIZE,
+            TITLE_COLOR,
+            FontType::Bold,
+        );
+        let line_height = line_metrics(TEXT_SIZE, FontType::Normal).height;
+        let top = PADDING + line_metrics(TITLE_SIZE, FontType::Bold).height.ceil() + SPACING;
+        for (i, line) in self.lines.iter().enumerate() {
+            let y = top + i as f32 * line_height;
+            ctx.draw_text(PADDING, y, &line.text, TEXT_SIZE, TEXT_COLOR);
+        }
+        if let Some(content) = &mut self.content {
+            content.draw(ctx, data);
+        }
+        for (button, _) in &mut self.buttons {
+            button.draw(ctx, data);
+        }
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let width = self.width.min(max_box.width() as f32).max(PADDING * 2.);
+        let inner_width = width - PADDING * 2.;
+        let mut y = PADDING + line_metrics(TITLE_SIZE, FontType::Bold).height.ceil() + SPACING;
+        if !self.text.is_empty() {
+            self.lines = wrap_text(&self.text, TEXT_SIZE, FontType::Normal, inner_width);
+            let line_height = line_metrics(TEXT_SIZE, FontType::Normal).height;
+            y += (self.lines.len() as f32 * line_height).ceil() + SPACING;
+        }
+        if let Some(content) = &mut self.content {
+            let remaining = (max_box.height() as f32 - y - PADDING).max(0.);
+            let size = content
+                .layout(AreaBox::with_size(inner_width as isize, remaining as isize))
+                .to_size_f32();
+            content.set_origin(PADDING, y);
+            y += size.1 + SPACING;
+        }
+        // Buttons are aligned to the right
+        let mut x = width - PADDING;
+        let mut row_height: f32 = 0.;
+        for (button, _) in self.buttons.iter_mut().rev() {
+            let (w, h) = button
+                .layout(AreaBox::with_size(inner_width as isize, isize::MAX))
+                .to_size_f32();
+            x -= w;
+            button.set_origin(x, y);
+            x -= BUTTON_GAP;
+            row_height = row_height.max(h);
+        }
+        self.layout_size = (width, (y + row_height + PADDING).ceil());
+        AreaBox::with_size(self.layout_size.0 as isize, self.layout_size.1 as isize)
+    }
+}
+
+impl<D, R> Drop for Dialog<D, R> {
+    fn drop(&mut self) {
+        // Closed some other way, e.g. with its window
+        self.result.resolve(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Modifiers;
+
+    fn key(
+        dialog: &mut Dialog<Vec<Option<bool>>, bool>,
+        data: &mut Vec<Option<bool>>,
+        key: usize,
+    ) -> EventCtx {
+        let mut ctx = EventCtx::new(
+            WindowEvent::KeyDown(key),
+            (f32::MIN, f32::MIN),
+            Modifiers::default(),
+            None,
+        );
+        ctx.popup = Some(1);
+        dialog.event(&mut ctx, data);
+        ctx
+    }
+
+    fn confirm() -> Dialog<Vec<Option<bool>>, bool> {
+        Dialog::confirm("Title", "Text")
+            .on_close(|answers: &mut Vec<Option<bool>>, answer| answers.push(answer))
+    }
+
+    #[test]
+    fn enter_chooses_the_first_button() {
+        let mut dialog = confirm();
+        let result = dialog.result();
+        let mut answers = Vec::new();
+        let ctx = key(&mut dialog, &mut answers, keys::RETURN);
+        assert_eq!(answers, vec![Some(true)]);
+        assert_eq!(result.take(), Some(Some(true)));
+        assert_eq!(ctx.close_popups, vec![1]);
+    }
+
+    #[test]
+    fn escape_chooses_the_cancel_button() {
+        let mut dialog = confirm();
+        let result = dialog.result();
+        let mut answers = Vec::new();
+        key(&mut dialog, &mut answers, keys::ESCAPE);
+        assert_eq!(answers, vec![Some(false)]);
+        assert_eq!(result.take(), Some(Some(false)));
+        // Nothing more once closed
+        key(&mut dialog, &mut answers, keys::RETURN);
+        assert_eq!(answers.len(), 1);
+    }
+
+    #[test]
+    fn escape_without_a_cancel_button_gives_no_value() {
+        let mut dialog = Dialog::new("Title").with_button("Yes", true);
+        let result = dialog.result();
+        key(&mut dialog, &mut Vec::new(), keys::ESCAPE);
+        assert_eq!(result.take(), Some(None));
+    }
+
+    #[test]
+    fn dropped_dialogs_resolve_without_a_value() {
+        let dialog = confirm();
+        let result = dialog.result();
+        assert!(!result.is_closed());
+        drop(dialog);
+        assert!(result.is_closed());
+        assert_eq!(result.take(), Some(None));
+    }
+
+    #[test]
+    fn buttons_are_aligned_to_the_right() {
+        let mut dialog = confirm().with_width(300.);
+        let size = dialog.layout(AreaBox::with_size(800, 600)).to_size_f32();
+        assert_eq!(size.0, 300.);
+        let (cancel, _) = &dialog.buttons[1];
+        let (ok, _) = &dialog.buttons[0];
+        assert!(ok.origin().0 < cancel.origin().0);
+        assert!(size.1 > cancel.origin().1);
+    }
+}
@@ -0,0 +1,690 @@
+//! 展开面板：点击标题显示或隐藏内容，高度用弹簧动画过渡，过渡期间内容被裁剪
+//!
+//! 手风琴把多个展开面板排成一列，可以只允许一个展开，也可以同时展开多个，展开的面板可以绑定到应用数据
+
+use std::rc::Rc;
+
+use tiny_skia::{PathBuilder, Stroke, Transform};
+
+use crate::core::WidgetPod;
+use crate::events::{keys, WindowEvent};
+use crate::r#box::AreaBox;
+use crate::traits::Widget;
+use crate::utils::binding::Binding;
+use crate::utils::drawing::{gen_paint, gen_rect_path, gen_round_rect_path};
+use crate::utils::spring::Spring;
+use crate::utils::text::{line_metrics, FontType};
+use crate::utils::theme::get_theme_color;
+use crate::utils::uid::gen_uid;
+use crate::utils::WidgetUid;
+use crate::{DrawCtx, EventCtx, UpdateCtx};
+
+const HEADER_HEIGHT: f32 = 36.;
+const PADDING: f32 = 12.;
+const TEXT_SIZE: f32 = 14.;
+const ARROW_SIZE: f32 = 16.;
+const TEXT_COLOR: u32 = 0x202020FF;
+const ARROW_COLOR: u32 = 0x606060FF;
+const HOVER_COLOR: u32 = 0x0000000C;
+const LINE_COLOR: u32 = 0x00000020;
+
+type ToggleCallback<D> = Rc<dyn Fn(&mut D, bool)>;
+type OpenCallback<D> = Rc<dyn Fn(&mut D, &[usize])>;
+
+/// A header and the content it reveals, shared by `Expander` and `Accordion`
+struct Section<D> {
+    title: String,
+    content: WidgetPod<D>,
+    expanded: bool,
+    /// Goes from 0 when collapsed to 1 when expanded
+    progress: Spring,
+    hovering: bool,
+    pressed: bool,
+    /// Height of the content when fully expanded
+    content_height: f32,
+    /// Position in the parent, set by the layout
+    top: f32,
+}
+
+impl<D> Section<D> {
+    fn new(title: &str, content: Box<dyn Widget<D>>) -> Self {
+        let mut progress = Spring::new(0.);
+        progress.speed = 16.;
+        Self {
+            title: title.into(),
+            content: content.into(),
+            expanded: false,
+            progress,
+            hovering: false,
+            pressed: false,
+            content_height: 0.,
+            top: 0.,
+        }
+    }
+
+    /// Returns whether the state changed, the height animates only when `animate`.
+    fn set_expanded(&mut self, v: bool, animate: bool) -> bool {
+        if self.expanded == v {
+            return false;
+        }
+        self.expanded = v;
+        let target = if v { 1. } else { 0. };
+        if animate {
+            self.progress.set_target(target);
+        } else {
+            self.progress.set_position(target);
+            self.progress.set_target(target);
+        }
+        true
+    }
+
+    /// Height of the visible part of the content.
+    fn visible_height(&mut self) -> f32 {
+        let progress = self.progress.position().clamp(0., 1.);
+        (self.content_height * progress).round()
+    }
+
+    fn height(&mut self) -> f32 {
+        HEADER_HEIGHT + self.visible_height()
+    }
+
+    /// Pass the event to the content, only what is visible of an expanded content takes input.
+    fn content_event(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        let (_, y) = ctx.mouse_pos();
+        let top = self.top + HEADER_HEIGHT;
+        let reaches = match ctx.event() {
+            WindowEvent::MouseDown(_, _)
+            | WindowEvent::MousePress(_, _)
+            | WindowEvent::ContextMenu(_, _)
+            | WindowEvent::MouseWheel(_)
+            | WindowEvent::MouseHWheel(_) => {
+                self.expanded && y >= top && y < top + self.visible_height()
+            }
+            WindowEvent::KeyDown(_)
+            | WindowEvent::KeyUp(_)
+            | WindowEvent::KeyPress(_)
+            | WindowEvent::CharInput(_, _)
+            | WindowEvent::CollectFocus => self.expanded,
+            WindowEvent::MouseMove(_, _) | WindowEvent::MouseUp(_, _) if !self.expanded => {
+                // The pointer is never on a collapsed content, it only sees it leave so hovers and drags end
+                let pos = (ctx.mouse_pos, ctx.window_mouse_pos);
+                ctx.mouse_pos = (f32::MIN, f32::MIN);
+                ctx.window_mouse_pos = ctx.mouse_pos;
+                self.content.event(ctx, data);
+                ctx.mouse_pos = pos.0;
+                ctx.window_mouse_pos = pos.1;
+                return;
+            }
+            _ => true,
+        };
+        if reaches {
+            self.content.event(ctx, data);
+        }
+    }
+
+    /// Track the pointer on the header, returns true when it's clicked.
+    fn header_event(&mut self, ctx: &mut EventCtx) -> bool {
+        let (x, y) = ctx.mouse_pos();
+        let (width, _) = ctx.widget_size();
+        let hovering = ctx.is_hovering()
+            && x >= 0.
+            && x < width
+            && y >= self.top
+            && y < self.top + HEADER_HEIGHT;
+        match ctx.event() {
+            WindowEvent::MouseMove(_, _) if hovering != self.hovering => {
+                self.hovering = hovering;
+                ctx.request_paint();
+            }
+            WindowEvent::MouseDown(_, _) if hovering && !ctx.is_handled() => {
+                self.pressed = true;
+                ctx.set_active(true);
+                ctx.request_focus();
+                ctx.set_handled();
+            }
+            WindowEvent::MouseUp(_, _) if self.pressed => {
+                self.pressed = false;
+                ctx.set_active(false);
+                return hovering;
+            }
+            _ => {}
+        }
+        false
+    }
+
+    fn layout(&mut self, width: isize, max_height: isize, top: f32) -> f32 {
+        let max_height = max_height.saturating_sub(HEADER_HEIGHT as isize).max(0);
+        let content = self.content.layout(AreaBox::with_size(width, max_height));
+        self.content_height = content.height() as f32;
+        self.content.set_origin(0., top + HEADER_HEIGHT);
+        self.top = top;
+        self.height()
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, data: &D, width: f32, focused: bool) {
+        let top = self.top;
+        let visible = self.visible_height();
+        if !self.progress.arrived() {
+            // The parents follow the height while it animates
+            ctx.request_layout();
+            ctx.request_anim_frame();
+        }
+        if self.hovering {
+            let path = gen_rect_path(0., top, width, HEADER_HEIGHT);
+            ctx.fill_path(&path, &gen_paint(HOVER_COLOR));
+        }
+        if focused {
+            let path = gen_round_rect_path(2.5, top + 2.5, width - 5., HEADER_HEIGHT - 5., 3.);
+            let paint = gen_paint(get_theme_color() | 0xFF);
+            ctx.stroke_path(&path, &paint, &Stroke::default());
+        }
+        // The arrow turns from right to down while expanding
+        let angle = self.progress.position().clamp(0., 1.) * 90.;
+        let mut pb = PathBuilder::new();
+        pb.move_to(-2., -4.);
+        pb.line_to(3., 0.);
+        pb.line_to(-2., 4.);
+        pb.close();
+        let (cx, cy) = (PADDING + ARROW_SIZE / 2., top + HEADER_HEIGHT / 2.);
+        if let Some(path) = pb
+            .finish()
+            .and_then(|p| p.transform(Transform::from_rotate(angle).post_translate(cx, cy)))
+        {
+            ctx.fill_path(&path, &gen_paint(ARROW_COLOR));
+        }
+        let text_x = PADDING + ARROW_SIZE + PADDING / 2.;
+        let line_height = line_metrics(TEXT_SIZE, FontType::Normal).height;
+        let title = &self.title;
+        ctx.with_save(|ctx| {
+            ctx.clip_rect(
+                text_x,
+                top,
+                (width - text_x - PADDING).max(0.),
+                HEADER_HEIGHT,
+            );
+            ctx.draw_text(
+                text_x,
+                top + (HEADER_HEIGHT - line_height) / 2.,
+                title,
+                TEXT_SIZE,
+                TEXT_COLOR,
+            );
+        });
+        if visible > 0. {
+            let content = &mut self.content;
+            ctx.with_save(|ctx| {
+                ctx.clip_rect(0., top + HEADER_HEIGHT, width, visible);
+                content.draw(ctx, data);
+            });
+        }
+        let line = gen_rect_path(0., top + HEADER_HEIGHT + visible - 1., width, 1.);
+        ctx.fill_path(&line, &gen_paint(LINE_COLOR));
+    }
+}
+
+/// A header revealing its content when clicked.
+pub struct Expander<D> {
+    _uid: WidgetUid,
+    section: Section<D>,
+    binding: Option<Binding<D, bool>>,
+    toggle_callback: Option<ToggleCallback<D>>,
+    layout_size: (f32, f32),
+}
+
+impl<D> Expander<D> {
+    pub fn new(title: &str, content: Box<dyn Widget<D>>) -> Self {
+        Self {
+            _uid: gen_uid(),
+            section: Section::new(title, content),
+            binding: None,
+            toggle_callback: None,
+            layout_size: (0., 0.),
+        }
+    }
+
+    /// Start expanded or collapsed, without animation.
+    pub fn with_expanded(mut self, v: bool) -> Self {
+        self.section.set_expanded(v, false);
+        self
+    }
+
+    /// Keep the expanded state in the application data.
+    pub fn with_binding(mut self, v: Binding<D, bool>) -> Self {
+        self.binding = Some(v);
+        self
+    }
+
+    /// Called with the new state when the user expands or collapses the content.
+    pub fn on_toggle<F: Fn(&mut D, bool) + 'static>(mut self, v: F) -> Self {
+        self.toggle_callback = Some(Rc::new(v));
+        self
+    }
+
+    #[inline]
+    pub fn set_title(&mut self, title: &str) {
+        self.section.title = title.into();
+    }
+
+    /// Expand or collapse with animation, a layout is needed afterwards.
+    pub fn set_expanded(&mut self, v: bool) {
+        self.section.set_expanded(v, true);
+    }
+
+    #[inline]
+    pub fn is_expanded(&self) -> bool {
+        self.section.expanded
+    }
+
+    fn toggle(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        let expanded = !self.section.expanded;
+        self.section.set_expanded(expanded, true);
+        if let Some(binding) = &self.binding {
+            *binding.get_mut(data) = expanded;
+        }
+        if let Some(callback) = &self.toggle_callback {
+            callback(data, expanded);
+        }
+        ctx.request_layout();
+        ctx.set_handled();
+    }
+}
+
+impl<D> Widget<D> for Expander<D> {
+    fn event(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        if ctx.event() == WindowEvent::CollectFocus {
+            // The header comes before the content in `Tab` order
+            ctx.register_for_focus();
+        }
+        self.section.content_event(ctx, data);
+        if self.section.header_event(ctx) {
+            self.toggle(ctx, data);
+        }
+        match ctx.event() {
+            WindowEvent::KeyDown(keys::SPACE) | WindowEvent::KeyDown(keys::RETURN)
+                if ctx.is_focused() && !ctx.is_handled() =>
+            {
+                self.toggle(ctx, data);
+            }
+            WindowEvent::FocusLost if self.section.hovering => {
+                self.section.hovering = false;
+                ctx.request_paint();
+            }
+            _ => {}
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        if let Some(binding) = &self.binding {
+            if self.section.set_expanded(*binding.get(data), true) {
+                ctx.request_layout();
+            }
+        }
+        self.section.content.update(ctx, data);
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, data: &D) {
+        let focused = ctx.is_focused();
+        self.section.draw(ctx, data, self.layout_size.0, focused);
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let height = self.section.layout(max_box.width(), max_box.height(), 0.);
+        self.layout_size = (max_box.width() as f32, height);
+        AreaBox::with_size(max_box.width(), height as isize)
+    }
+}
+
+/// Expanders in a column, with one or many of them open at a time.
+pub struct Accordion<D> {
+    _uid: WidgetUid,
+    sections: Vec<Section<D>>,
+    /// Let more than one section be open
+    multiple: bool,
+    /// Header moved with the arrow keys
+    cursor: usize,
+    binding: Option<Binding<D, Vec<usize>>>,
+    change_callback: Option<OpenCallback<D>>,
+    layout_size: (f32, f32),
+}
+
+impl<D> Accordion<D> {
+    pub fn new() -> Self {
+        Self {
+            _uid: gen_uid(),
+            sections: Vec::new(),
+            multiple: false,
+            cursor: 0,
+            binding: None,
+            change_callback: None,
+            layout_size: (0., 0.),
+        }
+    }
+
+    pub fn with_section(mut self, title: &str, content: Box<dyn Widget<D>>) -> Self {
+        self.add_section(title, content);
+        self
+    }
+
+    /// Start with a section open, without animation.
+    pub fn with_open(mut self, index: usize) -> Self {
+        self.open(index, true, false);
+        self
+    }
+
+    /// Let more than one section be open at a time.
+    pub fn with_multiple(mut self, v: bool) -> Self {
+        self.multiple = v;
+        self
+    }
+
+    /// Keep the indices of the open sections in the application data.
+    pub fn with_binding(mut self, v: Binding<D, Vec<usize>>) -> Self {
+        self.binding = Some(v);
+        self
+    }
+
+    /// Called with the indices of the open sections when the user opens or closes one.
+    pub fn on_change<F: Fn(&mut D, &[usize]) + 'static>(mut self, v: F) -> Self {
+        self.change_callback = Some(Rc::new(v));
+        self
+    }
+
+    pub fn add_section(&mut self, title: &str, content: Box<dyn Widget<D>>) {
+        self.sections.push(Section::new(title, content));
+    }
+
+    pub fn set_title(&mut self, index: usize, title: &str) {
+        if let Some(section) = self.sections.get_mut(index) {
+            section.title = title.into();
+        }
+    }
+
+    /// Open or close a section with animation, a layout is needed afterwards.
+    pub fn set_open(&mut self, index: usize, v: bool) {
+        self.open(index, v, true);
+    }
+
+    pub fn open_sections(&self) -> Vec<usize> {
+        self.sections
+            .iter()
+            .enumerate()
+            .filter(|(_, section)| section.expanded)
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    fn open(&mut self, index: usize, v: bool, animate: bool) {
+        if index >= self.sections.len() {
+            return;
+        }
+        if v && !self.multiple {
+            for (i, section) in self.sections.iter_mut().enumerate() {
+                if i != index {
+                    section.set_expanded(false, animate);
+                }
+            }
+        }
+        self.sections[index].set_expanded(v, animate);
+    }
+
+    fn toggle(&mut self, ctx: &mut EventCtx, data: &mut D, index: usize) {
+        let open = !self.sections[index].expanded;
+        self.open(index, open, true);
+        let sections = self.open_sections();
+        if let Some(callback) = &self.change_callback {
+            callback(data, &sections);
+        }
+        if let Some(binding) = &self.binding {
+            *binding.get_mut(data) = sections;
+        }
+        ctx.request_layout();
+        ctx.set_handled();
+    }
+}
+
+impl<D> Default for Accordion<D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<D> Widget<D> for Accordion<D> {
+    fn event(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        if ctx.event() == WindowEvent::CollectFocus {
+            // The headers come before the contents in `Tab` order
+            ctx.register_for_focus();
+        }
+        let mut clicked = None;
+        for (index, section) in self.sections.iter_mut().enumerate() {
+            section.content_event(ctx, data);
+            if section.header_event(ctx) {
+                clicked = Some(index);
+            }
+            if section.pressed && matches!(ctx.event(), WindowEvent::MouseDown(_, _)) {
+                self.cursor = index;
+            }
+        }
+        if let Some(index) = clicked {
+            self.toggle(ctx, data, index);
+        }
+        match ctx.event() {
+            WindowEvent::KeyDown(key) if ctx.is_focused() && !ctx.is_handled() => {
+                let last = self.sections.len().saturating_sub(1);
+                let cursor = match key {
+                    keys::UP => self.cursor.saturating_sub(1),
+                    keys::DOWN => (self.cursor + 1).min(last),
+                    keys::HOME => 0,
+                    keys::END => last,
+                    keys::SPACE | keys::RETURN if !self.sections.is_empty() => {
+                        self.toggle(ctx, data, self.cursor);
+                        return;
+                    }
+                    _ => return,
+                };
+                self.cursor = cursor;
+                ctx.request_paint();
+                ctx.set_handled();
+            }
+            WindowEvent::FocusLost => {
+                for section in &mut self.sections {
+                    section.hovering = false;
+                }
+                ctx.request_paint();
+            }
+            _ => {}
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        if let Some(binding) = &self.binding {
+            let open = binding.get(data);
+            let mut changed = false;
+            for (index, section) in self.sections.iter_mut().enumerate() {
+                changed |= section.set_expanded(open.contains(&index), true);
+            }
+            if changed {
+                ctx.request_layout();
+            }
+        }
+        for section in &mut self.sections {
+            section.content.update(ctx, data);
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, data: &D) {
+        let focused = ctx.is_focused();
+        let width = self.layout_size.0;
+        let cursor = self.cursor;
+        for (index, section) in self.sections.iter_mut().enumerate() {
+            section.draw(ctx, data, width, focused && index == cursor);
+        }
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let width = max_box.width();
+        let mut top = 0.;
+        for section in &mut self.sections {
+            top += section.layout(width, max_box.height(), top);
+        }
+        self.layout_size = (width as f32, top);
+        AreaBox::with_size(width, top as isize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::events::Modifiers;
+
+    /// Content 50 high
+    struct Block;
+
+    impl<D> Widget<D> for Block {
+        fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+            AreaBox::with_size(max_box.width(), 50)
+        }
+    }
+
+    fn send<D>(
+        widget: &mut dyn Widget<D>,
+        event: WindowEvent,
+        pos: (f32, f32),
+        data: &mut D,
+    ) -> EventCtx {
+        // The widget has the focus
+        let mut ctx = EventCtx::new(event, pos, Modifiers::default(), Some(0));
+        widget.event(&mut ctx, data);
+        ctx
+    }
+
+    fn click<D>(widget: &mut dyn Widget<D>, pos: (f32, f32), data: &mut D) {
+        send(widget, WindowEvent::MouseDown(0, 0), pos, data);
+        send(widget, WindowEvent::MouseUp(0, 0), pos, data);
+    }
+
+    fn accordion(multiple: bool) -> Accordion<Vec<usize>> {
+        let mut accordion = Accordion::new()
+            .with_section("One", Box::new(Block))
+            .with_section("Two", Box::new(Block))
+            .with_section("Three", Box::new(Block))
+            .with_multiple(multiple)
+            .with_binding(Binding::identity());
+        accordion.layout(AreaBox::with_size(200, 400));
+        accordion
+    }
+
+    #[test]
+    fn clicking_the_header_toggles() {
+        let mut expander =
+            Expander::new("Title", Box::new(Block)).with_binding(Binding::identity());
+        let mut expanded = false;
+        let size = expander.layout(AreaBox::with_size(200, 400));
+        assert_eq!(size.height(), HEADER_HEIGHT as isize);
+        click(&mut expander, (10., 10.), &mut expanded);
+        assert!(expanded && expander.is_expanded());
+        // Clicks below the header don't toggle
+        click(&mut expander, (10., HEADER_HEIGHT + 10.), &mut expanded);
+        assert!(expanded);
+    }
+
+    #[test]
+    fn opened_without_animation_has_the_full_height() {
+        let mut expander = Expander::<()>::new("Title", Box::new(Block)).with_expanded(true);
+        let size = expander.layout(AreaBox::with_size(200, 400));
+        assert_eq!(size.height(), HEADER_HEIGHT as isize + 50);
+    }
+
+    /// Content remembering whether the pointer was on it
+    struct Hover(Rc<Cell<bool>>);
+
+    impl Widget<()> for Hover {
+        fn event(&mut self, ctx: &mut EventCtx, _data: &mut ()) {
+            self.0.set(ctx.is_hovering());
+        }
+
+        fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+            AreaBox::with_size(max_box.width(), 50)
+        }
+    }
+
+    #[test]
+    fn collapsed_content_is_never_under_the_pointer() {
+        let hovered = Rc::new(Cell::new(false));
+        let mut expander = Expander::new("Title", Box::new(Hover(hovered.clone())));
+        expander.layout(AreaBox::with_size(200, 400));
+        let below = (10., HEADER_HEIGHT + 10.);
+        send(&mut expander, WindowEvent::MouseMove(0, 0), below, &mut ());
+        assert!(!hovered.get());
+        expander.section.set_expanded(true, false);
+        expander.layout(AreaBox::with_size(200, 400));
+        send(&mut expander, WindowEvent::MouseMove(0, 0), below, &mut ());
+        assert!(hovered.get());
+    }
+
+    #[test]
+    fn expanding_asks_for_frames() {
+        let mut expander = Expander::<()>::new("Title", Box::new(Block));
+        expander.layout(AreaBox::with_size(200, 400));
+        expander.section.set_expanded(true, true);
+        let mut pixmap = tiny_skia::Pixmap::new(200, 100).unwrap();
+        let mut pixmap = pixmap.as_mut();
+        let mut ctx = DrawCtx::new(&mut pixmap);
+        expander.draw(&mut ctx, &());
+        assert!(ctx.request_anim);
+    }
+
+    #[test]
+    fn one_section_is_open_at_a_time() {
+        let mut accordion = accordion(false);
+        let mut open = Vec::new();
+        click(&mut accordion, (10., 10.), &mut open);
+        assert_eq!(open, [0]);
+        click(&mut accordion, (10., HEADER_HEIGHT * 2. + 10.), &mut open);
+        assert_eq!(open, [2]);
+    }
+
+    #[test]
+    fn several_sections_may_be_open() {
+        let mut accordion = accordion(true);
+        let mut open = Vec::new();
+        click(&mut accordion, (10., 10.), &mut open);
+        click(&mut accordion, (10., HEADER_HEIGHT * 2. + 10.), &mut open);
+        assert_eq!(open, [0, 2]);
+    }
+
+    #[test]
+    fn keys_move_between_the_headers() {
+        let mut accordion = accordion(false);
+        let mut open = Vec::new();
+        send(
+            &mut accordion,
+            WindowEvent::KeyDown(keys::END),
+            (0., 0.),
+            &mut open,
+        );
+        send(
+            &mut accordion,
+            WindowEvent::KeyDown(keys::UP),
+            (0., 0.),
+            &mut open,
+        );
+        send(
+            &mut accordion,
+            WindowEvent::KeyDown(keys::SPACE),
+            (0., 0.),
+            &mut open,
+        );
+        assert_eq!(open, [1]);
+    }
+
+    #[test]
+    fn sections_follow_the_binding() {
+        let mut accordion = accordion(true);
+        let mut ctx = UpdateCtx::new();
+        accordion.update(&mut ctx, &vec![1, 2]);
+        assert!(ctx.request_layout);
+        assert_eq!(accordion.open_sections(), [1, 2]);
+    }
+}
@@ -1,75 +1,742 @@
-use tiny_skia::Pixmap;
+use std::io::Cursor;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
-use crate::DrawCtx;
-use crate::traits::{Widget};
+use ::image::codecs::gif::GifDecoder;
+use ::image::codecs::png::PngDecoder;
+use ::image::codecs::webp::WebPDecoder;
+use ::image::{AnimationDecoder, Frames, ImageFormat};
+use tiny_skia::{FilterQuality, Pixmap, PixmapPaint};
+
+use crate::events::{TimerToken, WindowEvent};
+use crate::r#box::AreaBox;
+use crate::traits::Widget;
+use crate::utils::drawing::rgba_to_pixmap;
+use crate::utils::svg::{parse_svg, render_svg, svg_size, SvgError, SvgTree};
 use crate::utils::uid::gen_uid;
-use crate::utils::{WidgetUid};
+use crate::utils::WidgetUid;
+use crate::{DrawCtx, EventCtx, UpdateCtx};
+
+use ::image::error::{DecodingError, ImageFormatHint, ParameterError, ParameterErrorKind};
+pub use ::image::ImageError;
+
+/// 图片如何填充控件的区域
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FillStrategy {
+    /// Stretch the image to the whole area, ignoring its aspect ratio
+    Fill,
+    /// Scale the image to fit inside the area
+    Contain,
+    /// Scale the image to cover the whole area, cropping the overflow
+    Cover,
+    /// Same as `Contain`, but never enlarge the image
+    ScaleDown,
+    /// Keep the original size
+    None,
+}
+
+/// 图片在控件区域内某个方向上的对齐方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageAlign {
+    Start,
+    Center,
+    End,
+}
+
+impl ImageAlign {
+    fn offset(self, space: f32) -> f32 {
+        match self {
+            ImageAlign::Start => 0.,
+            ImageAlign::Center => space / 2.,
+            ImageAlign::End => space,
+        }
+    }
+}
+
+/// Browsers play frames of this delay or shorter with about 100 ms instead, so do we
+const CLAMPED_FRAME_DELAY: Duration = Duration::from_millis(10);
+const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+
+/// 动图中的一帧，已经按照帧的处置与混合方式合成为完整的画面
+pub struct ImageFrame {
+    pub pixmap: Pixmap,
+    pub delay: Duration,
+}
+
+impl Clone for ImageFrame {
+    fn clone(&self) -> Self {
+        Self {
+            pixmap: self.pixmap.clone(),
+            delay: self.delay,
+        }
+    }
+}
+
+impl ImageFrame {
+    /// A transparent pixel, shown when there is no image.
+    fn blank() -> Self {
+        Self {
+            pixmap: Pixmap::new(1, 1).unwrap(),
+            delay: Duration::ZERO,
+        }
+    }
+}
+
+fn find(data: &[u8], pattern: &[u8]) -> Option<usize> {
+    data.windows(pattern.len()).position(|w| w == pattern)
+}
+
+/// How many times the animation should be played, `None` for forever.
+///
+/// The decoders don't expose it, so it's read from the headers directly.
+fn read_plays(format: ImageFormat, data: &[u8]) -> Option<u32> {
+    match format {
+        // NETSCAPE2.0 extension, the count is the number of repeats after the first play
+        ImageFormat::Gif => match find(data, b"NETSCAPE2.0") {
+            Some(i) => match data.get(i + 13..i + 15) {
+                Some(&[0, 0]) => None,
+                Some(&[lo, hi]) => Some(u16::from_le_bytes([lo, hi]) as u32 + 1),
+                _ => Some(1),
+            },
+            None => Some(1),
+        },
+        // acTL chunk: frame count then play count, big endian
+        ImageFormat::Png => find(data, b"acTL")
+            .and_then(|i| data.get(i + 8..i + 12))
+            .map(|v| u32::from_be_bytes([v[0], v[1], v[2], v[3]]))
+            .and_then(|v| if v == 0 { None } else { Some(v) }),
+        // ANIM chunk: chunk size, background colour then loop count, little endian
+        ImageFormat::WebP => find(data, b"ANIM")
+            .and_then(|i| data.get(i + 12..i + 14))
+            .map(|v| u16::from_le_bytes([v[0], v[1]]) as u32)
+            .and_then(|v| if v == 0 { None } else { Some(v) }),
+        _ => Some(1),
+    }
+}
+
+fn convert_frames(frames: Frames) -> Result<Vec<ImageFrame>, ImageError> {
+    let mut result = Vec::new();
+    for frame in frames {
+        let frame = frame?;
+        let delay = Duration::from(frame.delay());
+        let buffer = frame.into_buffer();
+        let pixmap = rgba_to_pixmap(buffer.width(), buffer.height(), buffer.as_raw())
+            .ok_or_else(empty_image_error)?;
+        result.push(ImageFrame {
+            pixmap,
+            delay: if delay <= CLAMPED_FRAME_DELAY {
+                DEFAULT_FRAME_DELAY
+            } else {
+                delay
+            },
+        });
+    }
+    if result.is_empty() {
+        return Err(empty_image_error());
+    }
+    Ok(result)
+}
+
+/// Decode all the frames of an image, a still image has only one frame.
+fn decode(data: &[u8]) -> Result<(Vec<ImageFrame>, Option<u32>), ImageError> {
+    let format = ::image::guess_format(data)?;
+    let frames = match format {
+        ImageFormat::Gif => Some(GifDecoder::new(Cursor::new(data))?.into_frames()),
+        ImageFormat::Png => {
+            let decoder = PngDecoder::new(Cursor::new(data))?;
+            if decoder.is_apng() {
+                Some(decoder.apng().into_frames())
+            } else {
+                None
+            }
+        }
+        ImageFormat::WebP => {
+            let decoder = WebPDecoder::new(Cursor::new(data))?;
+            if decoder.has_animation() {
+                Some(decoder.into_frames())
+            } else {
+                None
+            }
+        }
+        _ => None,
+    };
+    if let Some(frames) = frames {
+        return Ok((convert_frames(frames)?, read_plays(format, data)));
+    }
+    let img = ::image::load_from_memory_with_format(data, format)?.to_rgba8();
+    let pixmap =
+        rgba_to_pixmap(img.width(), img.height(), img.as_raw()).ok_or_else(empty_image_error)?;
+    Ok((
+        vec![ImageFrame {
+            pixmap,
+            delay: Duration::ZERO,
+        }],
+        Some(1),
+    ))
+}
+
+/// An image without any pixel can't be turned into a `Pixmap`
+fn empty_image_error() -> ImageError {
+    ImageError::Parameter(ParameterError::from_kind(
+        ParameterErrorKind::DimensionMismatch,
+    ))
+}
 
 pub struct ImageControl {
-    pos: (i32, i32),
-    g_pos: (i32, i32),
+    _uid: WidgetUid,
+    /// Wanted size, 0 means following the image
     size: (u32, u32),
-    img: Pixmap,
-    need_update: bool,
+    layout_size: (f32, f32),
+    /// Vector source of the image, rendered again when the drawn size changes
+    svg: Option<Rc<SvgTree>>,
+    frames: Vec<ImageFrame>,
+    current: usize,
+    /// When the current frame has been shown, moved forward while paused
+    frame_start: Instant,
+    paused_at: Option<Instant>,
+    /// Total plays of the animation, `None` for forever
+    plays: Option<u32>,
+    played: u32,
+    /// Shows the next frame of an animation
+    frame_timer: Option<TimerToken>,
+    /// Set when the animation is controlled from the code, the next update asks for a paint
+    need_paint: bool,
+    fill: FillStrategy,
+    align: (ImageAlign, ImageAlign),
+    quality: FilterQuality,
 }
+
 impl ImageControl {
-    pub fn new(x: i32, y: i32, w: u32, h: u32) -> Self {
-        ImageControl {
-            pos: (x, y),
-            size: (w, h),
-            g_pos: (x as i32, y as i32),
-            ..Default::default()
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn from_pixmap(img: Pixmap) -> Self {
+        let mut r = Self::default();
+        r.set_img(img);
+        r
+    }
+
+    /// Decode an image in PNG, JPEG, GIF or WebP format.
+    ///
+    /// Animated GIF, APNG and WebP images are played in loop as their files tell.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, ImageError> {
+        let (frames, plays) = decode(data)?;
+        let mut r = Self::default();
+        r.set_frames(frames, plays);
+        Ok(r)
+    }
+
+    /// Parse an SVG image, it's rendered crisply at the size it's drawn.
+    pub fn from_svg(data: &[u8]) -> Result<Self, SvgError> {
+        let tree = parse_svg(data)?;
+        let mut r = Self::default();
+        r.set_svg(tree);
+        Ok(r)
+    }
+
+    /// Load an image file, `.svg` and `.svgz` files are parsed as vector images.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, ImageError> {
+        let path = path.as_ref();
+        let data = std::fs::read(path).map_err(ImageError::IoError)?;
+        let is_svg = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("svg") || ext.eq_ignore_ascii_case("svgz"));
+        if is_svg {
+            Self::from_svg(&data).map_err(|e| {
+                ImageError::Decoding(DecodingError::new(
+                    ImageFormatHint::Name("SVG".to_string()),
+                    e,
+                ))
+            })
+        } else {
+            Self::from_bytes(&data)
         }
     }
+
+    pub fn with_size(mut self, w: u32, h: u32) -> Self {
+        self.size = (w, h);
+        self
+    }
+
+    pub fn with_fill(mut self, v: FillStrategy) -> Self {
+        self.fill = v;
+        self
+    }
+
+    /// Horizontal and vertical alignment of the image when it doesn't fill the area.
+    pub fn with_align(mut self, x: ImageAlign, y: ImageAlign) -> Self {
+        self.align = (x, y);
+        self
+    }
+
+    /// `FilterQuality::Nearest` keeps pixel arts sharp, the others are smoother.
+    pub fn with_quality(mut self, v: FilterQuality) -> Self {
+        self.quality = v;
+        self
+    }
+
+    /// Override how many times the animation is played, `None` for forever.
+    pub fn with_plays(mut self, v: Option<u32>) -> Self {
+        self.plays = v;
+        self
+    }
+
     pub fn set_img(&mut self, img: Pixmap) {
-        self.img = img;
+        self.set_frames(
+            vec![ImageFrame {
+                pixmap: img,
+                delay: Duration::ZERO,
+            }],
+            Some(1),
+        );
+    }
+
+    /// Replace the image with an animation, frames must have the same size.
+    ///
+    /// Without frames the control is empty like a new one.
+    pub fn set_frames(&mut self, frames: Vec<ImageFrame>, plays: Option<u32>) {
+        self.svg = None;
+        self.frames = if frames.is_empty() {
+            vec![ImageFrame::blank()]
+        } else {
+            frames
+        };
+        self.plays = plays;
+        self.restart();
+    }
+
+    pub fn set_svg(&mut self, tree: SvgTree) {
+        let pixmap = render_svg(&tree, 1.).unwrap_or_else(|| Pixmap::new(1, 1).unwrap());
+        self.set_img(pixmap);
+        self.svg = Some(Rc::new(tree));
+    }
+
+    #[inline]
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    #[inline]
+    pub fn current_frame(&self) -> usize {
+        self.current
+    }
+
+    #[inline]
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    /// Whether the animation has played all its loops.
+    pub fn is_finished(&self) -> bool {
+        self.plays.is_some_and(|plays| self.played >= plays)
+    }
+
+    pub fn pause(&mut self) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(Instant::now());
+            self.need_paint = true;
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if let Some(paused_at) = self.paused_at.take() {
+            self.frame_start += paused_at.elapsed();
+            self.need_paint = true;
+        }
+    }
+
+    /// Play again from the first frame.
+    pub fn restart(&mut self) {
+        self.current = 0;
+        self.played = 0;
+        self.frame_start = Instant::now();
+        if self.paused_at.is_some() {
+            self.paused_at = Some(self.frame_start);
+        }
+        self.need_paint = true;
+    }
+
+    /// Show a frame from its beginning, keeps the paused state.
+    pub fn seek(&mut self, frame: usize) {
+        self.current = frame.min(self.frames.len() - 1);
+        self.frame_start = Instant::now();
+        if self.paused_at.is_some() {
+            self.paused_at = Some(self.frame_start);
+        }
+        self.need_paint = true;
+    }
+
+    fn img(&self) -> &Pixmap {
+        &self.frames[self.current].pixmap
+    }
+
+    /// Move to the frame to show now, returns whether another frame will come.
+    fn advance(&mut self) -> bool {
+        if self.frames.len() < 2 || self.is_finished() {
+            return false;
+        }
+        let now = self.paused_at.unwrap_or_else(Instant::now);
+        loop {
+            let delay = self.frames[self.current].delay;
+            if now.duration_since(self.frame_start) < delay {
+                break;
+            }
+            if self.current + 1 == self.frames.len() {
+                self.played += 1;
+                if self.is_finished() {
+                    return false;
+                }
+                self.current = 0;
+            } else {
+                self.current += 1;
+            }
+            self.frame_start += delay;
+        }
+        self.paused_at.is_none()
+    }
+
+    /// Time left before the current frame is replaced.
+    fn frame_remaining(&self) -> Duration {
+        let shown = Instant::now().saturating_duration_since(self.frame_start);
+        self.frames[self.current].delay.saturating_sub(shown)
+    }
+
+    /// Size of the image before scaling, declared by the file for vector images.
+    fn natural_size(&self) -> (f32, f32) {
+        match &self.svg {
+            Some(tree) => svg_size(tree),
+            None => (self.img().width() as f32, self.img().height() as f32),
+        }
+    }
+
+    /// Scale of the image in the area of the control.
+    fn scale(&self, area: (f32, f32)) -> (f32, f32) {
+        let (iw, ih) = self.natural_size();
+        let (sx, sy) = (area.0 / iw, area.1 / ih);
+        match self.fill {
+            FillStrategy::Fill => (sx, sy),
+            FillStrategy::Contain => (sx.min(sy), sx.min(sy)),
+            FillStrategy::Cover => (sx.max(sy), sx.max(sy)),
+            FillStrategy::ScaleDown => {
+                let s = sx.min(sy).min(1.);
+                (s, s)
+            }
+            FillStrategy::None => (1., 1.),
+        }
     }
 }
+
 // 默认值
 impl Default for ImageControl {
     fn default() -> Self {
         Self {
-            pos: (0, 0),
+            _uid: gen_uid(),
             size: (0, 0),
-            g_pos: (0, 0),
-            need_update: true,
-            img: Pixmap::new(1, 1).unwrap(),
+            layout_size: (0., 0.),
+            svg: None,
+            frames: vec![ImageFrame::blank()],
+            current: 0,
+            frame_start: Instant::now(),
+            paused_at: None,
+            plays: Some(1),
+            played: 0,
+            frame_timer: None,
+            need_paint: false,
+            fill: FillStrategy::Contain,
+            align: (ImageAlign::Center, ImageAlign::Center),
+            quality: FilterQuality::Bilinear,
         }
     }
 }
+
 impl<D> Widget<D> for ImageControl {
-    fn update(&mut self, _data: &D) {
-        
-    }
-    fn draw(&mut self, _ctx: &mut DrawCtx<'_>, _data: &D) {
-        /*
-        let draw_x = x as f32;
-        let draw_y = y as f32;
-        let width = self.size.0 as f32;
-        let height = self.size.1 as f32;
-        // 绘图在这里
-        f.draw_image_with_size_at(
-            width,
-            height,
-            draw_x,
-            draw_y,
-            &self.img,
-            &Default::default(),
-        );
-         */
-        self.need_update = false;
+    fn event(&mut self, ctx: &mut EventCtx, _data: &mut D) {
+        if let WindowEvent::Timer(token) = ctx.event() {
+            if self.frame_timer == Some(token) {
+                self.frame_timer = None;
+                ctx.request_paint();
+            }
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _data: &D) {
+        // Drawing shows the frame and waits for the next one
+        if self.need_paint {
+            self.need_paint = false;
+            ctx.request_paint();
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx<'_>, _data: &D) {
+        // Wait for the next frame with a timer, a hidden animation waits until it's drawn again
+        if self.advance() && self.frame_timer.is_none() && ctx.is_visible() {
+            self.frame_timer = Some(ctx.request_timer(self.frame_remaining()));
+        }
+        let (width, height) = self.layout_size;
+        let (sx, sy) = self.scale((width, height));
+        let (iw, ih) = self.natural_size();
+        let (draw_width, draw_height) = (iw * sx, ih * sy);
+        let x = self.align.0.offset(width - draw_width);
+        let y = self.align.1.offset(height - draw_height);
+        // Vector images are already rendered at about the drawn size
+        let sx = draw_width / self.img().width() as f32;
+        let sy = draw_height / self.img().height() as f32;
+        let paint = PixmapPaint {
+            quality: self.quality,
+            ..Default::default()
+        };
+        let img = &self.frames[self.current].pixmap;
+        ctx.with_save(|ctx| {
+            ctx.clip_rect(0., 0., width, height);
+            ctx.translate(x, y);
+            ctx.scale(sx, sy);
+            ctx.draw_pixmap(0, 0, img.as_ref(), &paint);
+        });
     }
 
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let (iw, ih) = self.natural_size();
+        // Only one side given, keep the aspect ratio of the image
+        let (width, height) = match self.size {
+            (0, 0) => (iw, ih),
+            (w, 0) => (w as f32, w as f32 * ih / iw),
+            (0, h) => (h as f32 * iw / ih, h as f32),
+            (w, h) => (w as f32, h as f32),
+        };
+        self.layout_size = (
+            width.min(max_box.width() as f32),
+            height.min(max_box.height() as f32),
+        );
+        if let Some(tree) = self.svg.clone() {
+            // Render with the larger scale, the drawing stretches the other side
+            let (sx, sy) = self.scale(self.layout_size);
+            let zoom = sx.max(sy);
+            let size = ((iw * zoom).ceil() as u32, (ih * zoom).ceil() as u32);
+            if size != (self.img().width(), self.img().height()) {
+                if let Some(pixmap) = render_svg(&tree, zoom) {
+                    self.frames[0].pixmap = pixmap;
+                }
+            }
+        }
+        AreaBox::with_size(self.layout_size.0 as isize, self.layout_size.1 as isize)
+    }
 }
 
-impl<'a, 'b> Clone for ImageControl {
+impl Clone for ImageControl {
     fn clone(&self) -> Self {
         Self {
+            _uid: gen_uid(),
             size: self.size,
-            pos: self.pos,
-            g_pos: self.g_pos,
-            need_update: true,
-            img: self.img.clone(),
+            layout_size: self.layout_size,
+            svg: self.svg.clone(),
+            frames: self.frames.clone(),
+            current: 0,
+            frame_start: Instant::now(),
+            paused_at: self.paused_at.map(|_| Instant::now()),
+            plays: self.plays,
+            played: 0,
+            frame_timer: None,
+            need_paint: false,
+            fill: self.fill,
+            align: self.align,
+            quality: self.quality,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(fill: FillStrategy) -> ImageControl {
+        ImageControl::from_pixmap(Pixmap::new(40, 20).unwrap()).with_fill(fill)
+    }
+
+    #[test]
+    fn fill_strategies_scale_the_image() {
+        let area = (80., 80.);
+        assert_eq!(image(FillStrategy::Fill).scale(area), (2., 4.));
+        assert_eq!(image(FillStrategy::Contain).scale(area), (2., 2.));
+        assert_eq!(image(FillStrategy::Cover).scale(area), (4., 4.));
+        assert_eq!(image(FillStrategy::ScaleDown).scale(area), (1., 1.));
+        assert_eq!(image(FillStrategy::ScaleDown).scale((20., 20.)), (0.5, 0.5));
+        assert_eq!(image(FillStrategy::None).scale(area), (1., 1.));
+    }
+
+    #[test]
+    fn alignment_shares_the_free_space() {
+        assert_eq!(ImageAlign::Start.offset(30.), 0.);
+        assert_eq!(ImageAlign::Center.offset(30.), 15.);
+        assert_eq!(ImageAlign::End.offset(30.), 30.);
+        // A covering image overflows on both sides
+        assert_eq!(ImageAlign::Center.offset(-30.), -15.);
+    }
+
+    #[test]
+    fn one_given_side_keeps_the_aspect_ratio() {
+        let mut img = image(FillStrategy::Contain).with_size(80, 0);
+        let size = Widget::<()>::layout(&mut img, AreaBox::with_size(200, 200));
+        assert_eq!((size.width(), size.height()), (80, 40));
+        let mut img = image(FillStrategy::Contain).with_size(0, 10);
+        let size = Widget::<()>::layout(&mut img, AreaBox::with_size(200, 200));
+        assert_eq!((size.width(), size.height()), (20, 10));
+        let mut img = image(FillStrategy::Contain);
+        let size = Widget::<()>::layout(&mut img, AreaBox::with_size(30, 200));
+        assert_eq!((size.width(), size.height()), (30, 20));
+    }
+
+    #[test]
+    fn decodes_png() {
+        let mut data = Vec::new();
+        let img = ::image::RgbaImage::from_pixel(3, 2, ::image::Rgba([255, 0, 0, 128]));
+        img.write_to(
+            &mut std::io::Cursor::new(&mut data),
+            ::image::ImageFormat::Png,
+        )
+        .unwrap();
+        let control = ImageControl::from_bytes(&data).unwrap();
+        assert_eq!((control.img().width(), control.img().height()), (3, 2));
+        // Colours are premultiplied
+        let pixel = control.img().pixel(0, 0).unwrap();
+        assert_eq!((pixel.red(), pixel.alpha()), (128, 128));
+        assert!(ImageControl::from_bytes(b"not an image").is_err());
+    }
+
+    fn frames(delays: &[u64]) -> Vec<ImageFrame> {
+        delays
+            .iter()
+            .map(|&ms| ImageFrame {
+                pixmap: Pixmap::new(2, 2).unwrap(),
+                delay: Duration::from_millis(ms),
+            })
+            .collect()
+    }
+
+    fn animation(plays: Option<u32>) -> ImageControl {
+        let mut img = ImageControl::new();
+        img.set_frames(frames(&[100, 50, 100]), plays);
+        img
+    }
+
+    /// Pretend the current frame has been shown for some time
+    fn wait(img: &mut ImageControl, ms: u64) {
+        img.frame_start -= Duration::from_millis(ms);
+    }
+
+    #[test]
+    fn frames_follow_their_delays() {
+        let mut img = animation(None);
+        assert!(img.advance());
+        assert_eq!(img.current_frame(), 0);
+        wait(&mut img, 120);
+        assert!(img.advance());
+        assert_eq!(img.current_frame(), 1);
+        // Late frames are skipped, looping back to the start
+        wait(&mut img, 200);
+        assert!(img.advance());
+        assert_eq!(img.current_frame(), 0);
+    }
+
+    #[test]
+    fn stops_after_the_last_play() {
+        let mut img = animation(Some(1));
+        wait(&mut img, 260);
+        assert!(!img.advance());
+        assert!(img.is_finished());
+        assert_eq!(img.current_frame(), 2);
+        img.restart();
+        assert!(!img.is_finished());
+        assert_eq!(img.current_frame(), 0);
+    }
+
+    #[test]
+    fn paused_animation_keeps_its_frame() {
+        let mut img = animation(None);
+        img.pause();
+        assert!(img.is_paused());
+        wait(&mut img, 50);
+        assert!(!img.advance());
+        assert_eq!(img.current_frame(), 0);
+        img.seek(10);
+        assert_eq!(img.current_frame(), 2);
+        assert!(img.is_paused());
+        img.resume();
+        assert!(img.advance());
+        assert_eq!(img.current_frame(), 2);
+    }
+
+    #[test]
+    fn reads_the_loop_counts() {
+        let gif = |count: [u8; 2]| {
+            let mut data = b"GIF89a..!\xff\x0bNETSCAPE2.0\x03\x01".to_vec();
+            data.extend_from_slice(&count);
+            data
+        };
+        assert_eq!(read_plays(ImageFormat::Gif, &gif([0, 0])), None);
+        assert_eq!(read_plays(ImageFormat::Gif, &gif([2, 0])), Some(3));
+        assert_eq!(read_plays(ImageFormat::Gif, b"GIF89a"), Some(1));
+        let apng = b"\0\0\0\x08acTL\0\0\0\x03\0\0\0\x02";
+        assert_eq!(read_plays(ImageFormat::Png, apng), Some(2));
+        let webp = b"ANIM\x06\0\0\0\0\0\0\0\x04\0";
+        assert_eq!(read_plays(ImageFormat::WebP, webp), Some(4));
+    }
+
+    #[test]
+    fn short_gif_delays_use_the_default() {
+        let mut data = Vec::new();
+        {
+            let mut encoder = ::image::codecs::gif::GifEncoder::new(&mut data);
+            encoder
+                .set_repeat(::image::codecs::gif::Repeat::Infinite)
+                .unwrap();
+            for ms in [0, 10, 20, 200] {
+                let buffer = ::image::RgbaImage::from_pixel(2, 2, ::image::Rgba([0, 0, 255, 255]));
+                let delay = ::image::Delay::from_numer_denom_ms(ms, 1);
+                encoder
+                    .encode_frame(::image::Frame::from_parts(buffer, 0, 0, delay))
+                    .unwrap();
+            }
+        }
+        let img = ImageControl::from_bytes(&data).unwrap();
+        assert_eq!(img.frame_count(), 4);
+        assert_eq!(img.plays, None);
+        let delays: Vec<_> = img.frames.iter().map(|f| f.delay.as_millis()).collect();
+        assert_eq!(delays, [100, 100, 20, 200]);
+    }
+
+    #[test]
+    fn no_frames_leave_the_control_empty() {
+        let mut img = animation(Some(1));
+        img.set_frames(Vec::new(), None);
+        assert_eq!(img.frame_count(), 1);
+        assert_eq!(img.natural_size(), (1., 1.));
+        assert!(!img.advance());
+    }
+
+    #[test]
+    fn playback_controls_request_a_paint() {
+        let mut img = animation(Some(1));
+        let mut ctx = UpdateCtx::new();
+        Widget::<()>::update(&mut img, &mut ctx, &());
+        img.pause();
+        let mut ctx = UpdateCtx::new();
+        Widget::<()>::update(&mut img, &mut ctx, &());
+        assert!(ctx.request_paint);
+        for control in [ImageControl::resume, ImageControl::restart] {
+            control(&mut img);
+            let mut ctx = UpdateCtx::new();
+            Widget::<()>::update(&mut img, &mut ctx, &());
+            assert!(ctx.request_paint);
         }
+        img.seek(1);
+        let mut ctx = UpdateCtx::new();
+        Widget::<()>::update(&mut img, &mut ctx, &());
+        assert!(ctx.request_paint);
+        // Only once
+        let mut ctx = UpdateCtx::new();
+        Widget::<()>::update(&mut img, &mut ctx, &());
+        assert!(!ctx.request_paint);
     }
 }
@@ -1,69 +1,877 @@
-//! 这里是一个控件的基本模板
-//! 以留备用方便做新控件
+//! 单行输入框，另有密码、数字和格式掩码三种模式
 
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
+use tiny_skia::{PathBuilder, Stroke};
+use unicode_segmentation::UnicodeSegmentation;
 
+use crate::events::{keys, TimerToken, WindowEvent};
+use crate::r#box::AreaBox;
+use crate::system::enums::Cursor;
+use crate::system::{get_clipboard_text, number_separators, set_clipboard_text};
+use crate::traits::Widget;
+use crate::utils::drawing::{gen_paint, gen_rect_path, gen_round_rect_path};
+use crate::utils::editor::{grapheme_offsets, hit_offset, offset_x, word_at, TextEditor};
+use crate::utils::text::{line_metrics, measure_text, FontType};
+use crate::utils::theme::get_theme_color;
+use crate::utils::uid::gen_uid;
+use crate::utils::WidgetUid;
+use crate::{DrawCtx, EventCtx};
 
-use crate::traits::{Widget};
+const PADDING_X: f32 = 8.;
+const PADDING_Y: f32 = 6.;
+const RADIUS: f32 = 4.;
+/// Width used when neither the size nor the parent gives one
+const DEFAULT_WIDTH: f32 = 160.;
+const BACKGROUND_COLOR: u32 = 0xFFFFFFFF;
+const BORDER_COLOR: u32 = 0x00000024;
+const TEXT_COLOR: u32 = 0x202020FF;
+const PLACEHOLDER_COLOR: u32 = 0x20202070;
+const ICON_COLOR: u32 = 0x20202090;
+const ERROR_COLOR: u32 = 0xD13438FF;
+/// Width of the reveal button and the spinner buttons on the right
+const ACCESSORY_WIDTH: f32 = 24.;
+const BULLET: &str = "•";
+const CARET_BLINK: Duration = Duration::from_millis(530);
+const DOUBLE_CLICK: Duration = Duration::from_millis(500);
+/// Holding a spinner button steps again after the delay, then at the interval
+const SPIN_DELAY: Duration = Duration::from_millis(400);
+const SPIN_INTERVAL: Duration = Duration::from_millis(60);
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputMode {
+    Text,
+    /// Obscure the text, it can be revealed with the button on the right but never copied
+    Password,
+    /// A number with spinner buttons, the arrow keys and the wheel change it by `step`
+    Number {
+        min: f64,
+        max: f64,
+        step: f64,
+    },
+    /// Format the text with a pattern, `9` takes a digit, `a` a letter and `*` either of them,
+    /// other chars are inserted as they are, e.g. `(999) 999-9999` or `9999-99-99`
+    Mask(String),
+}
+
+fn is_slot(c: char) -> bool {
+    matches!(c, '9' | 'a' | '*')
+}
+
+fn slot_accepts(slot: char, c: char) -> bool {
+    match slot {
+        '9' => c.is_ascii_digit(),
+        'a' => c.is_alphabetic(),
+        _ => c.is_alphanumeric(),
+    }
+}
 
-use crate::utils::{uid::gen_uid};
-use crate::utils::{WidgetUid};
+/// Put the typed chars into the mask, returns the formatted text and where the caret goes.
+fn apply_mask(mask: &str, text: &str, caret: usize) -> (String, usize) {
+    let pattern: Vec<char> = mask.chars().collect();
+    // Chars matching the literal at the same place were inserted by the mask
+    let typed = |(i, (_, c)): (usize, (usize, char))| {
+        if pattern.get(i).is_some_and(|&m| !is_slot(m) && m == c) {
+            None
+        } else {
+            Some(c).filter(|c| c.is_alphanumeric())
+        }
+    };
+    let before = text
+        .char_indices()
+        .enumerate()
+        .filter(|(_, (b, _))| *b < caret)
+        .filter_map(typed)
+        .count();
+    let mut input = text.char_indices().enumerate().filter_map(typed).peekable();
+    let (mut out, mut out_caret, mut used) = (String::new(), 0, 0);
+    for (i, &m) in pattern.iter().enumerate() {
+        if is_slot(m) {
+            while input.peek().is_some_and(|&c| !slot_accepts(m, c)) {
+                input.next();
+                used += 1;
+            }
+            match input.next() {
+                Some(c) => {
+                    out.push(c);
+                    used += 1;
+                    if used <= before {
+                        out_caret = out.len();
+                    }
+                }
+                None => break,
+            }
+        } else if input.peek().is_some() || !pattern[i..].iter().any(|&c| is_slot(c)) {
+            out.push(m);
+            if used <= before && used > 0 {
+                out_caret = out.len();
+            }
+        } else {
+            break;
+        }
+    }
+    (out, out_caret)
+}
 
-pub struct InputControl {
+/// Parse a number written with the separators of the user's locale.
+fn parse_number(text: &str, (decimal, group): (char, char)) -> Option<f64> {
+    let text: String = text
+        .trim()
+        .chars()
+        .filter(|&c| c != group && !c.is_whitespace())
+        .map(|c| if c == decimal { '.' } else { c })
+        .collect();
+    text.parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+/// Format a number with as many decimals as the step has.
+fn format_number(value: f64, step: f64, decimal: char) -> String {
+    let step = format!("{}", step);
+    let decimals = step.split('.').nth(1).map_or(0, |d| d.len().min(6));
+    format!("{:.*}", decimals, value).replace('.', &decimal.to_string())
+}
+
+type TextCallback<D> = Rc<dyn Fn(&mut D, &str)>;
+
+pub struct InputControl<D = ()> {
     _uid: WidgetUid,
-    pos: (i32, i32),
-    g_pos: (i32, i32),
+    /// Wanted size, 0 means filling the width or fitting the text height
     size: (u32, u32),
-    value: String,
-    need_update: bool,
+    layout_size: (f32, f32),
+    editor: TextEditor,
+    placeholder: String,
+    text_size: f32,
+    /// How far the text is scrolled to the left to keep the caret visible
+    scroll_x: f32,
+    caret_visible: bool,
+    blink_timer: Option<TimerToken>,
+    dragging: bool,
+    /// Time and position of the last click, to detect double clicks
+    last_click: Option<(Instant, usize)>,
+    change_callback: Option<TextCallback<D>>,
+    submit_callback: Option<TextCallback<D>>,
+    mode: InputMode,
+    /// The password is shown as it is
+    revealed: bool,
+    /// Decimal and grouping separators of the user's locale
+    separators: (char, char),
+    /// The spinner button being held and its repeat timer
+    spinning: Option<(f64, TimerToken)>,
+    /// Space kept free on the right for a parent drawing over the input
+    trailing: f32,
 }
-impl InputControl {
-    pub fn new(x: i32, y: i32, w: u32, h: u32) -> Self {
-        InputControl {
-            pos: (x, y),
-            size: (w, h),
-            g_pos: (x as i32, y as i32),
-            ..Default::default()
+
+impl<D> Default for InputControl<D> {
+    fn default() -> Self {
+        Self {
+            _uid: gen_uid(),
+            size: (0, 0),
+            layout_size: (0., 0.),
+            editor: TextEditor::new(false),
+            placeholder: String::new(),
+            text_size: 14.,
+            scroll_x: 0.,
+            caret_visible: true,
+            blink_timer: None,
+            dragging: false,
+            last_click: None,
+            change_callback: None,
+            submit_callback: None,
+            mode: InputMode::Text,
+            revealed: false,
+            separators: number_separators(),
+            spinning: None,
+            trailing: 0.,
+        }
+    }
+}
+
+impl<D> InputControl<D> {
+    /// The parent places the field, it fills the width given unless `with_size` asks for a size.
+    ///
+    /// This replaces `new(x, y, w, h)`: positions are no longer given to controls.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_size(mut self, w: u32, h: u32) -> Self {
+        self.size = (w, h);
+        self
+    }
+
+    pub fn with_value(mut self, v: &str) -> Self {
+        self.set_value(v);
+        self
+    }
+
+    /// Text shown in a lighter colour while the input is empty.
+    pub fn with_placeholder(mut self, v: &str) -> Self {
+        self.placeholder = v.into();
+        self
+    }
+
+    pub fn with_text_size(mut self, v: f32) -> Self {
+        self.text_size = v;
+        self
+    }
+
+    /// Limit the number of chars, pasted texts are cut to fit.
+    pub fn with_max_length(mut self, v: usize) -> Self {
+        self.editor.set_max_length(Some(v));
+        self
+    }
+
+    pub fn with_mode(mut self, v: InputMode) -> Self {
+        self.set_mode(v);
+        self
+    }
+
+    /// Called with the new text after every edit made by the user.
+    pub fn on_change<F: Fn(&mut D, &str) + 'static>(mut self, v: F) -> Self {
+        self.change_callback = Some(Rc::new(v));
+        self
+    }
+
+    /// Called with the text when Return is pressed.
+    pub fn on_submit<F: Fn(&mut D, &str) + 'static>(mut self, v: F) -> Self {
+        self.submit_callback = Some(Rc::new(v));
+        self
+    }
+
+    /// Replace the text without calling `on_change`, the undo history is cleared.
+    #[inline]
+    pub fn set_value(&mut self, value: &str) {
+        self.editor.set_text(value);
+    }
+
+    #[inline]
+    pub fn value(&self) -> &str {
+        self.editor.text()
+    }
+
+    #[inline]
+    pub fn set_placeholder(&mut self, placeholder: &str) {
+        self.placeholder = placeholder.into();
+    }
+
+    #[inline]
+    pub fn set_max_length(&mut self, max_length: Option<usize>) {
+        self.editor.set_max_length(max_length);
+    }
+
+    /// Change the mode, the text of a mask is formatted again.
+    pub fn set_mode(&mut self, mode: InputMode) {
+        self.mode = mode;
+        self.revealed = false;
+        if let InputMode::Mask(mask) = &self.mode {
+            let (text, _) = apply_mask(mask, self.editor.text(), 0);
+            self.editor.set_text(&text);
         }
     }
 
     #[inline]
-    pub fn set_value(&mut self, value: String) {
-        self.value = value;
+    pub fn mode(&self) -> &InputMode {
+        &self.mode
+    }
+
+    /// Show or hide the password.
+    #[inline]
+    pub fn set_revealed(&mut self, revealed: bool) {
+        self.revealed = revealed;
+    }
+
+    #[inline]
+    pub fn is_revealed(&self) -> bool {
+        self.revealed
+    }
+
+    /// The parsed value in the number mode, `None` if the text is not a number.
+    pub fn number(&self) -> Option<f64> {
+        parse_number(self.editor.text(), self.separators)
     }
 
+    /// Set the value of the number mode, it's clamped into the range.
+    pub fn set_number(&mut self, value: f64) {
+        if let InputMode::Number { min, max, step } = self.mode {
+            let text = format_number(value.max(min).min(max), step, self.separators.0);
+            self.editor.set_text(&text);
+        }
+    }
+
+    /// Keep some space on the right free of text, e.g. for the arrow of a combo box.
     #[inline]
-    pub fn value(&mut self) -> &String {
-        &self.value
+    pub(crate) fn set_trailing(&mut self, width: f32) {
+        self.trailing = width;
+    }
+
+    /// Whether the text is a number in range or fills the mask, an empty text is always valid.
+    pub fn is_valid(&self) -> bool {
+        let text = self.editor.text();
+        match &self.mode {
+            _ if text.is_empty() => true,
+            InputMode::Number { min, max, .. } => {
+                self.number().is_some_and(|v| v >= *min && v <= *max)
+            }
+            InputMode::Mask(mask) => text.chars().count() == mask.chars().count(),
+            _ => true,
+        }
+    }
+
+    fn obscured(&self) -> bool {
+        self.mode == InputMode::Password && !self.revealed
+    }
+
+    fn accessory_width(&self) -> f32 {
+        match self.mode {
+            InputMode::Password | InputMode::Number { .. } => ACCESSORY_WIDTH,
+            _ => 0.,
+        }
+    }
+
+    /// The text as it's displayed.
+    fn display_text(&self) -> String {
+        if self.obscured() {
+            BULLET.repeat(self.editor.text().graphemes(true).count())
+        } else {
+            self.editor.text().to_string()
+        }
+    }
+
+    fn offsets(&self) -> Vec<(usize, f32)> {
+        let text = self.editor.text();
+        if self.obscured() {
+            // Every grapheme is shown as a bullet
+            let width = measure_text(BULLET, self.text_size, FontType::Normal);
+            let mut offsets = vec![(0, 0.)];
+            for (i, (pos, grapheme)) in text.grapheme_indices(true).enumerate() {
+                offsets.push((pos + grapheme.len(), (i + 1) as f32 * width));
+            }
+            offsets
+        } else {
+            grapheme_offsets(text, self.text_size, FontType::Normal)
+        }
+    }
+
+    /// Byte position under the horizontal mouse position.
+    fn hit_test(&self, x: f32) -> usize {
+        hit_offset(&self.offsets(), x - PADDING_X + self.scroll_x)
+    }
+
+    /// Whether the chars can be typed in the current mode.
+    fn accepts(&self, c: char) -> bool {
+        match self.mode {
+            InputMode::Number { .. } => {
+                let (decimal, group) = self.separators;
+                c.is_ascii_digit() || c == '-' || c == '+' || c == decimal || c == group
+            }
+            InputMode::Mask(_) => c.is_alphanumeric(),
+            _ => true,
+        }
+    }
+
+    fn insert(&mut self, text: &str) -> bool {
+        let text: String = text.chars().filter(|&c| self.accepts(c)).collect();
+        let edited = self.editor.insert(&text);
+        self.format_mask();
+        edited
+    }
+
+    /// Format the text again after an edit in the mask mode.
+    fn format_mask(&mut self) {
+        if let InputMode::Mask(mask) = &self.mode {
+            let (text, caret) = apply_mask(mask, self.editor.text(), self.editor.caret());
+            self.editor.rewrite(&text, caret);
+        }
+    }
+
+    /// Let deleting in the mask mode skip the chars inserted by the mask.
+    fn skip_literals(&mut self, forward: bool) {
+        if !matches!(self.mode, InputMode::Mask(_)) || self.editor.has_selection() {
+            return;
+        }
+        let text = self.editor.text();
+        let mut caret = self.editor.caret();
+        if forward {
+            while let Some(c) = text[caret..]
+                .chars()
+                .next()
+                .filter(|c| !c.is_alphanumeric())
+            {
+                caret += c.len_utf8();
+            }
+        } else {
+            while let Some(c) = text[..caret]
+                .chars()
+                .last()
+                .filter(|c| !c.is_alphanumeric())
+            {
+                caret -= c.len_utf8();
+            }
+        }
+        self.editor.set_caret(caret, false);
+    }
+
+    /// Change the number by some steps, snapping it to the steps from `min`.
+    fn spin(&mut self, ctx: &mut EventCtx, data: &mut D, steps: f64) {
+        // A step of zero or less can't move the value
+        if let InputMode::Number { min, max, step } = self.mode {
+            if step.is_nan() || step <= 0. {
+                return;
+            }
+            let current = self.number().unwrap_or_else(|| 0f64.max(min).min(max));
+            let value = min + ((current + steps * step - min) / step).round() * step;
+            let text = format_number(value.max(min).min(max), step, self.separators.0);
+            if text != self.editor.text() {
+                self.editor.select_all();
+                self.editor.insert(&text);
+                self.changed(ctx, data);
+            }
+            self.scroll_to_caret();
+        }
+    }
+
+    /// Scroll the text so the caret stays inside the box.
+    fn scroll_to_caret(&mut self) {
+        let offsets = self.offsets();
+        let caret_x = offset_x(&offsets, self.editor.caret());
+        let text_width = offsets.last().map_or(0., |(_, x)| *x);
+        let visible =
+            (self.layout_size.0 - PADDING_X * 2. - self.accessory_width() - self.trailing).max(1.);
+        if caret_x < self.scroll_x {
+            self.scroll_x = caret_x;
+        } else if caret_x > self.scroll_x + visible {
+            self.scroll_x = caret_x - visible;
+        }
+        self.scroll_x = self.scroll_x.min(text_width - visible).max(0.);
+    }
+
+    /// Show the caret right away and blink again from now.
+    fn restart_blink(&mut self, ctx: &mut EventCtx) {
+        self.caret_visible = true;
+        self.blink_timer = Some(ctx.request_timer(CARET_BLINK));
+    }
+
+    fn changed(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        if let Some(callback) = &self.change_callback {
+            callback(data, self.editor.text());
+        }
+        ctx.request_paint();
+    }
+
+    fn handle_key(&mut self, ctx: &mut EventCtx, data: &mut D, key: usize) {
+        let modifiers = ctx.modifiers();
+        let (ctrl, shift) = (modifiers.ctrl, modifiers.shift);
+        let number = matches!(self.mode, InputMode::Number { .. });
+        let password = self.mode == InputMode::Password;
+        // Words of a hidden password should not be guessed from the caret
+        let word = ctrl && !self.obscured();
+        let end = self.editor.text().len();
+        let mut edited = false;
+        match key {
+            keys::UP if number => return self.spin(ctx, data, 1.),
+            keys::DOWN if number => return self.spin(ctx, data, -1.),
+            keys::PRIOR if number => return self.spin(ctx, data, 10.),
+            keys::NEXT if number => return self.spin(ctx, data, -10.),
+            keys::LEFT if ctrl && !word => self.editor.set_caret(0, shift),
+            keys::RIGHT if ctrl && !word => self.editor.set_caret(end, shift),
+            keys::LEFT => self.editor.move_left(word, shift),
+            keys::RIGHT => self.editor.move_right(word, shift),
+            keys::HOME | keys::UP => self.editor.set_caret(0, shift),
+            keys::END | keys::DOWN => self.editor.set_caret(end, shift),
+            keys::BACK => {
+                if ctrl && !word {
+                    self.editor.set_caret(0, true);
+                }
+                self.skip_literals(false);
+                edited = self.editor.backspace(word);
+                self.format_mask();
+            }
+            keys::DELETE => {
+                if ctrl && !word {
+                    self.editor.set_caret(end, true);
+                }
+                self.skip_literals(true);
+                edited = self.editor.delete(word);
+                self.format_mask();
+            }
+            keys::A if ctrl => self.editor.select_all(),
+            keys::C if ctrl => {
+                if self.editor.has_selection() && !password {
+                    set_clipboard_text(self.editor.selected_text());
+                }
+            }
+            keys::X if ctrl => {
+                if !password {
+                    if let Some(text) = self.editor.cut() {
+                        set_clipboard_text(&text);
+                        self.format_mask();
+                        edited = true;
+                    }
+                }
+            }
+            keys::V if ctrl => {
+                if let Some(text) = get_clipboard_text() {
+                    edited = self.insert(&text);
+                }
+            }
+            keys::Z if ctrl && shift => edited = self.editor.redo(),
+            keys::Z if ctrl => edited = self.editor.undo(),
+            keys::Y if ctrl => edited = self.editor.redo(),
+            keys::RETURN => {
+                if let Some(callback) = &self.submit_callback {
+                    callback(data, self.editor.text());
+                }
+            }
+            _ => return,
+        }
+        ctx.set_handled();
+        if edited {
+            self.changed(ctx, data);
+        }
+        self.scroll_to_caret();
+        self.restart_blink(ctx);
+        ctx.request_paint();
+    }
+}
+
+impl<D> InputControl<D> {
+    /// The reveal button of the password mode, crossed out while the password is hidden.
+    fn draw_eye(&self, ctx: &mut DrawCtx) {
+        let (width, height) = self.layout_size;
+        let (cx, cy) = (width - ACCESSORY_WIDTH / 2. - 2., height / 2.);
+        let color = if self.revealed {
+            get_theme_color() | 0xFF
+        } else {
+            ICON_COLOR
+        };
+        let paint = gen_paint(color);
+        let mut pb = PathBuilder::new();
+        pb.move_to(cx - 7., cy);
+        pb.quad_to(cx, cy - 7., cx + 7., cy);
+        pb.quad_to(cx, cy + 7., cx - 7., cy);
+        pb.close();
+        if let Some(path) = pb.finish() {
+            ctx.stroke_path(&path, &paint, &Stroke::default());
+        }
+        if let Some(path) = PathBuilder::from_circle(cx, cy, 2.) {
+            ctx.fill_path(&path, &paint);
+        }
+        if !self.revealed {
+            let mut pb = PathBuilder::new();
+            pb.move_to(cx - 6., cy + 6.);
+            pb.line_to(cx + 6., cy - 6.);
+            if let Some(path) = pb.finish() {
+                ctx.stroke_path(&path, &paint, &Stroke::default());
+            }
+        }
+    }
+
+    /// The up and down buttons of the number mode.
+    fn draw_spinner(&self, ctx: &mut DrawCtx) {
+        let (width, height) = self.layout_size;
+        let left = width - ACCESSORY_WIDTH;
+        let cx = left + ACCESSORY_WIDTH / 2.;
+        let paint = gen_paint(ICON_COLOR);
+        ctx.fill_path(
+            &gen_rect_path(left, 4., 1., height - 8.),
+            &gen_paint(BORDER_COLOR),
+        );
+        for (cy, dir) in [(height / 4. + 1., -1.), (height * 3. / 4. - 1., 1.)].iter() {
+            let mut pb = PathBuilder::new();
+            pb.move_to(cx - 4., cy - dir * 2.);
+            pb.line_to(cx + 4., cy - dir * 2.);
+            pb.line_to(cx, cy + dir * 2.);
+            pb.close();
+            if let Some(path) = pb.finish() {
+                ctx.fill_path(&path, &paint);
+            }
+        }
     }
 }
-// 克隆
-impl Clone for InputControl {
+
+impl<D> Clone for InputControl<D> {
     fn clone(&self) -> Self {
         Self {
-            size: self.size,
-            pos: self.pos,
-            g_pos: self.g_pos,
-            need_update: true,
-            value: self.value.clone(),
             _uid: gen_uid(),
+            size: self.size,
+            layout_size: self.layout_size,
+            editor: self.editor.clone(),
+            placeholder: self.placeholder.clone(),
+            text_size: self.text_size,
+            scroll_x: self.scroll_x,
+            change_callback: self.change_callback.clone(),
+            submit_callback: self.submit_callback.clone(),
+            mode: self.mode.clone(),
+            trailing: self.trailing,
+            ..Default::default()
         }
     }
 }
 
-// 默认值
-impl Default for InputControl {
-    fn default() -> Self {
-        Self {
-            _uid: gen_uid(),
-            pos: (0, 0),
-            size: (0, 0),
-            g_pos: (0, 0),
-            value: String::with_capacity(256),
-            need_update: true,
+impl<D> Widget<D> for InputControl<D> {
+    fn event(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        let hovering = ctx.is_hovering();
+        let (x, y) = ctx.mouse_pos();
+        let accessory = self.accessory_width();
+        let on_accessory = accessory > 0. && x >= self.layout_size.0 - accessory;
+        match ctx.event() {
+            WindowEvent::CollectFocus => ctx.register_for_focus(),
+            WindowEvent::MouseDown(_, _) if on_accessory => {
+                if self.mode == InputMode::Password {
+                    self.revealed = !self.revealed;
+                    self.scroll_to_caret();
+                } else {
+                    let steps = if y < self.layout_size.1 / 2. { 1. } else { -1. };
+                    self.spin(ctx, data, steps);
+                    self.spinning = Some((steps, ctx.request_timer(SPIN_DELAY)));
+                    ctx.set_active(true);
+                }
+                ctx.request_focus();
+                ctx.set_handled();
+                ctx.request_paint();
+            }
+            WindowEvent::MouseUp(_, _) if self.spinning.is_some() => {
+                self.spinning = None;
+                ctx.set_active(false);
+            }
+            WindowEvent::Timer(token) if self.spinning.map(|s| s.1) == Some(token) => {
+                if let Some((steps, _)) = self.spinning {
+                    self.spin(ctx, data, steps);
+                    self.spinning = Some((steps, ctx.request_timer(SPIN_INTERVAL)));
+                }
+            }
+            WindowEvent::MouseWheel(delta)
+                if ctx.is_focused() && matches!(self.mode, InputMode::Number { .. }) =>
+            {
+                self.spin(ctx, data, delta.signum() as f64);
+                ctx.set_handled();
+            }
+            WindowEvent::MouseDown(_, _) => {
+                let pos = self.hit_test(x);
+                let now = Instant::now();
+                let double = match self.last_click {
+                    Some((time, last)) => last == pos && now - time < DOUBLE_CLICK,
+                    None => false,
+                };
+                if double && self.obscured() {
+                    self.editor.select_all();
+                    self.last_click = None;
+                } else if double {
+                    self.editor.select(word_at(self.editor.text(), pos));
+                    self.last_click = None;
+                } else {
+                    self.editor.set_caret(pos, ctx.modifiers().shift);
+                    self.last_click = Some((now, pos));
+                    self.dragging = true;
+                    ctx.set_active(true);
+                }
+                ctx.request_focus();
+                ctx.set_handled();
+                self.scroll_to_caret();
+                self.restart_blink(ctx);
+            }
+            WindowEvent::MouseMove(_, _) if self.dragging => {
+                let pos = self.hit_test(x);
+                if pos != self.editor.caret() {
+                    self.editor.set_caret(pos, true);
+                    self.scroll_to_caret();
+                    self.restart_blink(ctx);
+                    ctx.request_paint();
+                }
+            }
+            WindowEvent::MouseUp(_, _) if self.dragging => {
+                self.dragging = false;
+                ctx.set_active(false);
+            }
+            WindowEvent::KeyDown(key) if ctx.is_focused() => self.handle_key(ctx, data, key),
+            WindowEvent::CharInput(_, c) if ctx.is_focused() => {
+                // Ctrl with a letter sends control chars, AltGr sends Ctrl+Alt with printable ones
+                let modifiers = ctx.modifiers();
+                if !c.is_control() && (!modifiers.ctrl || modifiers.alt) {
+                    let mut buf = [0; 4];
+                    if self.insert(c.encode_utf8(&mut buf)) {
+                        self.changed(ctx, data);
+                    }
+                    ctx.set_handled();
+                    self.scroll_to_caret();
+                    self.restart_blink(ctx);
+                }
+            }
+            WindowEvent::Timer(token) if self.blink_timer == Some(token) => {
+                if ctx.is_focused() {
+                    self.caret_visible = !self.caret_visible;
+                    self.blink_timer = Some(ctx.request_timer(CARET_BLINK));
+                } else {
+                    self.blink_timer = None;
+                }
+                ctx.request_paint();
+            }
+            _ => {}
+        }
+        if self.dragging || (hovering && !on_accessory) {
+            ctx.set_cursor(Cursor::IBeam);
         }
     }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, _data: &D) {
+        let (width, height) = self.layout_size;
+        let focused = ctx.is_focused();
+        let path = gen_round_rect_path(0.5, 0.5, width - 1., height - 1., RADIUS);
+        ctx.fill_path(&path, &gen_paint(BACKGROUND_COLOR));
+        let border = if !self.is_valid() {
+            ERROR_COLOR
+        } else if focused {
+            get_theme_color() | 0xFF
+        } else {
+            BORDER_COLOR
+        };
+        ctx.stroke_path(&path, &gen_paint(border), &Stroke::default());
+
+        let line_height = line_metrics(self.text_size, FontType::Normal).height;
+        let top = ((height - line_height) / 2.).round();
+        let left = PADDING_X - self.scroll_x;
+        let offsets = self.offsets();
+        let selection = self.editor.selection();
+        let caret_x = left + offset_x(&offsets, self.editor.caret());
+        let (text, placeholder) = (self.display_text(), &self.placeholder);
+        let (size, caret_visible) = (self.text_size, self.caret_visible);
+        let accessory = self.accessory_width() + self.trailing;
+        ctx.with_save(|ctx| {
+            ctx.clip_rect(1., 1., width - 2. - accessory, height - 2.);
+            if !selection.is_empty() && focused {
+                let start = left + offset_x(&offsets, selection.start);
+                let end = left + offset_x(&offsets, selection.end);
+                let selection_color = (get_theme_color() & 0xFFFFFF00) | 0x60;
+                ctx.fill_path(
+                    &gen_rect_path(start, top, end - start, line_height),
+                    &gen_paint(selection_color),
+                );
+            }
+            if text.is_empty() {
+                ctx.draw_text(PADDING_X, top, placeholder, size, PLACEHOLDER_COLOR);
+            } else {
+                ctx.draw_text(left, top, &text, size, TEXT_COLOR);
+            }
+            if focused && caret_visible {
+                ctx.fill_path(
+                    &gen_rect_path(caret_x.round(), top, 1., line_height),
+                    &gen_paint(TEXT_COLOR),
+                );
+            }
+        });
+        match self.mode {
+            InputMode::Password => self.draw_eye(ctx),
+            InputMode::Number { .. } => self.draw_spinner(ctx),
+            _ => {}
+        }
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let width = if self.size.0 > 0 {
+            self.size.0 as f32
+        } else if max_box.width() == isize::MAX {
+            DEFAULT_WIDTH
+        } else {
+            max_box.width() as f32
+        };
+        let height = if self.size.1 > 0 {
+            self.size.1 as f32
+        } else {
+            line_metrics(self.text_size, FontType::Normal).height.ceil() + PADDING_Y * 2.
+        };
+        self.layout_size = (
+            width.min(max_box.width() as f32),
+            height.min(max_box.height() as f32),
+        );
+        self.scroll_to_caret();
+        AreaBox::with_size(self.layout_size.0 as isize, self.layout_size.1 as isize)
+    }
 }
-impl<D> Widget<D> for InputControl {
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Modifiers;
+
+    const PHONE: &str = "(999) 999-9999";
+
+    #[test]
+    fn mask_fills_slots_and_inserts_literals() {
+        assert_eq!(
+            apply_mask(PHONE, "5551234567", 10),
+            ("(555) 123-4567".into(), 14)
+        );
+        // Literals waiting for the next slot are left out
+        assert_eq!(apply_mask(PHONE, "555", 3), ("(555".into(), 4));
+        // Trailing literals are kept
+        assert_eq!(apply_mask("99%", "12", 2), ("12%".into(), 3));
+    }
+
+    #[test]
+    fn mask_moves_the_caret_over_literals() {
+        // Typing after a group jumps over the literals in between
+        assert_eq!(apply_mask(PHONE, "(5556", 5), ("(555) 6".into(), 7));
+        // The caret stays after the same typed char
+        assert_eq!(apply_mask(PHONE, "(555) 123", 2), ("(555) 123".into(), 2));
+        // Deleting a digit shifts the rest back through the literals
+        assert_eq!(apply_mask(PHONE, "(55) 123", 3), ("(551) 23".into(), 3));
+    }
+
+    #[test]
+    fn mask_skips_rejected_chars() {
+        assert_eq!(apply_mask("9999-99-99", "20a24", 5), ("2024".into(), 4));
+        assert_eq!(apply_mask("aa-99", "1ab2", 4), ("ab-2".into(), 4));
+    }
+
+    #[test]
+    fn numbers_use_locale_separators() {
+        assert_eq!(parse_number("1,234.5", ('.', ',')), Some(1234.5));
+        assert_eq!(parse_number(" 1.234,5 ", (',', '.')), Some(1234.5));
+        assert_eq!(parse_number("1 234,5", (',', '\u{a0}')), Some(1234.5));
+        assert_eq!(parse_number("-0,25", (',', '.')), Some(-0.25));
+        assert_eq!(parse_number("12a", ('.', ',')), None);
+        assert_eq!(parse_number("1e400", ('.', ',')), None);
+    }
+
+    #[test]
+    fn numbers_are_formatted_with_the_step_decimals() {
+        assert_eq!(format_number(2., 1., '.'), "2");
+        assert_eq!(format_number(1234.5, 0.1, ','), "1234,5");
+        assert_eq!(format_number(1. / 3., 0.01, ','), "0,33");
+        assert_eq!(format_number(1.5, 0.5, '.'), "1.5");
+    }
+
+    fn event(input: &mut InputControl<()>, event: WindowEvent, pos: (f32, f32)) -> EventCtx {
+        let mut ctx = EventCtx::new(event, pos, Modifiers::default(), Some(0));
+        input.event(&mut ctx, &mut ());
+        ctx
+    }
+
+    #[test]
+    fn non_positive_steps_leave_the_number() {
+        for step in [0., -1., f64::NAN] {
+            let mut input = InputControl::<()>::new()
+                .with_mode(InputMode::Number {
+                    min: 0.,
+                    max: 10.,
+                    step,
+                })
+                .with_value("5");
+            let mut ctx = event(&mut input, WindowEvent::None, (0., 0.));
+            input.spin(&mut ctx, &mut (), 1.);
+            assert_eq!(input.value(), "5");
+        }
+    }
+
+    #[test]
+    fn only_numbers_and_passwords_have_an_accessory() {
+        let mut input = InputControl::<()>::new().with_value("text");
+        input.layout(AreaBox::with_size(200, 30));
+        let right = input.layout_size.0;
+        event(&mut input, WindowEvent::MouseDown(0, 0), (right, 10.));
+        // A text click, not a spinner one
+        assert!(input.dragging);
+        assert!(input.spinning.is_none());
+    }
 }
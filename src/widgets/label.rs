@@ -1,18 +1,33 @@
+use std::rc::Rc;
+
+use crate::r#box::AreaBox;
 use crate::traits::Widget;
 use crate::utils::drawing::TextAlign;
+use crate::utils::text::{ellipsize, line_metrics, wrap_text, FontType, TextLine};
 use crate::utils::uid::gen_uid;
-use crate::utils::{WidgetUid};
+use crate::utils::WidgetUid;
+use crate::{DrawCtx, UpdateCtx};
 
+type TextBinding<D> = Rc<dyn Fn(&D) -> String>;
 
-pub struct TextLabelControl {
+pub struct TextLabelControl<D = ()> {
     _uid: WidgetUid,
     text: String,
     text_color: u32,
     text_size: f32,
     text_align: TextAlign,
+    /// Wrap lines longer than the available width, otherwise they're ellipsized
+    wrap: bool,
+    /// Lines after this one are dropped and the last one ends with an ellipsis
+    max_lines: Option<usize>,
+    text_binding: Option<TextBinding<D>>,
+    lines: Vec<TextLine>,
+    size: (f32, f32),
+    /// Set by the setters, the next update asks for a layout
+    need_layout: bool,
 }
 
-impl Default for TextLabelControl {
+impl<D> Default for TextLabelControl<D> {
     fn default() -> Self {
         Self {
             _uid: gen_uid(),
@@ -20,11 +35,17 @@ impl Default for TextLabelControl {
             text_size: 13.,
             text_align: TextAlign::Left,
             text_color: 0x202020FF,
+            wrap: true,
+            max_lines: None,
+            text_binding: None,
+            lines: Vec::new(),
+            size: (0., 0.),
+            need_layout: false,
         }
     }
 }
 
-impl TextLabelControl {
+impl<D> TextLabelControl<D> {
     pub fn new(text: &str) -> Self {
         Self {
             text: text.into(),
@@ -32,22 +53,100 @@ impl TextLabelControl {
         }
     }
 
+    /// Take the text from the application data, the label is laid out again when it changes.
+    pub fn with_text_binding(mut self, v: impl Fn(&D) -> String + 'static) -> Self {
+        self.text_binding = Some(Rc::new(v));
+        self
+    }
+
+    pub fn with_color(mut self, v: u32) -> Self {
+        self.set_color(v);
+        self
+    }
+
+    pub fn with_text_size(mut self, v: f32) -> Self {
+        self.set_text_size(v);
+        self
+    }
+
+    pub fn with_text_align(mut self, v: TextAlign) -> Self {
+        self.set_text_align(v);
+        self
+    }
+
+    pub fn with_wrap(mut self, v: bool) -> Self {
+        self.set_wrap(v);
+        self
+    }
+
+    pub fn with_max_lines(mut self, v: usize) -> Self {
+        self.set_max_lines(Some(v));
+        self
+    }
+
+    #[inline]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    #[inline]
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.into();
+        self.need_layout = true;
+    }
+
     #[inline]
     pub fn set_color(&mut self, color: u32) {
         self.text_color = color;
+        self.need_layout = true;
     }
 
     #[inline]
     pub fn set_text_size(&mut self, size: f32) {
         self.text_size = size;
+        self.need_layout = true;
     }
 
     #[inline]
     pub fn set_text_align(&mut self, align: TextAlign) {
         self.text_align = align;
+        self.need_layout = true;
+    }
+
+    #[inline]
+    pub fn set_wrap(&mut self, wrap: bool) {
+        self.wrap = wrap;
+        self.need_layout = true;
+    }
+
+    #[inline]
+    pub fn set_max_lines(&mut self, max_lines: Option<usize>) {
+        self.max_lines = max_lines.map(|v| v.max(1));
+        self.need_layout = true;
+    }
+
+    fn break_lines(&self, max_width: f32) -> Vec<TextLine> {
+        let (size, font) = (self.text_size, FontType::Normal);
+        let mut lines = if self.wrap {
+            wrap_text(&self.text, size, font, max_width)
+        } else {
+            self.text
+                .split('\n')
+                .map(|line| ellipsize(line.trim_end_matches('\r'), size, font, max_width))
+                .collect()
+        };
+        if let Some(max_lines) = self.max_lines {
+            if lines.len() > max_lines {
+                lines.truncate(max_lines);
+                let last = lines.pop().unwrap();
+                lines.push(ellipsize(&format!("{}…", last.text), size, font, max_width));
+            }
+        }
+        lines
     }
 }
-impl Clone for TextLabelControl {
+
+impl<D> Clone for TextLabelControl<D> {
     fn clone(&self) -> Self {
         Self {
             _uid: gen_uid(),
@@ -55,9 +154,120 @@ impl Clone for TextLabelControl {
             text_color: self.text_color,
             text_align: self.text_align,
             text: self.text.to_owned(),
+            wrap: self.wrap,
+            max_lines: self.max_lines,
+            text_binding: self.text_binding.clone(),
+            lines: self.lines.clone(),
+            size: self.size,
+            need_layout: self.need_layout,
         }
     }
 }
 
-impl<D> Widget<D> for TextLabelControl {
+impl<D> Widget<D> for TextLabelControl<D> {
+    fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        if let Some(binding) = &self.text_binding {
+            let text = binding(data);
+            if text != self.text {
+                self.text = text;
+                self.need_layout = true;
+            }
+        }
+        if self.need_layout {
+            self.need_layout = false;
+            ctx.request_layout();
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, _data: &D) {
+        let (width, height) = self.size;
+        let line_height = line_metrics(self.text_size, FontType::Normal).height;
+        let (align, size, color) = (self.text_align, self.text_size, self.text_color);
+        let lines = &self.lines;
+        ctx.with_save(|ctx| {
+            ctx.clip_rect(0., 0., width, height);
+            for (i, line) in lines.iter().enumerate() {
+                let x = match align {
+                    TextAlign::Left => 0.,
+                    TextAlign::Center => (width - line.width) / 2.,
+                    TextAlign::Right => width - line.width,
+                };
+                ctx.draw_text(x, i as f32 * line_height, &line.text, size, color);
+            }
+        });
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let max_width = if max_box.width() == isize::MAX {
+            f32::INFINITY
+        } else {
+            max_box.width() as f32
+        };
+        self.lines = self.break_lines(max_width);
+        let line_height = line_metrics(self.text_size, FontType::Normal).height;
+        let width = if max_width.is_finite() {
+            max_width
+        } else {
+            self.lines
+                .iter()
+                .map(|line| line.width)
+                .fold(0., f32::max)
+                .ceil()
+        };
+        let height = (self.lines.len() as f32 * line_height)
+            .ceil()
+            .min(max_box.height() as f32);
+        self.size = (width, height);
+        AreaBox::with_size(width as isize, height as isize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::text::measure_text;
+
+    #[test]
+    fn extra_lines_end_with_an_ellipsis() {
+        let mut label = TextLabelControl::<()>::new("one\ntwo\nthree").with_max_lines(2);
+        label.layout(AreaBox::with_size(200, 200));
+        let texts: Vec<_> = label.lines.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(texts, ["one", "two…"]);
+    }
+
+    #[test]
+    fn unbounded_width_fits_the_text() {
+        let mut label = TextLabelControl::<()>::new("hello\nhi");
+        let size = label.layout(AreaBox::with_size(isize::MAX, 200));
+        let width = measure_text("hello", 13., FontType::Normal).ceil();
+        assert_eq!(size.width(), width as isize);
+        assert_eq!(label.lines.len(), 2);
+    }
+
+    #[test]
+    fn changed_binding_requests_layout() {
+        let mut label =
+            TextLabelControl::<String>::default().with_text_binding(|s: &String| s.clone());
+        let mut ctx = UpdateCtx::new();
+        label.update(&mut ctx, &"".to_string());
+        assert!(!ctx.request_layout);
+        label.update(&mut ctx, &"text".to_string());
+        assert!(ctx.request_layout);
+        assert_eq!(label.text(), "text");
+    }
+
+    #[test]
+    fn setters_request_layout_on_the_next_update() {
+        let mut label = TextLabelControl::<()>::new("text");
+        let mut ctx = UpdateCtx::new();
+        label.update(&mut ctx, &());
+        assert!(!ctx.request_layout);
+        label.set_text_size(20.);
+        label.update(&mut ctx, &());
+        assert!(ctx.request_layout);
+        // Only once
+        let mut ctx = UpdateCtx::new();
+        label.update(&mut ctx, &());
+        assert!(!ctx.request_layout);
+    }
 }
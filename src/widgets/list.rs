@@ -0,0 +1,722 @@
+//! 虚拟化列表：只为可见的行创建、布局和绘制控件，滚出视野的行会被回收复用
+
+use crate::core::WidgetPod;
+use crate::events::{keys, Modifiers, WindowEvent};
+use crate::r#box::AreaBox;
+use crate::traits::Widget;
+use crate::utils::binding::Binding;
+use crate::utils::drawing::{gen_paint, gen_rect_path};
+use crate::utils::theme::get_theme_color;
+use crate::utils::uid::gen_uid;
+use crate::utils::WidgetUid;
+use crate::widgets::scroll::{ScrollDirection, ScrollState};
+use crate::{DrawCtx, EventCtx, UpdateCtx};
+
+/// Rows realized beyond the visible area on each side
+const OVERSCAN: usize = 2;
+const HOVER_COLOR: u32 = 0x0000000F;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RowHeight {
+    /// Every row has the same height
+    Fixed(f32),
+    /// Rows are measured when they show up, the value is used before that
+    Measured(f32),
+}
+
+/// Selected indices of a list like widget, shared by the list, the table and the tree.
+pub struct SelectionState {
+    pub multi_select: bool,
+    /// Sorted selected indices
+    selected: Vec<usize>,
+    anchor: Option<usize>,
+    cursor: Option<usize>,
+}
+
+impl SelectionState {
+    pub fn new() -> Self {
+        Self {
+            multi_select: false,
+            selected: Vec::new(),
+            anchor: None,
+            cursor: None,
+        }
+    }
+
+    #[inline]
+    pub fn selected(&self) -> &[usize] {
+        &self.selected
+    }
+
+    pub fn set_selected(&mut self, mut v: Vec<usize>) {
+        v.sort_unstable();
+        v.dedup();
+        self.cursor = v.last().copied();
+        self.anchor = self.cursor;
+        self.selected = v;
+    }
+
+    #[inline]
+    pub fn is_selected(&self, index: usize) -> bool {
+        self.selected.binary_search(&index).is_ok()
+    }
+
+    /// The item the keyboard is on
+    #[inline]
+    pub fn cursor(&self) -> Option<usize> {
+        self.cursor
+    }
+
+    pub fn clear(&mut self) {
+        self.selected.clear();
+        self.anchor = None;
+        self.cursor = None;
+    }
+
+    /// Drop the indices out of a list with `len` items
+    pub fn clamp(&mut self, len: usize) {
+        self.selected.retain(|&i| i < len);
+        if self.cursor.is_some_and(|i| i >= len) {
+            self.cursor = len.checked_sub(1);
+        }
+        if self.anchor.is_some_and(|i| i >= len) {
+            self.anchor = self.cursor;
+        }
+    }
+
+    /// Move the indices after the items are rearranged, dropping the ones mapped to `None`
+    pub fn remap(&mut self, f: impl Fn(usize) -> Option<usize>) {
+        let mut selected: Vec<usize> = self.selected.iter().filter_map(|&i| f(i)).collect();
+        selected.sort_unstable();
+        selected.dedup();
+        self.selected = selected;
+        self.anchor = self.anchor.and_then(&f);
+        self.cursor = self.cursor.and_then(&f);
+    }
+
+    /// Select by clicking, Ctrl toggles and Shift selects a range when multi selecting
+    pub fn click(&mut self, index: usize, modifiers: Modifiers) {
+        match self.anchor {
+            Some(anchor) if self.multi_select && modifiers.shift => {
+                self.selected = (anchor.min(index)..=anchor.max(index)).collect();
+            }
+            _ if self.multi_select && modifiers.ctrl => {
+                match self.selected.binary_search(&index) {
+                    Ok(i) => {
+                        self.selected.remove(i);
+                    }
+                    Err(i) => self.selected.insert(i, index),
+                }
+                self.anchor = Some(index);
+            }
+            _ => {
+                self.selected = vec![index];
+                self.anchor = Some(index);
+            }
+        }
+        self.cursor = Some(index);
+    }
+
+    /// Move the cursor by keyboard, Ctrl only moves the cursor when multi selecting.
+    /// Returns true if the selection changed.
+    pub fn navigate(&mut self, index: usize, modifiers: Modifiers) -> bool {
+        if modifiers.ctrl && self.multi_select {
+            self.cursor = Some(index);
+            return false;
+        }
+        self.click(
+            index,
+            Modifiers {
+                ctrl: false,
+                ..modifiers
+            },
+        );
+        true
+    }
+
+    /// Ctrl+Space toggles the cursor and Ctrl+A selects all, returns true if the selection changed
+    pub fn key_down(&mut self, key: usize, modifiers: Modifiers, len: usize) -> bool {
+        match key {
+            keys::SPACE if modifiers.ctrl => match self.cursor {
+                Some(i) => {
+                    self.click(i, modifiers);
+                    true
+                }
+                None => false,
+            },
+            keys::A if modifiers.ctrl && self.multi_select => {
+                self.selected = (0..len).collect();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Default for SelectionState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+type SelectCallback<D> = Box<dyn Fn(&mut D, &[usize])>;
+
+pub struct ListControl<D, T> {
+    _uid: WidgetUid,
+    items: Binding<D, Vec<T>>,
+    builder: Box<dyn Fn() -> Box<dyn Widget<T>>>,
+    row_height: RowHeight,
+    /// Height of every row when measuring
+    heights: Vec<f32>,
+    /// Top of every row when measuring, with the full height at the end
+    offsets: Vec<f32>,
+    len: usize,
+    rows: Vec<(usize, WidgetPod<T>)>,
+    pool: Vec<WidgetPod<T>>,
+    scroll: ScrollState,
+    width: f32,
+    unbounded_width: bool,
+    selection: SelectionState,
+    hovering: Option<usize>,
+    select_callback: SelectCallback<D>,
+    selection_color: u32,
+}
+
+impl<D, T> ListControl<D, T> {
+    pub fn new<F: Fn() -> Box<dyn Widget<T>> + 'static>(
+        items: Binding<D, Vec<T>>,
+        builder: F,
+    ) -> Self {
+        let mut scroll = ScrollState::new(ScrollDirection::Vertical);
+        scroll.set_drag_scroll(false);
+        Self {
+            _uid: gen_uid(),
+            items,
+            builder: Box::new(builder),
+            row_height: RowHeight::Fixed(24.),
+            heights: Vec::new(),
+            offsets: vec![0.],
+            len: 0,
+            rows: Vec::new(),
+            pool: Vec::new(),
+            scroll,
+            width: 0.,
+            unbounded_width: false,
+            selection: SelectionState::new(),
+            hovering: None,
+            select_callback: Box::new(|_, _| {}),
+            selection_color: (get_theme_color() & 0xFFFFFF00) | 0x60,
+        }
+    }
+
+    pub fn with_row_height(mut self, v: RowHeight) -> Self {
+        self.row_height = v;
+        self.heights.clear();
+        self.len = 0;
+        self
+    }
+
+    pub fn with_multi_select(mut self, v: bool) -> Self {
+        self.selection.multi_select = v;
+        self
+    }
+
+    /// Called with the selected indices every time the selection changes
+    pub fn on_select<F: Fn(&mut D, &[usize]) + 'static>(mut self, v: F) -> Self {
+        self.select_callback = Box::new(v);
+        self
+    }
+
+    #[inline]
+    pub fn selection(&self) -> &[usize] {
+        self.selection.selected()
+    }
+
+    pub fn set_selection(&mut self, v: Vec<usize>) {
+        self.selection.set_selected(v);
+    }
+
+    /// Scroll until the row is visible
+    pub fn scroll_to_index(&mut self, index: usize) {
+        if index < self.len {
+            let top = self.row_top(index);
+            let height = self.row_height_of(index);
+            self.scroll.scroll_into_view(0., top, 0., height);
+        }
+    }
+
+    fn row_top(&self, index: usize) -> f32 {
+        match self.row_height {
+            RowHeight::Fixed(h) => index as f32 * h,
+            RowHeight::Measured(_) => self.offsets[index.min(self.len)],
+        }
+    }
+
+    fn row_height_of(&self, index: usize) -> f32 {
+        match self.row_height {
+            RowHeight::Fixed(h) => h,
+            RowHeight::Measured(h) => self.heights.get(index).copied().unwrap_or(h),
+        }
+    }
+
+    fn content_height(&self) -> f32 {
+        self.row_top(self.len)
+    }
+
+    /// Index of the row at the height in the content, may be `len` if it's past the last row
+    fn index_at(&self, y: f32) -> usize {
+        if y <= 0. || y.is_nan() {
+            return 0;
+        }
+        match self.row_height {
+            RowHeight::Fixed(h) => ((y / h) as usize).min(self.len),
+            RowHeight::Measured(_) => match self
+                .offsets
+                .binary_search_by(|v| v.total_cmp(&y))
+            {
+                Ok(i) => i.min(self.len),
+                Err(i) => (i - 1).min(self.len),
+            },
+        }
+    }
+
+    fn rebuild_offsets(&mut self) {
+        self.offsets.clear();
+        self.offsets.reserve(self.heights.len() + 1);
+        let mut top = 0.;
+        self.offsets.push(top);
+        for h in self.heights.iter() {
+            top += h;
+            self.offsets.push(top);
+        }
+    }
+
+    fn sync_len(&mut self, len: usize) {
+        if len == self.len && (self.heights.len() == len || !self.is_measured()) {
+            return;
+        }
+        self.len = len;
+        if let RowHeight::Measured(h) = self.row_height {
+            self.heights.resize(len, h);
+            self.rebuild_offsets();
+        }
+        self.selection.clamp(len);
+        self.update_scroll_sizes();
+    }
+
+    #[inline]
+    fn is_measured(&self) -> bool {
+        matches!(self.row_height, RowHeight::Measured(_))
+    }
+
+    fn update_scroll_sizes(&mut self) {
+        let viewport = self.scroll.viewport();
+        self.scroll
+            .set_sizes(viewport, (self.width, self.content_height()));
+    }
+
+    /// Lay out a row, returns its height
+    fn measure(row_height: RowHeight, width: f32, unbounded: bool, pod: &mut WidgetPod<T>) -> f32 {
+        let width = if unbounded {
+            isize::MAX
+        } else {
+            width as isize
+        };
+        match row_height {
+            RowHeight::Fixed(h) => {
+                pod.layout(AreaBox::with_size(width, h as isize));
+                h
+            }
+            RowHeight::Measured(_) => {
+                pod.layout(AreaBox::with_size(width, isize::MAX)).height() as f32
+            }
+        }
+    }
+
+    /// Make sure the rows in the visible range exist, recycling the others
+    fn realize(&mut self, items: &[T]) {
+        self.sync_len(items.len());
+        let (_, offset) = self.scroll.offset();
+        let (_, height) = self.scroll.viewport();
+        let first = self.index_at(offset).saturating_sub(OVERSCAN);
+        let last = (self.index_at(offset + height) + OVERSCAN).min(self.len);
+
+        let mut i = 0;
+        while i < self.rows.len() {
+            let index = self.rows[i].0;
+            if index < first || index >= last {
+                let (_, pod) = self.rows.swap_remove(i);
+                self.pool.push(pod);
+            } else {
+                i += 1;
+            }
+        }
+
+        let mut dirty = false;
+        for (index, item) in items.iter().enumerate().take(last).skip(first) {
+            if self.rows.iter().any(|(i, _)| *i == index) {
+                continue;
+            }
+            let builder = &self.builder;
+            let mut pod = self.pool.pop().unwrap_or_else(|| builder().into());
+            // Laid out right after, no need to forward the requests
+            pod.update(&mut UpdateCtx::new(), item);
+            let h = Self::measure(self.row_height, self.width, self.unbounded_width, &mut pod);
+            if self.is_measured() && self.heights[index] != h {
+                self.heights[index] = h;
+                dirty = true;
+            }
+            self.rows.push((index, pod));
+        }
+        if dirty {
+            self.rebuild_offsets();
+            self.update_scroll_sizes();
+        }
+
+        let (_, offset) = self.scroll.offset();
+        for i in 0..self.rows.len() {
+            let top = self.row_top(self.rows[i].0);
+            self.rows[i].1.set_origin(0., top - offset);
+        }
+    }
+
+    /// Handle navigation keys, returns true if the selection changed
+    fn key_down(&mut self, key: usize, modifiers: Modifiers) -> bool {
+        if self.len == 0 {
+            return false;
+        }
+        let last = self.len - 1;
+        let cursor = self.selection.cursor();
+        let page = self.scroll.viewport().1;
+        let target = match key {
+            keys::UP => cursor.map_or(0, |i| i.saturating_sub(1)),
+            keys::DOWN => cursor.map_or(0, |i| (i + 1).min(last)),
+            keys::HOME => 0,
+            keys::END => last,
+            keys::PRIOR => cursor.map_or(0, |i| self.index_at(self.row_top(i) - page)),
+            keys::NEXT => cursor.map_or(0, |i| self.index_at(self.row_top(i) + page).min(last)),
+            _ => return self.selection.key_down(key, modifiers, self.len),
+        };
+        let changed = self.selection.navigate(target, modifiers);
+        self.scroll_to_index(target);
+        changed
+    }
+}
+
+impl<D, T> Widget<D> for ListControl<D, T> {
+    fn event(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        if self.scroll.bar_event(ctx) {
+            return;
+        }
+        let items = self.items.get_mut(data);
+        self.sync_len(items.len());
+        for (index, pod) in self.rows.iter_mut() {
+            if let Some(item) = items.get_mut(*index) {
+                pod.event(ctx, item);
+            }
+        }
+        self.scroll.event(ctx);
+
+        let (_, y) = ctx.mouse_pos();
+        let (_, offset) = self.scroll.offset();
+        let mut changed = false;
+        match ctx.event() {
+            WindowEvent::CollectFocus => ctx.register_for_focus(),
+            WindowEvent::MouseDown(_, _) => {
+                ctx.request_focus();
+                let index = self.index_at(y + offset);
+                if !ctx.is_handled() && index < self.len {
+                    self.selection.click(index, ctx.modifiers());
+                    changed = true;
+                    ctx.set_handled();
+                }
+                ctx.request_paint();
+            }
+            WindowEvent::MouseMove(_, _) => {
+                let index = self.index_at(y + offset);
+                let hovering = if ctx.is_hovering() && index < self.len {
+                    Some(index)
+                } else {
+                    None
+                };
+                if hovering != self.hovering {
+                    self.hovering = hovering;
+                    ctx.request_paint();
+                }
+            }
+            WindowEvent::KeyDown(key) if ctx.is_focused() => {
+                let cursor = self.selection.cursor();
+                changed = self.key_down(key, ctx.modifiers());
+                if changed || cursor != self.selection.cursor() {
+                    ctx.set_handled();
+                    ctx.request_anim_frame();
+                }
+            }
+            _ => {}
+        }
+        if changed {
+            (self.select_callback)(data, self.selection.selected());
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        let items = self.items.get(data);
+        self.realize(items);
+        for (index, pod) in self.rows.iter_mut() {
+            pod.update(ctx, &items[*index]);
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, data: &D) {
+        let items = self.items.get(data);
+        self.realize(items);
+        let (width, height) = self.scroll.viewport();
+        let focused = ctx.is_focused();
+        let rows = &mut self.rows;
+        let selection = &self.selection;
+        let selection_color = self.selection_color;
+        let hovering = self.hovering;
+        let cursor = self.selection.cursor();
+        ctx.with_save(|ctx| {
+            ctx.clip_rect(0., 0., width, height);
+            for (index, pod) in rows.iter_mut() {
+                let (x, y) = pod.origin();
+                let (_, h) = pod.size();
+                let path = gen_rect_path(x, y, width, h);
+                if selection.is_selected(*index) {
+                    ctx.fill_path(&path, &gen_paint(selection_color));
+                } else if hovering == Some(*index) {
+                    ctx.fill_path(&path, &gen_paint(HOVER_COLOR));
+                }
+                if focused && cursor == Some(*index) {
+                    let path = gen_rect_path(x + 0.5, y + 0.5, width - 1., h - 1.);
+                    ctx.stroke_path(
+                        &path,
+                        &gen_paint(selection_color | 0xFF),
+                        &Default::default(),
+                    );
+                }
+                pod.draw(ctx, &items[*index]);
+            }
+        });
+        self.scroll.draw_bars(ctx);
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        self.unbounded_width = max_box.width() == isize::MAX;
+        let mut width = if self.unbounded_width {
+            0.
+        } else {
+            max_box.width() as f32
+        };
+        let mut dirty = false;
+        for (index, pod) in self.rows.iter_mut() {
+            let h = Self::measure(self.row_height, width, self.unbounded_width, pod);
+            if self.unbounded_width {
+                width = width.max(pod.size().0);
+            }
+            if let RowHeight::Measured(_) = self.row_height {
+                if self.heights[*index] != h {
+                    self.heights[*index] = h;
+                    dirty = true;
+                }
+            }
+        }
+        if dirty {
+            self.rebuild_offsets();
+        }
+        self.width = width;
+        let height = if max_box.height() == isize::MAX {
+            self.content_height()
+        } else {
+            max_box.height() as f32
+        };
+        self.scroll
+            .set_sizes((width, height), (width, self.content_height()));
+        AreaBox::with_size(width as isize, height as isize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A row as tall as its item
+    struct Row(f32);
+
+    impl Widget<f32> for Row {
+        fn update(&mut self, _ctx: &mut UpdateCtx, data: &f32) {
+            self.0 = *data;
+        }
+
+        fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+            AreaBox::with_size(max_box.width(), self.0 as isize)
+        }
+    }
+
+    const NONE: Modifiers = Modifiers {
+        shift: false,
+        ctrl: false,
+        alt: false,
+    };
+    const SHIFT: Modifiers = Modifiers {
+        shift: true,
+        ..NONE
+    };
+    const CTRL: Modifiers = Modifiers { ctrl: true, ..NONE };
+
+    fn list(row_height: RowHeight, items: &[f32], height: isize) -> ListControl<Vec<f32>, f32> {
+        let mut list =
+            ListControl::new(Binding::identity(), || Box::new(Row(0.))).with_row_height(row_height);
+        list.sync_len(items.len());
+        list.layout(AreaBox::with_size(100, height));
+        list.realize(items);
+        list
+    }
+
+    #[test]
+    fn measured_rows_are_found_by_height() {
+        let items = [10., 20., 30., 40.];
+        let list = list(RowHeight::Measured(5.), &items, 1000);
+        assert_eq!(list.offsets, [0., 10., 30., 60., 100.]);
+        assert_eq!(list.content_height(), 100.);
+        assert_eq!(list.index_at(-3.), 0);
+        assert_eq!(list.index_at(15.), 1);
+        assert_eq!(list.index_at(30.), 2);
+        assert_eq!(list.index_at(99.), 3);
+        assert_eq!(list.index_at(100.), 4);
+        assert_eq!(list.index_at(f32::NAN), 0);
+        assert_eq!(list.index_at(-f32::NAN), 0);
+    }
+
+    #[test]
+    fn only_visible_rows_are_realized() {
+        let items = vec![20.; 100];
+        let mut list = list(RowHeight::Fixed(20.), &items, 100);
+        let realized = list.rows.len();
+        assert_eq!(realized, 5 + OVERSCAN);
+        list.scroll.set_offset(0., 1000.);
+        list.realize(&items);
+        let mut indices: Vec<usize> = list.rows.iter().map(|(i, _)| *i).collect();
+        indices.sort_unstable();
+        assert_eq!(indices, (50 - OVERSCAN..55 + OVERSCAN).collect::<Vec<_>>());
+        // The rows scrolled away are reused
+        assert_eq!(
+            list.rows.len() + list.pool.len(),
+            indices.len().max(realized)
+        );
+        let (_, top) = list.rows.iter().find(|(i, _)| *i == 50).unwrap().1.origin();
+        assert_eq!(top, 0.);
+    }
+
+    #[test]
+    fn keys_move_the_selection() {
+        let items = vec![20.; 20];
+        let mut list = list(RowHeight::Fixed(20.), &items, 100);
+        assert!(list.key_down(keys::DOWN, NONE));
+        assert_eq!(list.selection(), [0]);
+        assert!(list.key_down(keys::NEXT, NONE));
+        assert_eq!(list.selection(), [5]);
+        assert!(list.key_down(keys::END, NONE));
+        assert_eq!(list.selection(), [19]);
+        assert!(list.key_down(keys::DOWN, NONE));
+        assert_eq!(list.selection(), [19]);
+        assert!(list.key_down(keys::PRIOR, NONE));
+        assert_eq!(list.selection(), [14]);
+        assert!(!list.key_down(keys::LEFT, NONE));
+    }
+
+    #[test]
+    fn multi_select_with_keys() {
+        let items = vec![20.; 20];
+        let mut list = list(RowHeight::Fixed(20.), &items, 100).with_multi_select(true);
+        assert!(list.key_down(keys::DOWN, NONE));
+        assert!(list.key_down(keys::DOWN, SHIFT));
+        assert_eq!(list.selection(), [0, 1]);
+        assert!(list.key_down(keys::A, CTRL));
+        assert_eq!(list.selection().len(), 20);
+        list.sync_len(3);
+        assert_eq!(list.selection(), [0, 1, 2]);
+    }
+
+    fn multi() -> SelectionState {
+        SelectionState {
+            multi_select: true,
+            ..SelectionState::new()
+        }
+    }
+
+    #[test]
+    fn single_select_ignores_modifiers() {
+        let mut state = SelectionState::new();
+        state.click(2, NONE);
+        state.click(5, SHIFT);
+        assert_eq!(state.selected(), [5]);
+        state.click(3, CTRL);
+        assert_eq!(state.selected(), [3]);
+        assert!(!state.key_down(keys::A, CTRL, 10));
+    }
+
+    #[test]
+    fn shift_selects_from_the_anchor() {
+        let mut state = multi();
+        state.click(4, NONE);
+        state.click(1, SHIFT);
+        assert_eq!(state.selected(), [1, 2, 3, 4]);
+        // The anchor stays, so the range shrinks
+        state.click(3, SHIFT);
+        assert_eq!(state.selected(), [3, 4]);
+        assert_eq!(state.cursor(), Some(3));
+    }
+
+    #[test]
+    fn ctrl_toggles_and_moves_the_anchor() {
+        let mut state = multi();
+        state.click(1, NONE);
+        state.click(5, CTRL);
+        state.click(3, CTRL);
+        assert_eq!(state.selected(), [1, 3, 5]);
+        state.click(1, CTRL);
+        assert_eq!(state.selected(), [3, 5]);
+        state.click(3, SHIFT);
+        assert_eq!(state.selected(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn keyboard_moves_the_cursor() {
+        let mut state = multi();
+        state.click(2, NONE);
+        assert!(!state.navigate(3, CTRL));
+        assert_eq!((state.cursor(), state.selected()), (Some(3), &[2][..]));
+        assert!(state.key_down(keys::SPACE, CTRL, 10));
+        assert_eq!(state.selected(), [2, 3]);
+        assert!(state.navigate(5, SHIFT));
+        assert_eq!(state.selected(), [3, 4, 5]);
+        assert!(state.key_down(keys::A, CTRL, 4));
+        assert_eq!(state.selected(), [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn clamp_follows_the_length() {
+        let mut state = multi();
+        state.set_selected(vec![7, 2, 4, 2]);
+        assert_eq!(state.selected(), [2, 4, 7]);
+        assert_eq!(state.cursor(), Some(7));
+        state.clamp(5);
+        assert_eq!(state.selected(), [2, 4]);
+        assert_eq!(state.cursor(), Some(4));
+        state.clamp(0);
+        assert!(state.selected().is_empty());
+        assert_eq!(state.cursor(), None);
+    }
+
+    #[test]
+    fn remap_follows_moved_items() {
+        let mut state = multi();
+        state.set_selected(vec![1, 2, 4]);
+        // Item 2 is removed and the rest are reversed
+        state.remap(|i| if i == 2 { None } else { Some(4 - i) });
+        assert_eq!(state.selected(), [0, 3]);
+        assert_eq!(state.cursor(), Some(0));
+    }
+}
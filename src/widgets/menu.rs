@@ -0,0 +1,1153 @@
+//! 菜单：在弹出层中作为右键菜单打开，或者放在窗口标题区域的菜单栏里
+//!
+//! 菜单项可以带图标、勾选状态、快捷键文字和子菜单，标签中 `&` 后面的字母是助记键，按 Alt 时显示下划线
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use tiny_skia::{LineCap, LineJoin, PathBuilder, Stroke};
+
+use crate::core::WidgetPod;
+use crate::events::{keys, PopupId, TimerToken, WindowEvent};
+use crate::popup::{Placement, Popup};
+use crate::r#box::AreaBox;
+use crate::traits::Widget;
+use crate::utils::binding::Binding;
+use crate::utils::drawing::{gen_paint, gen_rect_path, gen_round_rect_path};
+use crate::utils::text::{line_metrics, measure_text, FontType};
+use crate::utils::theme::get_theme_color;
+use crate::utils::uid::gen_uid;
+use crate::utils::WidgetUid;
+use crate::{DrawCtx, EventCtx, UpdateCtx};
+
+use super::svg_icon::SvgIcon;
+
+const TEXT_SIZE: f32 = 14.;
+/// Space around the items inside the popup
+const PADDING: f32 = 4.;
+const ITEM_PADDING_X: f32 = 8.;
+const ITEM_PADDING_Y: f32 = 5.;
+const ICON_SIZE: f32 = 16.;
+const GAP: f32 = 8.;
+/// Space between the labels and the shortcuts
+const SHORTCUT_GAP: f32 = 32.;
+const ARROW_WIDTH: f32 = 12.;
+const SEPARATOR_HEIGHT: f32 = 9.;
+const MIN_WIDTH: f32 = 160.;
+const RADIUS: f32 = 4.;
+/// Time the pointer rests on an item before its submenu opens or the open one closes
+const SUBMENU_DELAY: Duration = Duration::from_millis(300);
+const BAR_PADDING_X: f32 = 10.;
+const BAR_PADDING_Y: f32 = 6.;
+const TEXT_COLOR: u32 = 0x202020FF;
+const DISABLED_COLOR: u32 = 0x20202060;
+const SHORTCUT_COLOR: u32 = 0x20202090;
+const SEPARATOR_COLOR: u32 = 0x0000001A;
+const HIGHLIGHT_COLOR: u32 = 0x00000010;
+const HOVER_COLOR: u32 = 0x0000000A;
+
+/// Remove the `&` marks of a label, returns the text and the byte index and lowercase letter of the mnemonic.
+fn parse_mnemonic(label: &str) -> (String, Option<(usize, char)>) {
+    let mut text = String::with_capacity(label.len());
+    let mut mnemonic = None;
+    let mut chars = label.chars();
+    while let Some(c) = chars.next() {
+        if c != '&' {
+            text.push(c);
+            continue;
+        }
+        // `&&` is a literal `&`
+        match chars.next() {
+            Some('&') => text.push('&'),
+            Some(c) => {
+                if mnemonic.is_none() {
+                    mnemonic = c.to_lowercase().next().map(|m| (text.len(), m));
+                }
+                text.push(c);
+            }
+            None => {}
+        }
+    }
+    (text, mnemonic)
+}
+
+/// The letter of a key pressed with Alt.
+fn key_char(key: usize) -> Option<char> {
+    match key {
+        0x30..=0x39 | 0x41..=0x5A => Some((key as u8 as char).to_ascii_lowercase()),
+        _ => None,
+    }
+}
+
+/// Draw a label and underline its mnemonic.
+fn draw_label(
+    ctx: &mut DrawCtx,
+    x: f32,
+    y: f32,
+    text: &str,
+    mnemonic: Option<(usize, char)>,
+    underline: bool,
+    color: u32,
+) {
+    ctx.draw_text(x, y, text, TEXT_SIZE, color);
+    if let (Some((index, _)), true) = (mnemonic, underline) {
+        let left = measure_text(&text[..index], TEXT_SIZE, FontType::Normal);
+        let width = text[index..].chars().next().map_or(0., |c| {
+            measure_text(c.encode_utf8(&mut [0; 4]), TEXT_SIZE, FontType::Normal)
+        });
+        let baseline = line_metrics(TEXT_SIZE, FontType::Normal).ascent;
+        let path = gen_rect_path((x + left).round(), (y + baseline).round() + 1., width, 1.);
+        ctx.fill_path(&path, &gen_paint(color));
+    }
+}
+
+/// Run with the application data when the item is chosen.
+type Action<D> = Rc<dyn Fn(&mut D)>;
+
+/// A command of a menu, or the entry of a submenu.
+pub struct MenuItem<D> {
+    label: String,
+    mnemonic: Option<(usize, char)>,
+    shortcut: String,
+    icon: Option<Rc<RefCell<SvgIcon>>>,
+    enabled: bool,
+    check: Option<Rc<Binding<D, bool>>>,
+    submenu: Option<Menu<D>>,
+    action: Option<Action<D>>,
+}
+
+impl<D> MenuItem<D> {
+    /// The letter after `&` in the label is the mnemonic, e.g. `"&Open"`, and `&&` is a literal `&`.
+    pub fn new(label: &str) -> Self {
+        let (label, mnemonic) = parse_mnemonic(label);
+        Self {
+            label,
+            mnemonic,
+            shortcut: String::new(),
+            icon: None,
+            enabled: true,
+            check: None,
+            submenu: None,
+            action: None,
+        }
+    }
+
+    /// Text of the keyboard shortcut shown on the right, e.g. `"Ctrl+O"`. The application handles the keys itself.
+    pub fn with_shortcut(mut self, v: &str) -> Self {
+        self.shortcut = v.into();
+        self
+    }
+
+    pub fn with_icon(mut self, v: SvgIcon) -> Self {
+        self.icon = Some(Rc::new(RefCell::new(v)));
+        self
+    }
+
+    pub fn with_enabled(mut self, v: bool) -> Self {
+        self.enabled = v;
+        self
+    }
+
+    /// Show a check mark from a `bool` of the application data, choosing the item toggles it.
+    pub fn with_check_binding(mut self, v: Binding<D, bool>) -> Self {
+        self.check = Some(Rc::new(v));
+        self
+    }
+
+    pub fn with_submenu(mut self, v: Menu<D>) -> Self {
+        self.submenu = Some(v);
+        self
+    }
+
+    /// Called when the item is chosen, after its check binding is toggled.
+    pub fn on_click<F: Fn(&mut D) + 'static>(mut self, v: F) -> Self {
+        self.action = Some(Rc::new(v));
+        self
+    }
+
+    fn has_submenu(&self) -> bool {
+        self.submenu.is_some()
+    }
+}
+
+impl<D> Clone for MenuItem<D> {
+    fn clone(&self) -> Self {
+        Self {
+            label: self.label.clone(),
+            mnemonic: self.mnemonic,
+            shortcut: self.shortcut.clone(),
+            icon: self.icon.clone(),
+            enabled: self.enabled,
+            check: self.check.clone(),
+            submenu: self.submenu.clone(),
+            action: self.action.clone(),
+        }
+    }
+}
+
+enum MenuEntry<D> {
+    Item(MenuItem<D>),
+    Separator,
+}
+
+impl<D> Clone for MenuEntry<D> {
+    fn clone(&self) -> Self {
+        match self {
+            MenuEntry::Item(item) => MenuEntry::Item(item.clone()),
+            MenuEntry::Separator => MenuEntry::Separator,
+        }
+    }
+}
+
+/// The entries of a menu, cheap to clone. Opened as a context menu with `open`, or put in a `MenuBar`.
+pub struct Menu<D = ()> {
+    entries: Rc<Vec<MenuEntry<D>>>,
+    window: bool,
+}
+
+impl<D> Menu<D> {
+    pub fn new() -> Self {
+        Self {
+            entries: Rc::new(Vec::new()),
+            window: false,
+        }
+    }
+
+    /// Show the menu and its submenus in windows of their own, so they can go past the edges of the window.
+    pub fn with_window(mut self, v: bool) -> Self {
+        self.window = v;
+        self
+    }
+
+    pub fn with_item(mut self, item: MenuItem<D>) -> Self {
+        Rc::make_mut(&mut self.entries).push(MenuEntry::Item(item));
+        self
+    }
+
+    pub fn with_separator(mut self) -> Self {
+        Rc::make_mut(&mut self.entries).push(MenuEntry::Separator);
+        self
+    }
+
+    pub fn with_submenu(self, label: &str, menu: Menu<D>) -> Self {
+        self.with_item(MenuItem::new(label).with_submenu(menu))
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn item(&self, index: usize) -> Option<&MenuItem<D>> {
+        match self.entries.get(index) {
+            Some(MenuEntry::Item(item)) => Some(item),
+            _ => None,
+        }
+    }
+}
+
+impl<D: 'static> Menu<D> {
+    /// Open as a context menu at a point of the widget handling the event.
+    ///
+    /// The menu closes once an item is chosen, or when clicking outside of it.
+    pub fn open(&self, ctx: &mut EventCtx, x: f32, y: f32) -> PopupId {
+        let content = MenuPopup::new(self.clone(), None, false, false);
+        let popup = Popup::new(Box::new(content))
+            .with_anchor(x, y, 0., 0.)
+            .with_gap(0.)
+            .with_window(self.window);
+        ctx.open_popup(popup)
+    }
+}
+
+impl<D> Clone for Menu<D> {
+    fn clone(&self) -> Self {
+        Self {
+            entries: self.entries.clone(),
+            window: self.window,
+        }
+    }
+}
+
+impl<D> Default for Menu<D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A menu shown in a popup
+struct MenuPopup<D> {
+    menu: Menu<D>,
+    /// The first popup of the chain of submenus, closing it closes the others
+    root: Option<PopupId>,
+    /// Opened by a menu bar, `Left` and `Right` go to it to switch menus
+    in_bar: bool,
+    /// Opened from the keyboard, the mnemonics are underlined
+    keyboard: bool,
+    highlighted: Option<usize>,
+    /// The open submenu and its entry
+    submenu: Option<(usize, PopupId)>,
+    /// Opens or closes submenus once the pointer rests on an item
+    hover_timer: Option<TimerToken>,
+    /// The pointer is over the menu, not over its submenu
+    pointer_inside: bool,
+    /// Top and height of the entries
+    rows: Vec<(f32, f32)>,
+    /// States of the check marks when last drawn
+    checks: Vec<bool>,
+    layout_size: (f32, f32),
+}
+
+impl<D: 'static> MenuPopup<D> {
+    fn new(menu: Menu<D>, root: Option<PopupId>, in_bar: bool, keyboard: bool) -> Self {
+        let mut popup = Self {
+            menu,
+            root,
+            in_bar,
+            keyboard,
+            highlighted: None,
+            submenu: None,
+            hover_timer: None,
+            pointer_inside: false,
+            rows: Vec::new(),
+            checks: Vec::new(),
+            layout_size: (0., 0.),
+        };
+        if keyboard {
+            popup.move_highlight(1);
+        }
+        popup
+    }
+
+    fn check_states(&self, data: &D) -> Vec<bool> {
+        self.menu
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                MenuEntry::Item(item) => item.check.as_ref().is_some_and(|b| *b.get(data)),
+                MenuEntry::Separator => false,
+            })
+            .collect()
+    }
+
+    fn row_at(&self, y: f32) -> Option<usize> {
+        self.rows
+            .iter()
+            .position(|&(top, height)| y >= top && y < top + height)
+    }
+
+    /// Highlight the next or previous item, wrapping around and skipping separators.
+    fn move_highlight(&mut self, delta: isize) {
+        let count = self.menu.entries.len() as isize;
+        let mut index = match self.highlighted {
+            Some(i) => i as isize,
+            None if delta > 0 => -1,
+            None => count,
+        };
+        for _ in 0..count {
+            index = (index + delta).rem_euclid(count);
+            if self.menu.item(index as usize).is_some() {
+                self.highlighted = Some(index as usize);
+                return;
+            }
+        }
+    }
+
+    fn open_submenu(&mut self, ctx: &mut EventCtx, index: usize, keyboard: bool) {
+        if self.submenu.map(|(i, _)| i) == Some(index) {
+            return;
+        }
+        self.close_submenu(ctx);
+        let mut menu = match self.menu.item(index).and_then(|item| item.submenu.clone()) {
+            Some(menu) => menu,
+            None => return,
+        };
+        // Submenus are shown like the menu opening them
+        menu.window = self.menu.window;
+        let (top, height) = self.rows.get(index).copied().unwrap_or((0., 0.));
+        let root = self.root.or_else(|| ctx.popup());
+        let content = MenuPopup::new(menu, root, self.in_bar, keyboard);
+        // The first item of the submenu lines up with its entry
+        let popup = Popup::new(Box::new(content))
+            .with_placement(Placement::Right)
+            .with_anchor(0., top - PADDING, self.layout_size.0, height)
+            .with_gap(-PADDING / 2.)
+            .with_window(self.menu.window);
+        self.submenu = Some((index, ctx.open_popup(popup)));
+    }
+
+    fn close_submenu(&mut self, ctx: &mut EventCtx) {
+        if let Some((_, id)) = self.submenu.take() {
+            ctx.close_popup(id);
+        }
+    }
+
+    /// Open the submenu of the highlighted item, or close the open one.
+    fn sync_submenu(&mut self, ctx: &mut EventCtx) {
+        let wanted = self.highlighted.filter(|&i| {
+            self.menu
+                .item(i)
+                .is_some_and(|item| item.enabled && item.has_submenu())
+        });
+        match wanted {
+            Some(index) => self.open_submenu(ctx, index, false),
+            None => self.close_submenu(ctx),
+        }
+    }
+
+    /// Run the item, or open its submenu.
+    fn choose(&mut self, ctx: &mut EventCtx, data: &mut D, index: usize, keyboard: bool) {
+        let item = match self.menu.item(index) {
+            Some(item) if item.enabled => item.clone(),
+            _ => return,
+        };
+        if item.has_submenu() {
+            self.highlighted = Some(index);
+            self.open_submenu(ctx, index, keyboard);
+            ctx.request_paint();
+            return;
+        }
+        if let Some(binding) = &item.check {
+            let checked = *binding.get(data);
+            *binding.get_mut(data) = !checked;
+        }
+        if let Some(action) = &item.action {
+            action(data);
+        }
+        // Close the whole chain of menus
+        if let Some(root) = self.root.or_else(|| ctx.popup()) {
+            ctx.close_popup(root);
+        }
+    }
+
+    /// Handle a key while this is the top menu, returns whether it was used.
+    fn handle_key(&mut self, ctx: &mut EventCtx, data: &mut D, key: usize) -> bool {
+        let highlighted_submenu = self
+            .highlighted
+            .and_then(|i| self.menu.item(i))
+            .is_some_and(|item| item.enabled && item.has_submenu());
+        match key {
+            keys::DOWN => self.move_highlight(1),
+            keys::UP => self.move_highlight(-1),
+            keys::HOME => {
+                self.highlighted = None;
+                self.move_highlight(1);
+            }
+            keys::END => {
+                self.highlighted = None;
+                self.move_highlight(-1);
+            }
+            keys::RIGHT if highlighted_submenu => {
+                if let Some(index) = self.highlighted {
+                    self.open_submenu(ctx, index, true);
+                }
+            }
+            keys::LEFT if self.root.is_some() => ctx.dismiss_popup(),
+            // Left and Right switch the menus of a menu bar
+            keys::LEFT | keys::RIGHT => return !self.in_bar,
+            keys::RETURN | keys::SPACE => {
+                if let Some(index) = self.highlighted {
+                    self.choose(ctx, data, index, true);
+                }
+            }
+            keys::MENU => {
+                if let Some(root) = self.root.or_else(|| ctx.popup()) {
+                    ctx.close_popup(root);
+                }
+            }
+            // Escape closes the menu through the popup layer, Alt with a letter goes to the menu bar
+            keys::ESCAPE => return false,
+            _ if ctx.modifiers().alt => return false,
+            // Other keys stay in the menu
+            _ => {}
+        }
+        ctx.request_paint();
+        true
+    }
+
+    fn draw_check(ctx: &mut DrawCtx, x: f32, y: f32, color: u32) {
+        let mut pb = PathBuilder::new();
+        pb.move_to(x + 3., y + 8.5);
+        pb.line_to(x + 6.5, y + 12.);
+        pb.line_to(x + 13., y + 4.5);
+        if let Some(path) = pb.finish() {
+            let stroke = Stroke {
+                width: 1.5,
+                line_cap: LineCap::Round,
+                line_join: LineJoin::Round,
+                ..Default::default()
+            };
+            ctx.stroke_path(&path, &gen_paint(color), &stroke);
+        }
+    }
+
+    fn draw_arrow(ctx: &mut DrawCtx, x: f32, cy: f32, color: u32) {
+        let mut pb = PathBuilder::new();
+        pb.move_to(x + 4., cy - 4.);
+        pb.line_to(x + 8., cy);
+        pb.line_to(x + 4., cy + 4.);
+        if let Some(path) = pb.finish() {
+            let stroke = Stroke {
+                width: 1.5,
+                line_cap: LineCap::Round,
+                line_join: LineJoin::Round,
+                ..Default::default()
+            };
+            ctx.stroke_path(&path, &gen_paint(color), &stroke);
+        }
+    }
+}
+
+impl<D: 'static> Widget<D> for MenuPopup<D> {
+    fn event(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        let (x, y) = ctx.mouse_pos();
+        let (width, height) = self.layout_size;
+        let inside = x >= 0. && y >= 0. && x < width && y < height;
+        let row = if inside { self.row_at(y) } else { None };
+        let item = row.filter(|&i| self.menu.item(i).is_some());
+        let top = self.submenu.is_none();
+        match ctx.event() {
+            WindowEvent::PopupClosed(id) if self.submenu.map(|(_, s)| s) == Some(id) => {
+                self.submenu = None;
+                ctx.request_paint();
+            }
+            WindowEvent::Timer(token) if self.hover_timer == Some(token) => {
+                self.hover_timer = None;
+                if self.pointer_inside {
+                    self.sync_submenu(ctx);
+                } else if let Some((index, _)) = self.submenu {
+                    // The pointer went on to the submenu, keep its entry highlighted
+                    self.highlighted = Some(index);
+                }
+                ctx.request_paint();
+            }
+            WindowEvent::MouseMove(_, _) => {
+                self.pointer_inside = inside;
+                if inside && item != self.highlighted {
+                    self.highlighted = item;
+                    self.hover_timer = Some(ctx.request_timer(SUBMENU_DELAY));
+                    ctx.request_paint();
+                }
+            }
+            WindowEvent::MouseDown(_, _) | WindowEvent::ContextMenu(_, _) if inside => {
+                ctx.set_handled()
+            }
+            WindowEvent::MouseUp(_, _) => {
+                if let Some(index) = item {
+                    self.choose(ctx, data, index, false);
+                    ctx.set_handled();
+                }
+            }
+            WindowEvent::KeyDown(key) if top && self.handle_key(ctx, data, key) => {
+                ctx.set_handled();
+            }
+            WindowEvent::CharInput(_, c) if top => {
+                let c = c.to_lowercase().next();
+                let found = (0..self.menu.entries.len()).find(|&i| {
+                    self.menu
+                        .item(i)
+                        .is_some_and(|item| item.mnemonic.map(|(_, m)| m) == c)
+                });
+                if let Some(index) = found {
+                    self.highlighted = Some(index);
+                    self.choose(ctx, data, index, true);
+                    ctx.request_paint();
+                }
+                ctx.set_handled();
+            }
+            _ => {}
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        let checks = self.check_states(data);
+        if checks != self.checks {
+            self.checks = checks;
+            ctx.request_paint();
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, data: &D) {
+        self.checks = self.check_states(data);
+        let (width, _) = self.layout_size;
+        let line_height = line_metrics(TEXT_SIZE, FontType::Normal).height;
+        let has_arrow = self
+            .menu
+            .entries
+            .iter()
+            .any(|e| matches!(e, MenuEntry::Item(item) if item.has_submenu()));
+        let right = width - PADDING - ITEM_PADDING_X;
+        let shortcut_right = if has_arrow {
+            right - ARROW_WIDTH - GAP
+        } else {
+            right
+        };
+        let icon_x = PADDING + ITEM_PADDING_X;
+        let label_x = icon_x + ICON_SIZE + GAP;
+        let accent = get_theme_color() | 0xFF;
+        for (index, entry) in self.menu.entries.iter().enumerate() {
+            let (top, height) = match self.rows.get(index) {
+                Some(&r) => r,
+                None => break,
+            };
+            let item = match entry {
+                MenuEntry::Item(item) => item,
+                MenuEntry::Separator => {
+                    let path = gen_rect_path(
+                        PADDING + ITEM_PADDING_X,
+                        top + (height / 2.).floor(),
+                        width - (PADDING + ITEM_PADDING_X) * 2.,
+                        1.,
+                    );
+                    ctx.fill_path(&path, &gen_paint(SEPARATOR_COLOR));
+                    continue;
+                }
+            };
+            let highlighted =
+                self.highlighted == Some(index) || self.submenu.map(|(i, _)| i) == Some(index);
+            if highlighted {
+                let path = gen_round_rect_path(PADDING, top, width - PADDING * 2., height, RADIUS);
+                ctx.fill_path(&path, &gen_paint(HIGHLIGHT_COLOR));
+            }
+            let color = if item.enabled {
+                TEXT_COLOR
+            } else {
+                DISABLED_COLOR
+            };
+            let icon_y = top + ((height - ICON_SIZE) / 2.).round();
+            let checked = self.checks.get(index).copied().unwrap_or(false);
+            match &item.icon {
+                Some(icon) => {
+                    if checked {
+                        // A checked item with an icon shows a box behind the icon
+                        let path = gen_round_rect_path(
+                            icon_x - 2.,
+                            icon_y - 2.,
+                            ICON_SIZE + 4.,
+                            ICON_SIZE + 4.,
+                            RADIUS,
+                        );
+                        ctx.fill_path(&path, &gen_paint(accent & 0xFFFFFF30));
+                    }
+                    let icon = &mut *icon.borrow_mut();
+                    ctx.with_save(|ctx| {
+                        ctx.translate(icon_x, icon_y);
+                        Widget::<D>::draw(icon, ctx, data);
+                    });
+                }
+                None if checked => Self::draw_check(ctx, icon_x, icon_y, color),
+                None => {}
+            }
+            let text_y = top + ((height - line_height) / 2.).round();
+            draw_label(
+                ctx,
+                label_x,
+                text_y,
+                &item.label,
+                item.mnemonic,
+                self.keyboard,
+                color,
+            );
+            if !item.shortcut.is_empty() {
+                let w = measure_text(&item.shortcut, TEXT_SIZE, FontType::Normal);
+                let color = if item.enabled {
+                    SHORTCUT_COLOR
+                } else {
+                    DISABLED_COLOR
+                };
+                ctx.draw_text(shortcut_right - w, text_y, &item.shortcut, TEXT_SIZE, color);
+            }
+            if item.has_submenu() {
+                Self::draw_arrow(ctx, right - ARROW_WIDTH, top + height / 2., color);
+            }
+        }
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let item_height =
+            line_metrics(TEXT_SIZE, FontType::Normal).height.ceil() + ITEM_PADDING_Y * 2.;
+        let mut top = PADDING;
+        let mut columns = (0f32, 0f32);
+        let mut has_arrow = false;
+        self.rows.clear();
+        for entry in self.menu.entries.iter() {
+            let height = match entry {
+                MenuEntry::Item(item) => {
+                    columns.0 = columns
+                        .0
+                        .max(measure_text(&item.label, TEXT_SIZE, FontType::Normal).ceil());
+                    columns.1 = columns
+                        .1
+                        .max(measure_text(&item.shortcut, TEXT_SIZE, FontType::Normal).ceil());
+                    has_arrow |= item.has_submenu();
+                    if let Some(icon) = &item.icon {
+                        let size = ICON_SIZE as isize;
+                        Widget::<D>::layout(
+                            &mut *icon.borrow_mut(),
+                            AreaBox::with_size(size, size),
+                        );
+                    }
+                    item_height
+                }
+                MenuEntry::Separator => SEPARATOR_HEIGHT,
+            };
+            self.rows.push((top, height));
+            top += height;
+        }
+        let mut width = (PADDING + ITEM_PADDING_X) * 2. + ICON_SIZE + GAP + columns.0;
+        if columns.1 > 0. {
+            width += SHORTCUT_GAP + columns.1;
+        }
+        if has_arrow {
+            width += GAP + ARROW_WIDTH;
+        }
+        self.layout_size = (
+            width.max(MIN_WIDTH).min(max_box.width() as f32),
+            (top + PADDING).min(max_box.height() as f32),
+        );
+        AreaBox::with_size(self.layout_size.0 as isize, self.layout_size.1 as isize)
+    }
+}
+
+/// Wraps a widget and opens a context menu when it's right clicked.
+pub struct ContextMenuArea<D> {
+    _uid: WidgetUid,
+    inner: WidgetPod<D>,
+    menu: Menu<D>,
+}
+
+impl<D: 'static> ContextMenuArea<D> {
+    pub fn new(inner: Box<dyn Widget<D>>, menu: Menu<D>) -> Self {
+        Self {
+            _uid: gen_uid(),
+            inner: inner.into(),
+            menu,
+        }
+    }
+
+    pub fn set_menu(&mut self, menu: Menu<D>) {
+        self.menu = menu;
+    }
+}
+
+impl<D: 'static> Widget<D> for ContextMenuArea<D> {
+    fn event(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        self.inner.event(ctx, data);
+        // The inner widgets may open their own menu
+        if let WindowEvent::ContextMenu(_, _) = ctx.event() {
+            if !ctx.is_handled() && !self.menu.is_empty() {
+                let (x, y) = ctx.mouse_pos();
+                self.menu.open(ctx, x, y);
+                ctx.set_handled();
+            }
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        self.inner.update(ctx, data);
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, data: &D) {
+        self.inner.draw(ctx, data);
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let size = self.inner.layout(max_box);
+        self.inner.set_origin(0., 0.);
+        size
+    }
+}
+
+struct BarMenu<D> {
+    label: String,
+    mnemonic: Option<(usize, char)>,
+    menu: Menu<D>,
+    /// Left and width of the title
+    bounds: (f32, f32),
+}
+
+/// A row of menu titles, put at the top of a `WindowControl` with `with_menu_bar`.
+///
+/// Pressing and releasing Alt, or F10, moves the keyboard to the titles, Alt with a mnemonic opens a menu.
+pub struct MenuBar<D> {
+    _uid: WidgetUid,
+    menus: Vec<BarMenu<D>>,
+    hovered: Option<usize>,
+    /// The title chosen with the keyboard, or the one with its menu open
+    highlighted: Option<usize>,
+    /// The open menu and its popup
+    open: Option<(usize, PopupId)>,
+    /// The titles have the keyboard, after Alt or F10
+    keyboard: bool,
+    /// Where the focus goes back when the titles give the keyboard back
+    return_focus: Option<WidgetUid>,
+    alt_down: bool,
+    /// Alt was pressed without another key, releasing it moves the keyboard to the titles
+    alt_armed: bool,
+    layout_size: (f32, f32),
+}
+
+impl<D: 'static> MenuBar<D> {
+    pub fn new() -> Self {
+        Self {
+            _uid: gen_uid(),
+            menus: Vec::new(),
+            hovered: None,
+            highlighted: None,
+            open: None,
+            keyboard: false,
+            return_focus: None,
+            alt_down: false,
+            alt_armed: false,
+            layout_size: (0., 0.),
+        }
+    }
+
+    /// Add a menu, `&` in the label marks its mnemonic like in `MenuItem::new`.
+    pub fn with_menu(mut self, label: &str, menu: Menu<D>) -> Self {
+        let (label, mnemonic) = parse_mnemonic(label);
+        self.menus.push(BarMenu {
+            label,
+            mnemonic,
+            menu,
+            bounds: (0., 0.),
+        });
+        self
+    }
+
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        self.open.is_some()
+    }
+
+    fn title_at(&self, x: f32, y: f32) -> Option<usize> {
+        if y < 0. || y >= self.layout_size.1 {
+            return None;
+        }
+        self.menus
+            .iter()
+            .position(|m| x >= m.bounds.0 && x < m.bounds.0 + m.bounds.1)
+    }
+
+    fn enter_keyboard(&mut self, ctx: &mut EventCtx) {
+        if self.keyboard || self.menus.is_empty() {
+            return;
+        }
+        self.keyboard = true;
+        self.highlighted = Some(0);
+        self.return_focus = ctx.focus;
+        ctx.request_focus();
+        ctx.request_paint();
+    }
+
+    fn leave_keyboard(&mut self, ctx: &mut EventCtx) {
+        if !self.keyboard {
+            return;
+        }
+        self.keyboard = false;
+        self.highlighted = self.open.map(|(i, _)| i);
+        if ctx.is_focused() {
+            ctx.focus = self.return_focus.take();
+        }
+        ctx.request_paint();
+    }
+
+    fn open_menu(&mut self, ctx: &mut EventCtx, index: usize, keyboard: bool) {
+        if let Some((_, id)) = self.open.take() {
+            ctx.close_popup(id);
+        }
+        let (left, width) = self.menus[index].bounds;
+        let menu = self.menus[index].menu.clone();
+        let window = menu.window;
+        let content = MenuPopup::new(menu, None, true, keyboard);
+        let popup = Popup::new(Box::new(content))
+            .with_anchor(left, 0., width, self.layout_size.1)
+            .with_gap(2.)
+            .with_window(window);
+        self.open = Some((index, ctx.open_popup(popup)));
+        self.highlighted = Some(index);
+        ctx.request_paint();
+    }
+
+    /// Move to the title before or after the highlighted one, wrapping around.
+    fn step(&self, delta: isize) -> usize {
+        let count = self.menus.len() as isize;
+        let current = self.highlighted.unwrap_or(0) as isize;
+        (current + delta).rem_euclid(count) as usize
+    }
+
+    fn handle_key(&mut self, ctx: &mut EventCtx, key: usize) {
+        match key {
+            keys::MENU => {
+                if !self.alt_down {
+                    self.alt_down = true;
+                    self.alt_armed = self.open.is_none();
+                    ctx.request_paint();
+                }
+                return;
+            }
+            keys::F10 if !ctx.modifiers().shift && self.open.is_none() => {
+                if self.keyboard {
+                    self.leave_keyboard(ctx);
+                } else {
+                    self.enter_keyboard(ctx);
+                }
+                ctx.set_handled();
+                return;
+            }
+            _ => {}
+        }
+        self.alt_armed = false;
+        if ctx.modifiers().alt {
+            let found = key_char(key).and_then(|c| {
+                self.menus
+                    .iter()
+                    .position(|m| m.mnemonic.map(|(_, m)| m) == Some(c))
+            });
+            if let Some(index) = found {
+                self.leave_keyboard(ctx);
+                self.open_menu(ctx, index, true);
+                ctx.set_handled();
+            }
+            return;
+        }
+        if self.open.is_some() {
+            // The open menu passes on Left and Right
+            let delta = match key {
+                keys::LEFT => -1,
+                keys::RIGHT => 1,
+                _ => return,
+            };
+            let next = self.step(delta);
+            self.open_menu(ctx, next, true);
+            ctx.set_handled();
+            return;
+        }
+        if !self.keyboard {
+            return;
+        }
+        match key {
+            keys::LEFT => self.highlighted = Some(self.step(-1)),
+            keys::RIGHT => self.highlighted = Some(self.step(1)),
+            keys::DOWN | keys::UP | keys::RETURN | keys::SPACE => {
+                let index = self.highlighted.unwrap_or(0);
+                self.open_menu(ctx, index, true);
+            }
+            keys::ESCAPE => self.leave_keyboard(ctx),
+            _ => {}
+        }
+        ctx.request_paint();
+        ctx.set_handled();
+    }
+}
+
+impl<D: 'static> Default for MenuBar<D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<D: 'static> Widget<D> for MenuBar<D> {
+    fn event(&mut self, ctx: &mut EventCtx, _data: &mut D) {
+        let (x, y) = ctx.mouse_pos();
+        let title = self.title_at(x, y);
+        if self.keyboard && self.open.is_none() && !ctx.is_focused() {
+            // The focus was moved elsewhere, e.g. by a click
+            self.return_focus = None;
+            self.leave_keyboard(ctx);
+        }
+        match ctx.event() {
+            WindowEvent::PopupClosed(id) if self.open.map(|(_, p)| p) == Some(id) => {
+                self.open = None;
+                self.highlighted = None;
+                self.leave_keyboard(ctx);
+                ctx.request_paint();
+            }
+            WindowEvent::FocusLost => {
+                self.alt_down = false;
+                self.alt_armed = false;
+                self.leave_keyboard(ctx);
+                ctx.request_paint();
+            }
+            WindowEvent::MouseMove(_, _) => {
+                if title != self.hovered {
+                    self.hovered = title;
+                    ctx.request_paint();
+                }
+                // Moving over the titles switches the open menu
+                if let (Some(index), Some((open, _))) = (title, self.open) {
+                    if index != open {
+                        self.open_menu(ctx, index, false);
+                    }
+                }
+            }
+            WindowEvent::MouseDown(_, _) => {
+                if let Some(index) = title {
+                    self.leave_keyboard(ctx);
+                    self.open_menu(ctx, index, false);
+                    ctx.set_handled();
+                }
+            }
+            WindowEvent::KeyDown(key) => self.handle_key(ctx, key),
+            WindowEvent::KeyUp(keys::MENU) => {
+                self.alt_down = false;
+                if self.alt_armed && self.open.is_none() {
+                    // Alt pressed and released alone
+                    if self.keyboard {
+                        self.leave_keyboard(ctx);
+                    } else {
+                        self.enter_keyboard(ctx);
+                    }
+                    ctx.set_handled();
+                }
+                self.alt_armed = false;
+                ctx.request_paint();
+            }
+            _ => {}
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, _data: &D) {
+        let height = self.layout_size.1;
+        let line_height = line_metrics(TEXT_SIZE, FontType::Normal).height;
+        let text_y = ((height - line_height) / 2.).round();
+        let underline = self.keyboard || self.alt_down;
+        for (index, menu) in self.menus.iter().enumerate() {
+            let (left, width) = menu.bounds;
+            let background = if self.highlighted == Some(index) {
+                Some(HIGHLIGHT_COLOR)
+            } else if self.hovered == Some(index) {
+                Some(HOVER_COLOR)
+            } else {
+                None
+            };
+            if let Some(color) = background {
+                let path = gen_round_rect_path(left, 2., width, height - 4., RADIUS);
+                ctx.fill_path(&path, &gen_paint(color));
+            }
+            draw_label(
+                ctx,
+                left + BAR_PADDING_X,
+                text_y,
+                &menu.label,
+                menu.mnemonic,
+                underline,
+                TEXT_COLOR,
+            );
+        }
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let mut left = PADDING;
+        for menu in &mut self.menus {
+            let width =
+                measure_text(&menu.label, TEXT_SIZE, FontType::Normal).ceil() + BAR_PADDING_X * 2.;
+            menu.bounds = (left, width);
+            left += width;
+        }
+        let width = if max_box.width() == isize::MAX {
+            left + PADDING
+        } else {
+            max_box.width() as f32
+        };
+        let height = line_metrics(TEXT_SIZE, FontType::Normal).height.ceil() + BAR_PADDING_Y * 2.;
+        self.layout_size = (width, height.min(max_box.height() as f32));
+        AreaBox::with_size(self.layout_size.0 as isize, self.layout_size.1 as isize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Modifiers;
+
+    #[derive(Default)]
+    struct Data {
+        wrap: bool,
+        saved: usize,
+    }
+
+    fn ctx(modifiers: Modifiers) -> EventCtx {
+        EventCtx::new(WindowEvent::None, (0., 0.), modifiers, None)
+    }
+
+    fn file_menu() -> Menu<Data> {
+        Menu::new()
+            .with_item(MenuItem::new("&Save").on_click(|d: &mut Data| d.saved += 1))
+            .with_separator()
+            .with_item(MenuItem::new("Save &As").with_enabled(false))
+            .with_item(
+                MenuItem::new("&Wrap").with_check_binding(Binding::new(
+                    |d: &Data| &d.wrap,
+                    |d: &mut Data| &mut d.wrap,
+                )),
+            )
+    }
+
+    #[test]
+    fn mnemonics_are_parsed_from_the_labels() {
+        assert_eq!(parse_mnemonic("&Open"), ("Open".into(), Some((0, 'o'))));
+        assert_eq!(
+            parse_mnemonic("Save &As"),
+            ("Save As".into(), Some((5, 'a')))
+        );
+        assert_eq!(
+            parse_mnemonic("R&&D &Tools"),
+            ("R&D Tools".into(), Some((4, 't')))
+        );
+        assert_eq!(parse_mnemonic("Plain&"), ("Plain".into(), None));
+        assert_eq!(key_char(0x41), Some('a'));
+        assert_eq!(key_char(0x35), Some('5'));
+        assert_eq!(key_char(keys::ESCAPE), None);
+    }
+
+    #[test]
+    fn highlight_skips_separators_and_wraps() {
+        let mut popup = MenuPopup::new(file_menu(), None, false, true);
+        assert_eq!(popup.highlighted, Some(0));
+        popup.move_highlight(1);
+        assert_eq!(popup.highlighted, Some(2));
+        popup.move_highlight(1);
+        popup.move_highlight(1);
+        assert_eq!(popup.highlighted, Some(0));
+        popup.move_highlight(-1);
+        assert_eq!(popup.highlighted, Some(3));
+    }
+
+    #[test]
+    fn choosing_runs_the_item_and_closes_the_menus() {
+        let mut data = Data::default();
+        let mut popup = MenuPopup::new(file_menu(), Some(7), false, false);
+        let mut ctx = ctx(Modifiers::default());
+        popup.choose(&mut ctx, &mut data, 3, false);
+        assert!(data.wrap);
+        assert_eq!(ctx.close_popups, [7]);
+        popup.choose(&mut ctx, &mut data, 0, false);
+        assert_eq!(data.saved, 1);
+        // Disabled items do nothing
+        let mut ctx = self::ctx(Modifiers::default());
+        popup.choose(&mut ctx, &mut data, 2, false);
+        assert!(ctx.close_popups.is_empty());
+    }
+
+    #[test]
+    fn alt_with_a_mnemonic_opens_the_bar_menu() {
+        let mut bar = MenuBar::new()
+            .with_menu("&File", file_menu())
+            .with_menu("&Edit", Menu::new());
+        let alt = Modifiers {
+            alt: true,
+            ..Default::default()
+        };
+        let mut ctx = ctx(alt);
+        bar.handle_key(&mut ctx, 0x45);
+        assert_eq!(bar.open.map(|(i, _)| i), Some(1));
+        assert!(ctx.is_handled());
+        // Right goes to the next menu, wrapping around
+        let mut ctx = self::ctx(Modifiers::default());
+        bar.handle_key(&mut ctx, keys::RIGHT);
+        assert_eq!(bar.open.map(|(i, _)| i), Some(0));
+        assert_eq!(ctx.close_popups.len(), 1);
+    }
+}
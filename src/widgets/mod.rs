@@ -2,11 +2,51 @@ pub mod window;
 pub use window::WindowControl;
 pub mod button;
 pub use button::ButtonControl;
+pub mod checkbox;
+pub use checkbox::Checkbox;
+pub mod radio;
+pub use radio::RadioGroup;
+pub mod switch;
+pub use switch::Switch;
+pub mod slider;
+pub use slider::{RangeSlider, Slider};
+pub mod progress;
+pub use progress::{ProgressBar, Spinner};
+pub mod skeleton;
+pub use skeleton::Skeleton;
 pub mod label;
 pub use label::TextLabelControl;
 pub mod image;
 pub use self::image::ImageControl;
+pub mod svg_icon;
+pub use svg_icon::SvgIcon;
 pub mod input;
 pub use input::InputControl;
+pub mod text_area;
+pub use text_area::TextArea;
+pub mod combo;
+pub use combo::ComboBox;
+pub mod menu;
+pub use menu::{ContextMenuArea, Menu, MenuBar, MenuItem};
+pub mod tooltip;
+pub use tooltip::Tooltip;
+pub mod dialog;
+pub use dialog::Dialog;
+pub mod scroll;
+pub use scroll::ScrollControl;
+pub mod list;
+pub use list::ListControl;
+pub mod table;
+pub use table::TableControl;
+pub mod tree;
+pub use tree::TreeControl;
+pub mod split;
+pub use split::SplitControl;
+pub mod tabs;
+pub use tabs::{TabPosition, Tabs};
+pub mod expander;
+pub use expander::{Accordion, Expander};
+pub mod navigator;
+pub use navigator::{Navigator, NavigatorHandle, Transition};
 
 pub(crate) mod template;
@@ -0,0 +1,512 @@
+//! 页面导航：按路由名创建页面并压入页面栈，通过 `NavigatorHandle` 发出压入、弹出、替换的命令
+//!
+//! 页面切换时按选择的过渡效果（滑动、淡入淡出、缩放）用二维弹簧播放动画，弹出的页面在退出动画结束前保持存活
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::mem;
+use std::rc::Rc;
+
+use tiny_skia::{Pixmap, PixmapPaint};
+
+use crate::core::WidgetPod;
+use crate::events::{keys, WindowEvent};
+use crate::r#box::AreaBox;
+use crate::traits::Widget;
+use crate::utils::spring::Spring2D;
+use crate::utils::uid::gen_uid;
+use crate::utils::WidgetUid;
+use crate::{DrawCtx, EventCtx, UpdateCtx};
+
+/// Motion of a page at rest, as `(offset, presence)`
+///
+/// The offset is a fraction of the width used by sliding, the presence goes from 0 to 1 and is
+/// used by fading and scaling.
+const REST: (f32, f32) = (0., 1.);
+/// Where a pushed page comes from and a popped page goes to
+const OUTSIDE: (f32, f32) = (1., 0.);
+/// Where a page covered by a new one goes
+const COVERED: (f32, f32) = (-0.3, 1.);
+/// Where a replaced page goes
+const REPLACED: (f32, f32) = (-0.3, 0.);
+/// Scale of a page with no presence when scaling
+const MIN_SCALE: f32 = 0.92;
+
+/// How pages appear and disappear.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Transition {
+    None,
+    /// New pages slide in from the right, over the previous one
+    Slide,
+    Fade,
+    /// New pages grow to their size while fading in
+    Scale,
+}
+
+enum Command {
+    Push(String),
+    Pop,
+    Replace(String),
+}
+
+#[derive(Default)]
+struct NavState {
+    commands: Vec<Command>,
+    /// Routes of the pages in the stack, the current one last
+    history: Vec<String>,
+}
+
+/// Sends commands to a `Navigator`, they are carried out after the current event.
+///
+/// Cloned handles point to the same navigator, so they can be moved into the callbacks of the pages.
+#[derive(Clone)]
+pub struct NavigatorHandle {
+    state: Rc<RefCell<NavState>>,
+}
+
+impl NavigatorHandle {
+    /// Show the page of a route over the current one, unknown routes are ignored.
+    pub fn push(&self, route: &str) {
+        self.state
+            .borrow_mut()
+            .commands
+            .push(Command::Push(route.into()));
+    }
+
+    /// Go back to the previous page, nothing happens on the first page.
+    pub fn pop(&self) {
+        self.state.borrow_mut().commands.push(Command::Pop);
+    }
+
+    /// Show the page of a route instead of the current one, unknown routes are ignored.
+    pub fn replace(&self, route: &str) {
+        self.state
+            .borrow_mut()
+            .commands
+            .push(Command::Replace(route.into()));
+    }
+
+    /// Route of the current page.
+    pub fn current(&self) -> Option<String> {
+        self.state.borrow().history.last().cloned()
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        self.state.borrow().history.len() > 1
+    }
+
+    /// Routes of the pages in the stack, the current one last.
+    pub fn history(&self) -> Vec<String> {
+        self.state.borrow().history.clone()
+    }
+}
+
+type RouteBuilder<D> = Box<dyn Fn() -> Box<dyn Widget<D>>>;
+
+struct Page<D> {
+    route: String,
+    widget: WidgetPod<D>,
+    motion: Spring2D,
+}
+
+/// A page popped or replaced, alive until its exit animation ends
+struct Leaving<D> {
+    page: Page<D>,
+    /// Popped pages are drawn over the current one, replaced pages under it
+    above: bool,
+}
+
+/// A stack of pages created from routes, moving between them with transitions.
+pub struct Navigator<D> {
+    _uid: WidgetUid,
+    routes: HashMap<String, RouteBuilder<D>>,
+    stack: Vec<Page<D>>,
+    leaving: Vec<Leaving<D>>,
+    state: Rc<RefCell<NavState>>,
+    transition: Transition,
+    layout_size: (f32, f32),
+}
+
+impl<D> Navigator<D> {
+    pub fn new() -> Self {
+        Self {
+            _uid: gen_uid(),
+            routes: HashMap::new(),
+            stack: Vec::new(),
+            leaving: Vec::new(),
+            state: Rc::new(RefCell::new(NavState::default())),
+            transition: Transition::Slide,
+            layout_size: (0., 0.),
+        }
+    }
+
+    /// Register the page created for a route, a new page is created every time it's pushed.
+    pub fn with_route<F>(mut self, route: &str, builder: F) -> Self
+    where
+        F: Fn() -> Box<dyn Widget<D>> + 'static,
+    {
+        self.routes.insert(route.into(), Box::new(builder));
+        self
+    }
+
+    /// The first page, shown without transition.
+    pub fn with_initial(self, route: &str) -> Self {
+        self.handle().push(route);
+        self
+    }
+
+    pub fn with_transition(mut self, v: Transition) -> Self {
+        self.transition = v;
+        self
+    }
+
+    pub fn handle(&self) -> NavigatorHandle {
+        NavigatorHandle {
+            state: self.state.clone(),
+        }
+    }
+
+    #[inline]
+    pub fn set_transition(&mut self, v: Transition) {
+        self.transition = v;
+    }
+
+    /// Move a page to `to`, starting at `from` if given.
+    fn animate(
+        transition: Transition,
+        motion: &mut Spring2D,
+        from: Option<(f32, f32)>,
+        to: (f32, f32),
+    ) {
+        match (transition, from) {
+            (Transition::None, _) => motion.set_position(to),
+            (_, Some(from)) => motion.set_position(from),
+            _ => {}
+        }
+        motion.set_target(to);
+    }
+
+    fn build(&self, route: &str) -> Option<Page<D>> {
+        let builder = self.routes.get(route)?;
+        let mut motion = Spring2D::new(REST);
+        motion.set_speed(16.);
+        Some(Page {
+            route: route.into(),
+            widget: builder().into(),
+            motion,
+        })
+    }
+
+    fn enter(&mut self, mut page: Page<D>) {
+        // The first page is just there
+        if !self.stack.is_empty() {
+            Self::animate(self.transition, &mut page.motion, Some(OUTSIDE), REST);
+        }
+        self.stack.push(page);
+    }
+
+    fn leave(&mut self, mut page: Page<D>, above: bool) {
+        let to = if above { OUTSIDE } else { REPLACED };
+        Self::animate(self.transition, &mut page.motion, None, to);
+        if self.transition != Transition::None {
+            self.leaving.push(Leaving { page, above });
+        }
+    }
+
+    /// Carry out the commands sent since the last time, returns whether the pages changed.
+    fn process(&mut self) -> bool {
+        let commands = mem::take(&mut self.state.borrow_mut().commands);
+        if commands.is_empty() {
+            return false;
+        }
+        for command in commands {
+            match command {
+                Command::Push(route) => {
+                    if let Some(page) = self.build(&route) {
+                        if let Some(top) = self.stack.last_mut() {
+                            Self::animate(self.transition, &mut top.motion, None, COVERED);
+                        }
+                        self.enter(page);
+                    }
+                }
+                Command::Pop if self.stack.len() > 1 => {
+                    if let Some(page) = self.stack.pop() {
+                        self.leave(page, true);
+                    }
+                    if let Some(top) = self.stack.last_mut() {
+                        Self::animate(self.transition, &mut top.motion, None, REST);
+                    }
+                }
+                Command::Pop => {}
+                Command::Replace(route) => {
+                    if let Some(page) = self.build(&route) {
+                        if let Some(old) = self.stack.pop() {
+                            self.leave(old, false);
+                        }
+                        self.enter(page);
+                    }
+                }
+            }
+        }
+        self.state.borrow_mut().history = self.stack.iter().map(|p| p.route.clone()).collect();
+        true
+    }
+
+    fn draw_page(&self, page: &mut Page<D>, ctx: &mut DrawCtx, data: &D) {
+        let (width, height) = self.layout_size;
+        let (offset, presence) = page.motion.position();
+        let presence = presence.clamp(0., 1.);
+        match self.transition {
+            Transition::None | Transition::Slide => {
+                ctx.with_save(|ctx| {
+                    ctx.translate((offset * width).round(), 0.);
+                    page.widget.draw(ctx, data);
+                });
+            }
+            _ if presence >= 1. => page.widget.draw(ctx, data),
+            _ if presence <= 0. => {}
+            transition => {
+                // The page is drawn on a layer first, so overlapping parts are not blended twice
+                let mut layer = match Pixmap::new(width.ceil() as u32, height.ceil() as u32) {
+                    Some(pixmap) => pixmap,
+                    None => return,
+                };
+                {
+                    let mut pixmap = layer.as_mut();
+                    let mut layer_ctx = DrawCtx::new(&mut pixmap);
+                    layer_ctx.widget_size = self.layout_size;
+                    layer_ctx.focus = ctx.focus;
+                    page.widget.draw(&mut layer_ctx, data);
+                    ctx.adopt_requests(&mut layer_ctx);
+                }
+                let paint = PixmapPaint {
+                    opacity: presence,
+                    ..Default::default()
+                };
+                ctx.with_save(|ctx| {
+                    if transition == Transition::Scale {
+                        let scale = MIN_SCALE + (1. - MIN_SCALE) * presence;
+                        ctx.translate(width / 2., height / 2.);
+                        ctx.scale(scale, scale);
+                        ctx.translate(-width / 2., -height / 2.);
+                    }
+                    ctx.draw_pixmap(0, 0, layer.as_ref(), &paint);
+                });
+            }
+        }
+    }
+}
+
+impl<D> Default for Navigator<D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Events only the current page receives
+fn is_input(event: WindowEvent) -> bool {
+    matches!(
+        event,
+        WindowEvent::MouseMove(_, _)
+            | WindowEvent::MouseDown(_, _)
+            | WindowEvent::MouseUp(_, _)
+            | WindowEvent::MousePress(_, _)
+            | WindowEvent::ContextMenu(_, _)
+            | WindowEvent::MouseWheel(_)
+            | WindowEvent::MouseHWheel(_)
+            | WindowEvent::KeyDown(_)
+            | WindowEvent::KeyUp(_)
+            | WindowEvent::KeyPress(_)
+            | WindowEvent::CharInput(_, _)
+            | WindowEvent::CollectFocus
+    )
+}
+
+impl<D> Widget<D> for Navigator<D> {
+    fn event(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        let event = ctx.event();
+        let current = self.stack.len().saturating_sub(1);
+        for (index, page) in self.stack.iter_mut().enumerate() {
+            if index == current || !is_input(event) {
+                page.widget.event(ctx, data);
+            }
+        }
+        if !is_input(event) {
+            for leaving in &mut self.leaving {
+                leaving.page.widget.event(ctx, data);
+            }
+        }
+        if let WindowEvent::KeyDown(keys::LEFT) = event {
+            // Alt+Left goes back like in a browser
+            if ctx.modifiers().alt && !ctx.is_handled() && self.stack.len() > 1 {
+                self.handle().pop();
+                ctx.set_handled();
+            }
+        }
+        if self.process() {
+            ctx.request_layout();
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        // Commands may be sent from where only the data is changed, e.g. a button callback
+        if self.process() {
+            ctx.request_layout();
+        }
+        for page in &mut self.stack {
+            page.widget.update(ctx, data);
+        }
+        for leaving in &mut self.leaving {
+            leaving.page.widget.update(ctx, data);
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, data: &D) {
+        let (width, height) = self.layout_size;
+        let mut stack = mem::take(&mut self.stack);
+        let mut leaving = mem::take(&mut self.leaving);
+        // A replaced page may arrive first, it stays under the new one until that one arrives too
+        if stack.last_mut().is_none_or(|p| p.motion.arrived()) {
+            leaving = leaving
+                .into_iter()
+                .filter_map(|mut l| {
+                    if l.page.motion.arrived() {
+                        None
+                    } else {
+                        Some(l)
+                    }
+                })
+                .collect();
+        }
+        ctx.with_save(|ctx| {
+            ctx.clip_rect(0., 0., width, height);
+            let len = stack.len();
+            // The page under the current one shows while the current one moves
+            if len > 1 && !stack[len - 1].motion.arrived() {
+                self.draw_page(&mut stack[len - 2], ctx, data);
+            }
+            for l in leaving.iter_mut().filter(|l| !l.above) {
+                self.draw_page(&mut l.page, ctx, data);
+            }
+            if let Some(page) = stack.last_mut() {
+                self.draw_page(page, ctx, data);
+            }
+            for l in leaving.iter_mut().filter(|l| l.above) {
+                self.draw_page(&mut l.page, ctx, data);
+            }
+        });
+        let animating = !leaving.is_empty() || stack.iter_mut().any(|p| !p.motion.arrived());
+        if animating {
+            ctx.request_anim_frame();
+        }
+        self.stack = stack;
+        self.leaving = leaving;
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        self.process();
+        self.layout_size = max_box.to_size_f32();
+        let size = AreaBox::with_size(max_box.width(), max_box.height());
+        for page in &mut self.stack {
+            page.widget.layout(size);
+        }
+        for leaving in &mut self.leaving {
+            leaving.page.widget.layout(size);
+        }
+        max_box
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Modifiers;
+
+    struct Page;
+
+    impl Widget<()> for Page {}
+
+    fn navigator(transition: Transition) -> Navigator<()> {
+        let mut navigator = Navigator::new()
+            .with_route("home", || Box::new(Page))
+            .with_route("list", || Box::new(Page))
+            .with_route("detail", || Box::new(Page))
+            .with_initial("home")
+            .with_transition(transition);
+        navigator.layout(AreaBox::with_size(400, 300));
+        navigator
+    }
+
+    fn update(navigator: &mut Navigator<()>) -> bool {
+        let mut ctx = UpdateCtx::new();
+        navigator.update(&mut ctx, &());
+        ctx.request_layout
+    }
+
+    #[test]
+    fn commands_change_the_stack() {
+        let mut navigator = navigator(Transition::Slide);
+        let handle = navigator.handle();
+        assert_eq!(handle.history(), ["home"]);
+        assert!(!handle.can_go_back());
+        handle.push("list");
+        handle.push("detail");
+        assert!(update(&mut navigator));
+        assert_eq!(handle.history(), ["home", "list", "detail"]);
+        handle.replace("home");
+        handle.pop();
+        update(&mut navigator);
+        assert_eq!(handle.history(), ["home", "list"]);
+        // The first page stays
+        handle.pop();
+        handle.pop();
+        update(&mut navigator);
+        assert_eq!(handle.current().as_deref(), Some("home"));
+        assert!(!update(&mut navigator));
+    }
+
+    #[test]
+    fn unknown_routes_are_ignored() {
+        let mut navigator = navigator(Transition::Slide);
+        let handle = navigator.handle();
+        handle.push("missing");
+        handle.replace("missing");
+        update(&mut navigator);
+        assert_eq!(handle.history(), ["home"]);
+    }
+
+    #[test]
+    fn leaving_pages_animate_out() {
+        let mut fading = navigator(Transition::Fade);
+        fading.handle().push("list");
+        update(&mut fading);
+        let top = fading.stack.last_mut().unwrap();
+        assert_eq!(top.motion.position(), OUTSIDE);
+        assert_eq!(top.motion.target(), REST);
+        fading.handle().pop();
+        update(&mut fading);
+        assert_eq!(fading.leaving.len(), 1);
+        assert!(fading.leaving[0].above);
+        // Without a transition the pages are dropped at once
+        let mut instant = navigator(Transition::None);
+        instant.handle().push("list");
+        instant.handle().pop();
+        update(&mut instant);
+        assert!(instant.leaving.is_empty());
+    }
+
+    #[test]
+    fn alt_left_goes_back() {
+        let mut navigator = navigator(Transition::Slide);
+        navigator.handle().push("list");
+        update(&mut navigator);
+        let modifiers = Modifiers {
+            alt: true,
+            ..Modifiers::default()
+        };
+        let mut ctx = EventCtx::new(WindowEvent::KeyDown(keys::LEFT), (0., 0.), modifiers, None);
+        navigator.event(&mut ctx, &mut ());
+        assert!(ctx.is_handled());
+        assert_eq!(navigator.handle().history(), ["home"]);
+    }
+}
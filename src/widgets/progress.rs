@@ -0,0 +1,342 @@
+//! 进度条与转圈的加载指示器，被裁剪到看不见的地方时不再请求新的动画帧
+
+use std::f32::consts::PI;
+use std::time::{Duration, Instant};
+
+use tiny_skia::{LineCap, Stroke};
+
+use crate::r#box::AreaBox;
+use crate::traits::Widget;
+use crate::utils::binding::Binding;
+use crate::utils::drawing::{gen_arc_path, gen_paint, gen_round_rect_path};
+use crate::utils::spring::Spring;
+use crate::utils::theme::get_theme_color;
+use crate::utils::uid::gen_uid;
+use crate::utils::WidgetUid;
+use crate::{DrawCtx, UpdateCtx};
+
+const TRACK_COLOR: u32 = 0x00000020;
+const DEFAULT_HEIGHT: u32 = 4;
+/// Width used when neither the size nor the parent gives one
+const DEFAULT_WIDTH: f32 = 160.;
+/// Time for the indeterminate bar to cross the track once
+const SWEEP_PERIOD: Duration = Duration::from_millis(1600);
+/// Length of the indeterminate bar relative to the track
+const SWEEP_LENGTH: f32 = 0.35;
+/// Time for a spinner to turn once
+const SPIN_PERIOD: Duration = Duration::from_millis(1200);
+/// Time for the arc of a spinner to grow and shrink back
+const ARC_PERIOD: Duration = Duration::from_millis(1800);
+const MIN_ARC: f32 = PI / 8.;
+const MAX_ARC: f32 = PI * 1.5;
+
+/// Progress of the animation in `0..1`, repeating every `period`.
+fn cycle(start: Instant, period: Duration) -> f32 {
+    (start.elapsed().as_secs_f32() / period.as_secs_f32()).fract()
+}
+
+/// How many times the arc of a spinner has grown and shrunk.
+fn start_cycles(start: Instant) -> u32 {
+    (start.elapsed().as_secs_f32() / ARC_PERIOD.as_secs_f32()) as u32
+}
+
+fn ease_in_out(t: f32) -> f32 {
+    t * t * (3. - 2. * t)
+}
+
+pub struct ProgressBar<D = ()> {
+    _uid: WidgetUid,
+    /// Between 0 and 1
+    value: f64,
+    indeterminate: bool,
+    /// Wanted size, a width of 0 fills the parent
+    size: (u32, u32),
+    color: Option<u32>,
+    /// The filled fraction, follows `value` smoothly
+    fill: Spring,
+    start: Instant,
+    binding: Option<Binding<D, f64>>,
+    layout_size: (f32, f32),
+}
+
+impl<D> ProgressBar<D> {
+    pub fn new() -> Self {
+        let mut fill = Spring::new(0.);
+        fill.speed = 10.;
+        Self {
+            _uid: gen_uid(),
+            value: 0.,
+            indeterminate: false,
+            size: (0, DEFAULT_HEIGHT),
+            color: None,
+            fill,
+            start: Instant::now(),
+            binding: None,
+            layout_size: (0., 0.),
+        }
+    }
+
+    /// The progress between 0 and 1.
+    pub fn with_value(mut self, v: f64) -> Self {
+        self.value = v.clamp(0., 1.);
+        self.fill.set_position(self.value as f32);
+        self.fill.set_target(self.value as f32);
+        self
+    }
+
+    /// Show a bar sweeping through the track instead, for jobs of unknown length.
+    pub fn with_indeterminate(mut self, v: bool) -> Self {
+        self.indeterminate = v;
+        self
+    }
+
+    pub fn with_size(mut self, w: u32, h: u32) -> Self {
+        self.size = (w, h);
+        self
+    }
+
+    /// Fill color, the theme color by default.
+    pub fn with_color(mut self, v: u32) -> Self {
+        self.color = Some(v);
+        self
+    }
+
+    /// Read the progress from the application data.
+    pub fn with_binding(mut self, v: Binding<D, f64>) -> Self {
+        self.binding = Some(v);
+        self
+    }
+
+    #[inline]
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    /// Set the progress, the bar grows to it smoothly.
+    pub fn set_value(&mut self, v: f64) {
+        self.value = v.clamp(0., 1.);
+        self.fill.set_target(self.value as f32);
+    }
+
+    #[inline]
+    pub fn is_indeterminate(&self) -> bool {
+        self.indeterminate
+    }
+
+    pub fn set_indeterminate(&mut self, v: bool) {
+        if v && !self.indeterminate {
+            self.start = Instant::now();
+        }
+        self.indeterminate = v;
+    }
+}
+
+impl<D> Default for ProgressBar<D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<D> Widget<D> for ProgressBar<D> {
+    fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        if let Some(binding) = &self.binding {
+            let value = binding.get(data).clamp(0., 1.);
+            if value != self.value {
+                self.set_value(value);
+                ctx.request_paint();
+            }
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, _data: &D) {
+        let (width, height) = self.layout_size;
+        let radius = height / 2.;
+        let color = self.color.unwrap_or_else(|| get_theme_color() | 0xFF);
+        ctx.fill_path(
+            &gen_round_rect_path(0., 0., width, height, radius),
+            &gen_paint(TRACK_COLOR),
+        );
+        let (from, to) = if self.indeterminate {
+            // The bar enters from the left and leaves to the right
+            let t = ease_in_out(cycle(self.start, SWEEP_PERIOD));
+            let head = t * (1. + SWEEP_LENGTH);
+            ((head - SWEEP_LENGTH).max(0.), head.min(1.))
+        } else {
+            (0., self.fill.position().clamp(0., 1.))
+        };
+        let animating = self.indeterminate || !self.fill.arrived();
+        let w = (to - from) * width;
+        if w > 0. {
+            let radius = radius.min(w / 2.);
+            ctx.fill_path(
+                &gen_round_rect_path(from * width, 0., w, height, radius),
+                &gen_paint(color),
+            );
+        }
+        if animating && ctx.is_visible() {
+            ctx.request_anim_frame();
+        }
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let width = match self.size.0 {
+            0 if max_box.width() == isize::MAX => DEFAULT_WIDTH,
+            0 => max_box.width() as f32,
+            w => w as f32,
+        };
+        self.layout_size = (
+            width.min(max_box.width() as f32),
+            (self.size.1 as f32).min(max_box.height() as f32),
+        );
+        AreaBox::with_size(self.layout_size.0 as isize, self.layout_size.1 as isize)
+    }
+}
+
+/// A circular indicator for jobs of unknown length.
+pub struct Spinner {
+    _uid: WidgetUid,
+    diameter: u32,
+    thickness: f32,
+    color: Option<u32>,
+    start: Instant,
+    layout_size: (f32, f32),
+}
+
+impl Spinner {
+    pub fn new() -> Self {
+        Self {
+            _uid: gen_uid(),
+            diameter: 24,
+            thickness: 3.,
+            color: None,
+            start: Instant::now(),
+            layout_size: (0., 0.),
+        }
+    }
+
+    pub fn with_diameter(mut self, v: u32) -> Self {
+        self.diameter = v;
+        self
+    }
+
+    /// Width of the arc.
+    pub fn with_thickness(mut self, v: f32) -> Self {
+        self.thickness = v;
+        self
+    }
+
+    /// Arc color, the theme color by default.
+    pub fn with_color(mut self, v: u32) -> Self {
+        self.color = Some(v);
+        self
+    }
+}
+
+impl Default for Spinner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for Spinner {
+    fn clone(&self) -> Self {
+        Self {
+            _uid: gen_uid(),
+            diameter: self.diameter,
+            thickness: self.thickness,
+            color: self.color,
+            start: self.start,
+            layout_size: self.layout_size,
+        }
+    }
+}
+
+impl<D> Widget<D> for Spinner {
+    fn draw(&mut self, ctx: &mut DrawCtx, _data: &D) {
+        let (width, height) = self.layout_size;
+        let radius = (width.min(height) - self.thickness) / 2.;
+        if radius <= 0. {
+            return;
+        }
+        let rotation = cycle(self.start, SPIN_PERIOD) * PI * 2.;
+        // The arc grows from its head and shrinks from its tail, so it never stops turning
+        let t = cycle(self.start, ARC_PERIOD);
+        let (head, tail) = if t < 0.5 {
+            (ease_in_out(t * 2.) * (MAX_ARC - MIN_ARC), 0.)
+        } else {
+            (
+                MAX_ARC - MIN_ARC,
+                ease_in_out(t * 2. - 1.) * (MAX_ARC - MIN_ARC),
+            )
+        };
+        // Each cycle ends where the next one starts
+        let base = rotation + (start_cycles(self.start) as f32 * (MAX_ARC - MIN_ARC)) % (PI * 2.);
+        let start = base + tail - PI / 2.;
+        let sweep = head - tail + MIN_ARC;
+        if let Some(path) = gen_arc_path(width / 2., height / 2., radius, start, sweep) {
+            let stroke = Stroke {
+                width: self.thickness,
+                line_cap: LineCap::Round,
+                ..Default::default()
+            };
+            let color = self.color.unwrap_or_else(|| get_theme_color() | 0xFF);
+            ctx.stroke_path(&path, &gen_paint(color), &stroke);
+        }
+        if ctx.is_visible() {
+            ctx.request_anim_frame();
+        }
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let size = self.diameter as f32;
+        self.layout_size = (
+            size.min(max_box.width() as f32),
+            size.min(max_box.height() as f32),
+        );
+        AreaBox::with_size(self.layout_size.0 as isize, self.layout_size.1 as isize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_clamped() {
+        let mut bar = ProgressBar::<()>::new().with_value(1.5);
+        assert_eq!(bar.value(), 1.);
+        bar.set_value(-0.5);
+        assert_eq!(bar.value(), 0.);
+        let mut bar = ProgressBar::new().with_binding(Binding::identity());
+        let mut ctx = UpdateCtx::new();
+        bar.update(&mut ctx, &2.);
+        assert_eq!(bar.value(), 1.);
+        assert!(ctx.request_paint);
+    }
+
+    #[test]
+    fn width_fills_the_parent() {
+        let mut bar = ProgressBar::<()>::new();
+        let size = bar.layout(AreaBox::with_size(300, 100));
+        assert_eq!(
+            (size.width(), size.height()),
+            (300, DEFAULT_HEIGHT as isize)
+        );
+        let size = bar.layout(AreaBox::with_size(isize::MAX, 100));
+        assert_eq!(size.width(), DEFAULT_WIDTH as isize);
+        let mut bar = ProgressBar::<()>::new().with_size(50, 2);
+        let size = bar.layout(AreaBox::with_size(300, 100));
+        assert_eq!((size.width(), size.height()), (50, 2));
+    }
+
+    #[test]
+    fn easing_keeps_the_ends() {
+        assert_eq!(ease_in_out(0.), 0.);
+        assert_eq!(ease_in_out(0.5), 0.5);
+        assert_eq!(ease_in_out(1.), 1.);
+        assert!(ease_in_out(0.1) < 0.1);
+        let start = Instant::now() - ARC_PERIOD * 2 - ARC_PERIOD / 4;
+        assert_eq!(start_cycles(start), 2);
+        assert!((cycle(start, ARC_PERIOD) - 0.25).abs() < 0.05);
+    }
+}
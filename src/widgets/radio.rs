@@ -0,0 +1,348 @@
+//! 单选按钮组，选中项保存在应用数据中的一个值里，通常是枚举
+
+use std::rc::Rc;
+
+use tiny_skia::{PathBuilder, Stroke};
+
+use crate::events::{keys, WindowEvent};
+use crate::r#box::AreaBox;
+use crate::traits::Widget;
+use crate::utils::binding::Binding;
+use crate::utils::drawing::gen_paint;
+use crate::utils::spring::Spring;
+use crate::utils::text::{line_metrics, measure_text, FontType};
+use crate::utils::theme::get_theme_color;
+use crate::utils::uid::gen_uid;
+use crate::utils::WidgetUid;
+use crate::{DrawCtx, EventCtx, UpdateCtx};
+
+const CIRCLE_SIZE: f32 = 16.;
+const DOT_RADIUS: f32 = 4.;
+const GAP: f32 = 8.;
+/// Space between two options
+const SPACING: f32 = 8.;
+const BACKGROUND_COLOR: u32 = 0xFFFFFFFF;
+const BORDER_COLOR: u32 = 0x00000070;
+const HOVER_BORDER_COLOR: u32 = 0x000000A0;
+const TEXT_COLOR: u32 = 0x202020FF;
+const DISABLED_COLOR: u32 = 0x20202060;
+
+struct RadioOption<T> {
+    value: T,
+    label: String,
+    /// Position and size in the group
+    rect: (f32, f32, f32, f32),
+}
+
+type ChangeCallback<D, T> = Rc<dyn Fn(&mut D, &T)>;
+
+pub struct RadioGroup<D, T> {
+    _uid: WidgetUid,
+    options: Vec<RadioOption<T>>,
+    binding: Binding<D, T>,
+    selected: Option<usize>,
+    horizontal: bool,
+    text_size: f32,
+    disabled: bool,
+    hovering: Option<usize>,
+    pressed: Option<usize>,
+    /// Size of the dot of the selected option, goes from 0 to 1
+    dot: Spring,
+    change_callback: Option<ChangeCallback<D, T>>,
+    layout_size: (f32, f32),
+}
+
+impl<D, T: PartialEq + Clone + 'static> RadioGroup<D, T> {
+    /// A group selecting the value pointed by `binding`, options are added with `with_option`.
+    pub fn new(binding: Binding<D, T>) -> Self {
+        let mut dot = Spring::new(1.);
+        dot.speed = 20.;
+        Self {
+            _uid: gen_uid(),
+            options: Vec::new(),
+            binding,
+            selected: None,
+            horizontal: false,
+            text_size: 14.,
+            disabled: false,
+            hovering: None,
+            pressed: None,
+            dot,
+            change_callback: None,
+            layout_size: (0., 0.),
+        }
+    }
+
+    pub fn with_option(mut self, value: T, label: &str) -> Self {
+        self.options.push(RadioOption {
+            value,
+            label: label.into(),
+            rect: (0., 0., 0., 0.),
+        });
+        self
+    }
+
+    /// Put the options in a row instead of a column.
+    pub fn with_horizontal(mut self, v: bool) -> Self {
+        self.horizontal = v;
+        self
+    }
+
+    pub fn with_text_size(mut self, v: f32) -> Self {
+        self.text_size = v;
+        self
+    }
+
+    pub fn with_disabled(mut self, v: bool) -> Self {
+        self.disabled = v;
+        self
+    }
+
+    /// Called with the new value when the user selects an option.
+    pub fn on_change<F: Fn(&mut D, &T) + 'static>(mut self, v: F) -> Self {
+        self.change_callback = Some(Rc::new(v));
+        self
+    }
+
+    #[inline]
+    pub fn set_disabled(&mut self, v: bool) {
+        self.disabled = v;
+    }
+
+    fn option_at(&self, x: f32, y: f32) -> Option<usize> {
+        self.options.iter().position(|option| {
+            let (ox, oy, w, h) = option.rect;
+            x >= ox && x < ox + w && y >= oy && y < oy + h
+        })
+    }
+
+    fn set_selected(&mut self, index: Option<usize>) {
+        if self.selected != index {
+            self.selected = index;
+            self.dot.set_position(0.);
+            self.dot.set_target(1.);
+        }
+    }
+
+    fn select(&mut self, ctx: &mut EventCtx, data: &mut D, index: usize) {
+        if self.selected == Some(index) {
+            return;
+        }
+        self.set_selected(Some(index));
+        let value = self.options[index].value.clone();
+        *self.binding.get_mut(data) = value.clone();
+        if let Some(callback) = &self.change_callback {
+            callback(data, &value);
+        }
+        ctx.request_anim_frame();
+    }
+}
+
+impl<D, T: PartialEq + Clone + 'static> Widget<D> for RadioGroup<D, T> {
+    fn event(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        let (x, y) = ctx.mouse_pos();
+        let hovering = if ctx.is_hovering() {
+            self.option_at(x, y)
+        } else {
+            None
+        };
+        if hovering != self.hovering {
+            self.hovering = hovering;
+            ctx.request_paint();
+        }
+        if self.disabled {
+            if self.pressed.is_some() && matches!(ctx.event(), WindowEvent::MouseUp(_, _)) {
+                self.pressed = None;
+                ctx.set_active(false);
+            }
+            return;
+        }
+        match ctx.event() {
+            WindowEvent::CollectFocus => ctx.register_for_focus(),
+            WindowEvent::MouseDown(_, _) => {
+                if let Some(index) = hovering {
+                    self.pressed = Some(index);
+                    ctx.set_active(true);
+                    ctx.request_focus();
+                    ctx.set_handled();
+                }
+            }
+            WindowEvent::MouseUp(_, _) => {
+                if let Some(index) = self.pressed.take() {
+                    ctx.set_active(false);
+                    if hovering == Some(index) {
+                        self.select(ctx, data, index);
+                    }
+                    ctx.set_handled();
+                }
+            }
+            WindowEvent::KeyDown(key) if ctx.is_focused() && !self.options.is_empty() => {
+                let last = self.options.len() - 1;
+                // Arrows move the selection, wrapping around like the system does
+                let index = match (key, self.selected) {
+                    (keys::UP, Some(i)) | (keys::LEFT, Some(i)) => {
+                        if i == 0 {
+                            last
+                        } else {
+                            i - 1
+                        }
+                    }
+                    (keys::DOWN, Some(i)) | (keys::RIGHT, Some(i)) => {
+                        if i == last {
+                            0
+                        } else {
+                            i + 1
+                        }
+                    }
+                    (keys::UP, None) | (keys::LEFT, None) => last,
+                    (keys::DOWN, None) | (keys::RIGHT, None) | (keys::SPACE, None) => 0,
+                    _ => return,
+                };
+                self.select(ctx, data, index);
+                ctx.set_handled();
+            }
+            _ => {}
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        let value = self.binding.get(data);
+        let index = self.options.iter().position(|o| &o.value == value);
+        if index != self.selected {
+            self.set_selected(index);
+            ctx.request_paint();
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, _data: &D) {
+        let dot = self.dot.position().max(0.);
+        if !self.dot.arrived() {
+            ctx.request_anim_frame();
+        }
+        let theme = get_theme_color() | 0xFF;
+        let accent = if self.disabled { DISABLED_COLOR } else { theme };
+        let focused = ctx.is_focused();
+        let line_height = line_metrics(self.text_size, FontType::Normal).height;
+        for (i, option) in self.options.iter().enumerate() {
+            let (x, y, _, h) = option.rect;
+            let (cx, cy) = (x + CIRCLE_SIZE / 2., y + h / 2.);
+            let selected = self.selected == Some(i);
+            if let Some(circle) = PathBuilder::from_circle(cx, cy, CIRCLE_SIZE / 2. - 0.5) {
+                ctx.fill_path(&circle, &gen_paint(BACKGROUND_COLOR));
+                let border = if selected {
+                    accent
+                } else if self.disabled {
+                    DISABLED_COLOR
+                } else if self.hovering == Some(i) || self.pressed == Some(i) {
+                    HOVER_BORDER_COLOR
+                } else {
+                    BORDER_COLOR
+                };
+                let stroke = Stroke {
+                    width: if selected { 1.5 } else { 1. },
+                    ..Default::default()
+                };
+                ctx.stroke_path(&circle, &gen_paint(border), &stroke);
+            }
+            if selected {
+                if let Some(path) = PathBuilder::from_circle(cx, cy, DOT_RADIUS * dot) {
+                    ctx.fill_path(&path, &gen_paint(accent));
+                }
+            }
+            // The focus ring follows the selected option, or the first one when none is
+            if focused && self.selected.unwrap_or(0) == i {
+                if let Some(ring) = PathBuilder::from_circle(cx, cy, CIRCLE_SIZE / 2. + 2.) {
+                    ctx.stroke_path(&ring, &gen_paint(theme), &Stroke::default());
+                }
+            }
+            let color = if self.disabled {
+                DISABLED_COLOR
+            } else {
+                TEXT_COLOR
+            };
+            ctx.draw_text(
+                x + CIRCLE_SIZE + GAP,
+                y + (h - line_height) / 2.,
+                &option.label,
+                self.text_size,
+                color,
+            );
+        }
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let line_height = line_metrics(self.text_size, FontType::Normal).height.ceil();
+        let height = line_height.max(CIRCLE_SIZE + 4.);
+        let (mut x, mut y) = (0f32, 0f32);
+        let (mut width, mut total_height) = (0f32, 0f32);
+        for option in &mut self.options {
+            let w = CIRCLE_SIZE
+                + GAP
+                + measure_text(&option.label, self.text_size, FontType::Normal).ceil();
+            option.rect = (x, y, w, height);
+            width = width.max(x + w);
+            total_height = total_height.max(y + height);
+            if self.horizontal {
+                x += w + SPACING * 2.;
+            } else {
+                y += height + SPACING;
+            }
+        }
+        self.layout_size = (
+            width.min(max_box.width() as f32),
+            total_height.min(max_box.height() as f32),
+        );
+        AreaBox::with_size(self.layout_size.0 as isize, self.layout_size.1 as isize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Modifiers;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Size {
+        Small,
+        Medium,
+        Large,
+    }
+
+    fn group() -> RadioGroup<Size, Size> {
+        RadioGroup::new(Binding::identity())
+            .with_option(Size::Small, "Small")
+            .with_option(Size::Medium, "Medium")
+            .with_option(Size::Large, "Large")
+    }
+
+    #[test]
+    fn selection_follows_the_bound_value() {
+        let mut group = group();
+        let mut ctx = UpdateCtx::new();
+        group.update(&mut ctx, &Size::Medium);
+        assert_eq!(group.selected, Some(1));
+        assert!(ctx.request_paint);
+        let mut data = Size::Medium;
+        let mut ctx = EventCtx::new(WindowEvent::None, (0., 0.), Modifiers::default(), None);
+        group.select(&mut ctx, &mut data, 2);
+        assert_eq!((group.selected, data), (Some(2), Size::Large));
+    }
+
+    #[test]
+    fn options_are_stacked_or_in_a_row() {
+        let mut group = group();
+        let size = Widget::<Size>::layout(&mut group, AreaBox::with_size(1000, 1000));
+        let (_, y, w, h) = group.options[1].rect;
+        assert_eq!(y, h + SPACING);
+        assert_eq!(group.option_at(w / 2., y + 1.), Some(1));
+        assert_eq!(group.option_at(w / 2., y - SPACING / 2.), None);
+        assert_eq!(size.height() as f32, h * 3. + SPACING * 2.);
+
+        let mut group = group.with_horizontal(true);
+        Widget::<Size>::layout(&mut group, AreaBox::with_size(1000, 1000));
+        let (x, y, _, _) = group.options[2].rect;
+        assert!(x > 0.);
+        assert_eq!(y, 0.);
+        assert_eq!(group.option_at(x + 1., 1.), Some(2));
+    }
+}
@@ -0,0 +1,670 @@
+//! 滚动容器，以及可以给其它需要滚动的控件复用的滚动状态
+
+use std::time::{Duration, Instant};
+
+use crate::core::WidgetPod;
+use crate::events::{TimerToken, WindowEvent};
+use crate::r#box::AreaBox;
+use crate::traits::Widget;
+use crate::utils::drawing::{gen_paint, gen_round_rect_path};
+use crate::utils::spring::Spring;
+use crate::{DrawCtx, EventCtx, UpdateCtx};
+
+/// Pixels to scroll for a wheel notch
+const WHEEL_STEP: f32 = 48.;
+const BAR_WIDTH: f32 = 6.;
+const BAR_MARGIN: f32 = 2.;
+const BAR_MIN_LENGTH: f32 = 24.;
+/// Width of the area near the edge where the scrollbar can be grabbed
+const BAR_HIT_WIDTH: f32 = 12.;
+const BAR_COLOR: u32 = 0x00000080;
+/// Time the scrollbars stay after the last scrolling before fading out
+const BAR_FADE_DELAY: Duration = Duration::from_millis(1000);
+/// How far the content keeps moving after a drag, in seconds of the release velocity
+const KINETIC_FACTOR: f32 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScrollDirection {
+    Vertical,
+    Horizontal,
+    Both,
+}
+
+impl ScrollDirection {
+    #[inline]
+    pub fn vertical(self) -> bool {
+        self != ScrollDirection::Horizontal
+    }
+
+    #[inline]
+    pub fn horizontal(self) -> bool {
+        self != ScrollDirection::Vertical
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ScrollDrag {
+    None,
+    Content {
+        start_mouse: (f32, f32),
+        start_offset: (f32, f32),
+    },
+    VerticalBar {
+        grab: f32,
+    },
+    HorizontalBar {
+        grab: f32,
+    },
+}
+
+/// Move a spring to a value immediately
+fn jump_spring(spring: &mut Spring, value: f32) {
+    spring.set_target(value);
+    spring.set_position(value);
+    spring.set_velocity(0.);
+}
+
+/// The scrolling logic of `ScrollControl`, usable by any widget that scrolls its own content.
+///
+/// Call `bar_event` before the content handles the event and `event` after it,
+/// then `draw_bars` once the content is drawn.
+pub struct ScrollState {
+    direction: ScrollDirection,
+    viewport: (f32, f32),
+    content: (f32, f32),
+    offset_x: Spring,
+    offset_y: Spring,
+    bar_alpha: Spring,
+    last_active: Instant,
+    /// Checks whether the scrollbars can fade out
+    fade_timer: Option<TimerToken>,
+    bar_hovering: bool,
+    drag: ScrollDrag,
+    drag_scroll: bool,
+    drag_sample: (Instant, (f32, f32)),
+    drag_velocity: (f32, f32),
+}
+
+impl ScrollState {
+    pub fn new(direction: ScrollDirection) -> Self {
+        let mut offset_x = Spring::new(0.);
+        let mut offset_y = Spring::new(0.);
+        let mut bar_alpha = Spring::new(0.);
+        offset_x.speed = 18.;
+        offset_y.speed = 18.;
+        bar_alpha.speed = 12.;
+        Self {
+            direction,
+            viewport: (0., 0.),
+            content: (0., 0.),
+            offset_x,
+            offset_y,
+            bar_alpha,
+            last_active: Instant::now(),
+            fade_timer: None,
+            bar_hovering: false,
+            drag: ScrollDrag::None,
+            drag_scroll: true,
+            drag_sample: (Instant::now(), (0., 0.)),
+            drag_velocity: (0., 0.),
+        }
+    }
+
+    #[inline]
+    pub fn direction(&self) -> ScrollDirection {
+        self.direction
+    }
+
+    /// Whether dragging the content with the mouse scrolls it
+    pub fn set_drag_scroll(&mut self, v: bool) {
+        self.drag_scroll = v;
+    }
+
+    /// Update the visible size and the full size of the content, the offset will be clamped.
+    pub fn set_sizes(&mut self, viewport: (f32, f32), content: (f32, f32)) {
+        self.viewport = viewport;
+        self.content = content;
+        let (max_x, max_y) = self.max_offset();
+        if self.offset_x.target > max_x {
+            self.offset_x.set_target(max_x);
+        }
+        if self.offset_y.target > max_y {
+            self.offset_y.set_target(max_y);
+        }
+    }
+
+    #[inline]
+    pub fn viewport(&self) -> (f32, f32) {
+        self.viewport
+    }
+
+    pub fn max_offset(&self) -> (f32, f32) {
+        (
+            if self.direction.horizontal() {
+                (self.content.0 - self.viewport.0).max(0.)
+            } else {
+                0.
+            },
+            if self.direction.vertical() {
+                (self.content.1 - self.viewport.1).max(0.)
+            } else {
+                0.
+            },
+        )
+    }
+
+    /// Current scrolled distance of the content
+    pub fn offset(&mut self) -> (f32, f32) {
+        let (max_x, max_y) = self.max_offset();
+        (
+            self.offset_x.position().max(0.).min(max_x),
+            self.offset_y.position().max(0.).min(max_y),
+        )
+    }
+
+    /// The offset the scrolling is heading to
+    pub fn target_offset(&self) -> (f32, f32) {
+        (self.offset_x.target, self.offset_y.target)
+    }
+
+    /// Scroll to a position smoothly
+    pub fn scroll_to(&mut self, x: f32, y: f32) {
+        let (max_x, max_y) = self.max_offset();
+        self.offset_x.set_target(x.max(0.).min(max_x));
+        self.offset_y.set_target(y.max(0.).min(max_y));
+        self.show_bars();
+    }
+
+    pub fn scroll_by(&mut self, dx: f32, dy: f32) {
+        let (x, y) = self.target_offset();
+        self.scroll_to(x + dx, y + dy);
+    }
+
+    /// Scroll as little as possible to make the area inside the content visible
+    pub fn scroll_into_view(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        let (mut tx, mut ty) = self.target_offset();
+        if x < tx {
+            tx = x;
+        } else if x + width > tx + self.viewport.0 {
+            tx = x + width - self.viewport.0;
+        }
+        if y < ty {
+            ty = y;
+        } else if y + height > ty + self.viewport.1 {
+            ty = y + height - self.viewport.1;
+        }
+        self.scroll_to(tx, ty);
+    }
+
+    /// Jump to a position without animation
+    pub fn set_offset(&mut self, x: f32, y: f32) {
+        let (max_x, max_y) = self.max_offset();
+        jump_spring(&mut self.offset_x, x.max(0.).min(max_x));
+        jump_spring(&mut self.offset_y, y.max(0.).min(max_y));
+    }
+
+    fn show_bars(&mut self) {
+        self.last_active = Instant::now();
+        if self.bar_alpha.target != 1. {
+            self.bar_alpha.set_target(1.);
+        }
+    }
+
+    /// Lengths of the horizontal and vertical tracks, leaving the corner when both bars show
+    fn tracks(&self) -> (f32, f32) {
+        let (max_x, max_y) = self.max_offset();
+        (
+            self.viewport.0 - BAR_MARGIN * 2. - if max_y > 0. { BAR_WIDTH } else { 0. },
+            self.viewport.1 - BAR_MARGIN * 2. - if max_x > 0. { BAR_WIDTH } else { 0. },
+        )
+    }
+
+    /// (position, length) of the vertical thumb
+    fn vertical_thumb(&mut self) -> Option<(f32, f32)> {
+        let (_, max_y) = self.max_offset();
+        if max_y <= 0. {
+            return None;
+        }
+        let track = self.tracks().1;
+        let len = (track * self.viewport.1 / self.content.1)
+            .max(BAR_MIN_LENGTH)
+            .min(track);
+        let (_, oy) = self.offset();
+        Some((BAR_MARGIN + (track - len) * oy / max_y, len))
+    }
+
+    /// (position, length) of the horizontal thumb
+    fn horizontal_thumb(&mut self) -> Option<(f32, f32)> {
+        let (max_x, _) = self.max_offset();
+        if max_x <= 0. {
+            return None;
+        }
+        let track = self.tracks().0;
+        let len = (track * self.viewport.0 / self.content.0)
+            .max(BAR_MIN_LENGTH)
+            .min(track);
+        let (ox, _) = self.offset();
+        Some((BAR_MARGIN + (track - len) * ox / max_x, len))
+    }
+
+    fn hit_vertical_bar(&self, x: f32, y: f32) -> bool {
+        self.max_offset().1 > 0.
+            && x >= self.viewport.0 - BAR_HIT_WIDTH
+            && x < self.viewport.0
+            && y >= 0.
+            && y < self.viewport.1
+    }
+
+    fn hit_horizontal_bar(&self, x: f32, y: f32) -> bool {
+        self.max_offset().0 > 0.
+            && y >= self.viewport.1 - BAR_HIT_WIDTH
+            && y < self.viewport.1
+            && x >= 0.
+            && x < self.viewport.0
+    }
+
+    /// Fade the scrollbars out once they have been idle long enough, or check again later.
+    fn schedule_fade(&mut self, ctx: &mut EventCtx) {
+        if self.bar_alpha.target <= 0. || self.fade_timer.is_some() {
+            return;
+        }
+        let idle = self.last_active.elapsed();
+        if idle >= BAR_FADE_DELAY && self.drag == ScrollDrag::None && !self.bar_hovering {
+            self.bar_alpha.set_target(0.);
+            ctx.request_anim_frame();
+        } else {
+            let delay = BAR_FADE_DELAY.checked_sub(idle).unwrap_or(BAR_FADE_DELAY);
+            self.fade_timer = Some(ctx.request_timer(delay));
+        }
+    }
+
+    /// Handle the scrollbars, returns true if the content should not receive the event.
+    pub fn bar_event(&mut self, ctx: &mut EventCtx) -> bool {
+        if let WindowEvent::Timer(token) = ctx.event() {
+            if self.fade_timer == Some(token) {
+                self.fade_timer = None;
+            }
+        }
+        let consumed = self.bar_input(ctx);
+        // Also starts the timer of the scrollbars shown by scrolling from the code
+        self.schedule_fade(ctx);
+        consumed
+    }
+
+    fn bar_input(&mut self, ctx: &mut EventCtx) -> bool {
+        let (x, y) = ctx.mouse_pos();
+        match ctx.event() {
+            WindowEvent::MouseDown(_, _) => {
+                if self.hit_vertical_bar(x, y) {
+                    if let Some((pos, len)) = self.vertical_thumb() {
+                        if y < pos || y >= pos + len {
+                            // Clicking on the track jumps to there
+                            let max_y = self.max_offset().1;
+                            let track = (self.tracks().1 - len).max(1.);
+                            self.scroll_to(
+                                self.target_offset().0,
+                                (y - BAR_MARGIN - len / 2.) * max_y / track,
+                            );
+                            self.drag = ScrollDrag::VerticalBar { grab: len / 2. };
+                        } else {
+                            self.drag = ScrollDrag::VerticalBar { grab: y - pos };
+                        }
+                    }
+                } else if self.hit_horizontal_bar(x, y) {
+                    if let Some((pos, len)) = self.horizontal_thumb() {
+                        if x < pos || x >= pos + len {
+                            let max_x = self.max_offset().0;
+                            let track = (self.tracks().0 - len).max(1.);
+                            self.scroll_to(
+                                (x - BAR_MARGIN - len / 2.) * max_x / track,
+                                self.target_offset().1,
+                            );
+                            self.drag = ScrollDrag::HorizontalBar { grab: len / 2. };
+                        } else {
+                            self.drag = ScrollDrag::HorizontalBar { grab: x - pos };
+                        }
+                    }
+                } else {
+                    return false;
+                }
+                self.show_bars();
+                ctx.set_handled();
+                ctx.request_anim_frame();
+                true
+            }
+            WindowEvent::MouseMove(_, _) => match self.drag {
+                ScrollDrag::VerticalBar { grab } => {
+                    if let Some((_, len)) = self.vertical_thumb() {
+                        let track = (self.tracks().1 - len).max(1.);
+                        let max_y = self.max_offset().1;
+                        let ox = self.target_offset().0;
+                        self.set_offset(ox, (y - BAR_MARGIN - grab) * max_y / track);
+                    }
+                    self.show_bars();
+                    ctx.request_anim_frame();
+                    true
+                }
+                ScrollDrag::HorizontalBar { grab } => {
+                    if let Some((_, len)) = self.horizontal_thumb() {
+                        let track = (self.tracks().0 - len).max(1.);
+                        let max_x = self.max_offset().0;
+                        let oy = self.target_offset().1;
+                        self.set_offset((x - BAR_MARGIN - grab) * max_x / track, oy);
+                    }
+                    self.show_bars();
+                    ctx.request_anim_frame();
+                    true
+                }
+                _ => {
+                    let hovering = self.hit_vertical_bar(x, y) || self.hit_horizontal_bar(x, y);
+                    if hovering != self.bar_hovering {
+                        self.bar_hovering = hovering;
+                        if hovering {
+                            self.show_bars();
+                        }
+                        ctx.request_anim_frame();
+                    }
+                    false
+                }
+            },
+            WindowEvent::MouseUp(_, _) => match self.drag {
+                ScrollDrag::VerticalBar { .. } | ScrollDrag::HorizontalBar { .. } => {
+                    self.drag = ScrollDrag::None;
+                    self.show_bars();
+                    ctx.request_anim_frame();
+                    true
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Handle wheel and content dragging, should be called after the content received the event.
+    pub fn event(&mut self, ctx: &mut EventCtx) {
+        let (x, y) = ctx.mouse_pos();
+        match ctx.event() {
+            WindowEvent::MouseWheel(delta) if !ctx.is_handled() => {
+                let step = -delta as f32 / 120. * WHEEL_STEP;
+                let (max_x, max_y) = self.max_offset();
+                if max_y > 0. {
+                    self.scroll_by(0., step);
+                } else if max_x > 0. {
+                    self.scroll_by(step, 0.);
+                } else {
+                    return;
+                }
+                ctx.set_handled();
+                ctx.request_anim_frame();
+            }
+            WindowEvent::MouseHWheel(delta) if !ctx.is_handled() && self.max_offset().0 > 0. => {
+                self.scroll_by(delta as f32 / 120. * WHEEL_STEP, 0.);
+                ctx.set_handled();
+                ctx.request_anim_frame();
+            }
+            WindowEvent::MouseDown(_, _) if !ctx.is_handled() && self.drag_scroll => {
+                let (max_x, max_y) = self.max_offset();
+                if max_x > 0. || max_y > 0. {
+                    let start_offset = self.offset();
+                    self.set_offset(start_offset.0, start_offset.1);
+                    self.drag = ScrollDrag::Content {
+                        start_mouse: (x, y),
+                        start_offset,
+                    };
+                    self.drag_sample = (Instant::now(), (x, y));
+                    self.drag_velocity = (0., 0.);
+                    ctx.set_handled();
+                }
+            }
+            WindowEvent::MouseMove(_, _) => {
+                if let ScrollDrag::Content {
+                    start_mouse,
+                    start_offset,
+                } = self.drag
+                {
+                    self.set_offset(
+                        start_offset.0 - (x - start_mouse.0),
+                        start_offset.1 - (y - start_mouse.1),
+                    );
+                    let (time, (lx, ly)) = self.drag_sample;
+                    let dt = time.elapsed().as_secs_f32();
+                    if dt > 0. {
+                        // Smooth the velocity a little since mouse events are noisy
+                        self.drag_velocity = (
+                            self.drag_velocity.0 * 0.2 + (lx - x) / dt * 0.8,
+                            self.drag_velocity.1 * 0.2 + (ly - y) / dt * 0.8,
+                        );
+                    }
+                    self.drag_sample = (Instant::now(), (x, y));
+                    self.show_bars();
+                    ctx.request_anim_frame();
+                }
+            }
+            WindowEvent::MouseUp(_, _) => {
+                if let ScrollDrag::Content { .. } = self.drag {
+                    self.drag = ScrollDrag::None;
+                    // The mouse stayed still before releasing, no inertia then
+                    if self.drag_sample.0.elapsed().as_millis() < 100 {
+                        let (vx, vy) = self.drag_velocity;
+                        let (ox, oy) = self.offset();
+                        self.offset_x.set_velocity(vx);
+                        self.offset_y.set_velocity(vy);
+                        self.scroll_to(ox + vx * KINETIC_FACTOR, oy + vy * KINETIC_FACTOR);
+                    }
+                    ctx.request_anim_frame();
+                }
+            }
+            _ => {}
+        }
+        self.schedule_fade(ctx);
+    }
+
+    /// Whether the scrolling or the scrollbars are still animating
+    pub fn is_animating(&mut self) -> bool {
+        !(self.offset_x.arrived() && self.offset_y.arrived() && self.bar_alpha.arrived())
+    }
+
+    /// Draw the overlay scrollbars in the viewport coordinate
+    pub fn draw_bars(&mut self, ctx: &mut DrawCtx) {
+        let alpha = self.bar_alpha.position().clamp(0., 1.);
+        if alpha > 0.01 {
+            let a = ((BAR_COLOR & 0xFF) as f32 * alpha) as u32;
+            let paint = gen_paint((BAR_COLOR & 0xFFFFFF00) | a);
+            if let Some((pos, len)) = self.vertical_thumb() {
+                let path = gen_round_rect_path(
+                    self.viewport.0 - BAR_WIDTH - BAR_MARGIN,
+                    pos,
+                    BAR_WIDTH,
+                    len,
+                    BAR_WIDTH / 2.,
+                );
+                ctx.fill_path(&path, &paint);
+            }
+            if let Some((pos, len)) = self.horizontal_thumb() {
+                let path = gen_round_rect_path(
+                    pos,
+                    self.viewport.1 - BAR_WIDTH - BAR_MARGIN,
+                    len,
+                    BAR_WIDTH,
+                    BAR_WIDTH / 2.,
+                );
+                ctx.fill_path(&path, &paint);
+            }
+        }
+        if self.is_animating() {
+            ctx.request_anim_frame();
+        }
+    }
+}
+
+/// A container which lets its child be larger than itself and scrolls it.
+pub struct ScrollControl<D> {
+    inner: WidgetPod<D>,
+    state: ScrollState,
+}
+
+impl<D> ScrollControl<D> {
+    pub fn new(inner: Box<dyn Widget<D>>) -> Self {
+        Self {
+            inner: inner.into(),
+            state: ScrollState::new(ScrollDirection::Vertical),
+        }
+    }
+
+    pub fn with_direction(mut self, v: ScrollDirection) -> Self {
+        self.state.direction = v;
+        self
+    }
+
+    pub fn with_drag_scroll(mut self, v: bool) -> Self {
+        self.state.set_drag_scroll(v);
+        self
+    }
+
+    #[inline]
+    pub fn state(&mut self) -> &mut ScrollState {
+        &mut self.state
+    }
+
+    fn sync_origin(&mut self) {
+        let (x, y) = self.state.offset();
+        self.inner.set_origin(-x, -y);
+    }
+}
+
+impl<D> Widget<D> for ScrollControl<D> {
+    fn event(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        self.sync_origin();
+        if self.state.bar_event(ctx) {
+            return;
+        }
+        self.inner.event(ctx, data);
+        self.state.event(ctx);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        self.inner.update(ctx, data);
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, data: &D) {
+        self.sync_origin();
+        let (width, height) = self.state.viewport();
+        let inner = &mut self.inner;
+        ctx.with_save(|ctx| {
+            ctx.clip_rect(0., 0., width, height);
+            inner.draw(ctx, data);
+        });
+        self.state.draw_bars(ctx);
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let direction = self.state.direction();
+        let child_box = AreaBox::with_size(
+            if direction.horizontal() {
+                isize::MAX
+            } else {
+                max_box.width()
+            },
+            if direction.vertical() {
+                isize::MAX
+            } else {
+                max_box.height()
+            },
+        );
+        let content = self.inner.layout(child_box);
+        let width = if max_box.width() == isize::MAX {
+            content.width()
+        } else {
+            max_box.width()
+        };
+        let height = if max_box.height() == isize::MAX {
+            content.height()
+        } else {
+            max_box.height()
+        };
+        self.state.set_sizes(
+            (width as f32, height as f32),
+            (content.width() as f32, content.height() as f32),
+        );
+        self.sync_origin();
+        AreaBox::with_size(width, height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Modifiers;
+
+    fn state(direction: ScrollDirection) -> ScrollState {
+        let mut state = ScrollState::new(direction);
+        state.set_sizes((100., 50.), (300., 250.));
+        state
+    }
+
+    #[test]
+    fn offset_is_limited_by_the_direction() {
+        assert_eq!(state(ScrollDirection::Vertical).max_offset(), (0., 200.));
+        assert_eq!(state(ScrollDirection::Horizontal).max_offset(), (200., 0.));
+        assert_eq!(state(ScrollDirection::Both).max_offset(), (200., 200.));
+        let mut small = ScrollState::new(ScrollDirection::Both);
+        small.set_sizes((100., 50.), (80., 20.));
+        assert_eq!(small.max_offset(), (0., 0.));
+    }
+
+    #[test]
+    fn scrolling_is_clamped() {
+        let mut state = state(ScrollDirection::Both);
+        state.scroll_to(-10., 500.);
+        assert_eq!(state.target_offset(), (0., 200.));
+        state.scroll_by(30., -50.);
+        assert_eq!(state.target_offset(), (30., 150.));
+        // Shrinking the content pulls the offset back
+        state.set_sizes((100., 50.), (300., 120.));
+        assert_eq!(state.target_offset(), (30., 70.));
+        state.set_offset(40., 10.);
+        assert_eq!(state.offset(), (40., 10.));
+    }
+
+    #[test]
+    fn scroll_into_view_moves_as_little_as_possible() {
+        let mut state = state(ScrollDirection::Vertical);
+        state.scroll_into_view(0., 80., 10., 20.);
+        assert_eq!(state.target_offset(), (0., 50.));
+        // Already visible
+        state.scroll_into_view(0., 60., 10., 20.);
+        assert_eq!(state.target_offset(), (0., 50.));
+        state.scroll_into_view(0., 20., 10., 20.);
+        assert_eq!(state.target_offset(), (0., 20.));
+    }
+
+    #[test]
+    fn thumb_follows_the_offset() {
+        let mut state = state(ScrollDirection::Vertical);
+        let track = 50. - BAR_MARGIN * 2.;
+        let (pos, len) = state.vertical_thumb().unwrap();
+        assert_eq!(pos, BAR_MARGIN);
+        assert_eq!(len, BAR_MIN_LENGTH.max(track * 50. / 250.));
+        state.set_offset(0., 200.);
+        let (pos, len) = state.vertical_thumb().unwrap();
+        assert_eq!(pos + len, BAR_MARGIN + track);
+        assert!(state.horizontal_thumb().is_none());
+    }
+
+    #[test]
+    fn wheel_starts_the_fade_timer() {
+        let mut state = state(ScrollDirection::Vertical);
+        let mut ctx = EventCtx::new(
+            WindowEvent::MouseWheel(-120),
+            (10., 10.),
+            Modifiers::default(),
+            None,
+        );
+        assert!(!state.bar_event(&mut ctx));
+        state.event(&mut ctx);
+        assert_eq!(state.target_offset(), (0., WHEEL_STEP));
+        assert_eq!(ctx.timers.len(), 1);
+        let (_, token) = ctx.timers[0];
+        assert_eq!(state.fade_timer, Some(token));
+    }
+}
@@ -0,0 +1,170 @@
+//! 内容加载前的占位块，带有一道扫过的微光；同一窗口里的占位块共用一道光
+
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+use tiny_skia::{Color, GradientStop, LinearGradient, Paint, Point, SpreadMode, Transform};
+
+use crate::r#box::AreaBox;
+use crate::traits::Widget;
+use crate::utils::drawing::{gen_paint, gen_round_rect_path};
+use crate::utils::uid::gen_uid;
+use crate::utils::WidgetUid;
+use crate::DrawCtx;
+
+const BASE_COLOR: u32 = 0x00000014;
+/// Space between two lines
+const LINE_GAP: f32 = 8.;
+/// Width of the last line relative to the others
+const LAST_LINE_WIDTH: f32 = 0.6;
+/// Width used when neither the size nor the parent gives one
+const DEFAULT_WIDTH: f32 = 160.;
+/// Width of the shimmer in pixels
+const SHIMMER_WIDTH: f32 = 160.;
+const SHIMMER_PERIOD: Duration = Duration::from_millis(1500);
+
+thread_local! {
+    /// All placeholders are animated from the same time, so the shimmer goes through them together
+    static EPOCH: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+fn epoch() -> Instant {
+    EPOCH.with(|epoch| {
+        let now = epoch.get().unwrap_or_else(Instant::now);
+        epoch.set(Some(now));
+        now
+    })
+}
+
+/// A placeholder shown in place of content being loaded.
+pub struct Skeleton {
+    _uid: WidgetUid,
+    /// Wanted size of a line, a width of 0 fills the parent
+    size: (u32, u32),
+    radius: f32,
+    /// Round the block into a circle or a pill
+    circle: bool,
+    lines: usize,
+    layout_size: (f32, f32),
+}
+
+impl Skeleton {
+    pub fn new() -> Self {
+        Self {
+            _uid: gen_uid(),
+            size: (0, 14),
+            radius: 4.,
+            circle: false,
+            lines: 1,
+            layout_size: (0., 0.),
+        }
+    }
+
+    pub fn with_size(mut self, w: u32, h: u32) -> Self {
+        self.size = (w, h);
+        self
+    }
+
+    pub fn with_radius(mut self, v: f32) -> Self {
+        self.radius = v;
+        self
+    }
+
+    /// Fully round corners, e.g. for an avatar.
+    pub fn with_circle(mut self, v: bool) -> Self {
+        self.circle = v;
+        self
+    }
+
+    /// Stack several lines like a paragraph, the last one is shorter.
+    pub fn with_lines(mut self, v: usize) -> Self {
+        self.lines = v.max(1);
+        self
+    }
+
+    fn shimmer_paint(ctx: &DrawCtx) -> Option<Paint<'static>> {
+        // The shimmer crosses the whole window, placed in window coordinates
+        let window_width = ctx.pixmapmut.width() as f32;
+        let t = (epoch().elapsed().as_secs_f32() / SHIMMER_PERIOD.as_secs_f32()).fract();
+        let x = -SHIMMER_WIDTH + t * (window_width + SHIMMER_WIDTH * 2.) - ctx.transform.tx;
+        let shader = LinearGradient::new(
+            Point::from_xy(x, 0.),
+            Point::from_xy(x + SHIMMER_WIDTH, 0.),
+            vec![
+                GradientStop::new(0., Color::from_rgba8(255, 255, 255, 0)),
+                GradientStop::new(0.5, Color::from_rgba8(255, 255, 255, 140)),
+                GradientStop::new(1., Color::from_rgba8(255, 255, 255, 0)),
+            ],
+            SpreadMode::Pad,
+            Transform::identity(),
+        )?;
+        Some(Paint {
+            shader,
+            anti_alias: true,
+            ..Default::default()
+        })
+    }
+}
+
+impl Default for Skeleton {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for Skeleton {
+    fn clone(&self) -> Self {
+        Self {
+            _uid: gen_uid(),
+            size: self.size,
+            radius: self.radius,
+            circle: self.circle,
+            lines: self.lines,
+            layout_size: self.layout_size,
+        }
+    }
+}
+
+impl<D> Widget<D> for Skeleton {
+    fn draw(&mut self, ctx: &mut DrawCtx, _data: &D) {
+        if !ctx.is_visible() {
+            return;
+        }
+        let (width, _) = self.layout_size;
+        let height = self.size.1 as f32;
+        let radius = if self.circle {
+            height.min(width) / 2.
+        } else {
+            self.radius.min(height / 2.)
+        };
+        let shimmer = Self::shimmer_paint(ctx);
+        for i in 0..self.lines {
+            let w = if i + 1 == self.lines && self.lines > 1 {
+                (width * LAST_LINE_WIDTH).round()
+            } else {
+                width
+            };
+            let path = gen_round_rect_path(0., i as f32 * (height + LINE_GAP), w, height, radius);
+            ctx.fill_path(&path, &gen_paint(BASE_COLOR));
+            if let Some(paint) = &shimmer {
+                ctx.fill_path(&path, paint);
+            }
+        }
+        ctx.request_anim_frame();
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let width = match self.size.0 {
+            0 if max_box.width() == isize::MAX => DEFAULT_WIDTH,
+            0 => max_box.width() as f32,
+            w => w as f32,
+        };
+        let lines = self.lines as f32;
+        let height = self.size.1 as f32 * lines + LINE_GAP * (lines - 1.);
+        self.layout_size = (
+            width.min(max_box.width() as f32),
+            height.min(max_box.height() as f32),
+        );
+        AreaBox::with_size(self.layout_size.0 as isize, self.layout_size.1 as isize)
+    }
+}
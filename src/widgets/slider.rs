@@ -0,0 +1,842 @@
+//! 滑块与双滑块，点击轨道时滑块由弹簧平滑移动过去，拖动时会显示当前值
+
+use std::rc::Rc;
+
+use tiny_skia::{PathBuilder, Stroke};
+
+use crate::events::{keys, WindowEvent};
+use crate::r#box::AreaBox;
+use crate::traits::Widget;
+use crate::utils::binding::Binding;
+use crate::utils::drawing::{gen_paint, gen_rect_path, gen_round_rect_path};
+use crate::utils::spring::Spring;
+use crate::utils::text::{line_metrics, measure_text, FontType};
+use crate::utils::theme::get_theme_color;
+use crate::utils::uid::gen_uid;
+use crate::utils::WidgetUid;
+use crate::{DrawCtx, EventCtx, UpdateCtx};
+
+const THUMB_RADIUS: f32 = 8.;
+const THUMB_HOVER_RADIUS: f32 = 9.;
+const TRACK_THICKNESS: f32 = 4.;
+const TICK_LENGTH: f32 = 4.;
+/// Tick marks are not drawn when there would be more than this
+const MAX_TICKS: f64 = 100.;
+/// Length used when neither the size nor the parent gives one
+const DEFAULT_LENGTH: f32 = 160.;
+const TRACK_COLOR: u32 = 0x00000030;
+const TICK_COLOR: u32 = 0x00000060;
+const THUMB_COLOR: u32 = 0xFFFFFFFF;
+const DISABLED_COLOR: u32 = 0x20202060;
+const TOOLTIP_COLOR: u32 = 0x202020E0;
+const TOOLTIP_TEXT_COLOR: u32 = 0xFFFFFFFF;
+const TOOLTIP_TEXT_SIZE: f32 = 12.;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Orientation {
+    Horizontal,
+    /// The minimum is at the bottom
+    Vertical,
+}
+
+type ValueFormatter = Rc<dyn Fn(f64) -> String>;
+type ChangeCallback<D, V> = Rc<dyn Fn(&mut D, V)>;
+
+/// The range, the orientation and the geometry shared by both sliders
+struct Track {
+    min: f64,
+    max: f64,
+    /// `None` for a continuous slider
+    step: Option<f64>,
+    ticks: bool,
+    orientation: Orientation,
+    /// Wanted length, 0 means filling the parent
+    length: u32,
+    layout_size: (f32, f32),
+    formatter: Option<ValueFormatter>,
+}
+
+impl Track {
+    fn new(min: f64, max: f64) -> Self {
+        Self {
+            min,
+            max: max.max(min),
+            step: None,
+            ticks: false,
+            orientation: Orientation::Horizontal,
+            length: 0,
+            layout_size: (0., 0.),
+            formatter: None,
+        }
+    }
+
+    /// Clamp a value into the range and snap it to the steps.
+    fn snap(&self, value: f64) -> f64 {
+        let value = match self.step {
+            Some(step) if step > 0. => self.min + ((value - self.min) / step).round() * step,
+            _ => value,
+        };
+        value.max(self.min).min(self.max)
+    }
+
+    fn fraction(&self, value: f64) -> f32 {
+        if self.max > self.min {
+            ((value - self.min) / (self.max - self.min)) as f32
+        } else {
+            0.
+        }
+    }
+
+    fn value_at(&self, fraction: f32) -> f64 {
+        self.snap(self.min + (self.max - self.min) * fraction.clamp(0., 1.) as f64)
+    }
+
+    /// What the arrow keys add, one step or a hundredth of the range.
+    fn key_step(&self) -> f64 {
+        self.step
+            .filter(|s| *s > 0.)
+            .unwrap_or((self.max - self.min) / 100.)
+    }
+
+    fn format(&self, value: f64) -> String {
+        if let Some(formatter) = &self.formatter {
+            return formatter(value);
+        }
+        let decimals = match self.step {
+            Some(step) => format!("{}", step)
+                .split('.')
+                .nth(1)
+                .map_or(0, |d| d.len().min(6)),
+            None => 2,
+        };
+        format!("{:.*}", decimals, value)
+    }
+
+    /// Length of the part of the track the thumb centre can go.
+    fn travel(&self) -> f32 {
+        let (w, h) = self.layout_size;
+        let length = match self.orientation {
+            Orientation::Horizontal => w,
+            Orientation::Vertical => h,
+        };
+        (length - THUMB_RADIUS * 2.).max(0.)
+    }
+
+    /// Centre of the thumb at a fraction of the range.
+    fn point(&self, fraction: f32) -> (f32, f32) {
+        let offset = THUMB_RADIUS + self.travel() * fraction;
+        match self.orientation {
+            Orientation::Horizontal => (offset, THUMB_RADIUS + 1.),
+            Orientation::Vertical => (THUMB_RADIUS + 1., self.layout_size.1 - offset),
+        }
+    }
+
+    /// Fraction of the range under the mouse.
+    fn fraction_at(&self, x: f32, y: f32) -> f32 {
+        let travel = self.travel().max(1.);
+        let offset = match self.orientation {
+            Orientation::Horizontal => x - THUMB_RADIUS,
+            Orientation::Vertical => self.layout_size.1 - y - THUMB_RADIUS,
+        };
+        (offset / travel).clamp(0., 1.)
+    }
+
+    /// Whether the key decreases or increases the value, and by how many key steps.
+    fn key_delta(&self, key: usize) -> Option<f64> {
+        match key {
+            keys::LEFT | keys::DOWN => Some(-1.),
+            keys::RIGHT | keys::UP => Some(1.),
+            keys::NEXT => Some(-10.),
+            keys::PRIOR => Some(10.),
+            _ => None,
+        }
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let thickness = THUMB_RADIUS * 2.
+            + 2.
+            + if self.ticks && self.step.is_some() {
+                TICK_LENGTH + 2.
+            } else {
+                0.
+            };
+        let (max_length, max_thickness) = match self.orientation {
+            Orientation::Horizontal => (max_box.width(), max_box.height()),
+            Orientation::Vertical => (max_box.height(), max_box.width()),
+        };
+        let length = if self.length > 0 {
+            self.length as f32
+        } else if max_length == isize::MAX {
+            DEFAULT_LENGTH
+        } else {
+            max_length as f32
+        };
+        let length = length.min(max_length as f32);
+        let thickness = thickness.min(max_thickness as f32);
+        self.layout_size = match self.orientation {
+            Orientation::Horizontal => (length, thickness),
+            Orientation::Vertical => (thickness, length),
+        };
+        AreaBox::with_size(self.layout_size.0 as isize, self.layout_size.1 as isize)
+    }
+
+    /// Draw the track with the part between two fractions filled.
+    fn draw_track(&self, ctx: &mut DrawCtx, from: f32, to: f32, accent: u32) {
+        let (x0, y0) = self.point(0.);
+        let (x1, y1) = self.point(1.);
+        let half = TRACK_THICKNESS / 2.;
+        let rect = |(ax, ay): (f32, f32), (bx, by): (f32, f32)| {
+            let (left, top) = (ax.min(bx) - half, ay.min(by) - half);
+            let (w, h) = (
+                (ax - bx).abs() + TRACK_THICKNESS,
+                (ay - by).abs() + TRACK_THICKNESS,
+            );
+            gen_round_rect_path(left, top, w, h, half)
+        };
+        ctx.fill_path(&rect((x0, y0), (x1, y1)), &gen_paint(TRACK_COLOR));
+        if to > from {
+            ctx.fill_path(&rect(self.point(from), self.point(to)), &gen_paint(accent));
+        }
+        if let (true, Some(step)) = (self.ticks, self.step) {
+            let count = (self.max - self.min) / step;
+            if step > 0. && count <= MAX_TICKS {
+                let paint = gen_paint(TICK_COLOR);
+                for i in 0..=count.floor() as usize {
+                    let (x, y) = self.point(self.fraction(self.min + step * i as f64));
+                    let tick = match self.orientation {
+                        Orientation::Horizontal => {
+                            gen_rect_path(x - 0.5, y + THUMB_RADIUS + 2., 1., TICK_LENGTH)
+                        }
+                        Orientation::Vertical => {
+                            gen_rect_path(x + THUMB_RADIUS + 2., y - 0.5, TICK_LENGTH, 1.)
+                        }
+                    };
+                    ctx.fill_path(&tick, &paint);
+                }
+            }
+        }
+    }
+
+    fn draw_thumb(&self, ctx: &mut DrawCtx, fraction: f32, accent: u32, hover: bool, focus: bool) {
+        let (x, y) = self.point(fraction);
+        let radius = if hover {
+            THUMB_HOVER_RADIUS
+        } else {
+            THUMB_RADIUS
+        } - 1.;
+        if let Some(path) = PathBuilder::from_circle(x, y, radius) {
+            ctx.fill_path(&path, &gen_paint(THUMB_COLOR));
+            let stroke = Stroke {
+                width: 2.,
+                ..Default::default()
+            };
+            ctx.stroke_path(&path, &gen_paint(accent), &stroke);
+        }
+        if focus {
+            if let Some(ring) = PathBuilder::from_circle(x, y, radius + 3.) {
+                ctx.stroke_path(&ring, &gen_paint(accent), &Stroke::default());
+            }
+        }
+    }
+
+    /// The value shown next to the thumb while dragging, outside of the widget.
+    fn draw_tooltip(&self, ctx: &mut DrawCtx, fraction: f32, value: f64) {
+        let text = self.format(value);
+        let (x, y) = self.point(fraction);
+        let line_height = line_metrics(TOOLTIP_TEXT_SIZE, FontType::Normal).height;
+        let w = measure_text(&text, TOOLTIP_TEXT_SIZE, FontType::Normal).ceil() + 12.;
+        let h = line_height.ceil() + 6.;
+        let (left, top) = match self.orientation {
+            Orientation::Horizontal => (x - w / 2., y - THUMB_RADIUS - 6. - h),
+            Orientation::Vertical => (x - THUMB_RADIUS - 6. - w, y - h / 2.),
+        };
+        let (left, top) = (left.round(), top.round());
+        ctx.fill_path(
+            &gen_round_rect_path(left, top, w, h, 4.),
+            &gen_paint(TOOLTIP_COLOR),
+        );
+        ctx.draw_text(
+            left + 6.,
+            top + 3.,
+            &text,
+            TOOLTIP_TEXT_SIZE,
+            TOOLTIP_TEXT_COLOR,
+        );
+    }
+}
+
+/// A thumb position in fractions of the range
+fn thumb_spring(fraction: f32) -> Spring {
+    let mut spring = Spring::new(fraction);
+    spring.speed = 18.;
+    spring
+}
+
+/// Move a thumb without animation
+fn jump(spring: &mut Spring, fraction: f32) {
+    spring.set_target(fraction);
+    spring.set_position(fraction);
+    spring.set_velocity(0.);
+}
+
+pub struct Slider<D = ()> {
+    _uid: WidgetUid,
+    track: Track,
+    value: f64,
+    thumb: Spring,
+    disabled: bool,
+    hovering: bool,
+    dragging: bool,
+    binding: Option<Binding<D, f64>>,
+    change_callback: Option<ChangeCallback<D, f64>>,
+}
+
+impl<D> Slider<D> {
+    pub fn new(min: f64, max: f64) -> Self {
+        Self {
+            _uid: gen_uid(),
+            track: Track::new(min, max),
+            value: min,
+            thumb: thumb_spring(0.),
+            disabled: false,
+            hovering: false,
+            dragging: false,
+            binding: None,
+            change_callback: None,
+        }
+    }
+
+    pub fn with_value(mut self, v: f64) -> Self {
+        self.value = self.track.snap(v);
+        jump(&mut self.thumb, self.track.fraction(self.value));
+        self
+    }
+
+    /// Only allow multiples of `step` from the minimum.
+    pub fn with_step(mut self, v: f64) -> Self {
+        self.track.step = Some(v);
+        self.value = self.track.snap(self.value);
+        self
+    }
+
+    /// Draw a tick mark at every step.
+    pub fn with_ticks(mut self, v: bool) -> Self {
+        self.track.ticks = v;
+        self
+    }
+
+    pub fn with_orientation(mut self, v: Orientation) -> Self {
+        self.track.orientation = v;
+        self
+    }
+
+    /// Length along the track, 0 fills the parent.
+    pub fn with_length(mut self, v: u32) -> Self {
+        self.track.length = v;
+        self
+    }
+
+    /// How the value is written in the tooltip.
+    pub fn with_formatter<F: Fn(f64) -> String + 'static>(mut self, v: F) -> Self {
+        self.track.formatter = Some(Rc::new(v));
+        self
+    }
+
+    pub fn with_disabled(mut self, v: bool) -> Self {
+        self.disabled = v;
+        self
+    }
+
+    pub fn with_binding(mut self, v: Binding<D, f64>) -> Self {
+        self.binding = Some(v);
+        self
+    }
+
+    /// Called with the new value while the user moves the thumb.
+    pub fn on_change<F: Fn(&mut D, f64) + 'static>(mut self, v: F) -> Self {
+        self.change_callback = Some(Rc::new(v));
+        self
+    }
+
+    #[inline]
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    /// Set the value, the thumb slides to it.
+    pub fn set_value(&mut self, v: f64) {
+        self.value = self.track.snap(v);
+        self.thumb.set_target(self.track.fraction(self.value));
+    }
+
+    #[inline]
+    pub fn set_disabled(&mut self, v: bool) {
+        self.disabled = v;
+    }
+
+    fn change(&mut self, data: &mut D, value: f64) {
+        let value = self.track.snap(value);
+        if value == self.value {
+            return;
+        }
+        self.value = value;
+        if let Some(binding) = &self.binding {
+            *binding.get_mut(data) = value;
+        }
+        if let Some(callback) = &self.change_callback {
+            callback(data, value);
+        }
+    }
+}
+
+impl<D> Widget<D> for Slider<D> {
+    fn event(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        let (x, y) = ctx.mouse_pos();
+        let hovering = ctx.is_hovering();
+        if hovering != self.hovering {
+            self.hovering = hovering;
+            ctx.request_paint();
+        }
+        if self.disabled {
+            if self.dragging && matches!(ctx.event(), WindowEvent::MouseUp(_, _)) {
+                self.dragging = false;
+                ctx.set_active(false);
+            }
+            return;
+        }
+        match ctx.event() {
+            WindowEvent::CollectFocus => ctx.register_for_focus(),
+            WindowEvent::MouseDown(_, _) => {
+                let (tx, ty) = self.track.point(self.track.fraction(self.value));
+                let on_thumb = (x - tx).powi(2) + (y - ty).powi(2) <= THUMB_HOVER_RADIUS.powi(2);
+                if !on_thumb {
+                    // Jump to the click, the thumb slides there smoothly
+                    let value = self.track.value_at(self.track.fraction_at(x, y));
+                    self.change(data, value);
+                    self.thumb.set_target(self.track.fraction(self.value));
+                }
+                self.dragging = true;
+                ctx.set_active(true);
+                ctx.request_focus();
+                ctx.set_handled();
+                ctx.request_anim_frame();
+            }
+            WindowEvent::MouseMove(_, _) if self.dragging => {
+                let value = self.track.value_at(self.track.fraction_at(x, y));
+                self.change(data, value);
+                jump(&mut self.thumb, self.track.fraction(self.value));
+                ctx.request_paint();
+            }
+            WindowEvent::MouseUp(_, _) if self.dragging => {
+                self.dragging = false;
+                ctx.set_active(false);
+                ctx.request_paint();
+            }
+            WindowEvent::KeyDown(key) if ctx.is_focused() => {
+                let value = match key {
+                    keys::HOME => self.track.min,
+                    keys::END => self.track.max,
+                    _ => match self.track.key_delta(key) {
+                        Some(delta) => self.value + delta * self.track.key_step(),
+                        None => return,
+                    },
+                };
+                self.change(data, value);
+                self.thumb.set_target(self.track.fraction(self.value));
+                ctx.set_handled();
+                ctx.request_anim_frame();
+            }
+            _ => {}
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        if let Some(binding) = &self.binding {
+            let value = self.track.snap(*binding.get(data));
+            if value != self.value {
+                self.set_value(value);
+                ctx.request_paint();
+            }
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, _data: &D) {
+        let fraction = self.thumb.position();
+        if !self.thumb.arrived() {
+            ctx.request_anim_frame();
+        }
+        let accent = if self.disabled {
+            DISABLED_COLOR
+        } else {
+            get_theme_color() | 0xFF
+        };
+        self.track.draw_track(ctx, 0., fraction, accent);
+        let hover = (self.hovering || self.dragging) && !self.disabled;
+        self.track
+            .draw_thumb(ctx, fraction, accent, hover, ctx.is_focused());
+        if self.dragging {
+            self.track.draw_tooltip(ctx, fraction, self.value);
+        }
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        self.track.layout(max_box)
+    }
+}
+
+/// A slider selecting a range with two thumbs, which can't cross each other.
+pub struct RangeSlider<D = ()> {
+    _uid: WidgetUid,
+    track: Track,
+    values: (f64, f64),
+    thumbs: (Spring, Spring),
+    /// The thumb moved by the keyboard and the drag, 0 is the lower one
+    active: usize,
+    disabled: bool,
+    hovering: bool,
+    dragging: bool,
+    binding: Option<Binding<D, (f64, f64)>>,
+    change_callback: Option<ChangeCallback<D, (f64, f64)>>,
+}
+
+impl<D> RangeSlider<D> {
+    pub fn new(min: f64, max: f64) -> Self {
+        Self {
+            _uid: gen_uid(),
+            track: Track::new(min, max),
+            values: (min, max),
+            thumbs: (thumb_spring(0.), thumb_spring(1.)),
+            active: 0,
+            disabled: false,
+            hovering: false,
+            dragging: false,
+            binding: None,
+            change_callback: None,
+        }
+    }
+
+    pub fn with_values(mut self, low: f64, high: f64) -> Self {
+        self.values = self.sorted(low, high);
+        jump(&mut self.thumbs.0, self.track.fraction(self.values.0));
+        jump(&mut self.thumbs.1, self.track.fraction(self.values.1));
+        self
+    }
+
+    pub fn with_step(mut self, v: f64) -> Self {
+        self.track.step = Some(v);
+        self.values = self.sorted(self.values.0, self.values.1);
+        self
+    }
+
+    pub fn with_ticks(mut self, v: bool) -> Self {
+        self.track.ticks = v;
+        self
+    }
+
+    pub fn with_orientation(mut self, v: Orientation) -> Self {
+        self.track.orientation = v;
+        self
+    }
+
+    pub fn with_length(mut self, v: u32) -> Self {
+        self.track.length = v;
+        self
+    }
+
+    pub fn with_formatter<F: Fn(f64) -> String + 'static>(mut self, v: F) -> Self {
+        self.track.formatter = Some(Rc::new(v));
+        self
+    }
+
+    pub fn with_disabled(mut self, v: bool) -> Self {
+        self.disabled = v;
+        self
+    }
+
+    pub fn with_binding(mut self, v: Binding<D, (f64, f64)>) -> Self {
+        self.binding = Some(v);
+        self
+    }
+
+    /// Called with the new `(low, high)` while the user moves a thumb.
+    pub fn on_change<F: Fn(&mut D, (f64, f64)) + 'static>(mut self, v: F) -> Self {
+        self.change_callback = Some(Rc::new(v));
+        self
+    }
+
+    #[inline]
+    pub fn values(&self) -> (f64, f64) {
+        self.values
+    }
+
+    pub fn set_values(&mut self, low: f64, high: f64) {
+        self.values = self.sorted(low, high);
+        self.thumbs.0.set_target(self.track.fraction(self.values.0));
+        self.thumbs.1.set_target(self.track.fraction(self.values.1));
+    }
+
+    #[inline]
+    pub fn set_disabled(&mut self, v: bool) {
+        self.disabled = v;
+    }
+
+    fn sorted(&self, low: f64, high: f64) -> (f64, f64) {
+        let (low, high) = (self.track.snap(low), self.track.snap(high));
+        (low.min(high), low.max(high))
+    }
+
+    fn thumb_mut(&mut self, index: usize) -> &mut Spring {
+        if index == 0 {
+            &mut self.thumbs.0
+        } else {
+            &mut self.thumbs.1
+        }
+    }
+
+    /// Move the active thumb, it stops at the other one.
+    fn change(&mut self, data: &mut D, value: f64) {
+        let value = self.track.snap(value);
+        let values = if self.active == 0 {
+            (value.min(self.values.1), self.values.1)
+        } else {
+            (self.values.0, value.max(self.values.0))
+        };
+        if values == self.values {
+            return;
+        }
+        self.values = values;
+        if let Some(binding) = &self.binding {
+            *binding.get_mut(data) = values;
+        }
+        if let Some(callback) = &self.change_callback {
+            callback(data, values);
+        }
+    }
+
+    fn active_value(&self) -> f64 {
+        if self.active == 0 {
+            self.values.0
+        } else {
+            self.values.1
+        }
+    }
+}
+
+impl<D> Widget<D> for RangeSlider<D> {
+    fn event(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        let (x, y) = ctx.mouse_pos();
+        let hovering = ctx.is_hovering();
+        if hovering != self.hovering {
+            self.hovering = hovering;
+            ctx.request_paint();
+        }
+        if self.disabled {
+            if self.dragging && matches!(ctx.event(), WindowEvent::MouseUp(_, _)) {
+                self.dragging = false;
+                ctx.set_active(false);
+            }
+            return;
+        }
+        match ctx.event() {
+            WindowEvent::CollectFocus => ctx.register_for_focus(),
+            WindowEvent::MouseDown(_, _) => {
+                let fraction = self.track.fraction_at(x, y);
+                let low = self.track.fraction(self.values.0);
+                let high = self.track.fraction(self.values.1);
+                // The nearest thumb is taken, the upper one when they overlap and the click is above
+                self.active = if (fraction - low).abs() < (fraction - high).abs()
+                    || (low == high && fraction < low)
+                {
+                    0
+                } else {
+                    1
+                };
+                let value = self.track.value_at(fraction);
+                self.change(data, value);
+                let target = self.track.fraction(self.active_value());
+                self.thumb_mut(self.active).set_target(target);
+                self.dragging = true;
+                ctx.set_active(true);
+                ctx.request_focus();
+                ctx.set_handled();
+                ctx.request_anim_frame();
+            }
+            WindowEvent::MouseMove(_, _) if self.dragging => {
+                let value = self.track.value_at(self.track.fraction_at(x, y));
+                self.change(data, value);
+                let target = self.track.fraction(self.active_value());
+                jump(self.thumb_mut(self.active), target);
+                ctx.request_paint();
+            }
+            WindowEvent::MouseUp(_, _) if self.dragging => {
+                self.dragging = false;
+                ctx.set_active(false);
+                ctx.request_paint();
+            }
+            WindowEvent::KeyDown(keys::TAB) if ctx.is_focused() => {
+                // Tab goes on to the upper thumb and Shift+Tab back to the lower one,
+                // past them the key moves the focus to the next widget
+                let next = if ctx.modifiers().shift { 0 } else { 1 };
+                if self.active != next {
+                    self.active = next;
+                    ctx.set_handled();
+                    ctx.request_paint();
+                }
+            }
+            WindowEvent::KeyDown(key) if ctx.is_focused() => {
+                let value = match key {
+                    keys::HOME => self.track.min,
+                    keys::END => self.track.max,
+                    _ => match self.track.key_delta(key) {
+                        Some(delta) => self.active_value() + delta * self.track.key_step(),
+                        None => return,
+                    },
+                };
+                self.change(data, value);
+                let target = self.track.fraction(self.active_value());
+                self.thumb_mut(self.active).set_target(target);
+                ctx.set_handled();
+                ctx.request_anim_frame();
+            }
+            _ => {}
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        if let Some(binding) = &self.binding {
+            let (low, high) = *binding.get(data);
+            let values = self.sorted(low, high);
+            if values != self.values {
+                self.set_values(values.0, values.1);
+                ctx.request_paint();
+            }
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, _data: &D) {
+        let low = self.thumbs.0.position();
+        let high = self.thumbs.1.position();
+        if !self.thumbs.0.arrived() || !self.thumbs.1.arrived() {
+            ctx.request_anim_frame();
+        }
+        let accent = if self.disabled {
+            DISABLED_COLOR
+        } else {
+            get_theme_color() | 0xFF
+        };
+        self.track.draw_track(ctx, low, high, accent);
+        let hover = (self.hovering || self.dragging) && !self.disabled;
+        let focused = ctx.is_focused();
+        // The active thumb is drawn last so it stays on top
+        let (other, active) = if self.active == 0 {
+            (high, low)
+        } else {
+            (low, high)
+        };
+        self.track.draw_thumb(ctx, other, accent, false, false);
+        self.track.draw_thumb(ctx, active, accent, hover, focused);
+        if self.dragging {
+            self.track.draw_tooltip(ctx, active, self.active_value());
+        }
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        self.track.layout(max_box)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Modifiers;
+
+    fn track(step: Option<f64>) -> Track {
+        let mut track = Track::new(10., 20.);
+        track.step = step;
+        track
+    }
+
+    #[test]
+    fn values_snap_to_the_steps() {
+        let track = track(Some(2.5));
+        assert_eq!(track.snap(13.), 12.5);
+        assert_eq!(track.snap(14.), 15.);
+        assert_eq!(track.snap(25.), 20.);
+        assert_eq!(track.snap(-3.), 10.);
+        assert_eq!(track.value_at(0.3), 12.5);
+        assert_eq!(track.key_step(), 2.5);
+        // Continuous sliders keep any value in the range
+        assert_eq!(self::track(None).snap(13.3), 13.3);
+        assert_eq!(self::track(None).key_step(), 0.1);
+    }
+
+    #[test]
+    fn labels_show_the_step_decimals() {
+        assert_eq!(track(Some(0.25)).format(12.5), "12.50");
+        assert_eq!(track(Some(1.)).format(12.), "12");
+        assert_eq!(track(None).format(12.), "12.00");
+    }
+
+    #[test]
+    fn vertical_tracks_start_at_the_bottom() {
+        let mut track = track(None);
+        track.orientation = Orientation::Vertical;
+        track.length = 100;
+        track.layout(AreaBox::with_size(100, 500));
+        let travel = 100. - THUMB_RADIUS * 2.;
+        assert_eq!(track.travel(), travel);
+        assert_eq!(track.point(0.).1, 100. - THUMB_RADIUS);
+        assert_eq!(
+            track.fraction_at(0., 100. - THUMB_RADIUS - travel / 4.),
+            0.25
+        );
+        assert_eq!(track.fraction_at(0., -20.), 1.);
+    }
+
+    #[test]
+    fn range_thumbs_stop_at_each_other() {
+        let mut data = (0., 0.);
+        let mut slider = RangeSlider::new(0., 100.)
+            .with_step(5.)
+            .with_values(60., 20.)
+            .with_binding(Binding::identity());
+        assert_eq!(slider.values(), (20., 60.));
+        slider.change(&mut data, 72.);
+        assert_eq!(slider.values(), (60., 60.));
+        slider.active = 1;
+        slider.change(&mut data, 41.);
+        assert_eq!(slider.values(), (60., 60.));
+        slider.change(&mut data, 83.);
+        assert_eq!((slider.values(), data), ((60., 85.), (60., 85.)));
+    }
+
+    #[test]
+    fn tab_leaves_the_range_after_the_last_thumb() {
+        let mut slider = RangeSlider::<()>::new(0., 100.);
+        let mut tab = |shift: bool| {
+            let modifiers = Modifiers {
+                shift,
+                ..Default::default()
+            };
+            let event = WindowEvent::KeyDown(keys::TAB);
+            let mut ctx = EventCtx::new(event, (0., 0.), modifiers, Some(0));
+            slider.event(&mut ctx, &mut ());
+            (slider.active, ctx.is_handled())
+        };
+        assert_eq!(tab(false), (1, true));
+        assert_eq!(tab(false), (1, false));
+        assert_eq!(tab(true), (0, true));
+        assert_eq!(tab(true), (0, false));
+    }
+
+    #[test]
+    fn slider_value_follows_the_binding() {
+        let mut slider = Slider::new(0., 1.)
+            .with_step(0.5)
+            .with_binding(Binding::identity());
+        let mut ctx = UpdateCtx::new();
+        slider.update(&mut ctx, &0.7);
+        assert_eq!(slider.value(), 0.5);
+        assert!(ctx.request_paint);
+    }
+}
@@ -0,0 +1,355 @@
+//! 分割面板，用可拖动的分割条把空间分给两个子控件
+
+use crate::core::WidgetPod;
+use crate::events::WindowEvent;
+use crate::r#box::AreaBox;
+use crate::system::enums::Cursor;
+use crate::traits::Widget;
+use crate::utils::binding::Binding;
+use crate::utils::drawing::{gen_paint, gen_rect_path};
+use crate::utils::uid::gen_uid;
+use crate::utils::WidgetUid;
+use crate::{DrawCtx, EventCtx, UpdateCtx};
+
+const BAR_SIZE: f32 = 6.;
+/// Distance in pixels under which the splitter jumps to a snap point
+const SNAP_DISTANCE: f32 = 12.;
+const LINE_COLOR: u32 = 0x00000030;
+const BAR_HOVER_COLOR: u32 = 0x00000018;
+const BAR_ACTIVE_COLOR: u32 = 0x00000030;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SplitDirection {
+    /// The children are placed side by side, the splitter moves horizontally
+    Horizontal,
+    /// The children are stacked, the splitter moves vertically
+    Vertical,
+}
+
+/// Divides its space between two children with a draggable splitter bar.
+pub struct SplitControl<D> {
+    _uid: WidgetUid,
+    first: WidgetPod<D>,
+    second: WidgetPod<D>,
+    direction: SplitDirection,
+    /// Part of the available space given to the first child, between 0 and 1
+    ratio: f32,
+    min_sizes: (f32, f32),
+    snap_points: Vec<f32>,
+    ratio_binding: Option<Binding<D, f32>>,
+    size: (f32, f32),
+    /// Offset of the mouse from the start of the bar while dragging
+    drag_grab: Option<f32>,
+    bar_hovering: bool,
+}
+
+impl<D> SplitControl<D> {
+    pub fn new(first: Box<dyn Widget<D>>, second: Box<dyn Widget<D>>) -> Self {
+        Self {
+            _uid: gen_uid(),
+            first: first.into(),
+            second: second.into(),
+            direction: SplitDirection::Horizontal,
+            ratio: 0.5,
+            min_sizes: (0., 0.),
+            snap_points: Vec::new(),
+            ratio_binding: None,
+            size: (0., 0.),
+            drag_grab: None,
+            bar_hovering: false,
+        }
+    }
+
+    pub fn with_direction(mut self, v: SplitDirection) -> Self {
+        self.direction = v;
+        self
+    }
+
+    pub fn with_ratio(mut self, v: f32) -> Self {
+        self.ratio = v.clamp(0., 1.);
+        self
+    }
+
+    /// Minimum sizes of the first and the second child along the split direction.
+    pub fn with_min_sizes(mut self, first: f32, second: f32) -> Self {
+        self.min_sizes = (first.max(0.), second.max(0.));
+        self
+    }
+
+    /// Ratios the splitter snaps to when dragged close to them.
+    pub fn with_snap_points(mut self, v: Vec<f32>) -> Self {
+        self.snap_points = v;
+        self
+    }
+
+    /// Keep the ratio in the application data, so it can be saved and restored.
+    pub fn with_ratio_binding(mut self, v: Binding<D, f32>) -> Self {
+        self.ratio_binding = Some(v);
+        self
+    }
+
+    #[inline]
+    pub fn ratio(&self) -> f32 {
+        self.ratio
+    }
+
+    /// Length along the split direction
+    #[inline]
+    fn main(&self, (x, y): (f32, f32)) -> f32 {
+        match self.direction {
+            SplitDirection::Horizontal => x,
+            SplitDirection::Vertical => y,
+        }
+    }
+
+    /// Space shared by the two children
+    #[inline]
+    fn available(&self) -> f32 {
+        (self.main(self.size) - BAR_SIZE).max(0.)
+    }
+
+    /// Size of the first child for a wanted size, respecting the min sizes.
+    fn constrain(&self, first: f32) -> f32 {
+        let available = self.available();
+        first
+            .min(available - self.min_sizes.1)
+            .max(self.min_sizes.0)
+            .min(available)
+            .max(0.)
+    }
+
+    fn snap(&self, first: f32) -> f32 {
+        let available = self.available();
+        self.snap_points
+            .iter()
+            .map(|ratio| ratio * available)
+            .filter(|point| (point - first).abs() < SNAP_DISTANCE)
+            .min_by(|a, b| (a - first).abs().total_cmp(&(b - first).abs()))
+            .unwrap_or(first)
+    }
+
+    /// Start of the bar along the split direction
+    fn bar_pos(&self) -> f32 {
+        self.main(self.second.origin()) - BAR_SIZE
+    }
+
+    fn hit_bar(&self, pos: (f32, f32)) -> bool {
+        let start = self.bar_pos();
+        let v = self.main(pos);
+        v >= start && v < start + BAR_SIZE
+    }
+
+    fn cursor(&self) -> Cursor {
+        match self.direction {
+            SplitDirection::Horizontal => Cursor::ResizeWestEast,
+            SplitDirection::Vertical => Cursor::ResizeNorthSouth,
+        }
+    }
+
+    /// Lay out the children again in the current size.
+    fn layout_children(&mut self) {
+        let (width, height) = self.size;
+        let first = self.constrain(self.ratio * self.available());
+        let second = self.available() - first;
+        match self.direction {
+            SplitDirection::Horizontal => {
+                self.first
+                    .layout(AreaBox::with_size(first as isize, height as isize));
+                self.second
+                    .layout(AreaBox::with_size(second as isize, height as isize));
+                self.first.set_origin(0., 0.);
+                self.second.set_origin(width - second, 0.);
+            }
+            SplitDirection::Vertical => {
+                self.first
+                    .layout(AreaBox::with_size(width as isize, first as isize));
+                self.second
+                    .layout(AreaBox::with_size(width as isize, second as isize));
+                self.first.set_origin(0., 0.);
+                self.second.set_origin(0., height - second);
+            }
+        }
+    }
+}
+
+impl<D> Widget<D> for SplitControl<D> {
+    fn event(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        let pos = ctx.mouse_pos();
+        match ctx.event() {
+            WindowEvent::MouseDown(_, _) if self.hit_bar(pos) => {
+                self.drag_grab = Some(self.main(pos) - self.bar_pos());
+                ctx.set_active(true);
+                ctx.set_cursor(self.cursor());
+                ctx.set_handled();
+                ctx.request_paint();
+                return;
+            }
+            WindowEvent::MouseMove(_, _) => {
+                if let Some(grab) = self.drag_grab {
+                    let first = self.constrain(self.snap(self.main(pos) - grab));
+                    let available = self.available();
+                    self.ratio = if available > 0. {
+                        first / available
+                    } else {
+                        self.ratio
+                    };
+                    if let Some(binding) = &self.ratio_binding {
+                        *binding.get_mut(data) = self.ratio;
+                    }
+                    self.layout_children();
+                    ctx.set_cursor(self.cursor());
+                    ctx.set_handled();
+                    ctx.request_paint();
+                    return;
+                }
+                let hovering = ctx.is_hovering() && self.hit_bar(pos);
+                if hovering != self.bar_hovering {
+                    self.bar_hovering = hovering;
+                    ctx.request_paint();
+                }
+                if hovering {
+                    ctx.set_cursor(self.cursor());
+                }
+            }
+            WindowEvent::MouseUp(_, _) if self.drag_grab.is_some() => {
+                self.drag_grab = None;
+                self.bar_hovering = ctx.is_hovering() && self.hit_bar(pos);
+                if self.bar_hovering {
+                    ctx.set_cursor(self.cursor());
+                }
+                ctx.set_active(false);
+                ctx.set_handled();
+                ctx.request_paint();
+                return;
+            }
+            _ => {}
+        }
+        self.first.event(ctx, data);
+        self.second.event(ctx, data);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        if let Some(binding) = &self.ratio_binding {
+            let ratio = binding.get(data).clamp(0., 1.);
+            if (ratio - self.ratio).abs() > f32::EPSILON && self.drag_grab.is_none() {
+                self.ratio = ratio;
+                self.layout_children();
+            }
+        }
+        self.first.update(ctx, data);
+        self.second.update(ctx, data);
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, data: &D) {
+        for pod in [&mut self.first, &mut self.second] {
+            let (x, y) = pod.origin();
+            let (width, height) = pod.size();
+            ctx.with_save(|ctx| {
+                ctx.clip_rect(x, y, width, height);
+                pod.draw(ctx, data);
+            });
+        }
+        let start = self.bar_pos();
+        let (width, height) = self.size;
+        let (bar, line) = match self.direction {
+            SplitDirection::Horizontal => (
+                gen_rect_path(start, 0., BAR_SIZE, height),
+                gen_rect_path(start + BAR_SIZE / 2. - 0.5, 0., 1., height),
+            ),
+            SplitDirection::Vertical => (
+                gen_rect_path(0., start, width, BAR_SIZE),
+                gen_rect_path(0., start + BAR_SIZE / 2. - 0.5, width, 1.),
+            ),
+        };
+        if self.drag_grab.is_some() {
+            ctx.fill_path(&bar, &gen_paint(BAR_ACTIVE_COLOR));
+        } else if self.bar_hovering {
+            ctx.fill_path(&bar, &gen_paint(BAR_HOVER_COLOR));
+        }
+        ctx.fill_path(&line, &gen_paint(LINE_COLOR));
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let unbounded = match self.direction {
+            SplitDirection::Horizontal => max_box.width() == isize::MAX,
+            SplitDirection::Vertical => max_box.height() == isize::MAX,
+        };
+        if unbounded {
+            // Nothing to divide, give both children their natural sizes
+            let a = self.first.layout(max_box).to_size_f32();
+            let b = self.second.layout(max_box).to_size_f32();
+            self.size = match self.direction {
+                SplitDirection::Horizontal => (a.0 + BAR_SIZE + b.0, a.1.max(b.1)),
+                SplitDirection::Vertical => (a.0.max(b.0), a.1 + BAR_SIZE + b.1),
+            };
+            self.ratio = self.main(a) / (self.main(a) + self.main(b)).max(1.);
+        } else {
+            self.size = max_box.to_size_f32();
+        }
+        self.layout_children();
+        AreaBox::with_size(self.size.0 as isize, self.size.1 as isize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A child with a natural size
+    struct Block(isize, isize);
+
+    impl Widget<()> for Block {
+        fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+            AreaBox::with_size(self.0.min(max_box.width()), self.1.min(max_box.height()))
+        }
+    }
+
+    fn split(direction: SplitDirection) -> SplitControl<()> {
+        SplitControl::new(Box::new(Block(40, 20)), Box::new(Block(60, 30)))
+            .with_direction(direction)
+    }
+
+    #[test]
+    fn min_sizes_limit_the_first_child() {
+        let mut split = split(SplitDirection::Horizontal).with_min_sizes(50., 30.);
+        split.layout(AreaBox::with_size(206, 100));
+        assert_eq!(split.available(), 200.);
+        assert_eq!(split.constrain(10.), 50.);
+        assert_eq!(split.constrain(100.), 100.);
+        assert_eq!(split.constrain(190.), 170.);
+        // The first min size wins when both don't fit
+        split.layout(AreaBox::with_size(66, 100));
+        assert_eq!(split.constrain(30.), 50.);
+    }
+
+    #[test]
+    fn snaps_to_the_nearest_point() {
+        let mut split = split(SplitDirection::Horizontal).with_snap_points(vec![0.25, 0.375, 0.75]);
+        split.layout(AreaBox::with_size(206, 100));
+        assert_eq!(split.snap(52.), 50.);
+        assert_eq!(split.snap(66.), 75.);
+        // Too far from both
+        assert_eq!(split.snap(62.), 62.);
+        assert_eq!(split.snap(160.), 150.);
+    }
+
+    #[test]
+    fn children_are_placed_around_the_bar() {
+        let mut split = split(SplitDirection::Vertical).with_ratio(0.25);
+        split.layout(AreaBox::with_size(100, 206));
+        assert_eq!(split.first.origin(), (0., 0.));
+        assert_eq!(split.second.origin(), (0., 56.));
+        assert_eq!(split.bar_pos(), 50.);
+        assert!(split.hit_bar((10., 53.)));
+        assert!(!split.hit_bar((10., 56.)));
+        assert_eq!(split.cursor(), Cursor::ResizeNorthSouth);
+    }
+
+    #[test]
+    fn unbounded_split_uses_natural_sizes() {
+        let mut split = split(SplitDirection::Horizontal);
+        let size = split.layout(AreaBox::with_size(isize::MAX, 100));
+        assert_eq!((size.width(), size.height()), (106, 30));
+        assert_eq!(split.ratio(), 0.4);
+    }
+}
@@ -0,0 +1,220 @@
+//! 矢量图标，按显示大小渲染，可以给单色图标重新着色
+
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+
+use tiny_skia::{Pixmap, PixmapPaint};
+
+use crate::r#box::AreaBox;
+use crate::traits::Widget;
+use crate::utils::svg::{parse_svg, render_svg, svg_size, tint_pixmap, SvgError, SvgTree};
+use crate::utils::theme::get_theme_color;
+use crate::utils::uid::gen_uid;
+use crate::utils::WidgetUid;
+use crate::events::WindowEvent;
+use crate::{DrawCtx, EventCtx};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IconColor {
+    /// Keep the colours of the file
+    Original,
+    /// Paint every visible pixel with this RGBA colour
+    Custom(u32),
+    /// Paint every visible pixel with the accent colour of the system
+    Theme,
+}
+
+impl IconColor {
+    fn resolve(self) -> Option<u32> {
+        match self {
+            IconColor::Original => None,
+            IconColor::Custom(color) => Some(color),
+            IconColor::Theme => Some(get_theme_color() | 0xFF),
+        }
+    }
+}
+
+pub struct SvgIcon {
+    _uid: WidgetUid,
+    tree: Rc<SvgTree>,
+    /// Wanted size, 0 means following the file
+    size: (u32, u32),
+    layout_size: (f32, f32),
+    color: IconColor,
+    /// Rendered icon, kept until the size, the colour or the theme changes.
+    /// The inner `None` is an icon with nothing to show at this size
+    cache: Option<Option<Pixmap>>,
+}
+
+impl SvgIcon {
+    pub fn new(tree: SvgTree) -> Self {
+        Self {
+            _uid: gen_uid(),
+            tree: Rc::new(tree),
+            size: (0, 0),
+            layout_size: (0., 0.),
+            color: IconColor::Original,
+            cache: None,
+        }
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, SvgError> {
+        Ok(Self::new(parse_svg(data)?))
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let data = std::fs::read(path)?;
+        Self::from_bytes(&data).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn with_size(mut self, w: u32, h: u32) -> Self {
+        self.size = (w, h);
+        self
+    }
+
+    pub fn with_color(mut self, v: IconColor) -> Self {
+        self.color = v;
+        self
+    }
+
+    #[inline]
+    pub fn set_color(&mut self, v: IconColor) {
+        if self.color != v {
+            self.color = v;
+            self.cache = None;
+        }
+    }
+}
+
+impl Clone for SvgIcon {
+    fn clone(&self) -> Self {
+        Self {
+            _uid: gen_uid(),
+            tree: self.tree.clone(),
+            size: self.size,
+            layout_size: self.layout_size,
+            color: self.color,
+            cache: None,
+        }
+    }
+}
+
+impl<D> Widget<D> for SvgIcon {
+    fn event(&mut self, ctx: &mut EventCtx, _data: &mut D) {
+        if ctx.event() == WindowEvent::ThemeChanged && self.color == IconColor::Theme {
+            self.cache = None;
+            ctx.request_paint();
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, _data: &D) {
+        let (width, height) = self.layout_size;
+        let tree = &self.tree;
+        let color = self.color;
+        let cache = self.cache.get_or_insert_with(|| {
+            let (iw, ih) = svg_size(tree);
+            let zoom = (width / iw).min(height / ih);
+            render_svg(tree, zoom).map(|mut pixmap| {
+                if let Some(color) = color.resolve() {
+                    tint_pixmap(&mut pixmap, color);
+                }
+                pixmap
+            })
+        });
+        if let Some(pixmap) = cache {
+            let x = ((width - pixmap.width() as f32) / 2.).round() as i32;
+            let y = ((height - pixmap.height() as f32) / 2.).round() as i32;
+            ctx.draw_pixmap(x, y, pixmap.as_ref(), &PixmapPaint::default());
+        }
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let (iw, ih) = svg_size(&self.tree);
+        let (width, height) = match self.size {
+            (0, 0) => (iw, ih),
+            (w, 0) => (w as f32, w as f32 * ih / iw),
+            (0, h) => (h as f32 * iw / ih, h as f32),
+            (w, h) => (w as f32, h as f32),
+        };
+        let layout_size = (
+            width.min(max_box.width() as f32),
+            height.min(max_box.height() as f32),
+        );
+        if layout_size != self.layout_size {
+            self.layout_size = layout_size;
+            self.cache = None;
+        }
+        AreaBox::with_size(self.layout_size.0 as isize, self.layout_size.1 as isize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ICON: &[u8] = br#"<svg xmlns="http://www.w3.org/2000/svg" width="16" height="8"/>"#;
+
+    fn layout(icon: &mut SvgIcon, max: (isize, isize)) -> (isize, isize) {
+        let size = Widget::<()>::layout(icon, AreaBox::with_size(max.0, max.1));
+        (size.width(), size.height())
+    }
+
+    #[test]
+    fn one_given_side_keeps_the_aspect_ratio() {
+        let mut icon = SvgIcon::from_bytes(ICON).unwrap();
+        assert_eq!(layout(&mut icon, (100, 100)), (16, 8));
+        let mut icon = icon.with_size(32, 0);
+        assert_eq!(layout(&mut icon, (100, 100)), (32, 16));
+        let mut icon = icon.with_size(0, 4);
+        assert_eq!(layout(&mut icon, (100, 100)), (8, 4));
+        assert_eq!(layout(&mut icon.with_size(64, 64), (20, 100)), (20, 64));
+    }
+
+    #[test]
+    fn custom_colors_tint_the_icon() {
+        assert_eq!(IconColor::Original.resolve(), None);
+        assert_eq!(IconColor::Custom(0x11223344).resolve(), Some(0x11223344));
+    }
+
+    #[test]
+    fn the_render_is_kept_until_something_changes() {
+        let mut icon = SvgIcon::from_bytes(ICON).unwrap();
+        layout(&mut icon, (100, 100));
+        // Nothing to show is kept as well
+        icon.cache = Some(None);
+        layout(&mut icon, (100, 100));
+        assert!(icon.cache.is_some());
+        layout(&mut icon, (10, 100));
+        assert!(icon.cache.is_none());
+
+        icon.cache = Some(None);
+        icon.set_color(IconColor::Original);
+        assert!(icon.cache.is_some());
+        icon.set_color(IconColor::Theme);
+        assert!(icon.cache.is_none());
+    }
+
+    #[test]
+    fn theme_changes_repaint_theme_icons() {
+        use crate::events::Modifiers;
+        let event = |icon: &mut SvgIcon| {
+            let mut ctx = EventCtx::new(
+                WindowEvent::ThemeChanged,
+                (0., 0.),
+                Modifiers::default(),
+                None,
+            );
+            Widget::<()>::event(icon, &mut ctx, &mut ());
+            ctx.request_paint
+        };
+        let mut icon = SvgIcon::from_bytes(ICON).unwrap();
+        icon.cache = Some(None);
+        assert!(!event(&mut icon));
+        assert!(icon.cache.is_some());
+        icon.set_color(IconColor::Theme);
+        icon.cache = Some(None);
+        assert!(event(&mut icon));
+        assert!(icon.cache.is_none());
+    }
+}
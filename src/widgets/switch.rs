@@ -0,0 +1,306 @@
+//! 开关，滑块的移动由弹簧驱动
+
+use std::rc::Rc;
+
+use tiny_skia::{PathBuilder, Stroke};
+
+use crate::events::{keys, WindowEvent};
+use crate::r#box::AreaBox;
+use crate::traits::Widget;
+use crate::utils::binding::Binding;
+use crate::utils::color::mix_color;
+use crate::utils::drawing::{gen_paint, gen_round_rect_path};
+use crate::utils::spring::Spring;
+use crate::utils::text::{line_metrics, measure_text, FontType};
+use crate::utils::theme::get_theme_color;
+use crate::utils::uid::gen_uid;
+use crate::utils::WidgetUid;
+use crate::{DrawCtx, EventCtx, UpdateCtx};
+
+const TRACK_WIDTH: f32 = 40.;
+const TRACK_HEIGHT: f32 = 20.;
+const THUMB_RADIUS: f32 = 6.;
+/// The thumb grows a little while hovered
+const THUMB_HOVER_RADIUS: f32 = 7.;
+const GAP: f32 = 8.;
+const OFF_BACKGROUND: u32 = 0xFFFFFFFF;
+const OFF_BORDER: u32 = 0x00000090;
+const OFF_THUMB: u32 = 0x000000A0;
+const ON_THUMB: u32 = 0xFFFFFFFF;
+const TEXT_COLOR: u32 = 0x202020FF;
+const DISABLED_COLOR: u32 = 0x20202060;
+
+type ChangeCallback<D> = Rc<dyn Fn(&mut D, bool)>;
+
+pub struct Switch<D = ()> {
+    _uid: WidgetUid,
+    label: String,
+    text_size: f32,
+    on: bool,
+    disabled: bool,
+    hovering: bool,
+    pressed: bool,
+    /// Position of the thumb, 0 is off and 1 is on
+    thumb: Spring,
+    /// Set when the state changes, the next update paints the thumb on its way
+    need_paint: bool,
+    binding: Option<Binding<D, bool>>,
+    change_callback: Option<ChangeCallback<D>>,
+    layout_size: (f32, f32),
+}
+
+impl<D> Switch<D> {
+    pub fn new(label: &str) -> Self {
+        let mut thumb = Spring::new(0.);
+        thumb.speed = 16.;
+        thumb.damper = 0.7;
+        Self {
+            _uid: gen_uid(),
+            label: label.into(),
+            text_size: 14.,
+            on: false,
+            disabled: false,
+            hovering: false,
+            pressed: false,
+            thumb,
+            need_paint: false,
+            binding: None,
+            change_callback: None,
+            layout_size: (0., 0.),
+        }
+    }
+
+    pub fn with_on(mut self, v: bool) -> Self {
+        self.on = v;
+        let position = if v { 1. } else { 0. };
+        self.thumb.set_position(position);
+        self.thumb.set_target(position);
+        self
+    }
+
+    pub fn with_text_size(mut self, v: f32) -> Self {
+        self.text_size = v;
+        self
+    }
+
+    pub fn with_disabled(mut self, v: bool) -> Self {
+        self.disabled = v;
+        self
+    }
+
+    /// Keep the state in the application data.
+    pub fn with_binding(mut self, v: Binding<D, bool>) -> Self {
+        self.binding = Some(v);
+        self
+    }
+
+    /// Called with the new state when the user flips the switch.
+    pub fn on_change<F: Fn(&mut D, bool) + 'static>(mut self, v: F) -> Self {
+        self.change_callback = Some(Rc::new(v));
+        self
+    }
+
+    #[inline]
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    /// Flip the switch, the thumb slides to the new side.
+    pub fn set_on(&mut self, v: bool) {
+        if self.on != v {
+            self.on = v;
+            self.thumb.set_target(if v { 1. } else { 0. });
+            self.need_paint = true;
+        }
+    }
+
+    #[inline]
+    pub fn set_label(&mut self, label: &str) {
+        self.label = label.into();
+    }
+
+    #[inline]
+    pub fn set_disabled(&mut self, v: bool) {
+        self.disabled = v;
+    }
+
+    fn toggle(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        self.set_on(!self.on);
+        if let Some(binding) = &self.binding {
+            *binding.get_mut(data) = self.on;
+        }
+        if let Some(callback) = &self.change_callback {
+            callback(data, self.on);
+        }
+        ctx.set_handled();
+        ctx.request_anim_frame();
+    }
+}
+
+impl<D> Widget<D> for Switch<D> {
+    fn event(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        let hovering = ctx.is_hovering();
+        if hovering != self.hovering {
+            self.hovering = hovering;
+            ctx.request_paint();
+        }
+        if self.disabled {
+            if self.pressed && matches!(ctx.event(), WindowEvent::MouseUp(_, _)) {
+                self.pressed = false;
+                ctx.set_active(false);
+            }
+            return;
+        }
+        match ctx.event() {
+            WindowEvent::CollectFocus => ctx.register_for_focus(),
+            WindowEvent::MouseDown(_, _) => {
+                self.pressed = true;
+                ctx.set_active(true);
+                ctx.request_focus();
+                ctx.set_handled();
+            }
+            WindowEvent::MouseUp(_, _) if self.pressed => {
+                self.pressed = false;
+                ctx.set_active(false);
+                if hovering {
+                    self.toggle(ctx, data);
+                }
+            }
+            WindowEvent::KeyDown(keys::SPACE) if ctx.is_focused() => self.toggle(ctx, data),
+            _ => {}
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        if let Some(binding) = &self.binding {
+            let on = *binding.get(data);
+            self.set_on(on);
+        }
+        // Drawing keeps the thumb moving
+        if self.need_paint {
+            self.need_paint = false;
+            ctx.request_paint();
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, _data: &D) {
+        let (_, height) = self.layout_size;
+        let progress = self.thumb.position();
+        if !self.thumb.arrived() {
+            ctx.request_anim_frame();
+        }
+        // The spring may overshoot, which only moves the thumb a bit further
+        let color_progress = progress.clamp(0., 1.);
+        let theme = get_theme_color() | 0xFF;
+        let accent = if self.disabled { DISABLED_COLOR } else { theme };
+        let y = ((height - TRACK_HEIGHT) / 2.).round();
+        let track = gen_round_rect_path(
+            0.5,
+            y + 0.5,
+            TRACK_WIDTH - 1.,
+            TRACK_HEIGHT - 1.,
+            TRACK_HEIGHT / 2. - 0.5,
+        );
+        ctx.fill_path(
+            &track,
+            &gen_paint(mix_color(OFF_BACKGROUND, accent, color_progress)),
+        );
+        let border = if self.disabled {
+            DISABLED_COLOR
+        } else {
+            OFF_BORDER
+        };
+        ctx.stroke_path(
+            &track,
+            &gen_paint(mix_color(border, accent, color_progress)),
+            &Stroke::default(),
+        );
+        let radius = if (self.hovering || self.pressed) && !self.disabled {
+            THUMB_HOVER_RADIUS
+        } else {
+            THUMB_RADIUS
+        };
+        let travel = TRACK_WIDTH - TRACK_HEIGHT;
+        let cx = TRACK_HEIGHT / 2. + travel * progress;
+        let thumb_color = if self.disabled {
+            DISABLED_COLOR
+        } else {
+            mix_color(OFF_THUMB, ON_THUMB, color_progress)
+        };
+        if let Some(path) = PathBuilder::from_circle(cx, y + TRACK_HEIGHT / 2., radius) {
+            ctx.fill_path(&path, &gen_paint(thumb_color));
+        }
+        if ctx.is_focused() {
+            let ring = gen_round_rect_path(
+                -2.,
+                y - 2.,
+                TRACK_WIDTH + 4.,
+                TRACK_HEIGHT + 4.,
+                TRACK_HEIGHT / 2. + 2.,
+            );
+            ctx.stroke_path(&ring, &gen_paint(theme), &Stroke::default());
+        }
+        let line_height = line_metrics(self.text_size, FontType::Normal).height;
+        let color = if self.disabled {
+            DISABLED_COLOR
+        } else {
+            TEXT_COLOR
+        };
+        ctx.draw_text(
+            TRACK_WIDTH + GAP,
+            (height - line_height) / 2.,
+            &self.label,
+            self.text_size,
+            color,
+        );
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let text_width = if self.label.is_empty() {
+            0.
+        } else {
+            GAP + measure_text(&self.label, self.text_size, FontType::Normal).ceil()
+        };
+        let line_height = line_metrics(self.text_size, FontType::Normal).height.ceil();
+        self.layout_size = (
+            (TRACK_WIDTH + text_width).min(max_box.width() as f32),
+            line_height
+                .max(TRACK_HEIGHT + 4.)
+                .min(max_box.height() as f32),
+        );
+        AreaBox::with_size(self.layout_size.0 as isize, self.layout_size.1 as isize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Modifiers;
+
+    #[test]
+    fn toggling_writes_the_binding() {
+        let mut data = false;
+        let mut switch = Switch::new("a").with_binding(Binding::identity());
+        let mut ctx = EventCtx::new(WindowEvent::None, (0., 0.), Modifiers::default(), None);
+        switch.toggle(&mut ctx, &mut data);
+        assert!(switch.is_on() && data);
+        assert!(ctx.request_anim);
+        let mut ctx = UpdateCtx::new();
+        switch.update(&mut ctx, &false);
+        assert!(!switch.is_on());
+        assert!(ctx.request_paint);
+    }
+
+    #[test]
+    fn set_on_paints_on_the_next_update() {
+        let mut switch = Switch::<()>::new("a");
+        switch.set_on(true);
+        let mut ctx = UpdateCtx::new();
+        switch.update(&mut ctx, &());
+        assert!(ctx.request_paint);
+        // Only once
+        let mut ctx = UpdateCtx::new();
+        switch.update(&mut ctx, &());
+        assert!(!ctx.request_paint);
+    }
+}
@@ -0,0 +1,783 @@
+//! 数据表格：列头可拖动调整宽度和顺序，点击排序，行和列表一样只为可见部分创建控件
+
+use std::cmp::Ordering;
+
+use crate::core::WidgetPod;
+use crate::events::{keys, Modifiers, WindowEvent};
+use crate::r#box::AreaBox;
+use crate::traits::Widget;
+use crate::utils::binding::Binding;
+use crate::utils::drawing::{gen_paint, gen_rect_path};
+use crate::utils::theme::get_theme_color;
+use crate::utils::uid::gen_uid;
+use crate::utils::WidgetUid;
+use crate::widgets::list::SelectionState;
+use crate::widgets::scroll::{ScrollDirection, ScrollState};
+use crate::{DrawCtx, EventCtx, UpdateCtx};
+
+const OVERSCAN: usize = 2;
+/// Distance to a column edge where resizing starts
+const RESIZE_HANDLE: f32 = 4.;
+/// Distance the mouse should move before a pressed header starts reordering
+const DRAG_THRESHOLD: f32 = 4.;
+const HEADER_COLOR: u32 = 0xF3F3F3FF;
+const HEADER_PRESSED_COLOR: u32 = 0xE0E0E0FF;
+const GRID_COLOR: u32 = 0xD0D0D0FF;
+const ARROW_COLOR: u32 = 0x606060FF;
+const HOVER_COLOR: u32 = 0x0000000F;
+
+type CellBuilder<T> = Box<dyn Fn() -> Box<dyn Widget<T>>>;
+type Compare<T> = Box<dyn Fn(&T, &T) -> Ordering>;
+type SelectCallback<D> = Box<dyn Fn(&mut D, &[usize])>;
+
+pub struct TableColumn<T> {
+    header: WidgetPod<()>,
+    builder: CellBuilder<T>,
+    width: f32,
+    min_width: f32,
+    compare: Option<Compare<T>>,
+}
+
+impl<T> TableColumn<T> {
+    /// A column with a widget as its header and a builder for its cells
+    pub fn new<F: Fn() -> Box<dyn Widget<T>> + 'static>(
+        header: Box<dyn Widget<()>>,
+        builder: F,
+    ) -> Self {
+        Self {
+            header: header.into(),
+            builder: Box::new(builder),
+            width: 120.,
+            min_width: 32.,
+            compare: None,
+        }
+    }
+
+    pub fn with_width(mut self, v: f32) -> Self {
+        self.width = v.max(self.min_width);
+        self
+    }
+
+    pub fn with_min_width(mut self, v: f32) -> Self {
+        self.min_width = v;
+        self.width = self.width.max(v);
+        self
+    }
+
+    /// Make the column sortable by clicking its header
+    pub fn with_sort<F: Fn(&T, &T) -> Ordering + 'static>(mut self, v: F) -> Self {
+        self.compare = Some(Box::new(v));
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum HeaderDrag {
+    None,
+    Resize {
+        column: usize,
+        start_x: f32,
+        start_width: f32,
+    },
+    Press {
+        column: usize,
+        start_x: f32,
+    },
+    Reorder {
+        column: usize,
+    },
+}
+
+pub struct TableControl<D, T> {
+    _uid: WidgetUid,
+    items: Binding<D, Vec<T>>,
+    columns: Vec<TableColumn<T>>,
+    /// Column indices in the displaying order
+    column_order: Vec<usize>,
+    row_height: f32,
+    header_height: f32,
+    /// Item indices in the displaying order
+    order: Vec<usize>,
+    len: usize,
+    /// Column index and whether it's ascending
+    sort: Option<(usize, bool)>,
+    need_sort: bool,
+    /// Realized rows with their displaying index, one cell for each column
+    rows: Vec<(usize, Vec<WidgetPod<T>>)>,
+    pool: Vec<Vec<WidgetPod<T>>>,
+    scroll: ScrollState,
+    size: (f32, f32),
+    /// Selection of displaying indices
+    selection: SelectionState,
+    hovering: Option<usize>,
+    drag: HeaderDrag,
+    select_callback: SelectCallback<D>,
+    selection_color: u32,
+}
+
+impl<D, T> TableControl<D, T> {
+    pub fn new(items: Binding<D, Vec<T>>) -> Self {
+        let mut scroll = ScrollState::new(ScrollDirection::Both);
+        scroll.set_drag_scroll(false);
+        Self {
+            _uid: gen_uid(),
+            items,
+            columns: Vec::new(),
+            column_order: Vec::new(),
+            row_height: 24.,
+            header_height: 28.,
+            order: Vec::new(),
+            len: 0,
+            sort: None,
+            need_sort: true,
+            rows: Vec::new(),
+            pool: Vec::new(),
+            scroll,
+            size: (0., 0.),
+            selection: SelectionState::new(),
+            hovering: None,
+            drag: HeaderDrag::None,
+            select_callback: Box::new(|_, _| {}),
+            selection_color: (get_theme_color() & 0xFFFFFF00) | 0x60,
+        }
+    }
+
+    pub fn with_column(mut self, v: TableColumn<T>) -> Self {
+        self.column_order.push(self.columns.len());
+        self.columns.push(v);
+        // Rows built before don't have the new cell
+        self.rows.clear();
+        self.pool.clear();
+        self
+    }
+
+    pub fn with_row_height(mut self, v: f32) -> Self {
+        self.row_height = v;
+        self
+    }
+
+    pub fn with_header_height(mut self, v: f32) -> Self {
+        self.header_height = v;
+        self
+    }
+
+    pub fn with_multi_select(mut self, v: bool) -> Self {
+        self.selection.multi_select = v;
+        self
+    }
+
+    /// Called with the selected item indices every time the selection changes
+    pub fn on_select<F: Fn(&mut D, &[usize]) + 'static>(mut self, v: F) -> Self {
+        self.select_callback = Box::new(v);
+        self
+    }
+
+    /// Sort by a column, the column needs a compare function
+    pub fn sort_by(&mut self, column: usize, ascending: bool) {
+        if self
+            .columns
+            .get(column)
+            .is_some_and(|c| c.compare.is_some())
+        {
+            self.sort = Some((column, ascending));
+            // Sorted when the items are available
+            self.need_sort = true;
+        }
+    }
+
+    /// Selected item indices
+    pub fn selection(&self) -> Vec<usize> {
+        self.selection
+            .selected()
+            .iter()
+            .map(|&i| self.order[i])
+            .collect()
+    }
+
+    fn column_x(&self, pos: usize) -> f32 {
+        self.column_order[..pos]
+            .iter()
+            .map(|&c| self.columns[c].width)
+            .sum()
+    }
+
+    fn content_width(&self) -> f32 {
+        self.columns.iter().map(|c| c.width).sum()
+    }
+
+    /// Displaying position of the column under the x in the content
+    fn column_pos_at(&self, x: f32) -> Option<usize> {
+        let mut left = 0.;
+        for (pos, &c) in self.column_order.iter().enumerate() {
+            let width = self.columns[c].width;
+            if x >= left && x < left + width {
+                return Some(pos);
+            }
+            left += width;
+        }
+        None
+    }
+
+    /// Column whose right edge is near the x in the content
+    fn resize_handle_at(&self, x: f32) -> Option<usize> {
+        let mut right = 0.;
+        for &c in self.column_order.iter() {
+            right += self.columns[c].width;
+            if (x - right).abs() <= RESIZE_HANDLE {
+                return Some(c);
+            }
+        }
+        None
+    }
+
+    fn body_size(&self) -> (f32, f32) {
+        (self.size.0, (self.size.1 - self.header_height).max(0.))
+    }
+
+    fn update_scroll_sizes(&mut self) {
+        let content = (self.content_width(), self.len as f32 * self.row_height);
+        self.scroll.set_sizes(self.body_size(), content);
+    }
+
+    /// Rebuild the displaying order, keeping the selected items selected
+    fn apply_sort(&mut self, items: &[T]) {
+        let selected: Vec<usize> = self
+            .selection
+            .selected()
+            .iter()
+            .filter_map(|&i| self.order.get(i).copied())
+            .collect();
+        self.len = items.len();
+        self.need_sort = false;
+        self.order = (0..self.len).collect();
+        if let Some((column, ascending)) = self.sort {
+            if let Some(compare) = &self.columns[column].compare {
+                self.order.sort_by(|&a, &b| {
+                    let o = compare(&items[a], &items[b]);
+                    if ascending {
+                        o
+                    } else {
+                        o.reverse()
+                    }
+                });
+            }
+        }
+        let mut inverse = vec![0; self.len];
+        for (pos, &index) in self.order.iter().enumerate() {
+            inverse[index] = pos;
+        }
+        self.selection.set_selected(
+            selected
+                .into_iter()
+                .filter(|&i| i < self.len)
+                .map(|i| inverse[i])
+                .collect(),
+        );
+        // Realized rows show other items now
+        let rows = self.rows.drain(..).map(|(_, cells)| cells);
+        self.pool.extend(rows);
+        self.update_scroll_sizes();
+    }
+
+    fn realize(&mut self, items: &[T]) {
+        if self.need_sort || self.len != items.len() {
+            self.apply_sort(items);
+        }
+        let (_, offset) = self.scroll.offset();
+        let (_, height) = self.body_size();
+        let first = ((offset / self.row_height) as usize).saturating_sub(OVERSCAN);
+        let last = (((offset + height) / self.row_height) as usize + 1 + OVERSCAN).min(self.len);
+
+        let mut i = 0;
+        while i < self.rows.len() {
+            let pos = self.rows[i].0;
+            if pos < first || pos >= last {
+                let (_, cells) = self.rows.swap_remove(i);
+                self.pool.push(cells);
+            } else {
+                i += 1;
+            }
+        }
+
+        for pos in first..last {
+            if self.rows.iter().any(|(p, _)| *p == pos) {
+                continue;
+            }
+            let mut cells = match self.pool.pop() {
+                Some(cells) => cells,
+                None => self.columns.iter().map(|c| (c.builder)().into()).collect(),
+            };
+            let item = &items[self.order[pos]];
+            for (c, cell) in cells.iter_mut().enumerate() {
+                cell.update(&mut UpdateCtx::new(), item);
+                let column = &self.columns[c];
+                cell.layout(AreaBox::with_size(
+                    column.width as isize,
+                    self.row_height as isize,
+                ));
+            }
+            self.rows.push((pos, cells));
+        }
+        self.place_cells();
+    }
+
+    fn place_cells(&mut self) {
+        let (ox, oy) = self.scroll.offset();
+        let lefts: Vec<f32> = (0..self.columns.len())
+            .map(|c| {
+                let pos = self.column_order.iter().position(|&i| i == c).unwrap();
+                self.column_x(pos)
+            })
+            .collect();
+        for (pos, cells) in self.rows.iter_mut() {
+            let top = self.header_height + *pos as f32 * self.row_height - oy;
+            for (c, cell) in cells.iter_mut().enumerate() {
+                cell.set_origin(lefts[c] - ox, top);
+            }
+        }
+        for (c, column) in self.columns.iter_mut().enumerate() {
+            column.header.set_origin(lefts[c] - ox, 0.);
+        }
+    }
+
+    fn key_down(&mut self, key: usize, modifiers: Modifiers) -> bool {
+        if self.len == 0 {
+            return false;
+        }
+        let last = self.len - 1;
+        let cursor = self.selection.cursor();
+        let page = (self.body_size().1 / self.row_height).max(1.) as usize;
+        let target = match key {
+            keys::UP => cursor.map_or(0, |i| i.saturating_sub(1)),
+            keys::DOWN => cursor.map_or(0, |i| (i + 1).min(last)),
+            keys::HOME => 0,
+            keys::END => last,
+            keys::PRIOR => cursor.map_or(0, |i| i.saturating_sub(page)),
+            keys::NEXT => cursor.map_or(0, |i| (i + page).min(last)),
+            _ => return self.selection.key_down(key, modifiers, self.len),
+        };
+        let changed = self.selection.navigate(target, modifiers);
+        let (ox, _) = self.scroll.target_offset();
+        self.scroll
+            .scroll_into_view(ox, target as f32 * self.row_height, 0., self.row_height);
+        changed
+    }
+
+    fn header_event(&mut self, ctx: &mut EventCtx) {
+        let (x, y) = ctx.mouse_pos();
+        let (ox, _) = self.scroll.offset();
+        let cx = x + ox;
+        match (ctx.event(), self.drag) {
+            (WindowEvent::MouseDown(_, _), _) if y < self.header_height => {
+                if let Some(column) = self.resize_handle_at(cx) {
+                    self.drag = HeaderDrag::Resize {
+                        column,
+                        start_x: x,
+                        start_width: self.columns[column].width,
+                    };
+                } else if let Some(pos) = self.column_pos_at(cx) {
+                    self.drag = HeaderDrag::Press {
+                        column: self.column_order[pos],
+                        start_x: x,
+                    };
+                }
+                if self.drag != HeaderDrag::None {
+                    // Keep getting the mouse when it leaves the table while dragging
+                    ctx.set_active(true);
+                }
+                ctx.set_handled();
+                ctx.request_paint();
+            }
+            (
+                WindowEvent::MouseMove(_, _),
+                HeaderDrag::Resize {
+                    column,
+                    start_x,
+                    start_width,
+                },
+            ) => {
+                let column = &mut self.columns[column];
+                column.width = (start_width + x - start_x).max(column.min_width);
+                ctx.request_layout();
+            }
+            (WindowEvent::MouseMove(_, _), HeaderDrag::Press { column, start_x })
+                if (x - start_x).abs() > DRAG_THRESHOLD =>
+            {
+                self.drag = HeaderDrag::Reorder { column };
+                ctx.request_paint();
+            }
+            (WindowEvent::MouseMove(_, _), HeaderDrag::Reorder { column }) => {
+                let current = self.column_order.iter().position(|&c| c == column).unwrap();
+                let target = match self.column_pos_at(cx) {
+                    Some(pos) => pos,
+                    None if cx < 0. => 0,
+                    None => self.column_order.len() - 1,
+                };
+                if target != current {
+                    self.column_order.remove(current);
+                    self.column_order.insert(target, column);
+                    self.place_cells();
+                    ctx.request_paint();
+                }
+            }
+            (WindowEvent::MouseUp(_, _), HeaderDrag::Press { column, .. }) => {
+                if self.columns[column].compare.is_some() {
+                    let ascending = match self.sort {
+                        Some((c, ascending)) if c == column => !ascending,
+                        _ => true,
+                    };
+                    self.sort_by(column, ascending);
+                }
+                self.drag = HeaderDrag::None;
+                ctx.set_active(false);
+                ctx.request_paint();
+            }
+            (WindowEvent::MouseUp(_, _), HeaderDrag::Resize { .. })
+            | (WindowEvent::MouseUp(_, _), HeaderDrag::Reorder { .. }) => {
+                self.drag = HeaderDrag::None;
+                ctx.set_active(false);
+                ctx.request_paint();
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<D, T> Widget<D> for TableControl<D, T> {
+    fn event(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        self.header_event(ctx);
+        if self.need_sort {
+            self.apply_sort(self.items.get(data));
+        }
+        if self.drag != HeaderDrag::None || ctx.is_handled() {
+            return;
+        }
+        // The scroll state works in the body coordinate, which is below the header
+        let pos = ctx.mouse_pos;
+        ctx.mouse_pos.1 -= self.header_height;
+        let bar = self.scroll.bar_event(ctx);
+        ctx.mouse_pos = pos;
+        if bar {
+            return;
+        }
+
+        let items = self.items.get_mut(data);
+        if self.len != items.len() {
+            return;
+        }
+        for (pos, cells) in self.rows.iter_mut() {
+            let item = &mut items[self.order[*pos]];
+            for cell in cells.iter_mut() {
+                cell.event(ctx, item);
+            }
+        }
+        self.scroll.event(ctx);
+
+        let (_, y) = ctx.mouse_pos();
+        let (_, offset) = self.scroll.offset();
+        let row = ((y - self.header_height + offset) / self.row_height).floor();
+        let row = if row >= 0. && (row as usize) < self.len {
+            Some(row as usize)
+        } else {
+            None
+        };
+        let mut changed = false;
+        match ctx.event() {
+            WindowEvent::CollectFocus => ctx.register_for_focus(),
+            WindowEvent::MouseDown(_, _) => {
+                ctx.request_focus();
+                if let Some(row) = row.filter(|_| !ctx.is_handled()) {
+                    self.selection.click(row, ctx.modifiers());
+                    changed = true;
+                    ctx.set_handled();
+                }
+            }
+            WindowEvent::MouseMove(_, _) => {
+                let hovering = row.filter(|_| ctx.is_hovering() && y >= self.header_height);
+                if hovering != self.hovering {
+                    self.hovering = hovering;
+                    ctx.request_paint();
+                }
+            }
+            WindowEvent::KeyDown(key) if ctx.is_focused() => {
+                let cursor = self.selection.cursor();
+                changed = self.key_down(key, ctx.modifiers());
+                if changed || cursor != self.selection.cursor() {
+                    ctx.set_handled();
+                    ctx.request_anim_frame();
+                }
+            }
+            _ => {}
+        }
+        if changed {
+            let selection = self.selection();
+            (self.select_callback)(data, &selection);
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        for column in &mut self.columns {
+            column.header.update(ctx, &());
+        }
+        let items = self.items.get(data);
+        self.realize(items);
+        for (pos, cells) in self.rows.iter_mut() {
+            let item = &items[self.order[*pos]];
+            for cell in cells.iter_mut() {
+                cell.update(ctx, item);
+            }
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, data: &D) {
+        let items = self.items.get(data);
+        self.realize(items);
+        let (width, height) = self.size;
+        let header_height = self.header_height;
+        let row_height = self.row_height;
+        let (_, oy) = self.scroll.offset();
+        let focused = ctx.is_focused();
+
+        // Body
+        let rows = &mut self.rows;
+        let order = &self.order;
+        let selection = &self.selection;
+        let selection_color = self.selection_color;
+        let hovering = self.hovering;
+        ctx.with_save(|ctx| {
+            ctx.clip_rect(0., header_height, width, height - header_height);
+            for (pos, cells) in rows.iter_mut() {
+                let top = header_height + *pos as f32 * row_height - oy;
+                let path = gen_rect_path(0., top, width, row_height);
+                if selection.is_selected(*pos) {
+                    ctx.fill_path(&path, &gen_paint(selection_color));
+                } else if hovering == Some(*pos) {
+                    ctx.fill_path(&path, &gen_paint(HOVER_COLOR));
+                }
+                if focused && selection.cursor() == Some(*pos) {
+                    let path = gen_rect_path(0.5, top + 0.5, width - 1., row_height - 1.);
+                    ctx.stroke_path(
+                        &path,
+                        &gen_paint(selection_color | 0xFF),
+                        &Default::default(),
+                    );
+                }
+                let item = &items[order[*pos]];
+                for cell in cells.iter_mut() {
+                    let (x, y) = cell.origin();
+                    let (w, h) = cell.size();
+                    ctx.with_save(|ctx| {
+                        ctx.clip_rect(x, y, w, h);
+                        cell.draw(ctx, item);
+                    });
+                }
+            }
+        });
+
+        // The header sticks to the top
+        ctx.fill_path(
+            &gen_rect_path(0., 0., width, header_height),
+            &gen_paint(HEADER_COLOR),
+        );
+        let dragging = match self.drag {
+            HeaderDrag::Press { column, .. } | HeaderDrag::Reorder { column } => Some(column),
+            _ => None,
+        };
+        let sort = self.sort;
+        for column_index in self.column_order.iter().copied() {
+            let column = &mut self.columns[column_index];
+            let (x, _) = column.header.origin();
+            let w = column.width;
+            if x + w < 0. || x > width {
+                continue;
+            }
+            if dragging == Some(column_index) {
+                ctx.fill_path(
+                    &gen_rect_path(x, 0., w, header_height),
+                    &gen_paint(HEADER_PRESSED_COLOR),
+                );
+            }
+            let header = &mut column.header;
+            ctx.with_save(|ctx| {
+                ctx.clip_rect(x, 0., w, header_height);
+                header.draw(ctx, &());
+            });
+            if let Some((c, ascending)) = sort {
+                if c == column_index {
+                    let cx = x + w - 12.;
+                    let cy = header_height / 2.;
+                    let mut pb = tiny_skia::PathBuilder::new();
+                    if ascending {
+                        pb.move_to(cx - 4., cy + 2.);
+                        pb.line_to(cx + 4., cy + 2.);
+                        pb.line_to(cx, cy - 2.);
+                    } else {
+                        pb.move_to(cx - 4., cy - 2.);
+                        pb.line_to(cx + 4., cy - 2.);
+                        pb.line_to(cx, cy + 2.);
+                    }
+                    pb.close();
+                    if let Some(path) = pb.finish() {
+                        ctx.fill_path(&path, &gen_paint(ARROW_COLOR));
+                    }
+                }
+            }
+            ctx.fill_path(
+                &gen_rect_path(x + w - 1., 4., 1., header_height - 8.),
+                &gen_paint(GRID_COLOR),
+            );
+        }
+        ctx.fill_path(
+            &gen_rect_path(0., header_height - 1., width, 1.),
+            &gen_paint(GRID_COLOR),
+        );
+
+        let scroll = &mut self.scroll;
+        ctx.with_save(|ctx| {
+            ctx.translate(0., header_height);
+            scroll.draw_bars(ctx);
+        });
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let width = if max_box.width() == isize::MAX {
+            self.content_width()
+        } else {
+            max_box.width() as f32
+        };
+        let height = if max_box.height() == isize::MAX {
+            self.header_height + self.len as f32 * self.row_height
+        } else {
+            max_box.height() as f32
+        };
+        self.size = (width, height);
+        for column in self.columns.iter_mut() {
+            column.header.layout(AreaBox::with_size(
+                column.width as isize,
+                self.header_height as isize,
+            ));
+        }
+        for (_, cells) in self.rows.iter_mut() {
+            for (c, cell) in cells.iter_mut().enumerate() {
+                cell.layout(AreaBox::with_size(
+                    self.columns[c].width as isize,
+                    self.row_height as isize,
+                ));
+            }
+        }
+        self.update_scroll_sizes();
+        self.place_cells();
+        AreaBox::with_size(width as isize, height as isize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+
+    struct Cell;
+
+    impl<T> Widget<T> for Cell {}
+
+    fn table() -> TableControl<Vec<i32>, i32> {
+        TableControl::new(Binding::identity())
+            .with_column(
+                TableColumn::new(Box::new(Cell), || Box::new(Cell))
+                    .with_width(100.)
+                    .with_sort(|a: &i32, b: &i32| a.cmp(b)),
+            )
+            .with_column(TableColumn::new(Box::new(Cell), || Box::new(Cell)).with_width(50.))
+    }
+
+    #[test]
+    fn sorting_keeps_the_selected_items() {
+        let items = vec![30, 10, 20];
+        let mut table = table().with_multi_select(true);
+        table.apply_sort(&items);
+        table.selection.set_selected(vec![0, 2]);
+        assert_eq!(table.selection(), [0, 2]);
+        table.sort_by(0, true);
+        table.apply_sort(&items);
+        assert_eq!(table.order, [1, 2, 0]);
+        assert_eq!(table.selection(), [2, 0]);
+        assert_eq!(table.selection.selected(), [1, 2]);
+        table.sort_by(0, false);
+        table.apply_sort(&items);
+        assert_eq!(table.order, [0, 2, 1]);
+        // Columns without a compare function can't sort
+        table.sort_by(1, true);
+        assert_eq!(table.sort, Some((0, false)));
+    }
+
+    #[test]
+    fn columns_are_found_in_the_displaying_order() {
+        let mut table = table();
+        assert_eq!(table.column_pos_at(120.), Some(1));
+        assert_eq!(table.resize_handle_at(102.), Some(0));
+        assert_eq!(table.resize_handle_at(150.), Some(1));
+        assert_eq!(table.column_pos_at(150.), None);
+        table.column_order = vec![1, 0];
+        assert_eq!(table.column_x(1), 50.);
+        assert_eq!(table.column_pos_at(20.), Some(0));
+        assert_eq!(table.resize_handle_at(52.), Some(1));
+        assert_eq!(table.content_width(), 150.);
+    }
+
+    #[test]
+    fn keys_page_by_visible_rows() {
+        let items: Vec<i32> = (0..50).collect();
+        let mut table = table().with_row_height(20.).with_header_height(20.);
+        table.size = (150., 120.);
+        table.apply_sort(&items);
+        assert!(table.key_down(keys::NEXT, Default::default()));
+        assert_eq!(table.selection(), [0]);
+        assert!(table.key_down(keys::NEXT, Default::default()));
+        assert_eq!(table.selection(), [5]);
+        assert!(table.key_down(keys::END, Default::default()));
+        assert_eq!(table.selection(), [49]);
+        assert_eq!(table.scroll.target_offset().1, 49. * 20. + 20. - 100.);
+    }
+
+    fn header_event(
+        table: &mut TableControl<Vec<i32>, i32>,
+        event: WindowEvent,
+        x: f32,
+    ) -> EventCtx {
+        let mut ctx = EventCtx::new(event, (x, 5.), Modifiers::default(), None);
+        table.header_event(&mut ctx);
+        ctx
+    }
+
+    #[test]
+    fn header_drags_hold_the_mouse() {
+        let mut table = table();
+        let ctx = header_event(&mut table, WindowEvent::MouseDown(0, 0), 102.);
+        assert_eq!(ctx.active, Some(true));
+        header_event(&mut table, WindowEvent::MouseMove(0, 0), 130.);
+        assert_eq!(table.columns[0].width, 128.);
+        let ctx = header_event(&mut table, WindowEvent::MouseUp(0, 0), 130.);
+        assert_eq!(ctx.active, Some(false));
+        assert!(table.drag == HeaderDrag::None);
+    }
+
+    #[test]
+    fn headers_are_updated() {
+        struct Header(Rc<std::cell::Cell<usize>>);
+
+        impl Widget<()> for Header {
+            fn update(&mut self, _: &mut UpdateCtx, _: &()) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let updates = Rc::new(std::cell::Cell::new(0));
+        let mut table = TableControl::new(Binding::identity())
+            .with_column(TableColumn::new(Box::new(Header(updates.clone())), || {
+                Box::new(Cell)
+            }));
+        table.update(&mut UpdateCtx::new(), &vec![1, 2]);
+        assert_eq!(updates.get(), 1);
+    }
+}
@@ -0,0 +1,965 @@
+//! 标签页：标签条放在上方或左侧，点击标签切换页面，页面在第一次显示时才创建
+//!
+//! 选中标签下方的指示条会滑动到新的位置；标签太多时标签条可以用滚轮滚动，标签可以拖动排序，也可以带关闭按钮
+
+use std::rc::Rc;
+
+use tiny_skia::{PathBuilder, Stroke};
+
+use crate::core::WidgetPod;
+use crate::events::{keys, WindowEvent};
+use crate::r#box::AreaBox;
+use crate::traits::Widget;
+use crate::utils::drawing::{gen_paint, gen_rect_path, gen_round_rect_path};
+use crate::utils::spring::Spring;
+use crate::utils::text::{line_metrics, measure_text, FontType};
+use crate::utils::theme::get_theme_color;
+use crate::utils::uid::gen_uid;
+use crate::utils::WidgetUid;
+use crate::{DrawCtx, EventCtx, UpdateCtx};
+
+const TAB_HEIGHT: f32 = 36.;
+/// Width of the strip when it's on the left
+const LEFT_STRIP_WIDTH: f32 = 160.;
+const PADDING: f32 = 14.;
+const TEXT_SIZE: f32 = 14.;
+const CLOSE_SIZE: f32 = 16.;
+const CLOSE_GAP: f32 = 6.;
+const INDICATOR_SIZE: f32 = 2.;
+/// Distance the pointer moves before a pressed tab is dragged
+const DRAG_THRESHOLD: f32 = 4.;
+/// Distance scrolled by a wheel notch
+const WHEEL_STEP: f32 = 40.;
+const STRIP_COLOR: u32 = 0xF3F3F3FF;
+const LINE_COLOR: u32 = 0x00000020;
+const HOVER_COLOR: u32 = 0x0000000C;
+const SELECTED_COLOR: u32 = 0xFFFFFFFF;
+const TEXT_COLOR: u32 = 0x505050FF;
+const SELECTED_TEXT_COLOR: u32 = 0x202020FF;
+const CLOSE_COLOR: u32 = 0x00000080;
+const CLOSE_HOVER_COLOR: u32 = 0x00000018;
+
+/// Where the tab strip goes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TabPosition {
+    Top,
+    Left,
+}
+
+type PageBuilder<D> = Box<dyn FnOnce() -> Box<dyn Widget<D>>>;
+type IndexCallback<D> = Rc<dyn Fn(&mut D, usize)>;
+type ReorderCallback<D> = Rc<dyn Fn(&mut D, usize, usize)>;
+
+struct Tab<D> {
+    title: String,
+    /// Creates the page the first time the tab is selected
+    builder: Option<PageBuilder<D>>,
+    page: Option<WidgetPod<D>>,
+    /// Start and length along the strip, before scrolling
+    span: (f32, f32),
+}
+
+impl<D> Tab<D> {
+    fn page(&mut self) -> Option<&mut WidgetPod<D>> {
+        if let Some(builder) = self.builder.take() {
+            self.page = Some(builder().into());
+        }
+        self.page.as_mut()
+    }
+}
+
+/// A tab being dragged along the strip
+struct Drag {
+    index: usize,
+    /// Where the pointer went down along the strip
+    from: f32,
+    /// Distance from the start of the tab to the pointer
+    grab: f32,
+    /// Pointer position along the strip
+    pos: f32,
+    /// Index when the drag started, to report the move
+    original: usize,
+    moved: bool,
+}
+
+/// Part of the strip under the pointer
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Hit {
+    Tab(usize),
+    Close(usize),
+}
+
+/// Pages switched with a strip of tabs.
+pub struct Tabs<D> {
+    _uid: WidgetUid,
+    tabs: Vec<Tab<D>>,
+    selected: usize,
+    position: TabPosition,
+    closable: bool,
+    reorderable: bool,
+    /// Offset of the strip when the tabs don't fit
+    scroll: Spring,
+    /// Start and length of the indicator along the strip
+    indicator: (Spring, Spring),
+    hovering: Option<Hit>,
+    pressed: Option<Hit>,
+    drag: Option<Drag>,
+    select_callback: Option<IndexCallback<D>>,
+    close_callback: Option<IndexCallback<D>>,
+    reorder_callback: Option<ReorderCallback<D>>,
+    layout_size: (f32, f32),
+}
+
+impl<D> Tabs<D> {
+    pub fn new() -> Self {
+        let spring = || {
+            let mut spring = Spring::new(0.);
+            spring.speed = 20.;
+            spring
+        };
+        Self {
+            _uid: gen_uid(),
+            tabs: Vec::new(),
+            selected: 0,
+            position: TabPosition::Top,
+            closable: false,
+            reorderable: true,
+            scroll: spring(),
+            indicator: (spring(), spring()),
+            hovering: None,
+            pressed: None,
+            drag: None,
+            select_callback: None,
+            close_callback: None,
+            reorder_callback: None,
+            layout_size: (0., 0.),
+        }
+    }
+
+    pub fn with_tab(mut self, title: &str, page: Box<dyn Widget<D>>) -> Self {
+        self.add_tab(title, page);
+        self
+    }
+
+    /// Add a tab whose page is created by `builder` when it's first selected.
+    pub fn with_lazy_tab<F>(mut self, title: &str, builder: F) -> Self
+    where
+        F: FnOnce() -> Box<dyn Widget<D>> + 'static,
+    {
+        self.add_lazy_tab(title, builder);
+        self
+    }
+
+    pub fn with_position(mut self, v: TabPosition) -> Self {
+        self.position = v;
+        self
+    }
+
+    /// Show a close button on the tabs.
+    pub fn with_closable(mut self, v: bool) -> Self {
+        self.closable = v;
+        self
+    }
+
+    /// Let the tabs be reordered by dragging, on by default.
+    pub fn with_reorderable(mut self, v: bool) -> Self {
+        self.reorderable = v;
+        self
+    }
+
+    /// Called with the index of the tab selected by the user.
+    pub fn on_select<F: Fn(&mut D, usize) + 'static>(mut self, v: F) -> Self {
+        self.select_callback = Some(Rc::new(v));
+        self
+    }
+
+    /// Called with the index of the tab closed by the user, after it's removed.
+    pub fn on_close<F: Fn(&mut D, usize) + 'static>(mut self, v: F) -> Self {
+        self.close_callback = Some(Rc::new(v));
+        self
+    }
+
+    /// Called with the old and the new index of a tab dragged to another place.
+    pub fn on_reorder<F: Fn(&mut D, usize, usize) + 'static>(mut self, v: F) -> Self {
+        self.reorder_callback = Some(Rc::new(v));
+        self
+    }
+
+    pub fn add_tab(&mut self, title: &str, page: Box<dyn Widget<D>>) {
+        self.tabs.push(Tab {
+            title: title.into(),
+            builder: None,
+            page: Some(page.into()),
+            span: (0., 0.),
+        });
+    }
+
+    pub fn add_lazy_tab<F>(&mut self, title: &str, builder: F)
+    where
+        F: FnOnce() -> Box<dyn Widget<D>> + 'static,
+    {
+        self.tabs.push(Tab {
+            title: title.into(),
+            builder: Some(Box::new(builder)),
+            page: None,
+            span: (0., 0.),
+        });
+    }
+
+    /// Remove a tab and its page, a layout is needed afterwards.
+    pub fn remove_tab(&mut self, index: usize) {
+        if index >= self.tabs.len() {
+            return;
+        }
+        self.tabs.remove(index);
+        if self.selected > index || self.selected >= self.tabs.len() {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        self.drag = None;
+        self.hovering = None;
+        self.pressed = None;
+    }
+
+    pub fn set_title(&mut self, index: usize, title: &str) {
+        if let Some(tab) = self.tabs.get_mut(index) {
+            tab.title = title.into();
+        }
+    }
+
+    /// Select a tab, a layout is needed afterwards.
+    pub fn set_selected(&mut self, index: usize) {
+        if index < self.tabs.len() {
+            self.selected = index;
+        }
+    }
+
+    #[inline]
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tabs.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tabs.is_empty()
+    }
+
+    /// Coordinate along the strip.
+    #[inline]
+    fn main(&self, pos: (f32, f32)) -> f32 {
+        match self.position {
+            TabPosition::Top => pos.0,
+            TabPosition::Left => pos.1,
+        }
+    }
+
+    /// Size of the strip across it.
+    #[inline]
+    fn thickness(&self) -> f32 {
+        match self.position {
+            TabPosition::Top => TAB_HEIGHT,
+            TabPosition::Left => LEFT_STRIP_WIDTH.min(self.layout_size.0),
+        }
+    }
+
+    /// Visible length of the strip.
+    #[inline]
+    fn visible_length(&self) -> f32 {
+        self.main(self.layout_size)
+    }
+
+    fn content_length(&self) -> f32 {
+        self.tabs.last().map_or(0., |tab| tab.span.0 + tab.span.1)
+    }
+
+    fn in_strip(&self, (x, y): (f32, f32)) -> bool {
+        let cross = match self.position {
+            TabPosition::Top => y,
+            TabPosition::Left => x,
+        };
+        x >= 0. && y >= 0. && cross < self.thickness() && self.main((x, y)) < self.visible_length()
+    }
+
+    /// Rectangle of a tab starting at `start` along the strip, in the widget.
+    fn tab_rect(&mut self, start: f32, length: f32) -> (f32, f32, f32, f32) {
+        let start = start - self.scroll.position();
+        match self.position {
+            TabPosition::Top => (start, 0., length, TAB_HEIGHT),
+            TabPosition::Left => (0., start, self.thickness(), length),
+        }
+    }
+
+    fn close_rect((x, y, w, h): (f32, f32, f32, f32)) -> (f32, f32, f32, f32) {
+        (
+            x + w - PADDING - CLOSE_SIZE,
+            y + (h - CLOSE_SIZE) / 2.,
+            CLOSE_SIZE,
+            CLOSE_SIZE,
+        )
+    }
+
+    fn hit(&mut self, pos: (f32, f32)) -> Option<Hit> {
+        if !self.in_strip(pos) {
+            return None;
+        }
+        let inside = |(x, y, w, h): (f32, f32, f32, f32)| {
+            pos.0 >= x && pos.1 >= y && pos.0 < x + w && pos.1 < y + h
+        };
+        for index in 0..self.tabs.len() {
+            let (start, length) = self.tabs[index].span;
+            let rect = self.tab_rect(start, length);
+            if inside(rect) {
+                if self.closable && inside(Self::close_rect(rect)) {
+                    return Some(Hit::Close(index));
+                }
+                return Some(Hit::Tab(index));
+            }
+        }
+        None
+    }
+
+    /// Put the tabs one after another along the strip.
+    fn arrange(&mut self) {
+        let mut start = 0.;
+        for tab in &mut self.tabs {
+            let length = match self.position {
+                TabPosition::Top => {
+                    let mut length =
+                        measure_text(&tab.title, TEXT_SIZE, FontType::Normal).ceil() + PADDING * 2.;
+                    if self.closable {
+                        length += CLOSE_SIZE + CLOSE_GAP;
+                    }
+                    length
+                }
+                TabPosition::Left => TAB_HEIGHT,
+            };
+            tab.span = (start, length);
+            start += length;
+        }
+    }
+
+    /// Keep the scroll offset in range and bring the selected tab into view.
+    fn scroll_to_selected(&mut self) {
+        let visible = self.visible_length();
+        let max = (self.content_length() - visible).max(0.);
+        let mut target = self.scroll.target.min(max).max(0.);
+        if let Some(tab) = self.tabs.get(self.selected) {
+            let (start, length) = tab.span;
+            if start < target {
+                target = start;
+            } else if start + length > target + visible {
+                target = (start + length - visible).min(max);
+            }
+        }
+        self.scroll.set_target(target);
+    }
+
+    fn move_indicator(&mut self) {
+        if let Some(tab) = self.tabs.get(self.selected) {
+            let (start, length) = tab.span;
+            self.indicator.0.set_target(start);
+            self.indicator.1.set_target(length);
+        }
+    }
+
+    fn select(&mut self, ctx: &mut EventCtx, data: &mut D, index: usize) {
+        if index == self.selected || index >= self.tabs.len() {
+            return;
+        }
+        self.selected = index;
+        self.move_indicator();
+        self.scroll_to_selected();
+        if let Some(callback) = &self.select_callback {
+            callback(data, index);
+        }
+        // The page may be new, and pages are laid out when shown
+        ctx.request_layout();
+        ctx.request_anim_frame();
+    }
+
+    fn close(&mut self, ctx: &mut EventCtx, data: &mut D, index: usize) {
+        let selected = self.selected;
+        self.remove_tab(index);
+        self.arrange();
+        self.move_indicator();
+        self.scroll_to_selected();
+        if let Some(callback) = &self.close_callback {
+            callback(data, index);
+        }
+        if index == selected {
+            if let Some(callback) = &self.select_callback {
+                if !self.tabs.is_empty() {
+                    callback(data, self.selected);
+                }
+            }
+        }
+        ctx.request_layout();
+        ctx.request_anim_frame();
+    }
+
+    /// Move the dragged tab to the place under the pointer.
+    fn drag_to(&mut self, pos: f32) {
+        let drag = match &mut self.drag {
+            Some(drag) => drag,
+            None => return,
+        };
+        drag.pos = pos;
+        if !drag.moved && (pos - drag.from).abs() < DRAG_THRESHOLD {
+            return;
+        }
+        drag.moved = true;
+        let mut index = drag.index;
+        let (_, length) = self.tabs[index].span;
+        let center = pos - drag.grab + length / 2.;
+        while index > 0 {
+            let (start, length) = self.tabs[index - 1].span;
+            if center >= start + length / 2. {
+                break;
+            }
+            self.tabs.swap(index, index - 1);
+            index -= 1;
+        }
+        while index + 1 < self.tabs.len() {
+            let (start, length) = self.tabs[index + 1].span;
+            if center <= start + length / 2. {
+                break;
+            }
+            self.tabs.swap(index, index + 1);
+            index += 1;
+        }
+        if index != drag.index {
+            // The selection follows the tab it was on
+            if self.selected == drag.index {
+                self.selected = index;
+            } else if self.selected == index {
+                self.selected = drag.index;
+            } else if drag.index < self.selected && index >= self.selected {
+                self.selected -= 1;
+            } else if drag.index > self.selected && index <= self.selected {
+                self.selected += 1;
+            }
+            drag.index = index;
+            self.arrange();
+        }
+    }
+
+    fn draw_tab(&mut self, ctx: &mut DrawCtx, index: usize, start: f32, dragged: bool) {
+        let (_, length) = self.tabs[index].span;
+        let rect = self.tab_rect(start, length);
+        let (x, y, w, h) = rect;
+        let selected = index == self.selected;
+        let background = if selected || dragged {
+            Some(SELECTED_COLOR)
+        } else if self
+            .hovering
+            .is_some_and(|hit| hit == Hit::Tab(index) || hit == Hit::Close(index))
+        {
+            Some(HOVER_COLOR)
+        } else {
+            None
+        };
+        if let Some(color) = background {
+            ctx.fill_path(&gen_rect_path(x, y, w, h), &gen_paint(color));
+        }
+        let line_height = line_metrics(TEXT_SIZE, FontType::Normal).height;
+        let text_color = if selected {
+            SELECTED_TEXT_COLOR
+        } else {
+            TEXT_COLOR
+        };
+        let title = &self.tabs[index].title;
+        let text_width = if self.closable {
+            w - PADDING * 2. - CLOSE_SIZE - CLOSE_GAP
+        } else {
+            w - PADDING * 2.
+        };
+        ctx.with_save(|ctx| {
+            ctx.clip_rect(x + PADDING, y, text_width.max(0.), h);
+            ctx.draw_text(
+                x + PADDING,
+                y + (h - line_height) / 2.,
+                title,
+                TEXT_SIZE,
+                text_color,
+            );
+        });
+        if self.closable {
+            let (cx, cy, cw, ch) = Self::close_rect(rect);
+            if self.hovering == Some(Hit::Close(index)) {
+                let path = gen_round_rect_path(cx, cy, cw, ch, 3.);
+                ctx.fill_path(&path, &gen_paint(CLOSE_HOVER_COLOR));
+            }
+            let mut cross = PathBuilder::new();
+            cross.move_to(cx + 4.5, cy + 4.5);
+            cross.line_to(cx + cw - 4.5, cy + ch - 4.5);
+            cross.move_to(cx + cw - 4.5, cy + 4.5);
+            cross.line_to(cx + 4.5, cy + ch - 4.5);
+            if let Some(cross) = cross.finish() {
+                let stroke = Stroke {
+                    width: 1.5,
+                    ..Default::default()
+                };
+                ctx.stroke_path(&cross, &gen_paint(CLOSE_COLOR), &stroke);
+            }
+        }
+    }
+}
+
+impl<D> Default for Tabs<D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Events only the page in view receives
+fn is_input(event: WindowEvent) -> bool {
+    matches!(
+        event,
+        WindowEvent::MouseMove(_, _)
+            | WindowEvent::MouseDown(_, _)
+            | WindowEvent::MouseUp(_, _)
+            | WindowEvent::MousePress(_, _)
+            | WindowEvent::ContextMenu(_, _)
+            | WindowEvent::MouseWheel(_)
+            | WindowEvent::MouseHWheel(_)
+            | WindowEvent::KeyDown(_)
+            | WindowEvent::KeyUp(_)
+            | WindowEvent::KeyPress(_)
+            | WindowEvent::CharInput(_, _)
+            | WindowEvent::CollectFocus
+    )
+}
+
+impl<D> Widget<D> for Tabs<D> {
+    fn event(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        let pos = ctx.mouse_pos();
+        let event = ctx.event();
+        // The strip comes before the page in `Tab` order
+        if event == WindowEvent::CollectFocus {
+            ctx.register_for_focus();
+        }
+        let selected = self.selected;
+        for (index, tab) in self.tabs.iter_mut().enumerate() {
+            if index == selected {
+                if let Some(page) = tab.page() {
+                    page.event(ctx, data);
+                }
+            } else if !is_input(event) {
+                if let Some(page) = &mut tab.page {
+                    page.event(ctx, data);
+                }
+            }
+        }
+        match event {
+            WindowEvent::MouseMove(_, _) => {
+                if self.drag.is_some() {
+                    let main = self.main(pos) + self.scroll.position();
+                    self.drag_to(main);
+                    ctx.request_paint();
+                    return;
+                }
+                let hit = if ctx.is_hovering() {
+                    self.hit(pos)
+                } else {
+                    None
+                };
+                if hit != self.hovering {
+                    self.hovering = hit;
+                    ctx.request_paint();
+                }
+            }
+            WindowEvent::MouseDown(_, _) if ctx.is_hovering() => {
+                let hit = self.hit(pos);
+                self.pressed = hit;
+                if let Some(Hit::Tab(index)) = hit {
+                    self.select(ctx, data, index);
+                    if self.reorderable {
+                        let index = self.selected;
+                        let main = self.main(pos) + self.scroll.position();
+                        self.drag = Some(Drag {
+                            index,
+                            from: main,
+                            grab: main - self.tabs[index].span.0,
+                            pos: main,
+                            original: index,
+                            moved: false,
+                        });
+                    }
+                }
+                if hit.is_some() {
+                    ctx.set_active(true);
+                    ctx.request_focus();
+                    ctx.set_handled();
+                }
+            }
+            WindowEvent::MouseUp(_, _) => {
+                let pressed = self.pressed.take();
+                if let Some(drag) = self.drag.take() {
+                    if drag.moved {
+                        // Slide the indicator from where the tab was dropped
+                        if drag.index == self.selected {
+                            self.indicator.0.set_position(drag.pos - drag.grab);
+                        }
+                        self.move_indicator();
+                        self.scroll_to_selected();
+                        if drag.index != drag.original {
+                            if let Some(callback) = &self.reorder_callback {
+                                callback(data, drag.original, drag.index);
+                            }
+                        }
+                        ctx.request_anim_frame();
+                    }
+                }
+                if let Some(Hit::Close(index)) = pressed {
+                    if self.hit(pos) == Some(Hit::Close(index)) {
+                        self.close(ctx, data, index);
+                    }
+                }
+                if pressed.is_some() {
+                    ctx.set_active(false);
+                    ctx.request_paint();
+                }
+            }
+            WindowEvent::MouseWheel(delta) | WindowEvent::MouseHWheel(delta)
+                if !ctx.is_handled() && self.in_strip(pos) =>
+            {
+                let max = (self.content_length() - self.visible_length()).max(0.);
+                let step = delta as f32 / 120. * WHEEL_STEP;
+                let step = if matches!(event, WindowEvent::MouseHWheel(_)) {
+                    -step
+                } else {
+                    step
+                };
+                let target = (self.scroll.target - step).min(max).max(0.);
+                self.scroll.set_target(target);
+                ctx.request_anim_frame();
+                ctx.set_handled();
+            }
+            WindowEvent::KeyDown(keys::TAB) if !ctx.is_handled() && ctx.modifiers().ctrl => {
+                // Ctrl+Tab goes through the tabs from anywhere in the page
+                let len = self.tabs.len();
+                if len > 1 {
+                    let next = if ctx.modifiers().shift {
+                        (self.selected + len - 1) % len
+                    } else {
+                        (self.selected + 1) % len
+                    };
+                    self.select(ctx, data, next);
+                }
+                ctx.set_handled();
+            }
+            WindowEvent::KeyDown(key) if ctx.is_focused() && !ctx.is_handled() => {
+                let (previous, next) = match self.position {
+                    TabPosition::Top => (keys::LEFT, keys::RIGHT),
+                    TabPosition::Left => (keys::UP, keys::DOWN),
+                };
+                let index = match key {
+                    _ if key == previous => self.selected.checked_sub(1),
+                    _ if key == next => Some(self.selected + 1),
+                    keys::HOME => Some(0),
+                    keys::END => self.tabs.len().checked_sub(1),
+                    _ => None,
+                };
+                if let Some(index) = index {
+                    self.select(ctx, data, index);
+                    ctx.set_handled();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        for tab in &mut self.tabs {
+            if let Some(page) = &mut tab.page {
+                page.update(ctx, data);
+            }
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, data: &D) {
+        let (width, height) = self.layout_size;
+        let thickness = self.thickness();
+        if let Some(page) = self
+            .tabs
+            .get_mut(self.selected)
+            .and_then(|tab| tab.page.as_mut())
+        {
+            page.draw(ctx, data);
+        }
+        let (strip_w, strip_h) = match self.position {
+            TabPosition::Top => (width, thickness),
+            TabPosition::Left => (thickness, height),
+        };
+        ctx.fill_path(
+            &gen_rect_path(0., 0., strip_w, strip_h),
+            &gen_paint(STRIP_COLOR),
+        );
+        let line = match self.position {
+            TabPosition::Top => gen_rect_path(0., strip_h - 1., strip_w, 1.),
+            TabPosition::Left => gen_rect_path(strip_w - 1., 0., 1., strip_h),
+        };
+        ctx.fill_path(&line, &gen_paint(LINE_COLOR));
+
+        let animating =
+            !self.scroll.arrived() || !self.indicator.0.arrived() || !self.indicator.1.arrived();
+        if animating {
+            ctx.request_anim_frame();
+        }
+        let dragged = self
+            .drag
+            .as_ref()
+            .filter(|drag| drag.moved)
+            .map(|drag| (drag.index, drag.pos - drag.grab));
+        let focused = ctx.is_focused();
+        ctx.with_save(|ctx| {
+            ctx.clip_rect(0., 0., strip_w, strip_h);
+            for index in 0..self.tabs.len() {
+                if dragged.map(|(i, _)| i) != Some(index) {
+                    let start = self.tabs[index].span.0;
+                    self.draw_tab(ctx, index, start, false);
+                }
+            }
+            if let Some((index, start)) = dragged {
+                self.draw_tab(ctx, index, start, true);
+            }
+            if self.tabs.is_empty() {
+                return;
+            }
+            // The indicator is on the dragged tab while the selected one moves
+            let (start, length) = match dragged {
+                Some((index, start)) if index == self.selected => (start, self.tabs[index].span.1),
+                _ => (self.indicator.0.position(), self.indicator.1.position()),
+            };
+            let (x, y, w, h) = self.tab_rect(start, length);
+            let color = get_theme_color() | 0xFF;
+            let indicator = match self.position {
+                TabPosition::Top => gen_rect_path(
+                    x + PADDING / 2.,
+                    y + h - INDICATOR_SIZE - 1.,
+                    (w - PADDING).max(0.),
+                    INDICATOR_SIZE,
+                ),
+                TabPosition::Left => gen_rect_path(
+                    x,
+                    y + PADDING / 2.,
+                    INDICATOR_SIZE + 1.,
+                    (h - PADDING).max(0.),
+                ),
+            };
+            ctx.fill_path(&indicator, &gen_paint(color));
+            if focused {
+                let (start, length) = self.tabs[self.selected].span;
+                let (x, y, w, h) = self.tab_rect(start, length);
+                let path = gen_round_rect_path(x + 2.5, y + 2.5, w - 5., h - 5., 3.);
+                ctx.stroke_path(&path, &gen_paint(color), &Stroke::default());
+            }
+        });
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        self.layout_size = max_box.to_size_f32();
+        self.arrange();
+        if self.selected >= self.tabs.len() {
+            self.selected = self.tabs.len().saturating_sub(1);
+        }
+        let (width, height) = self.layout_size;
+        let thickness = self.thickness();
+        let (origin, size) = match self.position {
+            TabPosition::Top => ((0., thickness), (width, (height - thickness).max(0.))),
+            TabPosition::Left => ((thickness, 0.), ((width - thickness).max(0.), height)),
+        };
+        let mut page_size = (0., 0.);
+        if let Some(page) = self.tabs.get_mut(self.selected).and_then(|tab| tab.page()) {
+            page_size = page
+                .layout(AreaBox::with_size(size.0 as isize, size.1 as isize))
+                .to_size_f32();
+            page.set_origin(origin.0, origin.1);
+        }
+        // The strip is as long as its tabs, or the page when it's longer
+        let strip = self.content_length();
+        let (w, h) = match self.position {
+            TabPosition::Top => (strip.max(page_size.0), thickness + page_size.1),
+            TabPosition::Left => (thickness + page_size.0, strip.max(page_size.1)),
+        };
+        self.layout_size = (w.min(width), h.min(height));
+        self.scroll_to_selected();
+        if let Some(tab) = self.tabs.get(self.selected) {
+            // Place the indicator at once the first time
+            let (start, length) = tab.span;
+            if self.indicator.1.target == 0. {
+                self.indicator.0.set_position(start);
+                self.indicator.1.set_position(length);
+            }
+            self.move_indicator();
+        }
+        AreaBox::with_size(self.layout_size.0 as isize, self.layout_size.1 as isize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::events::Modifiers;
+
+    struct Page;
+
+    impl Widget<Vec<String>> for Page {}
+
+    fn tabs(titles: &[&str]) -> Tabs<Vec<String>> {
+        let mut tabs = Tabs::new()
+            .with_closable(true)
+            .on_select(|log: &mut Vec<String>, i| log.push(format!("select {}", i)))
+            .on_close(|log: &mut Vec<String>, i| log.push(format!("close {}", i)))
+            .on_reorder(|log: &mut Vec<String>, from, to| {
+                log.push(format!("move {} {}", from, to))
+            });
+        for title in titles {
+            tabs.add_tab(title, Box::new(Page));
+        }
+        tabs.layout(AreaBox::with_size(800, 600));
+        tabs
+    }
+
+    fn send(
+        tabs: &mut Tabs<Vec<String>>,
+        event: WindowEvent,
+        pos: (f32, f32),
+        log: &mut Vec<String>,
+    ) {
+        // The tabs have the focus
+        let mut ctx = EventCtx::new(event, pos, Modifiers::default(), Some(0));
+        tabs.event(&mut ctx, log);
+    }
+
+    /// Middle of a tab on the strip
+    fn center(tabs: &Tabs<Vec<String>>, index: usize) -> (f32, f32) {
+        let (start, length) = tabs.tabs[index].span;
+        (start + length / 4., TAB_HEIGHT / 2.)
+    }
+
+    fn titles(tabs: &Tabs<Vec<String>>) -> Vec<&str> {
+        tabs.tabs.iter().map(|tab| tab.title.as_str()).collect()
+    }
+
+    /// A page of a fixed size
+    struct Fixed(isize, isize);
+
+    impl Widget<()> for Fixed {
+        fn layout(&mut self, _max_box: AreaBox) -> AreaBox {
+            AreaBox::with_size(self.0, self.1)
+        }
+    }
+
+    #[test]
+    fn size_is_the_strip_and_the_page() {
+        let mut tabs = Tabs::<()>::new().with_tab("One", Box::new(Fixed(300, 200)));
+        let size = tabs.layout(AreaBox::with_size(800, 600));
+        assert_eq!(size.to_size_f32(), (300., TAB_HEIGHT + 200.));
+        let size = tabs.layout(AreaBox::with_size(100, 100));
+        assert_eq!(size.to_size_f32(), (100., 100.));
+
+        let mut tabs = Tabs::<()>::new()
+            .with_position(TabPosition::Left)
+            .with_tab("One", Box::new(Fixed(300, 20)));
+        let size = tabs.layout(AreaBox::with_size(800, 600));
+        assert_eq!(size.to_size_f32(), (LEFT_STRIP_WIDTH + 300., TAB_HEIGHT));
+    }
+
+    #[test]
+    fn lazy_pages_are_built_when_selected() {
+        let built = Rc::new(Cell::new(false));
+        let flag = built.clone();
+        let mut tabs = tabs(&["One"]);
+        tabs.add_lazy_tab("Two", move || {
+            flag.set(true);
+            Box::new(Page)
+        });
+        tabs.layout(AreaBox::with_size(800, 600));
+        assert!(!built.get());
+        let mut log = Vec::new();
+        send(
+            &mut tabs,
+            WindowEvent::KeyDown(keys::RIGHT),
+            (0., 0.),
+            &mut log,
+        );
+        tabs.layout(AreaBox::with_size(800, 600));
+        assert!(built.get());
+        assert_eq!(log, ["select 1"]);
+    }
+
+    #[test]
+    fn keys_go_through_the_tabs() {
+        let mut tabs = tabs(&["One", "Two", "Three"]);
+        let mut log = Vec::new();
+        send(
+            &mut tabs,
+            WindowEvent::KeyDown(keys::END),
+            (0., 0.),
+            &mut log,
+        );
+        assert_eq!(tabs.selected(), 2);
+        // Stays at the end
+        send(
+            &mut tabs,
+            WindowEvent::KeyDown(keys::RIGHT),
+            (0., 0.),
+            &mut log,
+        );
+        send(
+            &mut tabs,
+            WindowEvent::KeyDown(keys::LEFT),
+            (0., 0.),
+            &mut log,
+        );
+        assert_eq!(tabs.selected(), 1);
+        send(
+            &mut tabs,
+            WindowEvent::KeyDown(keys::HOME),
+            (0., 0.),
+            &mut log,
+        );
+        assert_eq!(log, ["select 2", "select 1", "select 0"]);
+    }
+
+    #[test]
+    fn clicks_select_and_close() {
+        let mut tabs = tabs(&["One", "Two", "Three"]);
+        let mut log = Vec::new();
+        let pos = center(&tabs, 2);
+        send(&mut tabs, WindowEvent::MouseDown(0, 0), pos, &mut log);
+        send(&mut tabs, WindowEvent::MouseUp(0, 0), pos, &mut log);
+        assert_eq!(tabs.selected(), 2);
+        let (x, y, w, h) = Tabs::<Vec<String>>::close_rect(
+            tabs.tab_rect(tabs.tabs[0].span.0, tabs.tabs[0].span.1),
+        );
+        let pos = (x + w / 2., y + h / 2.);
+        send(&mut tabs, WindowEvent::MouseDown(0, 0), pos, &mut log);
+        send(&mut tabs, WindowEvent::MouseUp(0, 0), pos, &mut log);
+        assert_eq!(titles(&tabs), ["Two", "Three"]);
+        // Still on the same tab
+        assert_eq!(tabs.selected(), 1);
+        assert_eq!(log, ["select 2", "close 0"]);
+    }
+
+    #[test]
+    fn dragging_reorders_the_tabs() {
+        let mut tabs = tabs(&["One", "Two", "Three"]);
+        let mut log = Vec::new();
+        let from = center(&tabs, 0);
+        send(&mut tabs, WindowEvent::MouseDown(0, 0), from, &mut log);
+        let to = (tabs.content_length() - 1., from.1);
+        send(&mut tabs, WindowEvent::MouseMove(0, 0), to, &mut log);
+        send(&mut tabs, WindowEvent::MouseUp(0, 0), to, &mut log);
+        assert_eq!(titles(&tabs), ["Two", "Three", "One"]);
+        assert_eq!(tabs.selected(), 2);
+        assert_eq!(log, ["move 0 2"]);
+    }
+}
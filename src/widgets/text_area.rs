@@ -0,0 +1,720 @@
+//! 多行文本框，长行会自动折行，编辑后只重新排版改动过的段落
+
+use std::ops::Range;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use tiny_skia::Stroke;
+
+use super::scroll::{ScrollDirection, ScrollState};
+use crate::events::{keys, TimerToken, WindowEvent};
+use crate::r#box::AreaBox;
+use crate::system::enums::Cursor;
+use crate::system::{get_clipboard_text, set_clipboard_text};
+use crate::traits::Widget;
+use crate::utils::drawing::{gen_paint, gen_rect_path, gen_round_rect_path};
+use crate::utils::editor::{grapheme_offsets, hit_offset, offset_x, word_at, TextEditor};
+use crate::utils::text::{line_metrics, wrap_ranges, FontType};
+use crate::utils::theme::get_theme_color;
+use crate::utils::uid::gen_uid;
+use crate::utils::WidgetUid;
+use crate::{DrawCtx, EventCtx};
+
+const PADDING_X: f32 = 8.;
+const PADDING_Y: f32 = 6.;
+const RADIUS: f32 = 4.;
+const DEFAULT_WIDTH: f32 = 240.;
+const BACKGROUND_COLOR: u32 = 0xFFFFFFFF;
+const BORDER_COLOR: u32 = 0x00000024;
+const TEXT_COLOR: u32 = 0x202020FF;
+const PLACEHOLDER_COLOR: u32 = 0x20202070;
+/// Width of the selected line break at the end of a paragraph
+const NEWLINE_WIDTH: f32 = 4.;
+const CARET_BLINK: Duration = Duration::from_millis(530);
+const DOUBLE_CLICK: Duration = Duration::from_millis(500);
+
+type TextCallback<D> = Rc<dyn Fn(&mut D, &str)>;
+
+/// A visual line, byte positions are relative to the paragraph
+struct LineLayout {
+    range: Range<usize>,
+    offsets: Vec<(usize, f32)>,
+    /// The paragraph continues on the next line
+    wrapped: bool,
+}
+
+impl LineLayout {
+    /// Positions the caret can take on this line, the end of a wrapped line belongs to the next one
+    fn caret_offsets(&self) -> &[(usize, f32)] {
+        if self.wrapped {
+            &self.offsets[..self.offsets.len() - 1]
+        } else {
+            &self.offsets
+        }
+    }
+}
+
+struct Paragraph {
+    text: String,
+    lines: Vec<LineLayout>,
+}
+
+/// Where a paragraph starts, in visual lines and in bytes of the text
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ParagraphStart {
+    line: usize,
+    byte: usize,
+}
+
+fn layout_paragraph(text: &str, size: f32, max_width: f32) -> Paragraph {
+    let ranges = wrap_ranges(text, size, FontType::Normal, max_width);
+    let count = ranges.len();
+    let lines = ranges
+        .into_iter()
+        .enumerate()
+        .map(|(i, range)| {
+            let offsets = grapheme_offsets(&text[range.clone()], size, FontType::Normal)
+                .into_iter()
+                .map(|(pos, x)| (pos + range.start, x))
+                .collect();
+            LineLayout {
+                range,
+                offsets,
+                wrapped: i + 1 < count,
+            }
+        })
+        .collect();
+    Paragraph {
+        text: text.to_string(),
+        lines,
+    }
+}
+
+pub struct TextArea<D = ()> {
+    _uid: WidgetUid,
+    /// Wanted size, 0 means filling the width or growing with the text
+    size: (u32, u32),
+    /// Lines shown at least when growing with the text
+    rows: usize,
+    layout_size: (f32, f32),
+    editor: TextEditor,
+    placeholder: String,
+    text_size: f32,
+    paragraphs: Vec<Paragraph>,
+    /// Where every paragraph starts, kept with the paragraphs
+    starts: Vec<ParagraphStart>,
+    line_count: usize,
+    wrap_width: f32,
+    scroll: ScrollState,
+    /// Horizontal position kept while moving up and down through shorter lines
+    preferred_x: Option<f32>,
+    caret_visible: bool,
+    blink_timer: Option<TimerToken>,
+    dragging: bool,
+    last_click: Option<(Instant, usize)>,
+    change_callback: Option<TextCallback<D>>,
+}
+
+impl<D> Default for TextArea<D> {
+    fn default() -> Self {
+        let mut scroll = ScrollState::new(ScrollDirection::Vertical);
+        scroll.set_drag_scroll(false);
+        Self {
+            _uid: gen_uid(),
+            size: (0, 0),
+            rows: 3,
+            layout_size: (0., 0.),
+            editor: TextEditor::new(true),
+            placeholder: String::new(),
+            text_size: 14.,
+            paragraphs: Vec::new(),
+            starts: Vec::new(),
+            line_count: 0,
+            wrap_width: -1.,
+            scroll,
+            preferred_x: None,
+            caret_visible: true,
+            blink_timer: None,
+            dragging: false,
+            last_click: None,
+            change_callback: None,
+        }
+    }
+}
+
+impl<D> TextArea<D> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_size(mut self, w: u32, h: u32) -> Self {
+        self.size = (w, h);
+        self
+    }
+
+    /// Minimum number of visible lines when the height follows the text.
+    pub fn with_rows(mut self, v: usize) -> Self {
+        self.rows = v.max(1);
+        self
+    }
+
+    pub fn with_value(mut self, v: &str) -> Self {
+        self.set_value(v);
+        self
+    }
+
+    pub fn with_placeholder(mut self, v: &str) -> Self {
+        self.placeholder = v.into();
+        self
+    }
+
+    pub fn with_text_size(mut self, v: f32) -> Self {
+        self.text_size = v;
+        self.wrap_width = -1.;
+        self
+    }
+
+    pub fn with_max_length(mut self, v: usize) -> Self {
+        self.editor.set_max_length(Some(v));
+        self
+    }
+
+    /// Called with the new text after every edit made by the user.
+    pub fn on_change<F: Fn(&mut D, &str) + 'static>(mut self, v: F) -> Self {
+        self.change_callback = Some(Rc::new(v));
+        self
+    }
+
+    /// Replace the text without calling `on_change`, the undo history is cleared.
+    pub fn set_value(&mut self, value: &str) {
+        self.editor.set_text(value);
+        self.relayout(false);
+    }
+
+    #[inline]
+    pub fn value(&self) -> &str {
+        self.editor.text()
+    }
+
+    #[inline]
+    pub fn set_placeholder(&mut self, placeholder: &str) {
+        self.placeholder = placeholder.into();
+    }
+
+    #[inline]
+    pub fn set_max_length(&mut self, max_length: Option<usize>) {
+        self.editor.set_max_length(max_length);
+    }
+
+    fn line_height(&self) -> f32 {
+        line_metrics(self.text_size, FontType::Normal).height.ceil()
+    }
+
+    /// Wrap the paragraphs again, only the changed ones unless `full` is set.
+    fn relayout(&mut self, full: bool) {
+        let old = std::mem::take(&mut self.paragraphs);
+        let texts: Vec<&str> = self.editor.text().split('\n').collect();
+        let (mut prefix, mut suffix) = (0, 0);
+        if !full {
+            while prefix < old.len().min(texts.len()) && old[prefix].text == texts[prefix] {
+                prefix += 1;
+            }
+            while suffix < (old.len() - prefix).min(texts.len() - prefix)
+                && old[old.len() - 1 - suffix].text == texts[texts.len() - 1 - suffix]
+            {
+                suffix += 1;
+            }
+        }
+        let suffix_start = old.len() - suffix;
+        let mut old = old.into_iter();
+        let mut paragraphs: Vec<Paragraph> = old.by_ref().take(prefix).collect();
+        for text in &texts[prefix..texts.len() - suffix] {
+            paragraphs.push(layout_paragraph(text, self.text_size, self.wrap_width));
+        }
+        paragraphs.extend(old.skip(suffix_start - prefix));
+        self.paragraphs = paragraphs;
+        // The paragraphs before the first changed one start at the same place
+        self.starts.truncate(prefix);
+        let mut next = match (self.starts.last(), prefix.checked_sub(1)) {
+            (Some(start), Some(last)) => ParagraphStart {
+                line: start.line + self.paragraphs[last].lines.len(),
+                byte: start.byte + self.paragraphs[last].text.len() + 1,
+            },
+            _ => ParagraphStart { line: 0, byte: 0 },
+        };
+        for paragraph in &self.paragraphs[self.starts.len()..] {
+            self.starts.push(next);
+            next.line += paragraph.lines.len();
+            next.byte += paragraph.text.len() + 1;
+        }
+        self.line_count = next.line;
+    }
+
+    /// The visual line at `index` with its paragraph and the position where the paragraph starts.
+    fn line(&self, index: usize) -> Option<(usize, &Paragraph, &LineLayout)> {
+        let i = self
+            .starts
+            .partition_point(|start| start.line <= index)
+            .checked_sub(1)?;
+        let (start, paragraph) = (self.starts[i], &self.paragraphs[i]);
+        let line = paragraph.lines.get(index - start.line)?;
+        Some((start.byte, paragraph, line))
+    }
+
+    /// Index of the visual line showing the caret at `pos`.
+    fn locate(&self, pos: usize) -> usize {
+        let i = match self.starts.partition_point(|start| start.byte <= pos) {
+            0 => return 0,
+            i => i - 1,
+        };
+        let (start, paragraph) = (self.starts[i], &self.paragraphs[i]);
+        let line = paragraph
+            .lines
+            .iter()
+            .rposition(|line| start.byte + line.range.start <= pos)
+            .unwrap_or(0);
+        start.line + line
+    }
+
+    fn content_height(&self) -> f32 {
+        self.line_count as f32 * self.line_height() + PADDING_Y * 2.
+    }
+
+    /// Position of the caret in the content, from the top left corner of the text.
+    fn caret_pos(&self) -> (f32, f32) {
+        let caret = self.editor.caret();
+        let index = self.locate(caret);
+        match self.line(index) {
+            Some((start, _, line)) => (
+                offset_x(&line.offsets, caret - start),
+                index as f32 * self.line_height(),
+            ),
+            None => (0., 0.),
+        }
+    }
+
+    /// The position on a visual line nearest to `x`.
+    fn hit_line(&self, index: usize, x: f32) -> usize {
+        match self.line(index.min(self.line_count.saturating_sub(1))) {
+            Some((start, _, line)) => start + hit_offset(line.caret_offsets(), x),
+            None => 0,
+        }
+    }
+
+    /// Byte position under the mouse, `x` and `y` are relative to the widget.
+    fn hit_test(&mut self, x: f32, y: f32) -> usize {
+        let (_, offset_y) = self.scroll.offset();
+        let row = ((y - PADDING_Y + offset_y) / self.line_height())
+            .floor()
+            .max(0.);
+        self.hit_line(row as usize, x - PADDING_X)
+    }
+
+    fn scroll_to_caret(&mut self) {
+        let (x, y) = self.caret_pos();
+        let line_height = self.line_height();
+        self.scroll
+            .scroll_into_view(x, y, 1. + PADDING_X, line_height + PADDING_Y * 2.);
+    }
+
+    /// Move the caret by some visual lines, keeping its horizontal position.
+    fn move_lines(&mut self, delta: isize, extend: bool) {
+        let index = self.locate(self.editor.caret());
+        let count = self.line_count as isize;
+        let x = match self.preferred_x {
+            Some(x) => x,
+            None => self.caret_pos().0,
+        };
+        let target = index as isize + delta;
+        let pos = if target < 0 {
+            0
+        } else if target >= count {
+            self.editor.text().len()
+        } else {
+            self.hit_line(target as usize, x)
+        };
+        self.editor.set_caret(pos, extend);
+        self.preferred_x = Some(x);
+    }
+
+    /// Start or end of the visual line showing the caret.
+    fn line_edge(&self, end: bool) -> usize {
+        match self.line(self.locate(self.editor.caret())) {
+            Some((start, _, line)) if end => start + line.caret_offsets().last().map_or(0, |o| o.0),
+            Some((start, _, line)) => start + line.range.start,
+            None => 0,
+        }
+    }
+
+    fn restart_blink(&mut self, ctx: &mut EventCtx) {
+        self.caret_visible = true;
+        self.blink_timer = Some(ctx.request_timer(CARET_BLINK));
+    }
+
+    fn changed(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        let height = self.content_height();
+        self.relayout(false);
+        if self.content_height() != height && self.size.1 == 0 {
+            ctx.request_layout();
+        }
+        self.scroll.set_sizes(
+            self.layout_size,
+            (self.layout_size.0, self.content_height()),
+        );
+        if let Some(callback) = &self.change_callback {
+            callback(data, self.editor.text());
+        }
+        ctx.request_paint();
+    }
+
+    fn handle_key(&mut self, ctx: &mut EventCtx, data: &mut D, key: usize) {
+        let modifiers = ctx.modifiers();
+        let (ctrl, shift) = (modifiers.ctrl, modifiers.shift);
+        let page = ((self.layout_size.1 - PADDING_Y * 2.) / self.line_height()).max(1.) as isize;
+        let mut edited = false;
+        let mut vertical = false;
+        match key {
+            keys::LEFT => self.editor.move_left(ctrl, shift),
+            keys::RIGHT => self.editor.move_right(ctrl, shift),
+            keys::UP => {
+                self.move_lines(-1, shift);
+                vertical = true;
+            }
+            keys::DOWN => {
+                self.move_lines(1, shift);
+                vertical = true;
+            }
+            keys::PRIOR => {
+                self.move_lines(-page, shift);
+                vertical = true;
+            }
+            keys::NEXT => {
+                self.move_lines(page, shift);
+                vertical = true;
+            }
+            keys::HOME if ctrl => self.editor.set_caret(0, shift),
+            keys::END if ctrl => self.editor.set_caret(self.editor.text().len(), shift),
+            keys::HOME => self.editor.set_caret(self.line_edge(false), shift),
+            keys::END => self.editor.set_caret(self.line_edge(true), shift),
+            keys::BACK => edited = self.editor.backspace(ctrl),
+            keys::DELETE => edited = self.editor.delete(ctrl),
+            keys::RETURN => edited = self.editor.insert("\n"),
+            keys::A if ctrl => self.editor.select_all(),
+            keys::C if ctrl => {
+                if self.editor.has_selection() {
+                    set_clipboard_text(self.editor.selected_text());
+                }
+            }
+            keys::X if ctrl => {
+                if let Some(text) = self.editor.cut() {
+                    set_clipboard_text(&text);
+                    edited = true;
+                }
+            }
+            keys::V if ctrl => {
+                if let Some(text) = get_clipboard_text() {
+                    edited = self.editor.insert(&text);
+                }
+            }
+            keys::Z if ctrl && shift => edited = self.editor.redo(),
+            keys::Z if ctrl => edited = self.editor.undo(),
+            keys::Y if ctrl => edited = self.editor.redo(),
+            _ => return,
+        }
+        if !vertical {
+            self.preferred_x = None;
+        }
+        ctx.set_handled();
+        if edited {
+            self.changed(ctx, data);
+        }
+        self.scroll_to_caret();
+        self.restart_blink(ctx);
+        ctx.request_anim_frame();
+    }
+}
+
+impl<D> Clone for TextArea<D> {
+    fn clone(&self) -> Self {
+        let mut area = Self {
+            _uid: gen_uid(),
+            size: self.size,
+            rows: self.rows,
+            editor: self.editor.clone(),
+            placeholder: self.placeholder.clone(),
+            text_size: self.text_size,
+            change_callback: self.change_callback.clone(),
+            ..Default::default()
+        };
+        area.relayout(true);
+        area
+    }
+}
+
+impl<D> Widget<D> for TextArea<D> {
+    fn event(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        if self.scroll.bar_event(ctx) {
+            return;
+        }
+        let hovering = ctx.is_hovering();
+        let (x, y) = ctx.mouse_pos();
+        match ctx.event() {
+            WindowEvent::CollectFocus => ctx.register_for_focus(),
+            WindowEvent::MouseDown(_, _) => {
+                let pos = self.hit_test(x, y);
+                let now = Instant::now();
+                let double = match self.last_click {
+                    Some((time, last)) => last == pos && now - time < DOUBLE_CLICK,
+                    None => false,
+                };
+                if double {
+                    self.editor.select(word_at(self.editor.text(), pos));
+                    self.last_click = None;
+                } else {
+                    self.editor.set_caret(pos, ctx.modifiers().shift);
+                    self.last_click = Some((now, pos));
+                    self.dragging = true;
+                    ctx.set_active(true);
+                }
+                self.preferred_x = None;
+                ctx.request_focus();
+                ctx.set_handled();
+                self.restart_blink(ctx);
+            }
+            WindowEvent::MouseMove(_, _) if self.dragging => {
+                let pos = self.hit_test(x, y);
+                if pos != self.editor.caret() {
+                    self.editor.set_caret(pos, true);
+                    self.scroll_to_caret();
+                    self.restart_blink(ctx);
+                    ctx.request_anim_frame();
+                }
+            }
+            WindowEvent::MouseUp(_, _) if self.dragging => {
+                self.dragging = false;
+                ctx.set_active(false);
+            }
+            WindowEvent::KeyDown(key) if ctx.is_focused() => self.handle_key(ctx, data, key),
+            WindowEvent::CharInput(_, c) if ctx.is_focused() => {
+                let modifiers = ctx.modifiers();
+                if !c.is_control() && (!modifiers.ctrl || modifiers.alt) {
+                    let mut buf = [0; 4];
+                    if self.editor.insert(c.encode_utf8(&mut buf)) {
+                        self.changed(ctx, data);
+                    }
+                    self.preferred_x = None;
+                    ctx.set_handled();
+                    self.scroll_to_caret();
+                    self.restart_blink(ctx);
+                    ctx.request_anim_frame();
+                }
+            }
+            WindowEvent::Timer(token) if self.blink_timer == Some(token) => {
+                if ctx.is_focused() {
+                    self.caret_visible = !self.caret_visible;
+                    self.blink_timer = Some(ctx.request_timer(CARET_BLINK));
+                } else {
+                    self.blink_timer = None;
+                }
+                ctx.request_paint();
+            }
+            _ => {}
+        }
+        self.scroll.event(ctx);
+        if hovering || self.dragging {
+            ctx.set_cursor(Cursor::IBeam);
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, _data: &D) {
+        let (width, height) = self.layout_size;
+        let focused = ctx.is_focused();
+        let path = gen_round_rect_path(0.5, 0.5, width - 1., height - 1., RADIUS);
+        ctx.fill_path(&path, &gen_paint(BACKGROUND_COLOR));
+        let border = if focused {
+            get_theme_color() | 0xFF
+        } else {
+            BORDER_COLOR
+        };
+        ctx.stroke_path(&path, &gen_paint(border), &Stroke::default());
+
+        let (_, offset_y) = self.scroll.offset();
+        let line_height = self.line_height();
+        let (caret_x, caret_y) = self.caret_pos();
+        let selection = self.editor.selection();
+        let selection_color = (get_theme_color() & 0xFFFFFF00) | 0x60;
+        let (size, caret_visible) = (self.text_size, self.caret_visible);
+        // Only the lines inside the viewport are drawn
+        let first = (offset_y / line_height).floor().max(0.) as usize;
+        let last = (first + (height / line_height).ceil() as usize + 1).min(self.line_count);
+        let lines: Vec<_> = (first..last)
+            .filter_map(|index| self.line(index).map(|line| (index, line)))
+            .collect();
+        let empty = self.editor.text().is_empty();
+        let placeholder = &self.placeholder;
+        ctx.with_save(|ctx| {
+            ctx.clip_rect(1., 1., width - 2., height - 2.);
+            ctx.translate(PADDING_X, PADDING_Y - offset_y);
+            if empty {
+                ctx.draw_text(0., 0., placeholder, size, PLACEHOLDER_COLOR);
+            }
+            for &(index, (start, paragraph, line)) in &lines {
+                let y = index as f32 * line_height;
+                let (line_start, line_end) = (start + line.range.start, start + line.range.end);
+                if focused && selection.start <= line_end && selection.end > line_start {
+                    let from = offset_x(&line.offsets, selection.start.max(line_start) - start);
+                    let mut to = offset_x(&line.offsets, selection.end.min(line_end) - start);
+                    if !line.wrapped && selection.end > line_end {
+                        to += NEWLINE_WIDTH;
+                    }
+                    ctx.fill_path(
+                        &gen_rect_path(from, y, to - from, line_height),
+                        &gen_paint(selection_color),
+                    );
+                }
+                let text = &paragraph.text[line.range.clone()];
+                ctx.draw_text(0., y, text.trim_end(), size, TEXT_COLOR);
+            }
+            if focused && caret_visible {
+                ctx.fill_path(
+                    &gen_rect_path(caret_x.round(), caret_y, 1., line_height),
+                    &gen_paint(TEXT_COLOR),
+                );
+            }
+        });
+        self.scroll.draw_bars(ctx);
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let width = if self.size.0 > 0 {
+            self.size.0 as f32
+        } else if max_box.width() == isize::MAX {
+            DEFAULT_WIDTH
+        } else {
+            max_box.width() as f32
+        };
+        let width = width.min(max_box.width() as f32);
+        let wrap_width = (width - PADDING_X * 2.).max(1.);
+        if wrap_width != self.wrap_width {
+            self.wrap_width = wrap_width;
+            self.relayout(true);
+        }
+        let content_height = self.content_height();
+        let height = if self.size.1 > 0 {
+            self.size.1 as f32
+        } else {
+            content_height.max(self.rows as f32 * self.line_height() + PADDING_Y * 2.)
+        };
+        self.layout_size = (width, height.min(max_box.height() as f32));
+        self.scroll
+            .set_sizes(self.layout_size, (width, content_height));
+        AreaBox::with_size(self.layout_size.0 as isize, self.layout_size.1 as isize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "first paragraph\n\nthe third one is long enough to wrap a few times\nlast";
+
+    fn text_area(text: &str) -> TextArea {
+        let mut area = TextArea::new().with_value(text);
+        area.wrap_width = 80.;
+        area.relayout(true);
+        area
+    }
+
+    /// Paragraph texts with their line ranges, where they start and the line count.
+    type Layout = (Vec<(String, Vec<Range<usize>>)>, Vec<ParagraphStart>, usize);
+
+    fn layout(area: &TextArea) -> Layout {
+        let paragraphs = area
+            .paragraphs
+            .iter()
+            .map(|p| {
+                (
+                    p.text.clone(),
+                    p.lines.iter().map(|l| l.range.clone()).collect(),
+                )
+            })
+            .collect();
+        (paragraphs, area.starts.clone(), area.line_count)
+    }
+
+    fn assert_fresh(area: &TextArea) {
+        assert_eq!(layout(area), layout(&text_area(area.value())));
+    }
+
+    #[test]
+    fn edits_match_a_full_relayout() {
+        let mut area = text_area(TEXT);
+        assert!(area.line_count > 4);
+        let third = TEXT.find("the").unwrap();
+        area.editor.set_caret(third + 3, false);
+        area.editor.insert(" quick and");
+        area.relayout(false);
+        assert_fresh(&area);
+        // Split a paragraph
+        area.editor.insert("\n");
+        area.relayout(false);
+        assert_fresh(&area);
+        // Join it back with the one before
+        area.editor.set_caret(third, false);
+        area.editor.backspace(false);
+        area.relayout(false);
+        assert_fresh(&area);
+        area.editor.set_caret(area.value().len(), false);
+        area.editor.insert("\n\n");
+        area.relayout(false);
+        assert_fresh(&area);
+        area.editor.select_all();
+        area.editor.backspace(false);
+        area.relayout(false);
+        assert_fresh(&area);
+        assert_eq!(area.line_count, 1);
+    }
+
+    #[test]
+    fn unchanged_paragraphs_are_kept() {
+        let mut area = text_area(TEXT);
+        let lines: Vec<_> = area.paragraphs.iter().map(|p| p.lines.as_ptr()).collect();
+        area.editor.set_caret(TEXT.find("the").unwrap(), false);
+        area.editor.insert("x");
+        area.relayout(false);
+        let kept: Vec<_> = area.paragraphs.iter().map(|p| p.lines.as_ptr()).collect();
+        assert_eq!(kept[..2], lines[..2]);
+        assert_ne!(kept[2], lines[2]);
+        assert_eq!(kept[3], lines[3]);
+    }
+
+    #[test]
+    fn lines_and_positions_agree() {
+        let area = text_area(TEXT);
+        for index in 0..area.line_count {
+            let (start, paragraph, line) = area.line(index).unwrap();
+            assert_eq!(
+                &area.value()[start..start + paragraph.text.len()],
+                paragraph.text
+            );
+            assert_eq!(area.locate(start + line.range.start), index);
+        }
+        assert!(area.line(area.line_count).is_none());
+        assert_eq!(area.locate(area.value().len()), area.line_count - 1);
+    }
+
+    #[test]
+    fn vertical_moves_keep_the_column() {
+        let mut area = text_area("abcdef\nab\nabcdef");
+        area.editor.set_caret(5, false);
+        area.move_lines(1, false);
+        assert_eq!(area.editor.caret(), 9);
+        area.move_lines(1, false);
+        assert_eq!(area.editor.caret(), 15);
+        area.move_lines(1, true);
+        assert_eq!(area.editor.caret(), area.value().len());
+        assert_eq!(area.editor.anchor(), 15);
+        area.move_lines(-5, false);
+        assert_eq!(area.editor.caret(), 0);
+    }
+}
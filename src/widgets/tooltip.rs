@@ -0,0 +1,450 @@
+//! 提示气泡：鼠标在控件上停留一会儿后显示，可以跟随指针或者贴着控件，渐入渐出；按下鼠标或滚动时隐藏
+//!
+//! 任何控件都可以通过 `WidgetExt::tooltip` 加上提示，`WidgetExt` 在 prelude 中导出
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use tiny_skia::{Pixmap, PixmapPaint};
+
+use crate::core::WidgetPod;
+use crate::events::{PopupId, TimerToken, WindowEvent};
+use crate::popup::{Placement, Popup};
+use crate::r#box::AreaBox;
+use crate::traits::Widget;
+use crate::utils::drawing::{gen_paint, gen_round_rect_path};
+use crate::utils::spring::Spring;
+use crate::utils::text::{line_metrics, wrap_text, FontType, TextLine};
+use crate::utils::uid::gen_uid;
+use crate::utils::WidgetUid;
+use crate::{DrawCtx, EventCtx, UpdateCtx};
+
+const TEXT_SIZE: f32 = 13.;
+const PADDING_X: f32 = 8.;
+const PADDING_Y: f32 = 5.;
+const RADIUS: f32 = 4.;
+/// Longer text is wrapped
+const MAX_WIDTH: f32 = 320.;
+const BACKGROUND_COLOR: u32 = 0x2B2B2BF0;
+const TEXT_COLOR: u32 = 0xFFFFFFFF;
+const DEFAULT_DELAY: Duration = Duration::from_millis(600);
+/// Time to fade out before the popup is closed
+const FADE_OUT: Duration = Duration::from_millis(150);
+/// Height of the pointer, a following tooltip goes below it
+const POINTER_HEIGHT: f32 = 20.;
+const GAP: f32 = 6.;
+
+/// What a tooltip shows, made from a string or a boxed widget.
+pub enum TooltipContent<D> {
+    Text(String),
+    Widget(Box<dyn Widget<D>>),
+}
+
+impl<D> From<&str> for TooltipContent<D> {
+    fn from(v: &str) -> Self {
+        TooltipContent::Text(v.into())
+    }
+}
+
+impl<D> From<String> for TooltipContent<D> {
+    fn from(v: String) -> Self {
+        TooltipContent::Text(v)
+    }
+}
+
+impl<D> From<Box<dyn Widget<D>>> for TooltipContent<D> {
+    fn from(v: Box<dyn Widget<D>>) -> Self {
+        TooltipContent::Widget(v)
+    }
+}
+
+enum Content<D> {
+    Text(String, Vec<TextLine>),
+    Widget(WidgetPod<D>),
+}
+
+/// State shared by the tooltip and its bubble in the popup
+struct TooltipState<D> {
+    content: Content<D>,
+    /// Goes to 1 while showing and to 0 while hiding
+    opacity: Spring,
+}
+
+type SharedState<D> = Rc<RefCell<TooltipState<D>>>;
+
+/// The bubble drawn in the popup
+struct Bubble<D> {
+    state: SharedState<D>,
+    layout_size: (f32, f32),
+}
+
+impl<D> Bubble<D> {
+    fn draw_bubble(&mut self, ctx: &mut DrawCtx, data: &D) {
+        let (width, height) = self.layout_size;
+        let path = gen_round_rect_path(0., 0., width, height, RADIUS);
+        ctx.fill_path(&path, &gen_paint(BACKGROUND_COLOR));
+        match &mut self.state.borrow_mut().content {
+            Content::Text(_, lines) => {
+                let line_height = line_metrics(TEXT_SIZE, FontType::Normal).height;
+                for (i, line) in lines.iter().enumerate() {
+                    let y = PADDING_Y + i as f32 * line_height;
+                    ctx.draw_text(PADDING_X, y, &line.text, TEXT_SIZE, TEXT_COLOR);
+                }
+            }
+            Content::Widget(widget) => widget.draw(ctx, data),
+        }
+    }
+}
+
+impl<D> Widget<D> for Bubble<D> {
+    fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        if let Content::Widget(widget) = &mut self.state.borrow_mut().content {
+            widget.update(ctx, data);
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, data: &D) {
+        let opacity = {
+            let mut state = self.state.borrow_mut();
+            if !state.opacity.arrived() {
+                ctx.request_anim_frame();
+            }
+            state.opacity.position().clamp(0., 1.)
+        };
+        if opacity >= 1. {
+            self.draw_bubble(ctx, data);
+            return;
+        }
+        // Fading draws the bubble on a layer first, so overlapping parts are not blended twice
+        let (width, height) = self.layout_size;
+        let mut layer = match Pixmap::new(width.ceil() as u32, height.ceil() as u32) {
+            Some(pixmap) => pixmap,
+            None => return,
+        };
+        {
+            let mut pixmap = layer.as_mut();
+            let mut layer_ctx = DrawCtx::new(&mut pixmap);
+            layer_ctx.widget_size = self.layout_size;
+            self.draw_bubble(&mut layer_ctx, data);
+            ctx.adopt_requests(&mut layer_ctx);
+        }
+        let paint = PixmapPaint {
+            opacity,
+            ..Default::default()
+        };
+        ctx.draw_pixmap(0, 0, layer.as_ref(), &paint);
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let max_width = MAX_WIDTH.min(max_box.width() as f32) - PADDING_X * 2.;
+        let (width, height) = match &mut self.state.borrow_mut().content {
+            Content::Text(text, lines) => {
+                *lines = wrap_text(text, TEXT_SIZE, FontType::Normal, max_width);
+                let line_height = line_metrics(TEXT_SIZE, FontType::Normal).height;
+                let width = lines.iter().map(|l| l.width).fold(0., f32::max);
+                (width.ceil(), (lines.len() as f32 * line_height).ceil())
+            }
+            Content::Widget(widget) => {
+                let size = widget.layout(AreaBox::with_size(max_width as isize, max_box.height()));
+                widget.set_origin(PADDING_X, PADDING_Y);
+                size.to_size_f32()
+            }
+        };
+        self.layout_size = (width + PADDING_X * 2., height + PADDING_Y * 2.);
+        AreaBox::with_size(self.layout_size.0 as isize, self.layout_size.1 as isize)
+    }
+}
+
+/// Helpers available on every widget
+pub trait WidgetExt<D: 'static>: Widget<D> + Sized + 'static {
+    /// Show a text or a widget when the pointer rests on the widget.
+    fn tooltip(self, content: impl Into<TooltipContent<D>>) -> Tooltip<D> {
+        Tooltip::new(Box::new(self), content)
+    }
+}
+
+impl<D: 'static, W: Widget<D> + 'static> WidgetExt<D> for W {}
+
+/// Wraps a widget and shows a tooltip when the pointer rests on it, made by `WidgetExt::tooltip`.
+pub struct Tooltip<D> {
+    _uid: WidgetUid,
+    inner: WidgetPod<D>,
+    state: SharedState<D>,
+    delay: Duration,
+    follow_pointer: bool,
+    placement: Placement,
+    hovering: bool,
+    /// Pressing or scrolling hides the tooltip until the pointer leaves
+    suppressed: bool,
+    show_timer: Option<TimerToken>,
+    /// Closes the popup once faded out
+    close_timer: Option<TimerToken>,
+    popup: Option<PopupId>,
+    /// Set when the text changes while shown, the next update measures the bubble again
+    need_layout: bool,
+}
+
+impl<D: 'static> Tooltip<D> {
+    pub fn new(inner: Box<dyn Widget<D>>, content: impl Into<TooltipContent<D>>) -> Self {
+        let content = match content.into() {
+            TooltipContent::Text(text) => Content::Text(text, Vec::new()),
+            TooltipContent::Widget(widget) => Content::Widget(widget.into()),
+        };
+        let mut opacity = Spring::new(0.);
+        opacity.speed = 16.;
+        opacity.damper = 1.;
+        Self {
+            _uid: gen_uid(),
+            inner: inner.into(),
+            state: Rc::new(RefCell::new(TooltipState { content, opacity })),
+            delay: DEFAULT_DELAY,
+            follow_pointer: false,
+            placement: Placement::Bottom,
+            hovering: false,
+            suppressed: false,
+            show_timer: None,
+            close_timer: None,
+            popup: None,
+            need_layout: false,
+        }
+    }
+
+    /// Time the pointer rests on the widget before the tooltip shows.
+    pub fn with_delay(mut self, v: Duration) -> Self {
+        self.delay = v;
+        self
+    }
+
+    /// Show the tooltip under the pointer and move it with the pointer, instead of next to the widget.
+    pub fn with_follow_pointer(mut self, v: bool) -> Self {
+        self.follow_pointer = v;
+        self
+    }
+
+    /// Side of the widget the tooltip prefers, below by default. Unused when following the pointer.
+    pub fn with_placement(mut self, v: Placement) -> Self {
+        self.placement = v;
+        self
+    }
+
+    /// Change the text of the tooltip, a shown one is resized on the next update.
+    pub fn set_text(&mut self, text: &str) {
+        self.state.borrow_mut().content = Content::Text(text.into(), Vec::new());
+        self.need_layout = self.popup.is_some();
+    }
+
+    #[inline]
+    pub fn is_shown(&self) -> bool {
+        self.popup.is_some() && self.close_timer.is_none()
+    }
+
+    fn show(&mut self, ctx: &mut EventCtx) {
+        self.state.borrow_mut().opacity.set_target(1.);
+        ctx.request_anim_frame();
+        if self.popup.is_some() {
+            // Fading out, come back
+            self.close_timer = None;
+            return;
+        }
+        self.state.borrow_mut().opacity.set_position(0.);
+        let bubble = Bubble {
+            state: self.state.clone(),
+            layout_size: (0., 0.),
+        };
+        let mut popup = Popup::new(Box::new(bubble))
+            .with_interactive(false)
+            .with_frame(false);
+        popup = if self.follow_pointer {
+            let (x, y) = ctx.mouse_pos();
+            popup.with_anchor(x, y, 0., POINTER_HEIGHT).with_gap(2.)
+        } else {
+            popup.with_placement(self.placement).with_gap(GAP)
+        };
+        self.popup = Some(ctx.open_popup(popup));
+    }
+
+    fn hide(&mut self, ctx: &mut EventCtx) {
+        self.show_timer = None;
+        if self.popup.is_some() && self.close_timer.is_none() {
+            self.state.borrow_mut().opacity.set_target(0.);
+            self.close_timer = Some(ctx.request_timer(FADE_OUT));
+            ctx.request_anim_frame();
+        }
+    }
+}
+
+impl<D: 'static> Widget<D> for Tooltip<D> {
+    fn event(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        self.inner.event(ctx, data);
+        let hovering = ctx.is_hovering();
+        match ctx.event() {
+            WindowEvent::PopupClosed(id) if self.popup == Some(id) => {
+                self.popup = None;
+                self.close_timer = None;
+            }
+            WindowEvent::Timer(token) if self.show_timer == Some(token) => {
+                self.show_timer = None;
+                if self.hovering && !self.suppressed {
+                    self.show(ctx);
+                }
+            }
+            WindowEvent::Timer(token) if self.close_timer == Some(token) => {
+                if let Some(id) = self.popup {
+                    ctx.close_popup(id);
+                }
+            }
+            WindowEvent::MouseMove(_, _) => {
+                if hovering != self.hovering {
+                    self.hovering = hovering;
+                    if hovering {
+                        if !self.suppressed {
+                            self.show_timer = Some(ctx.request_timer(self.delay));
+                        }
+                    } else {
+                        self.suppressed = false;
+                        self.hide(ctx);
+                    }
+                } else if let (true, true, Some(id)) = (hovering, self.follow_pointer, self.popup) {
+                    let (x, y) = ctx.mouse_pos();
+                    ctx.move_popup(id, x, y, 0., POINTER_HEIGHT);
+                }
+            }
+            WindowEvent::MouseDown(_, _)
+            | WindowEvent::ContextMenu(_, _)
+            | WindowEvent::MouseWheel(_)
+            | WindowEvent::MouseHWheel(_)
+            | WindowEvent::KeyDown(_) => {
+                if hovering {
+                    self.suppressed = true;
+                }
+                self.hide(ctx);
+            }
+            WindowEvent::FocusLost => self.hide(ctx),
+            _ => {}
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        self.inner.update(ctx, data);
+        if self.need_layout {
+            self.need_layout = false;
+            ctx.request_layout();
+            ctx.request_paint();
+        }
+        // The bubble updates its widget while it's shown
+        if self.popup.is_none() {
+            if let Content::Widget(widget) = &mut self.state.borrow_mut().content {
+                widget.update(ctx, data);
+            }
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, data: &D) {
+        self.inner.draw(ctx, data);
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let size = self.inner.layout(max_box);
+        self.inner.set_origin(0., 0.);
+        size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Modifiers;
+
+    struct Block;
+
+    impl Widget<()> for Block {}
+
+    const INSIDE: (f32, f32) = (5., 5.);
+    const OUTSIDE: (f32, f32) = (-5., 5.);
+
+    fn send(tooltip: &mut Tooltip<()>, event: WindowEvent, pos: (f32, f32)) -> EventCtx {
+        let mut ctx = EventCtx::new(event, pos, Modifiers::default(), None);
+        tooltip.event(&mut ctx, &mut ());
+        ctx
+    }
+
+    /// Rest on the widget until the tooltip shows
+    fn hover(tooltip: &mut Tooltip<()>) -> EventCtx {
+        let ctx = send(tooltip, WindowEvent::MouseMove(5, 5), INSIDE);
+        let (_, token) = ctx.timers[0];
+        send(tooltip, WindowEvent::Timer(token), INSIDE)
+    }
+
+    #[test]
+    fn shows_after_resting_on_the_widget() {
+        let mut tooltip = Tooltip::new(Box::new(Block), "tip");
+        let ctx = send(&mut tooltip, WindowEvent::MouseMove(5, 5), INSIDE);
+        assert_eq!(ctx.timers.len(), 1);
+        assert!(!tooltip.is_shown());
+        let (_, token) = ctx.timers[0];
+        let ctx = send(&mut tooltip, WindowEvent::Timer(token), INSIDE);
+        assert_eq!(ctx.open_popups.len(), 1);
+        assert!(tooltip.is_shown());
+    }
+
+    #[test]
+    fn leaving_fades_out_before_closing() {
+        let mut tooltip = Tooltip::new(Box::new(Block), "tip");
+        hover(&mut tooltip);
+        let id = tooltip.popup.unwrap();
+        let ctx = send(&mut tooltip, WindowEvent::MouseMove(-5, 5), OUTSIDE);
+        assert!(!tooltip.is_shown());
+        assert!(ctx.close_popups.is_empty());
+        let token = tooltip.close_timer.unwrap();
+        let ctx = send(&mut tooltip, WindowEvent::Timer(token), OUTSIDE);
+        assert_eq!(ctx.close_popups, [id]);
+        send(&mut tooltip, WindowEvent::PopupClosed(id), OUTSIDE);
+        assert_eq!(tooltip.popup, None);
+    }
+
+    #[test]
+    fn pressing_hides_it_until_the_pointer_leaves() {
+        let mut tooltip = Tooltip::new(Box::new(Block), "tip");
+        hover(&mut tooltip);
+        send(&mut tooltip, WindowEvent::MouseDown(5, 5), INSIDE);
+        assert!(tooltip.suppressed && !tooltip.is_shown());
+        // The delay ending does not bring it back
+        let ctx = send(&mut tooltip, WindowEvent::MouseMove(6, 5), INSIDE);
+        assert!(ctx.timers.is_empty());
+        send(&mut tooltip, WindowEvent::MouseMove(-5, 5), OUTSIDE);
+        assert!(!tooltip.suppressed);
+        let ctx = send(&mut tooltip, WindowEvent::MouseMove(5, 5), INSIDE);
+        assert_eq!(ctx.timers.len(), 1);
+    }
+
+    #[test]
+    fn new_text_resizes_a_shown_tooltip() {
+        let mut tooltip = Tooltip::new(Box::new(Block), "tip");
+        tooltip.set_text("hidden");
+        let mut ctx = UpdateCtx::new();
+        tooltip.update(&mut ctx, &());
+        assert!(!ctx.request_layout);
+        hover(&mut tooltip);
+        tooltip.set_text("a longer tip");
+        let mut ctx = UpdateCtx::new();
+        tooltip.update(&mut ctx, &());
+        assert!(ctx.request_layout && ctx.request_paint);
+    }
+
+    #[test]
+    fn text_wraps_in_the_bubble() {
+        let state = Rc::new(RefCell::new(TooltipState {
+            content: Content::Text("a long tip ".repeat(20), Vec::new()),
+            opacity: Spring::new(1.),
+        }));
+        let mut bubble = Bubble {
+            state,
+            layout_size: (0., 0.),
+        };
+        let size = Widget::<()>::layout(&mut bubble, AreaBox::with_size(1000, 1000));
+        assert!(size.width() as f32 <= MAX_WIDTH);
+        let line_height = line_metrics(TEXT_SIZE, FontType::Normal).height;
+        assert!(size.height() as f32 > line_height * 2.);
+    }
+}
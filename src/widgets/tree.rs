@@ -0,0 +1,746 @@
+//! 树形视图：展开时才加载子节点，展开和折叠的高度由弹簧驱动
+
+use std::collections::{HashMap, HashSet};
+
+use tiny_skia::{PathBuilder, Transform};
+
+use crate::core::WidgetPod;
+use crate::events::{keys, Modifiers, WindowEvent};
+use crate::r#box::AreaBox;
+use crate::traits::Widget;
+use crate::utils::binding::Binding;
+use crate::utils::drawing::{gen_paint, gen_rect_path};
+use crate::utils::spring::Spring;
+use crate::utils::theme::get_theme_color;
+use crate::utils::uid::gen_uid;
+use crate::utils::WidgetUid;
+use crate::widgets::list::SelectionState;
+use crate::widgets::scroll::{ScrollDirection, ScrollState};
+use crate::{DrawCtx, EventCtx, UpdateCtx};
+
+const OVERSCAN: usize = 2;
+const INDENT: f32 = 16.;
+const GUIDE_COLOR: u32 = 0x00000020;
+const ARROW_COLOR: u32 = 0x606060FF;
+const HOVER_COLOR: u32 = 0x0000000F;
+
+/// A node of the hierarchical data shown by `TreeControl`.
+pub trait TreeNode: Sized {
+    fn children(&self) -> &[Self];
+    fn children_mut(&mut self) -> &mut [Self];
+    /// Whether the node can be expanded, may be true before the children are loaded
+    fn has_children(&self) -> bool {
+        !self.children().is_empty()
+    }
+    /// Called every time before the node expands, load the children here lazily
+    fn load_children(&mut self) {}
+}
+
+fn node_at<'a, N: TreeNode>(roots: &'a [N], path: &[usize]) -> Option<&'a N> {
+    let (first, rest) = path.split_first()?;
+    let mut node = roots.get(*first)?;
+    for &i in rest {
+        node = node.children().get(i)?;
+    }
+    Some(node)
+}
+
+fn node_at_mut<'a, N: TreeNode>(roots: &'a mut [N], path: &[usize]) -> Option<&'a mut N> {
+    let (first, rest) = path.split_first()?;
+    let mut node = roots.get_mut(*first)?;
+    for &i in rest {
+        node = node.children_mut().get_mut(i)?;
+    }
+    Some(node)
+}
+
+struct FlatRow {
+    path: Vec<usize>,
+    has_children: bool,
+    /// Visible part of the row while its ancestors are animating, from 0 to 1
+    factor: f32,
+    top: f32,
+}
+
+type SelectCallback<D> = Box<dyn Fn(&mut D, &[Vec<usize>])>;
+
+pub struct TreeControl<D, N> {
+    _uid: WidgetUid,
+    roots: Binding<D, Vec<N>>,
+    builder: Box<dyn Fn() -> Box<dyn Widget<N>>>,
+    row_height: f32,
+    expanded: HashSet<Vec<usize>>,
+    /// Expanding or collapsing nodes, the springs move between 0 and 1
+    animations: HashMap<Vec<usize>, Spring>,
+    flat: Vec<FlatRow>,
+    need_flatten: bool,
+    rows: Vec<(Vec<usize>, WidgetPod<N>)>,
+    pool: Vec<WidgetPod<N>>,
+    scroll: ScrollState,
+    size: (f32, f32),
+    /// Selection of indices in the flatten rows
+    selection: SelectionState,
+    hovering: Option<usize>,
+    select_callback: SelectCallback<D>,
+    selection_color: u32,
+}
+
+impl<D, N: TreeNode> TreeControl<D, N> {
+    pub fn new<F: Fn() -> Box<dyn Widget<N>> + 'static>(
+        roots: Binding<D, Vec<N>>,
+        builder: F,
+    ) -> Self {
+        let mut scroll = ScrollState::new(ScrollDirection::Vertical);
+        scroll.set_drag_scroll(false);
+        Self {
+            _uid: gen_uid(),
+            roots,
+            builder: Box::new(builder),
+            row_height: 24.,
+            expanded: HashSet::new(),
+            animations: HashMap::new(),
+            flat: Vec::new(),
+            need_flatten: true,
+            rows: Vec::new(),
+            pool: Vec::new(),
+            scroll,
+            size: (0., 0.),
+            selection: SelectionState::new(),
+            hovering: None,
+            select_callback: Box::new(|_, _| {}),
+            selection_color: (get_theme_color() & 0xFFFFFF00) | 0x60,
+        }
+    }
+
+    pub fn with_row_height(mut self, v: f32) -> Self {
+        self.row_height = v;
+        self
+    }
+
+    pub fn with_multi_select(mut self, v: bool) -> Self {
+        self.selection.multi_select = v;
+        self
+    }
+
+    /// Called with the paths of the selected nodes every time the selection changes
+    pub fn on_select<F: Fn(&mut D, &[Vec<usize>]) + 'static>(mut self, v: F) -> Self {
+        self.select_callback = Box::new(v);
+        self
+    }
+
+    /// Paths of the selected nodes, a path is the child indices from the roots
+    pub fn selection(&self) -> Vec<Vec<usize>> {
+        self.selection
+            .selected()
+            .iter()
+            .filter_map(|&i| self.flat.get(i).map(|r| r.path.clone()))
+            .collect()
+    }
+
+    #[inline]
+    pub fn is_expanded(&self, path: &[usize]) -> bool {
+        self.expanded.contains(path)
+    }
+
+    /// Expand or collapse a node with animation
+    pub fn set_expanded(&mut self, roots: &mut [N], path: &[usize], expand: bool) {
+        if expand == self.is_expanded(path) {
+            return;
+        }
+        let from = if expand { 0. } else { 1. };
+        if expand {
+            match node_at_mut(roots, path) {
+                Some(node) => node.load_children(),
+                None => return,
+            }
+            self.expanded.insert(path.to_vec());
+        } else {
+            self.expanded.remove(path);
+        }
+        let spring = self.animations.entry(path.to_vec()).or_insert_with(|| {
+            let mut spring = Spring::new(from);
+            spring.speed = 16.;
+            spring
+        });
+        spring.set_target(if expand { 1. } else { 0. });
+        self.need_flatten = true;
+    }
+
+    fn flatten(&mut self, roots: &[N]) {
+        let mut flat = Vec::with_capacity(self.flat.len());
+        let mut path = Vec::new();
+        self.flatten_nodes(roots, &mut path, 1., &mut flat);
+        let mut top = 0.;
+        for row in flat.iter_mut() {
+            row.top = top;
+            top += self.row_height * row.factor;
+        }
+        let old = std::mem::replace(&mut self.flat, flat);
+        self.need_flatten = false;
+
+        // Keep the selection on the same nodes
+        if !self.selection.selected().is_empty() || self.selection.cursor().is_some() {
+            let positions: HashMap<&[usize], usize> = self
+                .flat
+                .iter()
+                .enumerate()
+                .map(|(i, r)| (r.path.as_slice(), i))
+                .collect();
+            self.selection.remap(|i| {
+                old.get(i)
+                    .and_then(|r| positions.get(r.path.as_slice()).copied())
+            });
+        }
+        self.update_scroll_sizes();
+    }
+
+    fn flatten_nodes(
+        &mut self,
+        nodes: &[N],
+        path: &mut Vec<usize>,
+        factor: f32,
+        flat: &mut Vec<FlatRow>,
+    ) {
+        for (i, node) in nodes.iter().enumerate() {
+            path.push(i);
+            flat.push(FlatRow {
+                path: path.clone(),
+                has_children: node.has_children(),
+                factor,
+                top: 0.,
+            });
+            let animating = self
+                .animations
+                .get_mut(path.as_slice())
+                .map(|s| s.position().clamp(0., 1.));
+            if self.expanded.contains(path.as_slice()) || animating.is_some() {
+                let child_factor = factor * animating.unwrap_or(1.);
+                self.flatten_nodes(node.children(), path, child_factor, flat);
+            }
+            path.pop();
+        }
+    }
+
+    /// Update the positions of the rows while animating, returns true if still animating
+    fn step_animations(&mut self, roots: &[N]) -> bool {
+        if self.animations.is_empty() {
+            return false;
+        }
+        // Collapsed nodes leave the flatten rows when their animations end
+        self.animations.retain(|_, spring| !spring.arrived());
+        self.flatten(roots);
+        !self.animations.is_empty()
+    }
+
+    fn update_scroll_sizes(&mut self) {
+        let height = self.content_height();
+        let viewport = self.scroll.viewport();
+        self.scroll.set_sizes(viewport, (self.size.0, height));
+    }
+
+    fn content_height(&self) -> f32 {
+        self.flat
+            .last()
+            .map_or(0., |r| r.top + self.row_height * r.factor)
+    }
+
+    fn index_at(&self, y: f32) -> Option<usize> {
+        if y < 0. {
+            return None;
+        }
+        let i = match self
+            .flat
+            .binary_search_by(|r| r.top.total_cmp(&y))
+        {
+            Ok(i) => i,
+            Err(0) => return None,
+            Err(i) => i - 1,
+        };
+        // Rows in a collapsing subtree have the same top as the next one
+        let row = &self.flat[i];
+        if y < row.top + self.row_height * row.factor {
+            Some(i)
+        } else {
+            None
+        }
+    }
+
+    fn realize(&mut self, roots: &[N]) {
+        if self.need_flatten {
+            self.flatten(roots);
+        }
+        let (_, offset) = self.scroll.offset();
+        let (_, height) = self.scroll.viewport();
+        let first = self
+            .flat
+            .binary_search_by(|r| r.top.total_cmp(&offset))
+            .unwrap_or_else(|i| i.saturating_sub(1))
+            .saturating_sub(OVERSCAN);
+        let last = self
+            .flat
+            .binary_search_by(|r| r.top.total_cmp(&(offset + height)))
+            .unwrap_or_else(|i| i);
+        let last = (last + OVERSCAN).min(self.flat.len());
+        let visible = &self.flat[first..last];
+
+        let mut i = 0;
+        while i < self.rows.len() {
+            if visible.iter().any(|r| r.path == self.rows[i].0) {
+                i += 1;
+            } else {
+                let (_, pod) = self.rows.swap_remove(i);
+                self.pool.push(pod);
+            }
+        }
+        let width = self.size.0;
+        for index in first..last {
+            let row = &self.flat[index];
+            let left = (row.path.len() as f32) * INDENT;
+            let pod = match self.rows.iter_mut().find(|(p, _)| p == &row.path) {
+                Some((_, pod)) => pod,
+                None => {
+                    let builder = &self.builder;
+                    let mut pod = self.pool.pop().unwrap_or_else(|| builder().into());
+                    if let Some(node) = node_at(roots, &row.path) {
+                        pod.update(&mut UpdateCtx::new(), node);
+                    }
+                    pod.layout(AreaBox::with_size(
+                        (width - left).max(0.) as isize,
+                        self.row_height as isize,
+                    ));
+                    self.rows.push((row.path.clone(), pod));
+                    &mut self.rows.last_mut().unwrap().1
+                }
+            };
+            pod.set_origin(left, row.top - offset);
+        }
+    }
+
+    fn toggle(&mut self, roots: &mut [N], index: usize) {
+        if let Some(row) = self.flat.get(index) {
+            if row.has_children {
+                let path = row.path.clone();
+                let expand = !self.is_expanded(&path);
+                self.set_expanded(roots, &path, expand);
+            }
+        }
+    }
+
+    fn move_cursor(&mut self, target: usize, modifiers: Modifiers) -> bool {
+        let changed = self.selection.navigate(target, modifiers);
+        if let Some(row) = self.flat.get(target) {
+            self.scroll
+                .scroll_into_view(0., row.top, 0., self.row_height);
+        }
+        changed
+    }
+
+    /// Returns true if the selection changed
+    fn key_down(&mut self, roots: &mut [N], key: usize, modifiers: Modifiers) -> bool {
+        if self.flat.is_empty() {
+            return false;
+        }
+        let last = self.flat.len() - 1;
+        let cursor = self.selection.cursor();
+        let page = (self.scroll.viewport().1 / self.row_height).max(1.) as usize;
+        match key {
+            keys::UP => self.move_cursor(cursor.map_or(0, |i| i.saturating_sub(1)), modifiers),
+            keys::DOWN => self.move_cursor(cursor.map_or(0, |i| (i + 1).min(last)), modifiers),
+            keys::HOME => self.move_cursor(0, modifiers),
+            keys::END => self.move_cursor(last, modifiers),
+            keys::PRIOR => {
+                self.move_cursor(cursor.map_or(0, |i| i.saturating_sub(page)), modifiers)
+            }
+            keys::NEXT => self.move_cursor(cursor.map_or(0, |i| (i + page).min(last)), modifiers),
+            keys::RIGHT => match cursor {
+                Some(i) if self.flat[i].has_children => {
+                    let path = self.flat[i].path.clone();
+                    if self.is_expanded(&path) {
+                        // Go to the first child
+                        if i < last && self.flat[i + 1].path.len() > path.len() {
+                            return self.move_cursor(i + 1, modifiers);
+                        }
+                    } else {
+                        self.set_expanded(roots, &path, true);
+                    }
+                    false
+                }
+                _ => false,
+            },
+            keys::LEFT => match cursor {
+                Some(i) => {
+                    let path = self.flat[i].path.clone();
+                    if self.is_expanded(&path) {
+                        self.set_expanded(roots, &path, false);
+                        false
+                    } else if path.len() > 1 {
+                        // Go to the parent
+                        let parent = &path[..path.len() - 1];
+                        match self.flat.iter().position(|r| r.path == parent) {
+                            Some(p) => self.move_cursor(p, modifiers),
+                            None => false,
+                        }
+                    } else {
+                        false
+                    }
+                }
+                None => false,
+            },
+            keys::ADD | keys::OEM_PLUS | keys::SUBTRACT | keys::OEM_MINUS => {
+                if let Some(i) = cursor {
+                    let path = self.flat[i].path.clone();
+                    let expand = key == keys::ADD || key == keys::OEM_PLUS;
+                    if self.flat[i].has_children {
+                        self.set_expanded(roots, &path, expand);
+                    }
+                }
+                false
+            }
+            _ => self.selection.key_down(key, modifiers, self.flat.len()),
+        }
+    }
+}
+
+impl<D, N: TreeNode> Widget<D> for TreeControl<D, N> {
+    fn event(&mut self, ctx: &mut EventCtx, data: &mut D) {
+        if self.scroll.bar_event(ctx) {
+            return;
+        }
+        let roots = self.roots.get_mut(data);
+        if self.need_flatten {
+            self.flatten(roots);
+        }
+        for (path, pod) in self.rows.iter_mut() {
+            if let Some(node) = node_at_mut(roots, path) {
+                pod.event(ctx, node);
+            }
+        }
+        self.scroll.event(ctx);
+
+        let (x, y) = ctx.mouse_pos();
+        let (_, offset) = self.scroll.offset();
+        let index = self.index_at(y + offset);
+        let mut changed = false;
+        match ctx.event() {
+            WindowEvent::CollectFocus => ctx.register_for_focus(),
+            WindowEvent::MouseDown(_, _) => {
+                ctx.request_focus();
+                if let Some(index) = index.filter(|_| !ctx.is_handled()) {
+                    let depth = self.flat[index].path.len() as f32;
+                    if x >= (depth - 1.) * INDENT && x < depth * INDENT {
+                        // Clicking on the arrow
+                        self.toggle(roots, index);
+                    } else {
+                        self.selection.click(index, ctx.modifiers());
+                        changed = true;
+                    }
+                    ctx.set_handled();
+                    ctx.request_anim_frame();
+                }
+            }
+            WindowEvent::MouseMove(_, _) => {
+                let hovering = index.filter(|_| ctx.is_hovering());
+                if hovering != self.hovering {
+                    self.hovering = hovering;
+                    ctx.request_paint();
+                }
+            }
+            WindowEvent::KeyDown(key) if ctx.is_focused() => {
+                let cursor = self.selection.cursor();
+                changed = self.key_down(roots, key, ctx.modifiers());
+                if changed || cursor != self.selection.cursor() || self.need_flatten {
+                    ctx.set_handled();
+                    ctx.request_anim_frame();
+                }
+            }
+            _ => {}
+        }
+        if changed {
+            let selection = self.selection();
+            (self.select_callback)(data, &selection);
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, data: &D) {
+        let roots = self.roots.get(data);
+        self.flatten(roots);
+        self.realize(roots);
+        for (path, pod) in self.rows.iter_mut() {
+            if let Some(node) = node_at(roots, path) {
+                pod.update(ctx, node);
+            }
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut DrawCtx, data: &D) {
+        let roots = self.roots.get(data);
+        if self.step_animations(roots) {
+            ctx.request_anim_frame();
+        }
+        self.realize(roots);
+        let (width, height) = self.size;
+        let (_, offset) = self.scroll.offset();
+        let row_height = self.row_height;
+        let focused = ctx.is_focused();
+        let cursor = self.selection.cursor();
+        let guide = gen_paint(GUIDE_COLOR);
+        let arrow = gen_paint(ARROW_COLOR);
+
+        let first = self
+            .flat
+            .binary_search_by(|r| r.top.total_cmp(&offset))
+            .unwrap_or_else(|i| i.saturating_sub(1));
+        let flat = &self.flat;
+        let rows = &mut self.rows;
+        let selection = &self.selection;
+        let selection_color = self.selection_color;
+        let hovering = self.hovering;
+        let expanded = &self.expanded;
+        let animations = &mut self.animations;
+        ctx.with_save(|ctx| {
+            ctx.clip_rect(0., 0., width, height);
+            for (index, row) in flat.iter().enumerate().skip(first) {
+                let top = row.top - offset;
+                if top > height {
+                    break;
+                }
+                let h = row_height * row.factor;
+                if h < 0.5 {
+                    continue;
+                }
+                ctx.with_save(|ctx| {
+                    // Rows in an animating subtree show their top part
+                    ctx.clip_rect(0., top, width, h);
+                    let path = gen_rect_path(0., top, width, row_height);
+                    if selection.is_selected(index) {
+                        ctx.fill_path(&path, &gen_paint(selection_color));
+                    } else if hovering == Some(index) {
+                        ctx.fill_path(&path, &gen_paint(HOVER_COLOR));
+                    }
+                    if focused && cursor == Some(index) {
+                        let path = gen_rect_path(0.5, top + 0.5, width - 1., row_height - 1.);
+                        ctx.stroke_path(
+                            &path,
+                            &gen_paint(selection_color | 0xFF),
+                            &Default::default(),
+                        );
+                    }
+                    // Indentation guides of the ancestors
+                    let depth = row.path.len();
+                    for level in 1..depth {
+                        let x = (level as f32 - 0.5) * INDENT;
+                        ctx.fill_path(&gen_rect_path(x.floor(), top, 1., row_height), &guide);
+                    }
+                    if row.has_children {
+                        // The arrow turns from right to down while expanding
+                        let angle = match animations.get_mut(&row.path) {
+                            Some(spring) => spring.position().clamp(0., 1.) * 90.,
+                            None if expanded.contains(&row.path) => 90.,
+                            None => 0.,
+                        };
+                        let cx = (depth as f32 - 0.5) * INDENT;
+                        let cy = top + row_height / 2.;
+                        let mut pb = PathBuilder::new();
+                        pb.move_to(-2., -4.);
+                        pb.line_to(3., 0.);
+                        pb.line_to(-2., 4.);
+                        pb.close();
+                        if let Some(path) = pb.finish().and_then(|p| {
+                            p.transform(Transform::from_rotate(angle).post_translate(cx, cy))
+                        }) {
+                            ctx.fill_path(&path, &arrow);
+                        }
+                    }
+                    if let Some((_, pod)) = rows.iter_mut().find(|(p, _)| p == &row.path) {
+                        if let Some(node) = node_at(roots, &row.path) {
+                            pod.draw(ctx, node);
+                        }
+                    }
+                });
+            }
+        });
+        self.scroll.draw_bars(ctx);
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let width = if max_box.width() == isize::MAX {
+            240.
+        } else {
+            max_box.width() as f32
+        };
+        let height = if max_box.height() == isize::MAX {
+            self.content_height()
+        } else {
+            max_box.height() as f32
+        };
+        self.size = (width, height);
+        for (path, pod) in self.rows.iter_mut() {
+            let left = path.len() as f32 * INDENT;
+            pod.layout(AreaBox::with_size(
+                (width - left).max(0.) as isize,
+                self.row_height as isize,
+            ));
+        }
+        let content = self.content_height();
+        self.scroll.set_sizes((width, height), (width, content));
+        AreaBox::with_size(width as isize, height as isize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Node {
+        children: Vec<Node>,
+        /// Children created when the node expands
+        lazy: usize,
+    }
+
+    impl Node {
+        fn new(children: Vec<Node>) -> Self {
+            Self { children, lazy: 0 }
+        }
+
+        fn lazy(count: usize) -> Self {
+            Self {
+                children: Vec::new(),
+                lazy: count,
+            }
+        }
+    }
+
+    impl TreeNode for Node {
+        fn children(&self) -> &[Self] {
+            &self.children
+        }
+
+        fn children_mut(&mut self) -> &mut [Self] {
+            &mut self.children
+        }
+
+        fn has_children(&self) -> bool {
+            !self.children.is_empty() || self.lazy > 0
+        }
+
+        fn load_children(&mut self) {
+            while self.children.len() < self.lazy {
+                self.children.push(Node::new(Vec::new()));
+            }
+        }
+    }
+
+    struct Row;
+
+    impl Widget<Node> for Row {}
+
+    fn roots() -> Vec<Node> {
+        vec![
+            Node::new(vec![
+                Node::new(vec![Node::new(Vec::new())]),
+                Node::new(Vec::new()),
+            ]),
+            Node::lazy(2),
+        ]
+    }
+
+    fn tree() -> TreeControl<Vec<Node>, Node> {
+        let mut tree = TreeControl::new(Binding::identity(), || Box::new(Row));
+        tree.size = (100., 100.);
+        tree.scroll.set_sizes((100., 100.), (100., 0.));
+        tree
+    }
+
+    /// Expand without animation
+    fn expand(tree: &mut TreeControl<Vec<Node>, Node>, roots: &mut [Node], path: &[usize]) {
+        tree.set_expanded(roots, path, true);
+        tree.animations.clear();
+        tree.flatten(roots);
+    }
+
+    fn paths(tree: &TreeControl<Vec<Node>, Node>) -> Vec<Vec<usize>> {
+        tree.flat.iter().map(|r| r.path.clone()).collect()
+    }
+
+    #[test]
+    fn flatten_follows_the_expanded_nodes() {
+        let mut roots = roots();
+        let mut tree = tree();
+        tree.flatten(&roots);
+        assert_eq!(paths(&tree), [vec![0], vec![1]]);
+        expand(&mut tree, &mut roots, &[0]);
+        expand(&mut tree, &mut roots, &[0, 0]);
+        assert_eq!(
+            paths(&tree),
+            [vec![0], vec![0, 0], vec![0, 0, 0], vec![0, 1], vec![1]]
+        );
+        assert_eq!(tree.content_height(), 5. * 24.);
+        assert_eq!(tree.index_at(30.), Some(1));
+        // Collapsing a parent hides the expanded grandchildren too
+        tree.set_expanded(&mut roots, &[0], false);
+        tree.animations.clear();
+        tree.flatten(&roots);
+        assert_eq!(paths(&tree), [vec![0], vec![1]]);
+        assert!(tree.is_expanded(&[0, 0]));
+    }
+
+    #[test]
+    fn children_load_when_expanding() {
+        let mut roots = roots();
+        let mut tree = tree();
+        tree.flatten(&roots);
+        assert!(tree.flat[1].has_children);
+        assert!(roots[1].children.is_empty());
+        expand(&mut tree, &mut roots, &[1]);
+        assert_eq!(roots[1].children.len(), 2);
+        assert_eq!(paths(&tree), [vec![0], vec![1], vec![1, 0], vec![1, 1]]);
+    }
+
+    #[test]
+    fn expanding_rows_grow_from_nothing() {
+        let mut roots = roots();
+        let mut tree = tree();
+        tree.flatten(&roots);
+        tree.set_expanded(&mut roots, &[0], true);
+        tree.flatten(&roots);
+        assert_eq!(tree.flat.len(), 4);
+        // The animation may have run a little already
+        assert!(tree.flat[1].factor < 0.01);
+        assert!((tree.content_height() - 2. * 24.).abs() < 1.);
+        // The hidden rows can't be hit
+        assert_eq!(tree.index_at(30.), Some(3));
+        assert!(tree.step_animations(&roots));
+    }
+
+    #[test]
+    fn selection_stays_on_the_node() {
+        let mut roots = roots();
+        let mut tree = tree();
+        tree.flatten(&roots);
+        tree.selection.click(1, Default::default());
+        expand(&mut tree, &mut roots, &[0]);
+        assert_eq!(tree.selection(), [vec![1]]);
+        assert_eq!(tree.selection.selected(), [3]);
+    }
+
+    #[test]
+    fn arrows_walk_the_tree() {
+        let mut roots = roots();
+        let mut tree = tree();
+        tree.flatten(&roots);
+        let none = Default::default();
+        assert!(tree.key_down(&mut roots, keys::DOWN, none));
+        // Right expands, then goes to the first child
+        assert!(!tree.key_down(&mut roots, keys::RIGHT, none));
+        assert!(tree.is_expanded(&[0]));
+        tree.animations.clear();
+        tree.flatten(&roots);
+        assert!(tree.key_down(&mut roots, keys::RIGHT, none));
+        assert_eq!(tree.selection(), [vec![0, 0]]);
+        // Left goes to the parent, then collapses it
+        assert!(tree.key_down(&mut roots, keys::LEFT, none));
+        assert_eq!(tree.selection(), [vec![0]]);
+        assert!(!tree.key_down(&mut roots, keys::LEFT, none));
+        assert!(!tree.is_expanded(&[0]));
+    }
+}
@@ -3,6 +3,7 @@ use std::time::SystemTime;
 
 
 use crate::core::WidgetPod;
+use crate::r#box::AreaBox;
 use crate::system::enums::HitResult;
 use crate::traits::{Widget, TopWidget};
 
@@ -10,6 +11,8 @@ use crate::utils::uid::gen_uid;
 
 use crate::utils::{WidgetUid};
 
+use super::menu::MenuBar;
+
 
 #[derive(PartialEq, Debug, Clone, Copy)]
 enum WindowTitleHovering {
@@ -21,6 +24,8 @@ enum WindowTitleHovering {
 pub struct WindowControl<D> {
     _uid: WidgetUid,
     inner: WidgetPod<D>,
+    /// Drawn in the title area, above the content
+    menu_bar: Option<WidgetPod<D>>,
     start_time: SystemTime,
     mouse_down_time: SystemTime,
     btn_hovering: WindowTitleHovering,
@@ -42,6 +47,7 @@ impl<D> WindowControl<D> {
             mouse_down_time: SystemTime::now(),
             btn_hovering: WindowTitleHovering::None,
             inner: inner.into(),
+            menu_bar: None,
             size: (800, 600),
             destroyed: false,
         }
@@ -62,6 +68,15 @@ impl<D> WindowControl<D> {
         self
     }
 
+    /// Put a menu bar at the top of the window.
+    pub fn with_menu_bar(mut self, v: MenuBar<D>) -> Self
+    where
+        D: 'static,
+    {
+        self.menu_bar = Some((Box::new(v) as Box<dyn Widget<D>>).into());
+        self
+    }
+
     pub fn set_title(&mut self, v: String) {
         self.title = v;
     }
@@ -74,10 +89,38 @@ impl<D> WindowControl<D> {
 }
 
 impl<D> Widget<D> for WindowControl<D> {
-    fn draw(&mut self, ctx: &mut crate::DrawCtx, _data: &D) {
+    fn event(&mut self, ctx: &mut crate::EventCtx, data: &mut D) {
+        if let Some(bar) = &mut self.menu_bar {
+            bar.event(ctx, data);
+        }
+        self.inner.event(ctx, data);
+    }
+
+    fn update(&mut self, ctx: &mut crate::UpdateCtx, data: &D) {
+        if let Some(bar) = &mut self.menu_bar {
+            bar.update(ctx, data);
+        }
+        self.inner.update(ctx, data);
+    }
+
+    fn draw(&mut self, ctx: &mut crate::DrawCtx, data: &D) {
         // F74C00
         ctx.pixmapmut.fill(tiny_skia::Color::from_rgba8(0x00, 0x4c, 0xf7, 0xAA));
-        println!("Draw");
+        if let Some(bar) = &mut self.menu_bar {
+            bar.draw(ctx, data);
+        }
+        self.inner.draw(ctx, data);
+    }
+
+    fn layout(&mut self, max_box: AreaBox) -> AreaBox {
+        let mut top = 0;
+        if let Some(bar) = &mut self.menu_bar {
+            top = bar.layout(AreaBox::with_size(max_box.width(), max_box.height())).height();
+            bar.set_origin(0., 0.);
+        }
+        self.inner.layout(AreaBox::with_size(max_box.width(), max_box.height() - top));
+        self.inner.set_origin(0., top as f32);
+        max_box
     }
 }
 